@@ -0,0 +1,322 @@
+use crate::error::Result;
+use ethers::{
+    abi::{Function, Param, ParamType, StateMutability, Token},
+    contract::abigen,
+    types::{Address, Bytes, TransactionReceipt, H256, U256},
+    utils::keccak256,
+};
+
+abigen!(
+    IERC20,
+    r#"[
+        function balanceOf(address account) external view returns (uint256)
+        function decimals() external view returns (uint8)
+        function symbol() external view returns (string)
+        function name() external view returns (string)
+        function allowance(address owner, address spender) external view returns (uint256)
+        function approve(address spender, uint256 amount) external returns (bool)
+    ]"#
+);
+
+abigen!(
+    IUniswapV2Router02,
+    r#"[
+        function factory() external view returns (address)
+        function getAmountsOut(uint256 amountIn, address[] path) external view returns (uint256[] amounts)
+        function getAmountsIn(uint256 amountOut, address[] path) external view returns (uint256[] amounts)
+        function swapExactETHForTokens(uint256 amountOutMin, address[] path, address to, uint256 deadline) external payable returns (uint256[] amounts)
+        function swapExactETHForTokensSupportingFeeOnTransferTokens(uint256 amountOutMin, address[] path, address to, uint256 deadline) external payable
+        function swapETHForExactTokens(uint256 amountOut, address[] path, address to, uint256 deadline) external payable returns (uint256[] amounts)
+        function swapExactTokensForTokens(uint256 amountIn, uint256 amountOutMin, address[] path, address to, uint256 deadline) external returns (uint256[] amounts)
+        function swapExactTokensForTokensSupportingFeeOnTransferTokens(uint256 amountIn, uint256 amountOutMin, address[] path, address to, uint256 deadline) external
+        function swapTokensForExactTokens(uint256 amountOut, uint256 amountInMax, address[] path, address to, uint256 deadline) external returns (uint256[] amounts)
+        function addLiquidityETH(address token, uint256 amountTokenDesired, uint256 amountTokenMin, uint256 amountETHMin, address to, uint256 deadline) external payable returns (uint256 amountToken, uint256 amountETH, uint256 liquidity)
+        function removeLiquidityETHSupportingFeeOnTransferTokens(address token, uint256 liquidity, uint256 amountTokenMin, uint256 amountETHMin, address to, uint256 deadline) external returns (uint256 amountETH)
+    ]"#
+);
+
+// Camelot's router, whose swaps take a referrer
+abigen!(
+    ICamelotRouter,
+    r#"[
+        function swapExactETHForTokensSupportingFeeOnTransferTokens(uint256 amountOutMin, address[] path, address to, address referrer, uint256 deadline) external payable
+        function swapExactTokensForTokensSupportingFeeOnTransferTokens(uint256 amountIn, uint256 amountOutMin, address[] path, address to, address referrer, uint256 deadline) external
+        function swapExactTokensForETHSupportingFeeOnTransferTokens(uint256 amountIn, uint256 amountOutMin, address[] path, address to, address referrer, uint256 deadline) external
+    ]"#
+);
+
+abigen!(
+    IWETH,
+    r#"[
+        function deposit() external payable
+        function withdraw(uint256 amount) external
+    ]"#
+);
+
+abigen!(
+    IMulticall,
+    r#"[
+        function multicall(bytes[] data) external payable returns (bytes[] results)
+        function multicall(uint256 deadline, bytes[] data) external payable returns (bytes[] results)
+        function multicall(bytes32 previousBlockhash, bytes[] data) external payable returns (bytes[] results)
+    ]"#
+);
+
+abigen!(
+    IUniversalRouter,
+    r#"[
+        function execute(bytes commands, bytes[] inputs, uint256 deadline) external payable
+        function execute(bytes commands, bytes[] inputs) external payable
+    ]"#
+);
+
+abigen!(
+    IOneInchV5Router,
+    r#"[
+        struct SwapDescription { address srcToken; address dstToken; address srcReceiver; address dstReceiver; uint256 amount; uint256 minReturnAmount; uint256 flags; }
+        function swap(address executor, SwapDescription desc, bytes permit, bytes data) external payable returns (uint256 returnAmount, uint256 spentAmount)
+    ]"#
+);
+
+abigen!(
+    IOneInchV6Router,
+    r#"[
+        struct SwapDescription { address srcToken; address dstToken; address srcReceiver; address dstReceiver; uint256 amount; uint256 minReturnAmount; uint256 flags; }
+        function swap(address executor, SwapDescription desc, bytes data) external payable returns (uint256 returnAmount, uint256 spentAmount)
+    ]"#
+);
+
+abigen!(
+    IZeroExProxy,
+    r#"[
+        struct Transformation { uint32 deploymentNonce; bytes data; }
+        function transformERC20(address inputToken, address outputToken, uint256 inputTokenAmount, uint256 minOutputTokenAmount, Transformation[] transformations) external payable returns (uint256 outputTokenAmount)
+        function sellToUniswap(address[] tokens, uint256 sellAmount, uint256 minBuyAmount, bool isSushi) external payable returns (uint256 buyAmount)
+    ]"#
+);
+
+abigen!(
+    IAugustusSwapper,
+    r#"[
+        struct SimpleData { address fromToken; address toToken; uint256 fromAmount; uint256 toAmount; uint256 expectedAmount; address[] callees; bytes exchangeData; uint256[] startIndexes; uint256[] values; address beneficiary; address partner; uint256 feePercent; bytes permit; uint256 deadline; bytes16 uuid; }
+        function simpleSwap(SimpleData data) external payable returns (uint256 receivedAmount)
+    ]"#
+);
+
+abigen!(
+    ISwapRouter02,
+    r#"[
+        struct ExactInputSingleParams { address tokenIn; address tokenOut; uint24 fee; address recipient; uint256 amountIn; uint256 amountOutMinimum; uint160 sqrtPriceLimitX96; }
+        function exactInputSingle(ExactInputSingleParams params) external payable returns (uint256 amountOut)
+        function unwrapWETH9(uint256 amountMinimum, address recipient) external payable
+    ]"#
+);
+
+abigen!(
+    IQuoterV2,
+    r#"[
+        struct QuoteExactInputSingleParams { address tokenIn; address tokenOut; uint256 amountIn; uint24 fee; uint160 sqrtPriceLimitX96; }
+        function quoteExactInputSingle(QuoteExactInputSingleParams params) external returns (uint256 amountOut, uint160 sqrtPriceX96After, uint32 initializedTicksCrossed, uint256 gasEstimate)
+        function factory() external view returns (address)
+    ]"#
+);
+
+abigen!(
+    IUniswapV3Factory,
+    r#"[
+        function getPool(address tokenA, address tokenB, uint24 fee) external view returns (address pool)
+    ]"#
+);
+
+abigen!(
+    IUniswapV3Pool,
+    r#"[
+        function slot0() external view returns (uint160 sqrtPriceX96, int24 tick, uint16 observationIndex, uint16 observationCardinality, uint16 observationCardinalityNext, uint8 feeProtocol, bool unlocked)
+        function liquidity() external view returns (uint128)
+        function observe(uint32[] secondsAgos) external view returns (int56[] tickCumulatives, uint160[] secondsPerLiquidityCumulativeX128s)
+    ]"#
+);
+
+abigen!(
+    IV4Quoter,
+    r#"[
+        struct PoolKey { address currency0; address currency1; uint24 fee; int24 tickSpacing; address hooks; }
+        struct QuoteExactSingleParams { PoolKey poolKey; bool zeroForOne; uint128 exactAmount; bytes hookData; }
+        function quoteExactInputSingle(QuoteExactSingleParams params) external returns (uint256 amountOut, uint256 gasEstimate)
+    ]"#
+);
+
+abigen!(
+    IStateView,
+    r#"[
+        function getSlot0(bytes32 poolId) external view returns (uint160 sqrtPriceX96, int24 tick, uint24 protocolFee, uint24 lpFee)
+        function getLiquidity(bytes32 poolId) external view returns (uint128 liquidity)
+    ]"#
+);
+
+abigen!(
+    IPermit2,
+    r#"[
+        function allowance(address user, address token, address spender) external view returns (uint160 amount, uint48 expiration, uint48 nonce)
+        function approve(address token, address spender, uint160 amount, uint48 expiration) external
+    ]"#
+);
+
+abigen!(
+    ICurvePool,
+    r#"[
+        function coins(uint256 i) external view returns (address)
+        function balances(uint256 i) external view returns (uint256)
+        function get_dy(uint256 i, uint256 j, uint256 dx) external view returns (uint256)
+        function exchange(uint256 i, uint256 j, uint256 dx, uint256 min_dy) external payable returns (uint256)
+    ]"#
+);
+
+abigen!(
+    ICurveLegacyPool,
+    r#"[
+        function get_dy(int128 i, int128 j, uint256 dx) external view returns (uint256)
+        function exchange(int128 i, int128 j, uint256 dx, uint256 min_dy) external payable returns (uint256)
+    ]"#
+);
+
+abigen!(
+    ICurveCryptoPool,
+    r#"[
+        function exchange(uint256 i, uint256 j, uint256 dx, uint256 min_dy, bool use_eth) external payable returns (uint256)
+    ]"#
+);
+
+abigen!(
+    IBalancerVault,
+    r#"[
+        struct SingleSwap { bytes32 poolId; uint8 kind; address assetIn; address assetOut; uint256 amount; bytes userData; }
+        struct FundManagement { address sender; bool fromInternalBalance; address recipient; bool toInternalBalance; }
+        struct BatchSwapStep { bytes32 poolId; uint256 assetInIndex; uint256 assetOutIndex; uint256 amount; bytes userData; }
+        function swap(SingleSwap singleSwap, FundManagement funds, uint256 limit, uint256 deadline) external payable returns (uint256 amountCalculated)
+        function queryBatchSwap(uint8 kind, BatchSwapStep[] swaps, address[] assets, FundManagement funds) external returns (int256[] assetDeltas)
+        function getPoolTokens(bytes32 poolId) external view returns (address[] tokens, uint256[] balances, uint256 lastChangeBlock)
+    ]"#
+);
+
+abigen!(
+    IAerodromeRouter,
+    r#"[
+        struct AerodromeRoute { address from; address to; bool stable; address factory; }
+        function getAmountsOut(uint256 amountIn, AerodromeRoute[] routes) external view returns (uint256[] amounts)
+        function swapExactETHForTokens(uint256 amountOutMin, AerodromeRoute[] routes, address to, uint256 deadline) external payable returns (uint256[] amounts)
+        function swapExactETHForTokensSupportingFeeOnTransferTokens(uint256 amountOutMin, AerodromeRoute[] routes, address to, uint256 deadline) external payable
+        function swapExactTokensForTokens(uint256 amountIn, uint256 amountOutMin, AerodromeRoute[] routes, address to, uint256 deadline) external returns (uint256[] amounts)
+        function swapExactTokensForTokensSupportingFeeOnTransferTokens(uint256 amountIn, uint256 amountOutMin, AerodromeRoute[] routes, address to, uint256 deadline) external
+        function swapExactTokensForETHSupportingFeeOnTransferTokens(uint256 amountIn, uint256 amountOutMin, AerodromeRoute[] routes, address to, uint256 deadline) external
+    ]"#
+);
+
+abigen!(
+    IAerodromeFactory,
+    r#"[
+        function getPool(address tokenA, address tokenB, bool stable) external view returns (address)
+    ]"#
+);
+
+abigen!(
+    IAerodromePool,
+    r#"[
+        function getReserves() external view returns (uint256 reserve0, uint256 reserve1, uint256 blockTimestampLast)
+        function getAmountOut(uint256 amountIn, address tokenIn) external view returns (uint256)
+    ]"#
+);
+
+abigen!(
+    IChainlinkAggregator,
+    r#"[
+        function decimals() external view returns (uint8)
+        function latestRoundData() external view returns (uint80 roundId, int256 answer, uint256 startedAt, uint256 updatedAt, uint80 answeredInRound)
+    ]"#
+);
+
+abigen!(
+    IGasPriceOracle,
+    r#"[
+        function getL1Fee(bytes data) external view returns (uint256)
+    ]"#
+);
+
+abigen!(
+    INodeInterface,
+    r#"[
+        function gasEstimateL1Component(address to, bool contractCreation, bytes data) external payable returns (uint64 gasEstimateForL1, uint256 baseFee, uint256 l1BaseFeeEstimate)
+    ]"#
+);
+
+abigen!(
+    IUniswapV2Factory,
+    r#"[
+        function getPair(address tokenA, address tokenB) external view returns (address pair)
+    ]"#
+);
+
+abigen!(
+    IUniswapV2Pair,
+    r#"[
+        function getReserves() external view returns (uint112 reserve0, uint112 reserve1, uint32 blockTimestampLast)
+        function price0CumulativeLast() external view returns (uint256)
+        function price1CumulativeLast() external view returns (uint256)
+        function totalSupply() external view returns (uint256)
+    ]"#
+);
+
+// ETH paid out in a sell's receipt, read from the WETH Withdrawal events
+// emitted when unwrapping its proceeds. Aggregators may unwrap from a
+// contract other than the one called, so every unwrap in the receipt counts.
+pub fn weth_withdrawn(receipt: &TransactionReceipt, weth: Address) -> U256 {
+    let withdrawal = H256::from(keccak256("Withdrawal(address,uint256)"));
+    receipt.logs
+        .iter()
+        .filter(|log| log.address == weth && log.topics.len() == 2 && log.topics[0] == withdrawal)
+        .fold(U256::zero(), |acc, log| acc + U256::from_big_endian(&log.data))
+}
+
+// Units of `token` sent to `to` in a receipt, read from its Transfer events;
+// WETH or stablecoin proceeds
+pub fn token_received(receipt: &TransactionReceipt, token: Address, to: Address) -> U256 {
+    let transfer = H256::from(keccak256("Transfer(address,address,uint256)"));
+    receipt.logs
+        .iter()
+        .filter(|log| log.address == token && log.topics.len() == 3 && log.topics[0] == transfer)
+        .filter(|log| Address::from(log.topics[2]) == to)
+        .fold(U256::zero(), |acc, log| acc + U256::from_big_endian(&log.data))
+}
+
+pub fn encode_function_data(function_name: &str, tokens: &[Token]) -> Result<Bytes> {
+    encode_swap(function_name, false, tokens)
+}
+
+// The same swap on a router taking a referrer before the deadline
+pub fn encode_referrer_function_data(function_name: &str, tokens: &[Token]) -> Result<Bytes> {
+    encode_swap(function_name, true, tokens)
+}
+
+fn encode_swap(function_name: &str, referrer: bool, tokens: &[Token]) -> Result<Bytes> {
+    let mut inputs = vec![
+        Param { name: "amountIn".to_string(), kind: ParamType::Uint(256), internal_type: None },
+        Param { name: "amountOutMin".to_string(), kind: ParamType::Uint(256), internal_type: None },
+        Param { name: "path".to_string(), kind: ParamType::Array(Box::new(ParamType::Address)), internal_type: None },
+        Param { name: "to".to_string(), kind: ParamType::Address, internal_type: None },
+    ];
+    if referrer {
+        inputs.push(Param { name: "referrer".to_string(), kind: ParamType::Address, internal_type: None });
+    }
+    inputs.push(Param { name: "deadline".to_string(), kind: ParamType::Uint(256), internal_type: None });
+    #[allow(deprecated)]
+    let function = Function {
+        name: function_name.to_string(),
+        inputs,
+        outputs: vec![],
+        constant: None,
+        state_mutability: StateMutability::NonPayable,
+    };
+
+    let encoded = function.encode_input(tokens)?;
+    Ok(encoded.into())
+}
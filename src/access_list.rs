@@ -0,0 +1,47 @@
+use crate::bot::TradingBot;
+use ethers::{
+    prelude::*,
+    types::{
+        transaction::{eip2718::TypedTransaction, eip2930::AccessList},
+        BlockNumber, Eip2930TransactionRequest,
+    },
+};
+
+// Drops entries that cost more than they save: the sender and recipient are
+// warm from the start, so listing them without storage keys buys nothing
+fn trimmed(access_list: AccessList, tx: &TypedTransaction) -> AccessList {
+    let warm = |address: &Address| Some(*address) == tx.from().copied() || Some(*address) == tx.to_addr().copied();
+    AccessList(access_list.0.into_iter().filter(|item| !(item.storage_keys.is_empty() && warm(&item.address))).collect())
+}
+
+impl TradingBot {
+    // Asks the node which accounts and slots the sell touches
+    // (eth_createAccessList) and declares them up front, so the pair's,
+    // token's and router's cold slots are paid for at the access-list rate.
+    // Legacy-priced sells become EIP-2930 transactions to carry the list.
+    // Nodes without the method leave the sell as it was.
+    pub(crate) async fn attach_access_list(&self, tx: &mut TypedTransaction) {
+        if !self.access_lists {
+            return;
+        }
+        let access_list = match self.provider.create_access_list(tx, Some(BlockNumber::Pending.into())).await {
+            Ok(created) => trimmed(created.access_list, tx),
+            Err(e) => {
+                println!("Sending without an access list: {}", e);
+                return;
+            }
+        };
+        if access_list.0.is_empty() {
+            return;
+        }
+
+        match tx {
+            TypedTransaction::Legacy(request) => {
+                *tx = TypedTransaction::Eip2930(Eip2930TransactionRequest::new(request.clone(), access_list));
+            }
+            _ => {
+                tx.set_access_list(access_list);
+            }
+        }
+    }
+}
@@ -0,0 +1,176 @@
+use crate::abi::{AerodromeRoute, IAerodromeFactory, IAerodromePool, IAerodromeRouter, IAerodromeRouterCalls};
+use crate::bot::TradingBot;
+use crate::decode::{RouterSwap, SwapAmounts};
+use crate::error::{MktmkrError, Result};
+use crate::math::mul_div;
+use ethers::{
+    abi::AbiDecode,
+    signers::Signer,
+    types::{Address, Bytes, U256},
+};
+use futures_util::future::join_all;
+use std::sync::Arc;
+
+// Aerodrome on Base and Velodrome V2, which it forks, on Optimism:
+// (chain id, name in the trade log, Router, PoolFactory)
+pub const DEFAULT_AERODROME_VENUES: &[(u64, &str, &str, &str)] = &[
+    (10, "Velodrome", "0xa062aE8A9c5e11aaA026fc2670B0D65cCc8B2858", "0xF1046053aa5682b4F9a81b5481394DA16BE5FF5a"),
+    (8453, "Aerodrome", "0xcF77a3Ba9A5CA399B7c97c74d54e5b1Beb874E43", "0x420DD381b31aEf6683db6B902084cB0FFECe40Da"),
+];
+
+// Gas an Aerodrome sell is assumed to take
+pub(crate) const AERODROME_SELL_GAS: u64 = 180_000;
+
+// An Aerodrome router and the factory of the pools it swaps through. Each
+// token can have a volatile (constant-product) and a stable (x³y + y³x)
+// pool with WETH.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AerodromeVenue {
+    pub name: &'static str,
+    pub router: Address,
+    pub factory: Address,
+}
+
+pub fn aerodrome_preset(chain_id: u64) -> Option<AerodromeVenue> {
+    let (_, name, router, factory) = DEFAULT_AERODROME_VENUES.iter().find(|(id, ..)| *id == chain_id)?;
+    Some(AerodromeVenue {
+        name,
+        router: router.parse().expect("valid preset address"),
+        factory: factory.parse().expect("valid preset address"),
+    })
+}
+
+// Decodes Aerodrome router calldata for every swap that can buy a token;
+// None for anything else
+pub fn decode_aerodrome_swap(input: &[u8]) -> Option<RouterSwap> {
+    use IAerodromeRouterCalls as Call;
+
+    let (amounts, routes, to, deadline) = match Call::decode(input).ok()? {
+        Call::SwapExactETHForTokens(c) => {
+            (SwapAmounts::ExactIn { amount_in: None, amount_out_min: c.amount_out_min }, c.routes, c.to, c.deadline)
+        }
+        Call::SwapExactETHForTokensSupportingFeeOnTransferTokens(c) => {
+            (SwapAmounts::ExactIn { amount_in: None, amount_out_min: c.amount_out_min }, c.routes, c.to, c.deadline)
+        }
+        Call::SwapExactTokensForTokens(c) => (
+            SwapAmounts::ExactIn { amount_in: Some(c.amount_in), amount_out_min: c.amount_out_min },
+            c.routes,
+            c.to,
+            c.deadline,
+        ),
+        Call::SwapExactTokensForTokensSupportingFeeOnTransferTokens(c) => (
+            SwapAmounts::ExactIn { amount_in: Some(c.amount_in), amount_out_min: c.amount_out_min },
+            c.routes,
+            c.to,
+            c.deadline,
+        ),
+        _ => return None,
+    };
+
+    // Each hop names its own input and output; the path chains them
+    let path = std::iter::once(routes.first()?.from).chain(routes.iter().map(|route| route.to)).collect();
+    Some(RouterSwap { amounts, path, to, deadline })
+}
+
+impl TradingBot {
+    fn aerodrome_venue(&self) -> Result<AerodromeVenue> {
+        self.aerodrome.ok_or_else(|| MktmkrError::Config("no Aerodrome router on this chain, set one explicitly".into()))
+    }
+
+    fn aerodrome_hop(&self, venue: AerodromeVenue, token: Address, stable: bool) -> AerodromeRoute {
+        AerodromeRoute { from: token, to: self.weth, stable, factory: venue.factory }
+    }
+
+    // The better of the token's volatile and stable WETH pools for selling
+    // `amount`, as (stable, WETH out); None when neither quotes. The router
+    // quotes pools that don't exist as zero.
+    pub(crate) async fn aerodrome_quote(&self, token: Address, amount: U256) -> Result<Option<(bool, U256)>> {
+        let venue = self.aerodrome_venue()?;
+        let router = IAerodromeRouter::new(venue.router, Arc::new(self.provider.clone()));
+        let calls: Vec<_> = [false, true]
+            .into_iter()
+            .map(|stable| router.get_amounts_out(amount, vec![self.aerodrome_hop(venue, token, stable)]))
+            .collect();
+        let quotes = join_all(calls.iter().map(|call| call.call())).await;
+        Ok([false, true]
+            .into_iter()
+            .zip(quotes)
+            .filter_map(|(stable, quote)| Some((stable, quote.ok()?.last().copied()?)))
+            .filter(|(_, amount_out)| !amount_out.is_zero())
+            .max_by_key(|(_, amount_out)| *amount_out))
+    }
+
+    // Calldata selling `amount` through the token's `stable` or volatile
+    // pool, or None if it doesn't encode. The router unwraps the WETH to the
+    // wallet unless proceeds stay in WETH.
+    pub(crate) fn aerodrome_sell_call(
+        &self,
+        token: Address,
+        stable: bool,
+        amount: U256,
+        amount_out_min: U256,
+        deadline: U256,
+    ) -> Result<Option<Bytes>> {
+        let venue = self.aerodrome_venue()?;
+        let router = IAerodromeRouter::new(venue.router, Arc::new(self.provider.clone()));
+        let routes = vec![self.aerodrome_hop(venue, token, stable)];
+        let wallet = self.wallet.address();
+        let call = match self.weth_proceeds {
+            Some(_) => router
+                .swap_exact_tokens_for_tokens_supporting_fee_on_transfer_tokens(amount, amount_out_min, routes, wallet, deadline)
+                .calldata(),
+            None => router
+                .swap_exact_tokens_for_eth_supporting_fee_on_transfer_tokens(amount, amount_out_min, routes, wallet, deadline)
+                .calldata(),
+        };
+        Ok(call)
+    }
+
+    // The token's deeper Aerodrome WETH pool and whether it's the stable
+    // one, looked up once
+    pub(crate) async fn aerodrome_pool(&self, token: Address) -> Result<(Address, bool)> {
+        if let Some(pool) = self.aerodrome_pools.lock().await.get(&token) {
+            return Ok(*pool);
+        }
+
+        let venue = self.aerodrome_venue()?;
+        let client = Arc::new(self.provider.clone());
+        let factory = IAerodromeFactory::new(venue.factory, client.clone());
+        let mut deepest = None;
+        for stable in [false, true] {
+            let pool = factory.get_pool(token, self.weth, stable).call().await?;
+            if pool.is_zero() {
+                continue;
+            }
+            let (reserve0, reserve1, _) = IAerodromePool::new(pool, client.clone()).get_reserves().call().await?;
+            let weth_reserve = if token < self.weth { reserve1 } else { reserve0 };
+            if deepest.is_none_or(|(_, _, deepest)| weth_reserve > deepest) {
+                deepest = Some((pool, stable, weth_reserve));
+            }
+        }
+        let Some((pool, stable, _)) = deepest else {
+            return Err(MktmkrError::Config(format!("no {:?}/WETH pool on {}", token, venue.name)));
+        };
+
+        self.aerodrome_pools.lock().await.insert(token, (pool, stable));
+        Ok((pool, stable))
+    }
+
+    // (token, WETH) reserves of the token's deeper Aerodrome pool. Volatile
+    // pools price like a V2 pair; a stable pool stands in as a
+    // constant-product pool holding its token balance at its current price.
+    pub(crate) async fn aerodrome_reserves(&self, token: Address) -> Result<(U256, U256)> {
+        let (pool, stable) = self.aerodrome_pool(token).await?;
+        let pool = IAerodromePool::new(pool, Arc::new(self.provider.clone()));
+        let (reserve0, reserve1, _) = pool.get_reserves().call().await?;
+
+        // Pools sort their tokens by address
+        let (token_reserve, weth_reserve) = if token < self.weth { (reserve0, reserve1) } else { (reserve1, reserve0) };
+        if !stable {
+            return Ok((token_reserve, weth_reserve));
+        }
+        let probe = (token_reserve / 1000).max(U256::one());
+        let amount_out = pool.get_amount_out(probe, token).call().await?;
+        Ok((token_reserve, mul_div(amount_out, token_reserve, probe)))
+    }
+}
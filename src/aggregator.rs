@@ -0,0 +1,73 @@
+use crate::abi::{i_augustus_swapper, i_one_inch_v5_router, i_one_inch_v6_router, IZeroExProxyCalls};
+use crate::decode::{RouterSwap, SwapAmounts};
+use ethers::{
+    abi::AbiDecode,
+    types::{Address, U256},
+};
+
+// Placeholder aggregators use for the native token
+const NATIVE_TOKEN: Address = Address::repeat_byte(0xee);
+
+// (chain id, aggregator router) for 1inch v5 and v6, the 0x Exchange Proxy
+// and Paraswap's Augustus v5
+pub(crate) const DEFAULT_AGGREGATORS: &[(u64, &str)] = &[
+    (1, "0x1111111254EEB25477B68fb85Ed929f73A960582"),
+    (1, "0x111111125421cA6dc452d289314280a0f8842A65"),
+    (1, "0xDef1C0ded9bec7F1a1670819833240f027b25EfF"),
+    (1, "0xDEF171Fe48CF0115B1d80b88dc8eAB59176FEe57"),
+    (8453, "0x1111111254EEB25477B68fb85Ed929f73A960582"),
+    (8453, "0x111111125421cA6dc452d289314280a0f8842A65"),
+    (8453, "0xDef1C0ded9bec7F1a1670819833240f027b25EfF"),
+    (8453, "0x59C7C832e96D2568bea6db468C1aAdcbbDa08A52"),
+    (42161, "0x1111111254EEB25477B68fb85Ed929f73A960582"),
+    (42161, "0x111111125421cA6dc452d289314280a0f8842A65"),
+    (42161, "0xDef1C0ded9bec7F1a1670819833240f027b25EfF"),
+    (42161, "0xDEF171Fe48CF0115B1d80b88dc8eAB59176FEe57"),
+];
+
+// Decodes a 1inch, 0x or Paraswap swap as a single hop from what the user
+// pays to what they receive. Native-token inputs are paid with the call's
+// value, so they decode without an input amount.
+pub fn decode_aggregator_swap(input: &[u8]) -> Option<RouterSwap> {
+    let (token_in, token_out, amount_in, amount_out_min, to, deadline) =
+        if let Ok(c) = i_one_inch_v5_router::SwapCall::decode(input) {
+            let desc = c.desc;
+            (desc.src_token, desc.dst_token, desc.amount, desc.min_return_amount, desc.dst_receiver, U256::MAX)
+        } else if let Ok(c) = i_one_inch_v6_router::SwapCall::decode(input) {
+            let desc = c.desc;
+            (desc.src_token, desc.dst_token, desc.amount, desc.min_return_amount, desc.dst_receiver, U256::MAX)
+        } else if let Ok(call) = IZeroExProxyCalls::decode(input) {
+            match call {
+                IZeroExProxyCalls::TransformERC20(c) => (
+                    c.input_token,
+                    c.output_token,
+                    c.input_token_amount,
+                    c.min_output_token_amount,
+                    Address::zero(),
+                    U256::MAX,
+                ),
+                IZeroExProxyCalls::SellToUniswap(c) => {
+                    let path = c.tokens;
+                    if path.len() < 2 {
+                        return None;
+                    }
+                    let amount_in = (path[0] != NATIVE_TOKEN).then_some(c.sell_amount);
+                    let amounts = SwapAmounts::ExactIn { amount_in, amount_out_min: c.min_buy_amount };
+                    return Some(RouterSwap { amounts, path, to: Address::zero(), deadline: U256::MAX });
+                }
+            }
+        } else if let Ok(c) = i_augustus_swapper::SimpleSwapCall::decode(input) {
+            let data = c.data;
+            (data.from_token, data.to_token, data.from_amount, data.to_amount, data.beneficiary, data.deadline)
+        } else {
+            return None;
+        };
+
+    let amount_in = (token_in != NATIVE_TOKEN).then_some(amount_in);
+    Some(RouterSwap {
+        amounts: SwapAmounts::ExactIn { amount_in, amount_out_min },
+        path: vec![token_in, token_out],
+        to,
+        deadline,
+    })
+}
@@ -0,0 +1,149 @@
+use crate::abi::IERC20;
+use crate::bot::TradingBot;
+use crate::error::Result;
+use crate::fees::{FeeMode, GasBid};
+use ethers::{
+    prelude::*,
+    types::{Address, U256},
+};
+use serde::Deserialize;
+use std::sync::Arc;
+use std::time::Duration;
+
+// How long an approval is waited for before sells go ahead without it
+const APPROVAL_TIMEOUT: Duration = Duration::from_secs(120);
+
+// How much an automatic approval grants the router
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ApprovalMode {
+    // The wallet's balance when approving, topped up as needed
+    #[default]
+    Exact,
+    // type(uint256).max, approved once
+    Infinite,
+}
+
+// Router allowance handling
+#[derive(Debug, Clone, Default)]
+pub struct Approvals {
+    // Approve the router when its allowance falls short; otherwise short
+    // sells are skipped
+    pub auto_approve: bool,
+    pub mode: ApprovalMode,
+    // Node estimate when unset
+    pub gas_limit: Option<U256>,
+    // Caps what approvals bid; approvals still go out above max_gas_price
+    pub max_fee: Option<U256>,
+    // Set every allowance back to zero when the session ends
+    pub revoke_on_exit: bool,
+}
+
+impl TradingBot {
+    // Makes sure the routers may spend every token's balance, before trading
+    pub(crate) async fn check_allowances(&self) -> Result<()> {
+        for token in self.tokens() {
+            let balance = self.inventory(token.address).await?;
+            if balance.is_zero() {
+                continue;
+            }
+            for spender in self.spenders(token.address).await {
+                self.ensure_allowance(token.address, spender, balance).await?;
+            }
+        }
+        Ok(())
+    }
+
+    // True once `spender` may spend `amount` of the token, approving it
+    // first if auto_approve is on
+    pub(crate) async fn ensure_allowance(&self, token: Address, spender: Address, amount: U256) -> Result<bool> {
+        let erc20 = IERC20::new(token, Arc::new(self.provider.clone()));
+        let allowance = erc20.allowance(self.wallet.address(), spender).call().await?;
+        if allowance >= amount {
+            return Ok(true);
+        }
+        if !self.approvals.auto_approve {
+            println!("Router allowance for {:?} is {}, short of {}; approve it or enable auto_approve", token, allowance, amount);
+            return Ok(false);
+        }
+
+        let approved = match self.approvals.mode {
+            ApprovalMode::Exact => amount.max(self.inventory(token).await?),
+            ApprovalMode::Infinite => U256::MAX,
+        };
+        self.approve(token, spender, approved).await
+    }
+
+    // Sets the routers' allowances to zero for every token they have one for
+    pub(crate) async fn revoke_allowances(&self) -> Result<()> {
+        for token in self.tokens() {
+            let erc20 = IERC20::new(token.address, Arc::new(self.provider.clone()));
+            for spender in self.spenders(token.address).await {
+                if !erc20.allowance(self.wallet.address(), spender).call().await?.is_zero() {
+                    self.approve(token.address, spender, U256::zero()).await?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // Sends approve(spender, amount) and waits for it to be mined; true if
+    // it succeeded
+    async fn approve(&self, token: Address, spender: Address, amount: U256) -> Result<bool> {
+        let erc20 = IERC20::new(token, Arc::new(self.provider.clone()));
+        let data = erc20.approve(spender, amount).calldata().unwrap_or_default();
+        self.send_approval(token, token, data, spender, amount).await
+    }
+
+    // Sends an approval of the token to `to`, the token itself or Permit2,
+    // and waits for it to be mined
+    pub(crate) async fn send_approval(&self, token: Address, to: Address, data: Bytes, spender: Address, amount: U256) -> Result<bool> {
+        let mut tx = self.approval_bid().await?.transaction(self.wallet.address(), to, data);
+        if let Some(gas_limit) = self.approvals.gas_limit {
+            tx.set_gas(gas_limit);
+        }
+
+        let nonce = self.next_nonce().await?;
+        tx.set_nonce(nonce);
+        let tx_hash = match self.sign_and_send(&mut tx).await {
+            Ok(tx_hash) => tx_hash,
+            Err(e) => {
+                self.nonces.lock().await.release(nonce);
+                return Err(e);
+            }
+        };
+        println!("Approving {} of {:?} for {:?} in {}", amount, token, spender, self.tx_link(tx_hash));
+
+        let pending = PendingTransaction::new(tx_hash, &self.provider);
+        match tokio::time::timeout(APPROVAL_TIMEOUT, pending).await {
+            Ok(Ok(Some(receipt))) if receipt.status == Some(1u64.into()) => Ok(true),
+            Ok(Ok(Some(_))) => {
+                println!("Approval of {:?} reverted", token);
+                Ok(false)
+            }
+            Ok(Ok(None)) => {
+                println!("Approval of {:?} was dropped", token);
+                Ok(false)
+            }
+            Ok(Err(e)) => Err(e.into()),
+            Err(_) => {
+                println!("Approval of {:?} not mined within {:?}", token, APPROVAL_TIMEOUT);
+                Ok(false)
+            }
+        }
+    }
+
+    // The network price, capped at approvals.max_fee; also bid by WETH
+    // unwraps, sweeps and liquidity changes
+    pub(crate) async fn approval_bid(&self) -> Result<GasBid> {
+        let cap = |fee: U256| self.approvals.max_fee.map_or(fee, |max_fee| fee.min(max_fee));
+        Ok(match self.fee_mode {
+            FeeMode::Legacy => GasBid::Legacy(Some(cap(self.provider.get_gas_price().await?))),
+            FeeMode::Eip1559 => {
+                let (max_fee, max_priority_fee) = self.provider.estimate_eip1559_fees(None).await?;
+                let max_fee = cap(max_fee);
+                GasBid::Eip1559 { max_fee, max_priority_fee: max_priority_fee.min(max_fee) }
+            }
+        })
+    }
+}
@@ -0,0 +1,97 @@
+use crate::abi::{BatchSwapStep, FundManagement, IBalancerVault, SingleSwap};
+use crate::bot::TradingBot;
+use crate::error::{MktmkrError, Result};
+use crate::math::mul_div;
+use crate::transport::Transport;
+use ethers::{
+    providers::Provider,
+    signers::Signer,
+    types::{Address, Bytes, H160, H256, U256},
+};
+use std::sync::Arc;
+
+// Balancer V2's Vault, at the same address on every chain it's deployed to
+pub const BALANCER_VAULT: Address = H160([
+    0xba, 0x12, 0x22, 0x22, 0x22, 0x22, 0x8d, 0x8b, 0xa4, 0x45, 0x95, 0x8a, 0x75, 0xa0, 0x70, 0x4d, 0x56, 0x6b, 0xf2, 0xc8,
+]);
+
+// Gas a Balancer sell is assumed to take
+pub(crate) const BALANCER_SELL_GAS: u64 = 200_000;
+// The Vault's SwapKind for exact-input swaps
+const GIVEN_IN: u8 = 0;
+
+impl TradingBot {
+    fn balancer_pool(&self, token: Address) -> Result<H256> {
+        self.token(token)
+            .and_then(|target| target.balancer_pool)
+            .ok_or_else(|| MktmkrError::Config(format!("{:?} has no Balancer pool configured", token)))
+    }
+
+    fn vault(&self) -> IBalancerVault<Provider<Transport>> {
+        IBalancerVault::new(BALANCER_VAULT, Arc::new(self.provider.clone()))
+    }
+
+    // Swaps from and to the wallet's own balances, not the Vault's
+    fn funds(&self) -> FundManagement {
+        FundManagement {
+            sender: self.wallet.address(),
+            from_internal_balance: false,
+            recipient: self.wallet.address(),
+            to_internal_balance: false,
+        }
+    }
+
+    // WETH the token's pool pays for `amount`, simulated through the Vault
+    pub(crate) async fn balancer_quote(&self, token: Address, amount: U256) -> Result<U256> {
+        let step = BatchSwapStep {
+            pool_id: self.balancer_pool(token)?.0,
+            asset_in_index: U256::zero(),
+            asset_out_index: U256::one(),
+            amount,
+            user_data: Bytes::new(),
+        };
+        let deltas = self.vault().query_batch_swap(GIVEN_IN, vec![step], vec![token, self.weth], self.funds()).call().await?;
+        // Deltas are what the Vault takes in, so its output is negative
+        let paid = deltas.get(1).copied().unwrap_or_default();
+        Ok(if paid.is_negative() { paid.unsigned_abs() } else { U256::zero() })
+    }
+
+    // Vault calldata selling `amount` into the token's pool, or None if it
+    // doesn't encode; the Vault unwraps WETH to ETH when asked for the zero
+    // address
+    pub(crate) fn balancer_sell_call(
+        &self,
+        token: Address,
+        amount: U256,
+        amount_out_min: U256,
+        deadline: U256,
+    ) -> Result<Option<Bytes>> {
+        let swap = SingleSwap {
+            pool_id: self.balancer_pool(token)?.0,
+            kind: GIVEN_IN,
+            asset_in: token,
+            asset_out: match self.weth_proceeds {
+                Some(_) => self.weth,
+                None => Address::zero(),
+            },
+            amount,
+            user_data: Bytes::new(),
+        };
+        Ok(self.vault().swap(swap, self.funds(), amount_out_min, deadline).calldata())
+    }
+
+    // (token, ETH) reserves of a constant-product pool holding the Balancer
+    // pool's token balance at its current price, for sizing and valuation
+    pub(crate) async fn balancer_reserves(&self, token: Address) -> Result<(U256, U256)> {
+        let pool = self.balancer_pool(token)?;
+        let (tokens, balances, _) = self.vault().get_pool_tokens(pool.0).call().await?;
+        let balance = tokens
+            .iter()
+            .position(|held| *held == token)
+            .and_then(|k| balances.get(k).copied())
+            .ok_or_else(|| MktmkrError::Contract(format!("Balancer pool {:?} doesn't hold {:?}", pool, token)))?;
+        let probe = (balance / 1000).max(U256::one());
+        let amount_out = self.balancer_quote(token, probe).await?;
+        Ok((balance, mul_div(amount_out, balance, probe)))
+    }
+}
@@ -0,0 +1,637 @@
+use crate::allowance::Approvals;
+use crate::builder::TradingBotBuilder;
+use crate::bundle::BundleRelay;
+use crate::chainlink::Chainlink;
+use crate::chunks::{ChunkedSell, Chunking};
+use crate::sweep::Sweep;
+use crate::routing::{AggregatorApi, Venue};
+use crate::v3::V3Venue;
+use crate::aerodrome::AerodromeVenue;
+use crate::v4::{V4Pool, V4Venue};
+use crate::volatility::{PriceMoves, VolatilityScaling};
+use crate::weth::WethProceeds;
+use crate::counterparty::Counterparties;
+use crate::curve::CurvePool;
+use crate::decode::PathMatch;
+use crate::error::Result;
+use crate::cooldown::{CooldownState, Cooldowns};
+use crate::exits::{ExitKind, PriceExit};
+use crate::jitter::Jitter;
+use crate::fees::{FeeMode, L1Fee};
+use crate::flow::NetFlow;
+use crate::health::EndpointHealth;
+use crate::intents::{CowApi, OpenIntent};
+use crate::market_data::ReferenceFeed;
+use crate::metadata::TokenMetadata;
+use crate::nonce::NonceManager;
+use crate::oracle::{Observations, TwapGate};
+use crate::paper::PaperBook;
+use crate::params::ParamsHandle;
+use crate::profile::Profile;
+use crate::queue::ExecutionQueue;
+use crate::receipts::InFlight;
+use crate::reorg::{ChainTracker, Fill};
+use crate::reserves::ReserveCache;
+use crate::replacement::PendingBuys;
+use crate::report::SessionReport;
+use crate::schedule::Schedule;
+use crate::seen::SeenCache;
+use crate::shutdown::Shutdown;
+use crate::sizing::SellSizing;
+use crate::skew::InventorySkew;
+use crate::source::MempoolSource;
+use crate::strategy::Strategy;
+use crate::target::{OnTarget, Progress, Target};
+use crate::tax::TokenTax;
+use crate::throttle::SellThrottle;
+use crate::transport::Transport;
+use crate::trigger::{AwaitingBuy, TriggerMode};
+use crate::paths::PathSearch;
+use crate::twap::TwapSchedule;
+use crate::unwind::Unwind;
+use crate::watchlist::WatchedWallet;
+use ethers::{
+    prelude::*,
+    providers::Provider,
+    types::U256,
+    utils::format_ether,
+};
+use chrono::Utc;
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::time::{Duration, Instant, SystemTime};
+use std::sync::{Arc, RwLock};
+use tokio::sync::{Mutex, Notify};
+
+// A token the bot sells into, with its own sizing and stop target
+#[derive(Debug, Clone, PartialEq)]
+pub struct TokenTarget {
+    pub address: Address,
+    pub sell_percentage: f64,
+    pub target: Target,
+    pub on_target: OnTarget,
+    // Buys spending less than this don't trigger a sell
+    pub min_buy_eth: U256,
+    // Name of the profile the execution settings below came from
+    pub profile: Option<String>,
+    // Unset uses the bot's default slippage
+    pub slippage_bps: Option<u32>,
+    // Sells quoted to return less ETH than this are skipped
+    pub min_sell_output: U256,
+    // Likewise in USD, at the Chainlink ETH/USD price
+    pub min_sell_output_usd: Option<f64>,
+    // Chainlink feed pricing the token in USD, for the report
+    pub usd_feed: Option<Address>,
+    // Its market on the reference exchange, e.g. "PEPEUSDT"
+    pub cex_symbol: Option<String>,
+    pub gas_price_multiplier: f64,
+    pub max_sell_per_tx: Option<U256>,
+    // Rolling one-hour limit; sells beyond it are queued
+    pub max_sell_per_hour: Option<U256>,
+    // Sent through the private relays instead of the public mempool
+    pub private: bool,
+    pub venue: Venue,
+    // Tokens V2 sells pass through on the way to WETH; empty sells direct,
+    // or along the best path found when the direct pair is shallow
+    pub hops: Vec<Address>,
+    // Its V4 pool, for the "v4" venue and as a "best" candidate
+    pub v4_pool: Option<V4Pool>,
+    // Its Curve pool, for the "curve" venue and as a "best" candidate
+    pub curve_pool: Option<Address>,
+    // Its Balancer V2 pool id, likewise for "balancer"
+    pub balancer_pool: Option<H256>,
+    // Price levels watched every block, selling part of the inventory at a
+    // profit or all of it at a loss
+    pub take_profit: Option<PriceExit>,
+    pub stop_loss: Option<PriceExit>,
+    // Everything is sold once the price falls this many percent from its
+    // session high
+    pub trailing_stop_percentage: Option<f64>,
+}
+
+impl TokenTarget {
+    pub fn new(address: Address, sell_percentage: f64, target: Target) -> Self {
+        Self {
+            address,
+            sell_percentage,
+            target,
+            on_target: OnTarget::Stop,
+            min_buy_eth: U256::zero(),
+            profile: None,
+            slippage_bps: None,
+            min_sell_output: U256::zero(),
+            min_sell_output_usd: None,
+            usd_feed: None,
+            cex_symbol: None,
+            gas_price_multiplier: 1.0,
+            max_sell_per_tx: None,
+            max_sell_per_hour: None,
+            private: false,
+            venue: Venue::V2,
+            hops: Vec::new(),
+            v4_pool: None,
+            curve_pool: None,
+            balancer_pool: None,
+            take_profit: None,
+            stop_loss: None,
+            trailing_stop_percentage: None,
+        }
+    }
+
+    // Takes sizing, slippage and gas settings from `profile`; the stop
+    // target is left alone
+    pub fn apply_profile(&mut self, profile: &Profile) {
+        self.profile = Some(profile.name.clone());
+        self.sell_percentage = profile.sell_percentage;
+        self.slippage_bps = profile.slippage_bps;
+        self.gas_price_multiplier = profile.gas_price_multiplier;
+        self.max_sell_per_tx = profile.max_sell_per_tx;
+    }
+}
+
+pub struct TradingBot {
+    pub(crate) provider: Provider<Transport>,
+    // Further endpoints on the same chain that sells fail over to
+    pub(crate) fallbacks: Vec<Provider<Transport>>,
+    // Private RPCs taking sells marked private, in order of preference
+    pub(crate) private_relays: Vec<Provider<Transport>>,
+    // Private sells still unmined after this are also sent publicly
+    pub(crate) public_fallback: Duration,
+    // Bundle mode: sells into pending buys go to these relays and builders
+    // right behind them; the first simulates bundles and takes MEV-Share
+    // backruns
+    pub(crate) bundle_relays: Vec<BundleRelay>,
+    // Endpoints that must accept each sell
+    pub(crate) broadcast_quorum: usize,
+    // Per-endpoint latency and errors, primary first
+    pub(crate) health: Arc<Mutex<Vec<EndpointHealth>>>,
+    // Pending-transaction feeds, the node's own first
+    pub(crate) sources: Vec<Arc<dyn MempoolSource>>,
+    // Turns what the bot sees into orders
+    pub(crate) strategy: Arc<dyn Strategy>,
+    pub(crate) wallet: LocalWallet,
+    // The primary endpoint with the wallet, for filling and signing sells
+    pub(crate) client: SignerMiddleware<Provider<Transport>, LocalWallet>,
+    pub(crate) nonces: Arc<Mutex<NonceManager>>,
+    pub(crate) params: ParamsHandle,
+    pub(crate) router: Address,
+    // The V2 router's factory, else asked of it, and pair init code hash,
+    // else pairs are looked up
+    pub(crate) factory: Option<Address>,
+    pub(crate) init_code_hash: Option<H256>,
+    // Names the V2 router in the trade log
+    pub(crate) v2_name: String,
+    // Whether the V2 router's swaps take a referrer, as Camelot's do
+    pub(crate) v2_referrer: bool,
+    // Links logged transactions to their explorer pages
+    pub(crate) explorer_url: Option<String>,
+    // Uniswap Universal Router, watched for V2 buys alongside the V2 router
+    pub(crate) universal_router: Option<Address>,
+    // Uniswap V3 (or PancakeSwap V3) router and quoter, selling tokens
+    // traded on V3 and watched for V3 buys
+    pub(crate) v3: Option<V3Venue>,
+    // Uniswap V4 contracts, selling tokens traded on V4 and watched for V4
+    // buys through their Universal Router
+    pub(crate) v4: Option<V4Venue>,
+    // Aerodrome (or Velodrome) router and pool factory, selling tokens
+    // traded there and watched for buys through its router
+    pub(crate) aerodrome: Option<AerodromeVenue>,
+    // Swap APIs quoted for tokens routed to the best venue, and the
+    // contracts their routes needed allowances for
+    pub(crate) aggregator_apis: Vec<AggregatorApi>,
+    pub(crate) aggregator_spenders: Arc<Mutex<HashSet<Address>>>,
+    // 1inch, 0x and Paraswap routers, watched for buys that end in our tokens
+    pub(crate) aggregators: Vec<Address>,
+    pub(crate) weth: Address,
+    // Stablecoin used to value proceeds for USD targets
+    pub(crate) usd_token: Option<Address>,
+    // Chainlink feeds valuing ETH and tokens in USD, when enabled
+    pub(crate) chainlink: Option<Chainlink>,
+    // Wall-clock end of the trading session
+    pub(crate) deadline: SystemTime,
+    pub(crate) fee_mode: FeeMode,
+    pub(crate) l1_fee: L1Fee,
+    // Sells expected to cost more than this in fees, L1 data included, are
+    // skipped
+    pub(crate) max_sell_fee: Option<U256>,
+    pub(crate) chain_id: u64,
+    // How often the chain makes blocks, from its preset or the config
+    pub(crate) block_time: Duration,
+    // Percentile of recent blocks' tips bid as the priority fee
+    pub(crate) priority_fee_percentile: f64,
+    // Tolerance below the quote for tokens without their own slippage_bps
+    pub(crate) slippage_bps: u32,
+    // Validity of each swap, counted from the latest block's timestamp
+    pub(crate) swap_deadline: Duration,
+    pub(crate) schedule: Schedule,
+    pub(crate) unwind: Option<Unwind>,
+    pub(crate) report: Arc<Mutex<SessionReport>>,
+    // Submitted sells not yet confirmed
+    pub(crate) in_flight: Arc<Mutex<HashMap<H256, InFlight>>>,
+    // Blocks a sell's receipt must be buried under before it is accounted
+    // for, and how long an unmined sell is tracked
+    pub(crate) confirmations: u64,
+    pub(crate) receipt_timeout: Duration,
+    // Sells unmined this many blocks after being sent are rebid fee_bump
+    // percent higher, up to max_fee_bumps times and the escalation cap;
+    // 0 never rebids
+    pub(crate) escalate_after_blocks: u64,
+    pub(crate) fee_bump_percent: u64,
+    pub(crate) max_fee_bumps: u32,
+    pub(crate) max_escalated_fee: Option<U256>,
+    // Recent heads, and the fills and included buys a reorg could undo
+    pub(crate) chain: Arc<Mutex<ChainTracker>>,
+    pub(crate) recent_fills: Arc<Mutex<HashMap<H256, Fill>>>,
+    pub(crate) recent_buys: Arc<Mutex<HashMap<H256, (H256, u64)>>>,
+    pub(crate) throttle: Arc<Mutex<SellThrottle>>,
+    // Sell orders waiting for the executor, which is woken on each new one
+    pub(crate) orders: Arc<Mutex<ExecutionQueue>>,
+    // Large sells split into randomized chunks, when configured, and the
+    // chunks still to go
+    pub(crate) chunking: Option<Chunking>,
+    pub(crate) chunked: Arc<Mutex<Vec<ChunkedSell>>>,
+    // Sells pay out WETH, unwrapped in batches, when set
+    pub(crate) weth_proceeds: Option<WethProceeds>,
+    // The USD token, when sells pay it out instead of ETH
+    pub(crate) stable: Option<Address>,
+    // CoW order book that queued, sliced and chunked sells are posted to,
+    // when set, and the orders still open there
+    pub(crate) intents: Option<CowApi>,
+    pub(crate) open_intents: Arc<Mutex<HashMap<String, OpenIntent>>>,
+    // Hop tokens searched for tokens whose direct pair is shallow
+    pub(crate) path_search: Option<PathSearch>,
+    // ETH above a float sent to a cold wallet when the session ends
+    pub(crate) sweep: Option<Sweep>,
+    // Where liquidity deposits are recorded, for fee and impermanent loss
+    // accounting
+    pub(crate) lp_state_path: PathBuf,
+    // Time-weighted sells running alongside the reactive strategy
+    pub(crate) twaps: Arc<Mutex<Vec<TwapSchedule>>>,
+    pub(crate) order_ready: Arc<Notify>,
+    // Tokens whose post-target action has run
+    pub(crate) reached: Arc<Mutex<HashSet<Address>>>,
+    // Cumulative progress at each token's last buy-back
+    pub(crate) cycle_start: Arc<Mutex<HashMap<Address, Progress>>>,
+    pub(crate) shutdown: Shutdown,
+    // Simulate sells instead of broadcasting them
+    pub(crate) dry_run: bool,
+    // Simulate each sell against the pending block before sending it
+    pub(crate) preflight: bool,
+    // Measure each token's transfer taxes at startup
+    pub(crate) tax_detection: bool,
+    // Declare the slots each sell touches in an EIP-2930 access list
+    pub(crate) access_lists: bool,
+    // How far a sell's quoted proceeds must exceed its gas cost
+    pub(crate) gas_margin_bps: u64,
+    // Sells are shrunk so their price impact stays within this
+    pub(crate) max_price_impact_bps: Option<u64>,
+    pub(crate) taxes: Arc<Mutex<HashMap<Address, TokenTax>>>,
+    pub(crate) approvals: Approvals,
+    // Virtual fills against live reserves; set in paper-trading mode
+    pub(crate) paper: Option<Arc<Mutex<PaperBook>>>,
+    pub(crate) paper_report_path: Option<PathBuf>,
+    // Pending transactions already handled
+    pub(crate) seen: Arc<Mutex<SeenCache>>,
+    pub(crate) trigger: TriggerMode,
+    pub(crate) path_match: PathMatch,
+    // Wait between a swap log's buy and the sell into it
+    pub(crate) swap_log_delay: Duration,
+    // Spot-versus-TWAP condition gating sells into buys, when configured,
+    // and the V2 price cumulatives sampled for it
+    pub(crate) twap_gate: Option<TwapGate>,
+    pub(crate) observations: Arc<Mutex<Observations>>,
+    // Skew toward a target inventory ratio, when configured, and each
+    // token's factor as of the last block
+    pub(crate) skew: Option<InventorySkew>,
+    pub(crate) skew_factors: Arc<Mutex<HashMap<Address, f64>>>,
+    // Price exits already acted on, and each token's highest pool price this
+    // session in ETH per whole token, for trailing stops
+    pub(crate) exits_fired: Arc<Mutex<HashSet<(Address, ExitKind)>>>,
+    pub(crate) price_peaks: Arc<Mutex<HashMap<Address, f64>>>,
+    // Volatility scaling of sell_percentage, when configured, and the price
+    // moves it is estimated from
+    pub(crate) volatility_scaling: Option<VolatilityScaling>,
+    pub(crate) price_moves: Arc<Mutex<PriceMoves>>,
+    // Random reaction delay and trigger skipping, when configured
+    pub(crate) jitter: Option<Jitter>,
+    // Sell sizing along a curve in the buy size, when configured
+    pub(crate) sizing: Option<SellSizing>,
+    // Per-sender and global trigger cooldowns, when configured, and the
+    // buys they hold back
+    pub(crate) cooldowns: Option<Cooldowns>,
+    pub(crate) cooldown_state: Arc<Mutex<CooldownState>>,
+    // Exchange tickers giving a reference price, when configured, and their
+    // last prices by symbol
+    pub(crate) reference: Option<ReferenceFeed>,
+    pub(crate) tickers: Arc<Mutex<HashMap<String, (f64, Instant)>>>,
+    // Rolling buy-minus-sell pressure gating sells, when configured
+    pub(crate) net_flow: Option<Arc<Mutex<NetFlow>>>,
+    // Buys bidding less than this are ignored
+    pub(crate) min_effective_gas_price: Option<U256>,
+    // Base fee of the latest block, for judging whether buys will land soon
+    pub(crate) base_fee: Arc<Mutex<Option<U256>>>,
+    // Senders whose buys are ignored, or the only ones acted on
+    pub(crate) counterparties: Counterparties,
+    // Wallets whose trades are flagged and, if configured, mirrored
+    pub(crate) watchlist: HashMap<Address, WatchedWallet>,
+    // Buys seen longer ago than this are re-checked against current reserves
+    // before a sell goes into them
+    pub(crate) buy_ttl: Duration,
+    // Buys whose sell waits for their inclusion, in confirmed-trigger mode
+    pub(crate) awaiting: Arc<Mutex<HashMap<H256, AwaitingBuy>>>,
+    // Buys acted on, by (sender, nonce), and the senders among them
+    pub(crate) pending_buys: Arc<Mutex<PendingBuys>>,
+    pub(crate) tracked_senders: Arc<RwLock<HashSet<Address>>>,
+    // Names, symbols and decimals of the configured tokens
+    pub(crate) metadata: HashMap<Address, TokenMetadata>,
+    // Token -> WETH pair
+    pub(crate) pairs: Arc<Mutex<HashMap<Address, Address>>>,
+    // V2 pair reserves followed from Sync logs while trading
+    pub(crate) reserve_cache: Arc<Mutex<ReserveCache>>,
+    // Token -> deepest V3 WETH pool
+    pub(crate) v3_pools: Arc<Mutex<HashMap<Address, Address>>>,
+    // Token -> its Curve pool's coin indices
+    pub(crate) curve_pools: Arc<Mutex<HashMap<Address, CurvePool>>>,
+    // Token -> deeper Aerodrome WETH pool, and whether it's stable
+    pub(crate) aerodrome_pools: Arc<Mutex<HashMap<Address, (Address, bool)>>>,
+}
+
+// How long a stopping bot waits for its unconfirmed sells
+pub(crate) const SETTLE_TIMEOUT: Duration = Duration::from_secs(120);
+// Times a dropped websocket is re-established, with its subscriptions,
+// before calls on it fail
+pub(crate) const WS_RECONNECTS: usize = 10;
+// Wait before resubscribing once the pending stream is lost, doubling while
+// it keeps dropping soon after each resubscribe
+const RESUBSCRIBE_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_RESUBSCRIBE_BACKOFF: Duration = Duration::from_secs(60);
+
+impl TradingBot {
+    pub fn builder() -> TradingBotBuilder {
+        TradingBotBuilder::default()
+    }
+
+    pub fn provider(&self) -> &Provider<Transport> {
+        &self.provider
+    }
+
+    pub fn router(&self) -> Address {
+        self.router
+    }
+
+    pub fn strategy_name(&self) -> &str {
+        self.strategy.name()
+    }
+
+    pub fn v2_name(&self) -> &str {
+        &self.v2_name
+    }
+
+    pub fn universal_router(&self) -> Option<Address> {
+        self.universal_router
+    }
+
+    pub fn v3_router(&self) -> Option<Address> {
+        self.v3.map(|v3| v3.router)
+    }
+
+    pub fn v3_name(&self) -> Option<&'static str> {
+        self.v3.map(|v3| v3.name)
+    }
+
+    pub fn aerodrome_router(&self) -> Option<Address> {
+        self.aerodrome.map(|aerodrome| aerodrome.router)
+    }
+
+    pub fn v4_router(&self) -> Option<Address> {
+        self.v4.map(|v4| v4.universal_router)
+    }
+
+    pub fn intent_api(&self) -> Option<&str> {
+        self.intents.as_ref().map(CowApi::url)
+    }
+
+    pub fn aggregators(&self) -> &[Address] {
+        &self.aggregators
+    }
+
+    pub fn explorer_url(&self) -> Option<&str> {
+        self.explorer_url.as_deref()
+    }
+
+    pub fn weth(&self) -> Address {
+        self.weth
+    }
+
+    pub fn chain_id(&self) -> u64 {
+        self.chain_id
+    }
+
+    pub fn usd_token(&self) -> Option<Address> {
+        self.usd_token
+    }
+
+    pub fn chainlink(&self) -> Option<Chainlink> {
+        self.chainlink
+    }
+
+    pub fn wallet_address(&self) -> Address {
+        self.wallet.address()
+    }
+
+    pub fn deadline(&self) -> SystemTime {
+        self.deadline
+    }
+
+    pub fn is_dry_run(&self) -> bool {
+        self.dry_run
+    }
+
+    pub fn is_paper_trading(&self) -> bool {
+        self.paper.is_some()
+    }
+
+    pub fn params(&self) -> &ParamsHandle {
+        &self.params
+    }
+
+    pub fn tokens(&self) -> Vec<TokenTarget> {
+        self.params.read().tokens.clone()
+    }
+
+    pub fn token(&self, address: Address) -> Option<TokenTarget> {
+        self.params.read().token(address).cloned()
+    }
+
+    pub async fn total_sold(&self, token: Address) -> U256 {
+        self.report.lock().await.token(token).tokens_sold
+    }
+
+    pub async fn report(&self) -> SessionReport {
+        let mut report = self.report.lock().await.clone();
+        report.unconfirmed = self.unconfirmed().await;
+        report.metadata = self.metadata.clone();
+        report.stable = self.stable;
+        report.chain_id = self.chain_id;
+        report.wallet = self.wallet.address();
+        for token in self.tokens() {
+            match self.inventory(token.address).await {
+                Ok(balance) => {
+                    report.balances.insert(token.address, balance);
+                }
+                Err(e) => println!("Could not read the {:?} balance for the report: {}", token.address, e),
+            }
+        }
+        if self.chainlink.is_some() {
+            match self.eth_usd().await {
+                Ok(eth_usd) => report.eth_usd = eth_usd,
+                Err(e) => println!("Could not price ETH in USD for the report: {}", e),
+            }
+            for token in self.tokens() {
+                match self.token_usd(token.address).await {
+                    Ok(Some(usd)) => {
+                        report.token_usd.insert(token.address, usd);
+                    }
+                    Ok(None) => {}
+                    Err(e) => println!("Could not price {:?} in USD for the report: {}", token.address, e),
+                }
+            }
+        }
+        report
+    }
+
+    // Checks router allowances when trading live and measures transfer taxes
+    pub(crate) async fn prepare(&self) -> Result<()> {
+        if !self.dry_run && self.paper.is_none() {
+            self.check_allowances().await?;
+        }
+        if self.tax_detection {
+            self.detect_taxes().await?;
+        }
+        Ok(())
+    }
+
+    // Trades until expiry or shutdown, unwinds tokens that missed their
+    // target if configured, then waits for in-flight sells and prints the
+    // session summary
+    pub async fn run(&self) -> Result<()> {
+        let live = !self.dry_run && self.paper.is_none();
+        self.prepare().await?;
+        let mut result = self.trade_sessions().await;
+        if result.is_ok() && !self.shutdown.is_triggered() && SystemTime::now() >= self.deadline {
+            result = self.unwind_remaining().await;
+        }
+        self.settle_in_flight(SETTLE_TIMEOUT).await?;
+        self.unwrap_weth(true).await?;
+        self.sweep_proceeds().await?;
+        if live && self.approvals.revoke_on_exit {
+            self.revoke_allowances().await?;
+        }
+
+        let throttle = self.throttle.lock().await;
+        for token in self.tokens() {
+            let queued = throttle.queued(token.address);
+            if !queued.is_zero() {
+                println!("Dropping {} still queued behind sell limits", self.format_amount(token.address, queued));
+            }
+        }
+        drop(throttle);
+        for schedule in self.twaps.lock().await.drain(..) {
+            println!(
+                "TWAP of {} stopped with {} slices left",
+                self.format_amount(schedule.twap.token, schedule.twap.amount), schedule.slices_left
+            );
+        }
+        for sell in self.chunked.lock().await.drain(..) {
+            println!("Dropping {} of unsold chunks", self.format_amount(sell.token, sell.remaining()));
+        }
+        for order in self.orders.lock().await.clear() {
+            println!("Dropping unexecuted sell of {}", self.format_amount(order.token, order.desired()));
+        }
+
+        if !self.shutdown.is_triggered() {
+            for token in self.tokens() {
+                if !self.target_reached(token.address).await? {
+                    let progress = self.progress(token.address).await;
+                    println!(
+                        "Time expired before {:?} reached {}: {} sold for {} ETH",
+                        token.address, token.target, progress.tokens_sold, format_ether(progress.eth_received)
+                    );
+                }
+            }
+        }
+        print!("{}", self.report().await);
+        for endpoint in self.health().await {
+            println!("Endpoint {}", endpoint);
+        }
+
+        if let Some(book) = self.paper_report().await? {
+            print!("{}", book);
+            if let Some(path) = &self.paper_report_path {
+                book.write_json(path)?;
+                println!("Paper trading report written to {}", path.display());
+            }
+        }
+
+        result
+    }
+
+    // Monitors the mempool while a trading window is open and sleeps, with
+    // no subscription held, while it is closed
+    async fn trade_sessions(&self) -> Result<()> {
+        let mut shutdown = self.shutdown.clone();
+        let mut backoff = RESUBSCRIBE_BACKOFF;
+
+        loop {
+            if SystemTime::now() >= self.deadline || shutdown.is_triggered() || self.targets_done().await {
+                return Ok(());
+            }
+
+            let now = Utc::now();
+            if self.schedule.is_open(now) {
+                let close = self.schedule
+                    .current_close(now)
+                    .map(SystemTime::from)
+                    .unwrap_or(self.deadline)
+                    .min(self.deadline);
+                let started = Instant::now();
+                let result = self.monitor_mempool(close).await;
+                if SystemTime::now() >= close || shutdown.is_triggered() || self.targets_done().await {
+                    result?;
+                    continue;
+                }
+
+                // Still inside the window: the subscription or its connection
+                // dropped. Resubscribing on the same bot keeps queued sells,
+                // tracked buys and progress.
+                match result {
+                    Err(e) if !e.is_transient() => return Err(e),
+                    Err(e) => println!("Lost pending transactions: {}", e),
+                    Ok(()) => println!("Pending-transaction stream ended"),
+                }
+                if started.elapsed() >= MAX_RESUBSCRIBE_BACKOFF {
+                    backoff = RESUBSCRIBE_BACKOFF;
+                }
+                println!("Resubscribing in {:?}", backoff);
+                tokio::select! {
+                    _ = tokio::time::sleep(backoff) => {}
+                    _ = shutdown.wait() => return Ok(()),
+                }
+                backoff = (backoff * 2).min(MAX_RESUBSCRIBE_BACKOFF);
+                continue;
+            }
+
+            let Some(open) = self.schedule.next_open(now) else {
+                println!("No upcoming trading window before the session deadline");
+                return Ok(());
+            };
+            let open = SystemTime::from(open).min(self.deadline);
+            println!("Outside trading windows; sleeping until {}", humantime::format_rfc3339_seconds(open));
+
+            tokio::select! {
+                _ = tokio::time::sleep(open.duration_since(SystemTime::now()).unwrap_or_default()) => {}
+                _ = shutdown.wait() => return Ok(()),
+            }
+        }
+    }
+}
@@ -0,0 +1,1271 @@
+use crate::abi::IERC20;
+use crate::allowance::Approvals;
+use crate::aggregator::DEFAULT_AGGREGATORS;
+use crate::bot::{TokenTarget, TradingBot};
+use crate::chains::{chain_preset, ChainPreset};
+use crate::chainlink::{Chainlink, PriceFeeds, DEFAULT_ETH_USD_FEEDS};
+use crate::bundle::BundleRelay;
+use crate::chunks::Chunking;
+use crate::exits::{PriceExit, PriceLevel};
+use crate::sweep::Sweep;
+use crate::routing::{AggregatorApi, Venue};
+use crate::v3::{V3Venue, FEE_TIERS, PANCAKE_FEE_TIERS};
+use crate::aerodrome::{aerodrome_preset, AerodromeVenue};
+use crate::v4::V4Venue;
+use crate::volatility::{PriceMoves, VolatilityScaling};
+use crate::weth::WethProceeds;
+use crate::cooldown::{CooldownState, Cooldowns};
+use crate::counterparty::Counterparties;
+use crate::forks::{takes_referrer, v2_preset};
+use crate::decode::PathMatch;
+use crate::error::{MktmkrError, Result};
+use crate::fees::{FeeMode, L1Fee};
+use crate::flow::NetFlow;
+use crate::health::EndpointHealth;
+use crate::jitter::Jitter;
+use crate::intents::{CowApi, IntentOrders, DEFAULT_COW_APIS};
+use crate::math::percent_to_bps;
+use crate::market_data::ReferenceFeed;
+use crate::mempool::{watched_contracts, PendingTxMode};
+use crate::metadata::TokenMetadata;
+use crate::nonce::NonceManager;
+use crate::oracle::{Observations, TwapGate};
+use crate::paper::PaperBook;
+use crate::params::{ParamsHandle, StrategyParams};
+use crate::paths::PathSearch;
+use crate::profile::Profile;
+use crate::queue::{BacklogPolicy, ExecutionQueue};
+use crate::reorg::ChainTracker;
+use crate::reserves::ReserveCache;
+use crate::replacement::PendingBuys;
+use crate::report::SessionReport;
+use crate::schedule::Schedule;
+use crate::seen::SeenCache;
+use crate::shutdown::Shutdown;
+use crate::sizing::SellSizing;
+use crate::skew::InventorySkew;
+use crate::source::{MempoolSource, NodeSource};
+use crate::strategy::{SellIntoBuys, Strategy};
+use crate::target::Target;
+use crate::throttle::SellThrottle;
+use crate::transport::connect;
+use crate::trigger::TriggerMode;
+use crate::twap::{Twap, TwapSchedule};
+use crate::unwind::Unwind;
+use crate::watchlist::WatchedWallet;
+use ethers::{
+    prelude::*,
+    types::{Address, H256, U256},
+};
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use tokio::sync::{Mutex, Notify};
+
+// Block time assumed on chains outside the registry
+const DEFAULT_BLOCK_TIME: Duration = Duration::from_secs(12);
+
+// (chain id, Uniswap Universal Router)
+const DEFAULT_UNIVERSAL_ROUTERS: &[(u64, &str)] = &[
+    (1, "0x3fC91A3afd70395Cd496C647d5a6CC9D4B2b7FAD"),
+    (8453, "0x3fC91A3afd70395Cd496C647d5a6CC9D4B2b7FAD"),
+    (42161, "0x5E325eDA8064b456f4781070C0738d849c824258"),
+    (11155111, "0x3fC91A3afd70395Cd496C647d5a6CC9D4B2b7FAD"),
+];
+
+// (chain id, name, SwapRouter02 or SmartRouter, QuoterV2, fee tiers)
+const DEFAULT_V3_VENUES: &[(u64, &str, &str, &str, &[u32])] = &[
+    (1, "Uniswap V3", "0x68b3465833fb72A70ecDF485E0e4C7bD8665Fc45", "0x61fFE014bA17989E743c5F6cB21bF9697530B21e", FEE_TIERS),
+    (56, "PancakeSwap V3", "0x13f4EA83D0bd40E75C8222255bc855a974568Dd4", "0xB048Bbc1Ee6b733FFfCFb9e9CeF7375518e25997", PANCAKE_FEE_TIERS),
+    (8453, "Uniswap V3", "0x2626664c2603336E57B271c5C0b26F421741e481", "0x3d4e44Eb1374240CE5F1B871ab261CD16335B76a", FEE_TIERS),
+    (42161, "Uniswap V3", "0x68b3465833fb72A70ecDF485E0e4C7bD8665Fc45", "0x61fFE014bA17989E743c5F6cB21bF9697530B21e", FEE_TIERS),
+    (11155111, "Uniswap V3", "0x3bFA4769FB09eefC5a80d6E87c3B9C650f7Ae48E", "0xEd1f6473345F45b75F8179591dd5bA1888cf2FB3", FEE_TIERS),
+];
+
+// (chain id, V4-capable Universal Router, V4Quoter, StateView, PoolManager)
+const DEFAULT_V4_VENUES: &[(u64, &str, &str, &str, &str)] = &[
+    (
+        1,
+        "0x66a9893cC07D91D95644AEDD05D03f95e1dBA8Af", "0x52F0E24D1c21C8A0cB1e5a5dD6198556BD9E1203",
+        "0x7fFE42C4a5DEeA5b0feC41C94C136Cf115597227", "0x000000000004444c5dc75cB358380D2e3dE08A90",
+    ),
+    (
+        8453,
+        "0x6fF5693b99212Da76ad316178A184AB56D299b43", "0x0d5e0F971ED27FBfF6c2837bf31316121532048D",
+        "0xA3c0c9b65baD0b08107Aa264b0f3dB444b867A71", "0x498581fF718922c3f8e6A244956aF099B2652b2b",
+    ),
+    (
+        42161,
+        "0xA51afAFe0263b40EdaEf0Df8781eA9aa03E381a3", "0x3972C00f7ed4885e145823eb7C655375d275A1C5",
+        "0x76Fd297e2D437cd7f76d50F01AfE6160f86e9990", "0x360E68faCcca8cA495c1B759Fd9EEe466db9FB32",
+    ),
+    (
+        11155111,
+        "0x3A9D48AB9751398BbFa63ad67599Bb04e4BdF98b", "0x61B3f2011A92d183C7dbaDBdA940a7555Ccf9227",
+        "0xE1Dd9c3fA50EDB962E442f60DfBc432e24537E4C", "0xE03A1074c86CFeDd5C142C4F04F1a1536e203543",
+    ),
+];
+
+// (chain id, USDC, or USDT on BSC) for valuing proceeds of USD targets
+const DEFAULT_USD_TOKENS: &[(u64, &str)] = &[
+    (1, "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48"),
+    (10, "0x0b2C639c533813f4Aa9D7837CAf62653d097Ff85"),
+    (56, "0x55d398326f99059fF775485246999027B3197955"),
+    (8453, "0x833589fCD6eDb6E08f4c7C32D4f71b54bdA02913"),
+    (42161, "0xaf88d065e77c8cC2239327C5EDb3A432268e5831"),
+    (11155111, "0x1c7D4B196Cb0C7B01d743Fbc6116a902379C7238"),
+];
+
+#[derive(Clone)]
+pub struct TradingBotBuilder {
+    ws_url: Option<String>,
+    chain_id: Option<u64>,
+    block_time: Option<Duration>,
+    explorer_url: Option<String>,
+    fallback_urls: Vec<String>,
+    private_rpc_urls: Vec<String>,
+    public_fallback: Duration,
+    bundle_relays: Vec<BundleRelay>,
+    chunking: Option<Chunking>,
+    weth_proceeds: Option<WethProceeds>,
+    stable_proceeds: bool,
+    path_search: Option<PathSearch>,
+    intents: Option<IntentOrders>,
+    sweep: Option<Sweep>,
+    lp_state_path: PathBuf,
+    twaps: Vec<Twap>,
+    broadcast_quorum: usize,
+    pending_txs: PendingTxMode,
+    fetch_concurrency: usize,
+    txpool_poll_interval: Duration,
+    filter_recipients: bool,
+    sources: Vec<Arc<dyn MempoolSource>>,
+    strategy: Arc<dyn Strategy>,
+    wallet: Option<LocalWallet>,
+    tokens: Vec<Address>,
+    targets: Vec<TokenTarget>,
+    v2_fork: Option<String>,
+    router: Option<Address>,
+    factory: Option<Address>,
+    init_code_hash: Option<H256>,
+    universal_router: Option<Address>,
+    v3: Option<V3Venue>,
+    v4: Option<V4Venue>,
+    aerodrome: Option<AerodromeVenue>,
+    aggregator_apis: Vec<AggregatorApi>,
+    aggregators: Option<Vec<Address>>,
+    weth: Option<Address>,
+    usd_token: Option<Address>,
+    chainlink: Option<PriceFeeds>,
+    sell_percentage: f64,
+    target: Target,
+    profile: Option<Profile>,
+    max_gas_price: Option<U256>,
+    fee_mode: Option<FeeMode>,
+    l1_fee: Option<L1Fee>,
+    max_sell_fee: Option<U256>,
+    priority_fee_percentile: f64,
+    params: Option<ParamsHandle>,
+    deadline: SystemTime,
+    slippage_bps: u32,
+    swap_deadline: Duration,
+    schedule: Schedule,
+    unwind: Option<Unwind>,
+    trigger: TriggerMode,
+    path_match: PathMatch,
+    swap_log_delay: Duration,
+    buy_ttl: Duration,
+    watchlist: HashMap<Address, WatchedWallet>,
+    counterparties: Counterparties,
+    net_flow: Option<NetFlow>,
+    twap_gate: Option<TwapGate>,
+    skew: Option<InventorySkew>,
+    volatility_scaling: Option<VolatilityScaling>,
+    jitter: Option<Jitter>,
+    sizing: Option<SellSizing>,
+    cooldowns: Option<Cooldowns>,
+    reference: Option<ReferenceFeed>,
+    min_effective_gas_price: Option<U256>,
+    confirmations: u64,
+    receipt_timeout: Duration,
+    escalate_after_blocks: u64,
+    fee_bump_percent: u64,
+    max_fee_bumps: u32,
+    max_escalated_fee: Option<U256>,
+    queue_depth: usize,
+    coalesce_sells: bool,
+    backlog_policy: BacklogPolicy,
+    shutdown: Shutdown,
+    dry_run: bool,
+    preflight: bool,
+    tax_detection: bool,
+    access_lists: bool,
+    gas_margin_percent: f64,
+    max_price_impact_bps: Option<u64>,
+    approvals: Approvals,
+    paper: Option<PaperBook>,
+    paper_report_path: Option<PathBuf>,
+}
+
+impl Default for TradingBotBuilder {
+    fn default() -> Self {
+        Self {
+            ws_url: None,
+            chain_id: None,
+            block_time: None,
+            explorer_url: None,
+            fallback_urls: Vec::new(),
+            private_rpc_urls: Vec::new(),
+            public_fallback: Duration::from_secs(60),
+            bundle_relays: Vec::new(),
+            chunking: None,
+            weth_proceeds: None,
+            stable_proceeds: false,
+            path_search: None,
+            intents: None,
+            sweep: None,
+            lp_state_path: PathBuf::from("mktmkr-lp.json"),
+            twaps: Vec::new(),
+            broadcast_quorum: 1,
+            pending_txs: PendingTxMode::default(),
+            fetch_concurrency: 16,
+            txpool_poll_interval: Duration::from_secs(1),
+            filter_recipients: true,
+            sources: Vec::new(),
+            strategy: Arc::new(SellIntoBuys),
+            wallet: None,
+            tokens: Vec::new(),
+            targets: Vec::new(),
+            v2_fork: None,
+            router: None,
+            factory: None,
+            init_code_hash: None,
+            universal_router: None,
+            v3: None,
+            aerodrome: None,
+            v4: None,
+            aggregator_apis: Vec::new(),
+            aggregators: None,
+            weth: None,
+            usd_token: None,
+            chainlink: None,
+            sell_percentage: 10.0,
+            target: Target::EthReceived(U256::exp10(20)), // 100 ETH
+            profile: None,
+            max_gas_price: None,
+            fee_mode: None,
+            l1_fee: None,
+            max_sell_fee: None,
+            priority_fee_percentile: 50.0,
+            params: None,
+            deadline: SystemTime::now() + Duration::from_secs(3600),
+            slippage_bps: 100,
+            swap_deadline: Duration::from_secs(300),
+            schedule: Schedule::default(),
+            unwind: None,
+            trigger: TriggerMode::default(),
+            path_match: PathMatch::default(),
+            swap_log_delay: Duration::ZERO,
+            buy_ttl: Duration::from_secs(120),
+            watchlist: HashMap::new(),
+            counterparties: Counterparties::default(),
+            confirmations: 1,
+            receipt_timeout: Duration::from_secs(300),
+            escalate_after_blocks: 3,
+            fee_bump_percent: 15,
+            max_fee_bumps: 5,
+            max_escalated_fee: None,
+            queue_depth: 32,
+            coalesce_sells: true,
+            backlog_policy: BacklogPolicy::default(),
+            net_flow: None,
+            twap_gate: None,
+            skew: None,
+            volatility_scaling: None,
+            jitter: None,
+            sizing: None,
+            cooldowns: None,
+            reference: None,
+            min_effective_gas_price: None,
+            shutdown: Shutdown::default(),
+            dry_run: false,
+            preflight: true,
+            tax_detection: true,
+            access_lists: false,
+            gas_margin_percent: 0.0,
+            max_price_impact_bps: None,
+            approvals: Approvals::default(),
+            paper: None,
+            paper_report_path: None,
+        }
+    }
+}
+
+impl TradingBotBuilder {
+    pub fn ws_url(mut self, ws_url: impl Into<String>) -> Self {
+        self.ws_url = Some(ws_url.into());
+        self
+    }
+
+    // The chain the endpoints must be on; building fails on any other
+    pub fn chain_id(mut self, chain_id: u64) -> Self {
+        self.chain_id = Some(chain_id);
+        self
+    }
+
+    // Overrides the chain's preset block time, which paces receipt polling
+    pub fn block_time(mut self, block_time: Duration) -> Self {
+        self.block_time = Some(block_time);
+        self
+    }
+
+    // Overrides the chain's preset block explorer, linked from the log
+    pub fn explorer_url(mut self, explorer_url: impl Into<String>) -> Self {
+        self.explorer_url = Some(explorer_url.into());
+        self
+    }
+
+    // Another endpoint on the same chain: its pending transactions are
+    // merged with the primary's, and sells fail over to it
+    pub fn fallback_url(mut self, ws_url: impl Into<String>) -> Self {
+        self.fallback_urls.push(ws_url.into());
+        self
+    }
+
+    // A private RPC (Flashbots Protect, MEV Blocker) for sells of tokens
+    // marked private, tried in the order added
+    pub fn private_rpc_url(mut self, url: impl Into<String>) -> Self {
+        self.private_rpc_urls.push(url.into());
+        self
+    }
+
+    // How long a private sell may go unmined before it's also sent publicly
+    pub fn public_fallback(mut self, public_fallback: Duration) -> Self {
+        self.public_fallback = public_fallback;
+        self
+    }
+
+    // Bundle mode: each sell into a pending buy is simulated with
+    // eth_callBundle on the first relay added and sent with eth_sendBundle
+    // to every relay, right behind the buy, for the next block
+    pub fn bundle_relay(mut self, relay: BundleRelay) -> Self {
+        self.bundle_relays.push(relay);
+        self
+    }
+
+    // Splits sells worth at least chunking.min_sell into randomized chunks
+    // sold over several blocks, to spread their price impact
+    pub fn chunking(mut self, chunking: Chunking) -> Self {
+        self.chunking = Some(chunking);
+        self
+    }
+
+    // Sells of tokens without their own path whose direct WETH pair holds
+    // too little search the paths through these hop tokens for the best
+    pub fn path_search(mut self, search: PathSearch) -> Self {
+        self.path_search = Some(search);
+        self
+    }
+
+    // Queued, sliced and chunked sells are posted as CoW Protocol orders
+    // rather than swapped; sells into buys still swap
+    pub fn intents(mut self, intents: IntentOrders) -> Self {
+        self.intents = Some(intents);
+        self
+    }
+
+    // Sells pay out WETH instead of ETH, saving the router's unwrap on each;
+    // the wallet's WETH is unwrapped once it reaches the threshold, and at
+    // the end of the session
+    pub fn weth_proceeds(mut self, proceeds: WethProceeds) -> Self {
+        self.weth_proceeds = Some(proceeds);
+        self
+    }
+
+    // Sells pay out the USD token, through its WETH pair, instead of ETH;
+    // USD targets and the report then count the stablecoin received
+    pub fn stable_proceeds(mut self, enabled: bool) -> Self {
+        self.stable_proceeds = enabled;
+        self
+    }
+
+    // Once in-flight sells settle at the end of the session, sends the
+    // wallet's ETH above sweep.float to sweep.to
+    pub fn sweep(mut self, sweep: Sweep) -> Self {
+        self.sweep = Some(sweep);
+        self
+    }
+
+    // Where `lp add` and `lp remove` record deposits
+    pub fn lp_state_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.lp_state_path = path.into();
+        self
+    }
+
+    // Sells a fixed amount of a configured token evenly over a window
+    // alongside the reactive strategy, whatever buys come in
+    pub fn twap(mut self, twap: Twap) -> Self {
+        self.twaps.push(twap);
+        self
+    }
+
+    // How many healthy endpoints each sell is sent through
+    pub fn broadcast_quorum(mut self, quorum: usize) -> Self {
+        self.broadcast_quorum = quorum;
+        self
+    }
+
+    // How the provider streams pending transactions; full bodies save a
+    // lookup per transaction but aren't supported everywhere
+    pub fn pending_txs(mut self, mode: PendingTxMode) -> Self {
+        self.pending_txs = mode;
+        self
+    }
+
+    // Hash lookups in flight at once in the default pending-tx mode
+    pub fn fetch_concurrency(mut self, fetch_concurrency: usize) -> Self {
+        self.fetch_concurrency = fetch_concurrency;
+        self
+    }
+
+    // How often the txpool is polled in txpool pending-tx mode, and HTTP
+    // endpoints are polled for heads, logs and pending hashes
+    pub fn txpool_poll_interval(mut self, txpool_poll_interval: Duration) -> Self {
+        self.txpool_poll_interval = txpool_poll_interval;
+        self
+    }
+
+    // Whether providers that support it filter pending transactions by
+    // recipient before streaming them
+    pub fn filter_recipients(mut self, filter_recipients: bool) -> Self {
+        self.filter_recipients = filter_recipients;
+        self
+    }
+
+    // Adds a pending-transaction feed alongside the node's own
+    pub fn mempool_source(mut self, source: Arc<dyn MempoolSource>) -> Self {
+        self.sources.push(source);
+        self
+    }
+
+    // What the bot trades on; by default it sells into buys
+    pub fn strategy(mut self, strategy: Arc<dyn Strategy>) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    pub fn wallet(mut self, wallet: LocalWallet) -> Self {
+        self.wallet = Some(wallet);
+        self
+    }
+
+    // Adds a token sized with the builder-wide sell percentage and target
+    pub fn token(mut self, token: Address) -> Self {
+        self.tokens.push(token);
+        self
+    }
+
+    // Adds a token with its own sell percentage and target
+    pub fn token_target(mut self, target: TokenTarget) -> Self {
+        self.targets.push(target);
+        self
+    }
+
+    // The V2 deployment sold on, by preset name ("uniswap", "sushiswap",
+    // "pancakeswap"); defaults to the chain's, Uniswap's off the registry
+    pub fn v2_fork(mut self, preset: impl Into<String>) -> Self {
+        self.v2_fork = Some(preset.into());
+        self
+    }
+
+    // Overrides the preset's V2 router
+    pub fn router(mut self, router: Address) -> Self {
+        self.router = Some(router);
+        self
+    }
+
+    // Overrides the preset's V2 factory; otherwise asked of the router
+    pub fn factory(mut self, factory: Address) -> Self {
+        self.factory = Some(factory);
+        self
+    }
+
+    // Lets pair addresses be derived from the factory instead of looked up
+    pub fn init_code_hash(mut self, init_code_hash: H256) -> Self {
+        self.init_code_hash = Some(init_code_hash);
+        self
+    }
+
+    // Overrides the chain's default Universal Router
+    pub fn universal_router(mut self, universal_router: Address) -> Self {
+        self.universal_router = Some(universal_router);
+        self
+    }
+
+    // Overrides the chain's default V3 router and quoter
+    pub fn v3(mut self, v3: V3Venue) -> Self {
+        self.v3 = Some(v3);
+        self
+    }
+
+    // Overrides the chain's default Uniswap V4 contracts
+    pub fn v4(mut self, v4: V4Venue) -> Self {
+        self.v4 = Some(v4);
+        self
+    }
+
+    // Overrides the chain's default Aerodrome router and pool factory
+    pub fn aerodrome(mut self, aerodrome: AerodromeVenue) -> Self {
+        self.aerodrome = Some(aerodrome);
+        self
+    }
+
+    // Quotes sells of tokens on the "best" venue through an aggregator's
+    // swap API too, alongside the Uniswap pools
+    pub fn aggregator_api(mut self, api: AggregatorApi) -> Self {
+        self.aggregator_apis.push(api);
+        self
+    }
+
+    // Overrides the chain's default aggregator routers; an empty list stops
+    // watching aggregators
+    pub fn aggregators(mut self, aggregators: Vec<Address>) -> Self {
+        self.aggregators = Some(aggregators);
+        self
+    }
+
+    // Overrides the chain's default wrapped native token
+    pub fn weth(mut self, weth: Address) -> Self {
+        self.weth = Some(weth);
+        self
+    }
+
+    // Overrides the chain's default stablecoin for USD targets
+    pub fn usd_token(mut self, usd_token: Address) -> Self {
+        self.usd_token = Some(usd_token);
+        self
+    }
+
+    // Values USD targets, USD sell floors and the report with Chainlink
+    // feeds instead of quoting through the stablecoin's pair
+    pub fn chainlink(mut self, feeds: PriceFeeds) -> Self {
+        self.chainlink = Some(feeds);
+        self
+    }
+
+    // Default sell percentage for tokens added with `token`
+    pub fn sell_percentage(mut self, sell_percentage: f64) -> Self {
+        self.sell_percentage = sell_percentage;
+        self
+    }
+
+    // Default target for tokens added with `token`
+    pub fn target(mut self, target: Target) -> Self {
+        self.target = target;
+        self
+    }
+
+    pub fn target_eth(self, target_eth: U256) -> Self {
+        self.target(Target::EthReceived(target_eth))
+    }
+
+    // Profile for tokens added with `token`; replaces `sell_percentage`
+    pub fn profile(mut self, profile: Profile) -> Self {
+        self.profile = Some(profile);
+        self
+    }
+
+    pub fn max_gas_price(mut self, max_gas_price: U256) -> Self {
+        self.max_gas_price = Some(max_gas_price);
+        self
+    }
+
+    // The chain's gas style by default; type-2 transactions, or legacy gas
+    // pricing for chains without EIP-1559
+    pub fn fee_mode(mut self, fee_mode: FeeMode) -> Self {
+        self.fee_mode = Some(fee_mode);
+        self
+    }
+
+    // Overrides the chain's L1 data fee model, for rollups off the registry
+    pub fn l1_fee(mut self, l1_fee: L1Fee) -> Self {
+        self.l1_fee = Some(l1_fee);
+        self
+    }
+
+    // Skips sells expected to cost more than this in fees, L1 data included
+    pub fn max_sell_fee(mut self, max_sell_fee: U256) -> Self {
+        self.max_sell_fee = Some(max_sell_fee);
+        self
+    }
+
+    // Percentile of recent blocks' tips to bid as the priority fee
+    pub fn priority_fee_percentile(mut self, percentile: f64) -> Self {
+        self.priority_fee_percentile = percentile;
+        self
+    }
+
+    // Blocks deep a sell's receipt must be before it counts; 1 is the block
+    // it was mined in
+    pub fn confirmations(mut self, confirmations: u64) -> Self {
+        self.confirmations = confirmations;
+        self
+    }
+
+    // How long an unmined sell is waited for before it is no longer tracked
+    pub fn receipt_timeout(mut self, timeout: Duration) -> Self {
+        self.receipt_timeout = timeout;
+        self
+    }
+
+    // Blocks a sell may sit unmined before it is rebid; 0 never rebids
+    pub fn escalate_after_blocks(mut self, blocks: u64) -> Self {
+        self.escalate_after_blocks = blocks;
+        self
+    }
+
+    // Raise per rebid, at least the 10% nodes require of a replacement
+    pub fn fee_bump_percent(mut self, percent: u64) -> Self {
+        self.fee_bump_percent = percent;
+        self
+    }
+
+    pub fn max_fee_bumps(mut self, bumps: u32) -> Self {
+        self.max_fee_bumps = bumps;
+        self
+    }
+
+    // Highest fee a rebid may offer; max_gas_price when unset
+    pub fn max_escalated_fee(mut self, max_fee: U256) -> Self {
+        self.max_escalated_fee = Some(max_fee);
+        self
+    }
+
+    // Most sell orders waiting for the executor at once
+    pub fn queue_depth(mut self, depth: usize) -> Self {
+        self.queue_depth = depth;
+        self
+    }
+
+    // Folds triggers for a token into its waiting order, so a burst of buys
+    // becomes one larger sell
+    pub fn coalesce_sells(mut self, coalesce: bool) -> Self {
+        self.coalesce_sells = coalesce;
+        self
+    }
+
+    // What happens to new orders once queue_depth are waiting
+    pub fn backlog_policy(mut self, policy: BacklogPolicy) -> Self {
+        self.backlog_policy = policy;
+        self
+    }
+
+    // Uses a shared parameter handle instead of the token and gas settings
+    // above, so the caller can retune the bot while it runs
+    pub fn params(mut self, params: ParamsHandle) -> Self {
+        self.params = Some(params);
+        self
+    }
+
+    // Ends the session `expiry` from now
+    pub fn expiry(mut self, expiry: Duration) -> Self {
+        self.deadline = SystemTime::now() + expiry;
+        self
+    }
+
+    // Ends the session at a fixed time, which also holds across restarts
+    pub fn deadline(mut self, deadline: SystemTime) -> Self {
+        self.deadline = deadline;
+        self
+    }
+
+    // Tolerance below the quote for tokens whose profile sets none; every
+    // sell's amountOutMin comes from one or the other
+    pub fn slippage_bps(mut self, slippage_bps: u32) -> Self {
+        self.slippage_bps = slippage_bps;
+        self
+    }
+
+    // How long each submitted swap stays valid on-chain
+    pub fn swap_deadline(mut self, swap_deadline: Duration) -> Self {
+        self.swap_deadline = swap_deadline;
+        self
+    }
+
+    // Restricts trading to the schedule's windows
+    pub fn schedule(mut self, schedule: Schedule) -> Self {
+        self.schedule = schedule;
+        self
+    }
+
+    // Whether buys trigger sells while pending or once included
+    pub fn trigger(mut self, trigger: TriggerMode) -> Self {
+        self.trigger = trigger;
+        self
+    }
+
+    // How long to wait after a buy seen in swap logs before selling into it
+    pub fn swap_log_delay(mut self, swap_log_delay: Duration) -> Self {
+        self.swap_log_delay = swap_log_delay;
+        self
+    }
+
+    // Age past which a buy awaiting inclusion is re-checked against current
+    // reserves before selling into it
+    pub fn buy_ttl(mut self, buy_ttl: Duration) -> Self {
+        self.buy_ttl = buy_ttl;
+        self
+    }
+
+    // Flags a wallet's trades in our tokens, and mirrors its sells if
+    // configured to
+    pub fn watch(mut self, wallet: WatchedWallet) -> Self {
+        self.watchlist.insert(wallet.address, wallet);
+        self
+    }
+
+    // Senders whose buys are ignored, or the only ones sold into
+    pub fn counterparties(mut self, counterparties: Counterparties) -> Self {
+        self.counterparties = counterparties;
+        self
+    }
+
+    // Whether buys must end in our token or may pass through it
+    pub fn path_match(mut self, path_match: PathMatch) -> Self {
+        self.path_match = path_match;
+        self
+    }
+
+    // Only sells into buys while net buy pressure over the flow's window
+    // clears its threshold
+    pub fn net_flow(mut self, net_flow: NetFlow) -> Self {
+        self.net_flow = Some(net_flow);
+        self
+    }
+
+    // Buys only trigger sells while spot is on the gate's side of the
+    // token's TWAP
+    pub fn twap_gate(mut self, gate: TwapGate) -> Self {
+        self.twap_gate = Some(gate);
+        self
+    }
+
+    // Sizes sells and buys, and their trigger thresholds, by how far
+    // holdings are from the skew's target ratio
+    pub fn inventory_skew(mut self, skew: InventorySkew) -> Self {
+        self.skew = Some(skew);
+        self
+    }
+
+    // Sells a larger fraction of each buy while the pool is volatile and a
+    // smaller one while it's quiet
+    pub fn volatility_scaling(mut self, scaling: VolatilityScaling) -> Self {
+        self.volatility_scaling = Some(scaling);
+        self
+    }
+
+    // Delays each order by a random amount and ignores a share of triggers,
+    // so sandwich bots can't time our sells
+    pub fn jitter(mut self, jitter: Jitter) -> Self {
+        self.jitter = Some(jitter);
+        self
+    }
+
+    // Sizes sells along a curve in the buy size instead of as a flat
+    // sell_percentage
+    pub fn sell_sizing(mut self, sizing: SellSizing) -> Self {
+        self.sizing = Some(sizing);
+        self
+    }
+
+    // Holds back buys arriving within a cooldown of the sender's last
+    // trigger, or of anyone's, and sells into them together once it passes
+    pub fn cooldowns(mut self, cooldowns: Cooldowns) -> Self {
+        self.cooldowns = Some(cooldowns);
+        self
+    }
+
+    // Follows exchange tickers for a reference price to compare the pool's
+    // with
+    pub fn reference(mut self, feed: ReferenceFeed) -> Self {
+        self.reference = Some(feed);
+        self
+    }
+
+    // Ignores buys bidding an effective gas price below this
+    pub fn min_effective_gas_price(mut self, min_effective_gas_price: U256) -> Self {
+        self.min_effective_gas_price = Some(min_effective_gas_price);
+        self
+    }
+
+    // Sells the remaining inventory of tokens that missed their target once
+    // the deadline passes
+    pub fn unwind_at_expiry(mut self, unwind: Unwind) -> Self {
+        self.unwind = Some(unwind);
+        self
+    }
+
+    pub fn shutdown(mut self, shutdown: Shutdown) -> Self {
+        self.shutdown = shutdown;
+        self
+    }
+
+    // Logs detected buys and simulates sells without broadcasting anything;
+    // balance checks become warnings so any wallet can be used
+    pub fn dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    // Simulates every sell with eth_call before sending it, skipping those
+    // that would revert; on by default
+    pub fn preflight(mut self, preflight: bool) -> Self {
+        self.preflight = preflight;
+        self
+    }
+
+    // Measures each token's buy and sell tax at startup by simulating small
+    // swaps, and sizes quotes and paper fills after it; on by default
+    pub fn detect_taxes(mut self, detect_taxes: bool) -> Self {
+        self.tax_detection = detect_taxes;
+        self
+    }
+
+    // Attaches an access list from eth_createAccessList to every sell; off
+    // by default, as not every chain's nodes serve it
+    pub fn access_lists(mut self, access_lists: bool) -> Self {
+        self.access_lists = access_lists;
+        self
+    }
+
+    // Skips sells whose quoted proceeds don't exceed their estimated gas
+    // cost by this much; 0 still skips those that would lose money
+    pub fn gas_margin_percent(mut self, gas_margin_percent: f64) -> Self {
+        self.gas_margin_percent = gas_margin_percent;
+        self
+    }
+
+    // Caps each sell's price impact against live reserves, fee aside;
+    // whatever a sell is shrunk by waits in the queue for sells held back
+    // by limits
+    pub fn max_price_impact_bps(mut self, max_price_impact_bps: u64) -> Self {
+        self.max_price_impact_bps = Some(max_price_impact_bps);
+        self
+    }
+
+    // Whether and how the router is approved for tokens when its allowance
+    // falls short, and whether allowances are revoked at the end
+    pub fn approvals(mut self, approvals: Approvals) -> Self {
+        self.approvals = approvals;
+        self
+    }
+
+    // Fills sells virtually against live reserves and tracks P&L instead of
+    // trading; the optional path receives a JSON report at session end
+    pub fn paper_trading(mut self, report_path: Option<PathBuf>) -> Self {
+        self.paper = Some(PaperBook::default());
+        self.paper_report_path = report_path;
+        self
+    }
+
+    // Starting paper inventory for a token instead of the wallet balance
+    pub fn paper_inventory(mut self, token: Address, amount: U256) -> Self {
+        self.paper.get_or_insert_with(PaperBook::default).inventory_overrides.insert(token, amount);
+        self
+    }
+
+    // Connects, resolves per-chain defaults and refuses to return a bot whose
+    // wallet can't pay for gas or holds none of the token
+    pub async fn build(self) -> Result<TradingBot> {
+        let ws_url = self.ws_url.ok_or_else(|| MktmkrError::Config("ws_url is required".into()))?;
+        let wallet = self.wallet.ok_or_else(|| MktmkrError::Config("wallet is required".into()))?;
+        let simulated = self.dry_run || self.paper.is_some();
+
+        let params = match self.params {
+            Some(params) => params,
+            None => {
+                let mut tokens = self.targets;
+                tokens.extend(self.tokens.into_iter().map(|address| {
+                    let mut target = TokenTarget::new(address, self.sell_percentage, self.target);
+                    if let Some(profile) = &self.profile {
+                        target.apply_profile(profile);
+                    }
+                    target
+                }));
+                ParamsHandle::new(StrategyParams { tokens, max_gas_price: self.max_gas_price })
+            }
+        };
+        params.read().validate()?;
+
+        let provider = connect(&ws_url, self.txpool_poll_interval).await?;
+        let chain_id = provider.get_chainid().await?.as_u64();
+        if let Some(expected) = self.chain_id.filter(|expected| *expected != chain_id) {
+            return Err(MktmkrError::Config(format!("{} is on chain {}, not {}", ws_url, chain_id, expected)));
+        }
+        let chain = chain_preset(chain_id);
+        let wallet = wallet.with_chain_id(chain_id);
+        let mut fallbacks = Vec::new();
+        for url in &self.fallback_urls {
+            let fallback = connect(url, self.txpool_poll_interval).await?;
+            let fallback_chain_id = fallback.get_chainid().await?.as_u64();
+            if fallback_chain_id != chain_id {
+                return Err(MktmkrError::Config(format!(
+                    "fallback {} is on chain {}, not {}",
+                    url, fallback_chain_id, chain_id
+                )));
+            }
+            fallbacks.push(fallback);
+        }
+        if self.broadcast_quorum == 0 || self.broadcast_quorum > fallbacks.len() + 1 {
+            return Err(MktmkrError::Config(format!(
+                "broadcast_quorum must be between 1 and the {} configured endpoints",
+                fallbacks.len() + 1
+            )));
+        }
+        for twap in &self.twaps {
+            if !params.read().tokens.iter().any(|token| token.address == twap.token) {
+                return Err(MktmkrError::Config(format!("TWAP token {:?} is not a configured token", twap.token)));
+            }
+        }
+        // Relays only take transactions, so their chain isn't checked
+        let mut private_relays = Vec::new();
+        for url in &self.private_rpc_urls {
+            private_relays.push(connect(url, self.txpool_poll_interval).await?);
+        }
+        if private_relays.is_empty() && params.read().tokens.iter().any(|token| token.private) {
+            return Err(MktmkrError::Config("tokens sold privately need at least one private RPC".into()));
+        }
+        let v2_fork = self.v2_fork.clone().unwrap_or_else(|| chain.map_or("uniswap", |chain| chain.v2_fork).to_string());
+        let preset = v2_preset(&v2_fork, chain_id);
+        let router = match (self.router, &preset) {
+            (Some(router), _) => router,
+            (None, Some(preset)) => preset.router,
+            (None, None) => {
+                return Err(MktmkrError::Config(format!("no {} router for chain {}, set one explicitly", v2_fork, chain_id)))
+            }
+        };
+        // The preset's factory and hash only hold for its own router
+        let preset = preset.filter(|preset| preset.router == router);
+        let factory = self.factory.or(preset.as_ref().and_then(|preset| preset.factory));
+        let init_code_hash = self.init_code_hash.or(preset
+            .as_ref()
+            .filter(|preset| preset.factory == factory)
+            .and_then(|preset| preset.init_code_hash));
+        let v2_name = preset.map_or_else(|| "V2 router".to_string(), |preset| preset.name);
+        let weth = match self.weth {
+            Some(weth) => weth,
+            None => match chain {
+                Some(chain) => chain.wrapped_native.parse().expect("valid preset address"),
+                None => return Err(MktmkrError::Config(format!("no default WETH for chain {}, set one explicitly", chain_id))),
+            },
+        };
+
+        let universal_router = self.universal_router.or_else(|| {
+            DEFAULT_UNIVERSAL_ROUTERS
+                .iter()
+                .find(|(id, _)| *id == chain_id)
+                .map(|(_, universal_router)| universal_router.parse().expect("valid preset address"))
+        });
+        let v3 = self.v3.or_else(|| {
+            DEFAULT_V3_VENUES.iter().find(|(id, ..)| *id == chain_id).map(|(_, name, router, quoter, fee_tiers)| V3Venue {
+                name,
+                router: router.parse().expect("valid preset address"),
+                quoter: quoter.parse().expect("valid preset address"),
+                fee_tiers,
+            })
+        });
+        if v3.is_none() && params.read().tokens.iter().any(|token| token.venue == Venue::V3) {
+            return Err(MktmkrError::Config(format!("no default V3 router for chain {}, set one explicitly", chain_id)));
+        }
+        let v4 = self.v4.or_else(|| {
+            DEFAULT_V4_VENUES.iter().find(|(id, ..)| *id == chain_id).map(|(_, universal_router, quoter, state_view, pool_manager)| V4Venue {
+                universal_router: universal_router.parse().expect("valid preset address"),
+                quoter: quoter.parse().expect("valid preset address"),
+                state_view: state_view.parse().expect("valid preset address"),
+                pool_manager: pool_manager.parse().expect("valid preset address"),
+            })
+        });
+        for token in &params.read().tokens {
+            if token.venue == Venue::V4 && (v4.is_none() || token.v4_pool.is_none()) {
+                return Err(MktmkrError::Config(format!(
+                    "{:?} trades on V4, which needs its v4_pool and V4 contracts on chain {}",
+                    token.address, chain_id
+                )));
+            }
+            // V4 sells pay out native ETH
+            if token.venue == Venue::V4 && self.weth_proceeds.is_some() {
+                return Err(MktmkrError::Config(format!("{:?} trades on V4, whose sells can't pay out WETH", token.address)));
+            }
+        }
+        let aerodrome = self.aerodrome.or_else(|| aerodrome_preset(chain_id));
+        if aerodrome.is_none() && params.read().tokens.iter().any(|token| token.venue == Venue::Aerodrome) {
+            return Err(MktmkrError::Config(format!("no default Aerodrome router for chain {}, set one explicitly", chain_id)));
+        }
+        let aggregators = self.aggregators.unwrap_or_else(|| {
+            DEFAULT_AGGREGATORS
+                .iter()
+                .filter(|(id, _)| *id == chain_id)
+                .map(|(_, aggregator)| aggregator.parse().expect("valid preset address"))
+                .collect()
+        });
+        let usd_token = self.usd_token.or_else(|| {
+            DEFAULT_USD_TOKENS
+                .iter()
+                .find(|(id, _)| *id == chain_id)
+                .map(|(_, usd_token)| usd_token.parse().expect("valid preset address"))
+        });
+        let chainlink = match self.chainlink {
+            Some(feeds) => {
+                let eth_usd = feeds.eth_usd.or_else(|| {
+                    DEFAULT_ETH_USD_FEEDS
+                        .iter()
+                        .find(|(id, _)| *id == chain_id)
+                        .map(|(_, feed)| feed.parse().expect("valid preset address"))
+                });
+                let eth_usd = eth_usd.ok_or_else(|| {
+                    MktmkrError::Config(format!("no default Chainlink ETH/USD feed for chain {}, set one explicitly", chain_id))
+                })?;
+                Some(Chainlink { eth_usd, max_age: feeds.max_age })
+            }
+            None => None,
+        };
+        if chainlink.is_none() {
+            let usd_exit = |exit: Option<PriceExit>| exit.is_some_and(|exit| matches!(exit.level, PriceLevel::Usd(_)));
+            let prices_usd = |t: &TokenTarget| {
+                t.min_sell_output_usd.is_some() || t.usd_feed.is_some() || usd_exit(t.take_profit) || usd_exit(t.stop_loss)
+            };
+            if let Some(token) = params.read().tokens.iter().find(|t| prices_usd(t)) {
+                return Err(MktmkrError::Config(format!("{:?} prices in USD, which needs Chainlink price feeds", token.address)));
+            }
+        }
+        // Chainlink values ETH proceeds without the stablecoin
+        let usd_targets = chainlink.is_none() && params.read().tokens.iter().any(|t| matches!(t.target, Target::UsdReceived(_)));
+        let needs_usd = self.stable_proceeds || usd_targets;
+        if needs_usd && usd_token.is_none() {
+            return Err(MktmkrError::Config(format!("no default USD token for chain {}, set one explicitly", chain_id)));
+        }
+        let stable = usd_token.filter(|_| self.stable_proceeds);
+        if stable.is_some() {
+            if self.weth_proceeds.is_some() {
+                return Err(MktmkrError::Config("sells can pay out WETH or a stablecoin, not both".into()));
+            }
+            for token in &params.read().tokens {
+                if matches!(token.target, Target::EthReceived(_)) {
+                    return Err(MktmkrError::Config(format!(
+                        "{:?} sells for a stablecoin and needs a usd_received or tokens_sold target",
+                        token.address
+                    )));
+                }
+                if matches!(token.venue, Venue::V3 | Venue::V4 | Venue::Curve | Venue::Balancer | Venue::Aerodrome) {
+                    return Err(MktmkrError::Config(format!(
+                        "{:?} sells for a stablecoin, which only V2 and aggregator sells pay out; use venue v2 or best",
+                        token.address
+                    )));
+                }
+            }
+        }
+
+        let intents = match self.intents {
+            Some(intents) => {
+                let url = intents.api_url.or_else(|| {
+                    DEFAULT_COW_APIS.iter().find(|(id, _)| *id == chain_id).map(|(_, url)| url.to_string())
+                });
+                let url = url.ok_or_else(|| {
+                    MktmkrError::Config(format!("no default CoW Protocol order book for chain {}, set one explicitly", chain_id))
+                })?;
+                Some(CowApi::new(url, intents.validity))
+            }
+            None => None,
+        };
+
+        let eth_balance = provider.get_balance(wallet.address(), None).await?;
+        if eth_balance.is_zero() {
+            let error = MktmkrError::InsufficientBalance {
+                asset: "ETH".to_string(),
+                required: U256::one(),
+                available: eth_balance,
+            };
+            if !simulated {
+                return Err(error);
+            }
+            println!("Warning: {}", error);
+        }
+
+        let client = Arc::new(provider.clone());
+        let tokens = params.snapshot().tokens;
+        for token in &tokens {
+            let token_balance = IERC20::new(token.address, client.clone())
+                .balance_of(wallet.address())
+                .call()
+                .await?;
+            if token_balance.is_zero() {
+                let error = MktmkrError::InsufficientBalance {
+                    asset: format!("{:?}", token.address),
+                    required: U256::one(),
+                    available: token_balance,
+                };
+                if !simulated {
+                    return Err(error);
+                }
+                println!("Warning: {}", error);
+            }
+        }
+
+        // Hop tokens and the stablecoin too, for naming paths and proceeds
+        let hops = self.path_search.iter().flat_map(|search| &search.hops);
+        let named: HashSet<Address> = tokens
+            .iter()
+            .flat_map(|token| std::iter::once(&token.address).chain(&token.hops))
+            .chain(hops)
+            .chain(&stable)
+            .copied()
+            .collect();
+        let mut metadata = HashMap::new();
+        for token in named {
+            metadata.insert(token, TokenMetadata::fetch(&provider, token).await);
+        }
+
+        let health = std::iter::once("node".to_string())
+            .chain((1..=fallbacks.len()).map(|i| format!("fallback {}", i)))
+            .map(EndpointHealth::new)
+            .collect();
+        let watched = watched_contracts(
+            router,
+            universal_router,
+            v3.map(|v3| v3.router),
+            v4.map(|v4| v4.universal_router),
+            aerodrome.map(|aerodrome| aerodrome.router),
+            &aggregators,
+        );
+        let mut sources: Vec<Arc<dyn MempoolSource>> = Vec::new();
+        for (i, provider) in std::iter::once(&provider).chain(&fallbacks).enumerate() {
+            sources.push(Arc::new(NodeSource {
+                name: if i == 0 { "node".to_string() } else { format!("fallback {}", i) },
+                provider: provider.clone(),
+                mode: self.pending_txs,
+                fetch_concurrency: self.fetch_concurrency,
+                txpool_poll_interval: self.txpool_poll_interval,
+                filter_recipients: self.filter_recipients,
+                watched: watched.clone(),
+            }));
+        }
+        sources.extend(self.sources);
+
+        let pending_buys = PendingBuys::default();
+        let tracked_senders = pending_buys.senders();
+
+        Ok(TradingBot {
+            client: SignerMiddleware::new(provider.clone(), wallet.clone()),
+            nonces: Arc::new(Mutex::new(NonceManager::default())),
+            provider,
+            fallbacks,
+            private_relays,
+            public_fallback: self.public_fallback,
+            bundle_relays: self.bundle_relays,
+            chunking: self.chunking,
+            weth_proceeds: self.weth_proceeds,
+            stable,
+            path_search: self.path_search,
+            intents,
+            open_intents: Arc::new(Mutex::new(HashMap::new())),
+            sweep: self.sweep,
+            lp_state_path: self.lp_state_path,
+            chunked: Arc::new(Mutex::new(Vec::new())),
+            twaps: Arc::new(Mutex::new(self.twaps.into_iter().map(TwapSchedule::new).collect())),
+            broadcast_quorum: self.broadcast_quorum,
+            health: Arc::new(Mutex::new(health)),
+            sources,
+            strategy: self.strategy,
+            wallet,
+            params,
+            router,
+            factory,
+            init_code_hash,
+            v2_name,
+            v2_referrer: takes_referrer(&v2_fork),
+            explorer_url: self.explorer_url.or(chain.map(|chain| chain.explorer.to_string())),
+            universal_router,
+            v3,
+            v4,
+            aerodrome,
+            aggregator_apis: self.aggregator_apis,
+            aggregator_spenders: Arc::new(Mutex::new(HashSet::new())),
+            aggregators,
+            weth,
+            usd_token,
+            chainlink,
+            deadline: self.deadline,
+            fee_mode: self.fee_mode.or(chain.map(|chain| chain.fee_mode)).unwrap_or_default(),
+            l1_fee: self.l1_fee.or(chain.map(|chain| chain.l1_fee)).unwrap_or_default(),
+            max_sell_fee: self.max_sell_fee,
+            chain_id,
+            block_time: self.block_time.or(chain.map(ChainPreset::block_time)).unwrap_or(DEFAULT_BLOCK_TIME),
+            priority_fee_percentile: self.priority_fee_percentile,
+            slippage_bps: self.slippage_bps,
+            swap_deadline: self.swap_deadline,
+            schedule: self.schedule,
+            unwind: self.unwind,
+            report: Arc::new(Mutex::new(SessionReport::default())),
+            in_flight: Arc::new(Mutex::new(HashMap::new())),
+            confirmations: self.confirmations.max(1),
+            receipt_timeout: self.receipt_timeout,
+            escalate_after_blocks: self.escalate_after_blocks,
+            fee_bump_percent: self.fee_bump_percent.max(10),
+            max_fee_bumps: self.max_fee_bumps,
+            max_escalated_fee: self.max_escalated_fee,
+            chain: Arc::new(Mutex::new(ChainTracker::default())),
+            recent_fills: Arc::new(Mutex::new(HashMap::new())),
+            recent_buys: Arc::new(Mutex::new(HashMap::new())),
+            shutdown: self.shutdown,
+            dry_run: self.dry_run,
+            preflight: self.preflight,
+            tax_detection: self.tax_detection,
+            access_lists: self.access_lists,
+            gas_margin_bps: percent_to_bps(self.gas_margin_percent),
+            max_price_impact_bps: self.max_price_impact_bps,
+            taxes: Arc::new(Mutex::new(HashMap::new())),
+            approvals: self.approvals,
+            paper: self.paper.map(|book| Arc::new(Mutex::new(book))),
+            paper_report_path: self.paper_report_path,
+            seen: Arc::new(Mutex::new(SeenCache::default())),
+            trigger: self.trigger,
+            path_match: self.path_match,
+            swap_log_delay: self.swap_log_delay,
+            buy_ttl: self.buy_ttl,
+            watchlist: self.watchlist,
+            counterparties: self.counterparties,
+            net_flow: self.net_flow.map(|flow| Arc::new(Mutex::new(flow))),
+            twap_gate: self.twap_gate,
+            skew: self.skew,
+            skew_factors: Arc::new(Mutex::new(HashMap::new())),
+            exits_fired: Arc::new(Mutex::new(HashSet::new())),
+            price_peaks: Arc::new(Mutex::new(HashMap::new())),
+            volatility_scaling: self.volatility_scaling,
+            jitter: self.jitter,
+            sizing: self.sizing,
+            cooldowns: self.cooldowns,
+            cooldown_state: Arc::new(Mutex::new(CooldownState::default())),
+            price_moves: Arc::new(Mutex::new(PriceMoves::default())),
+            observations: Arc::new(Mutex::new(Observations::default())),
+            reference: self.reference,
+            tickers: Arc::new(Mutex::new(HashMap::new())),
+            min_effective_gas_price: self.min_effective_gas_price,
+            base_fee: Arc::new(Mutex::new(None)),
+            awaiting: Arc::new(Mutex::new(HashMap::new())),
+            pending_buys: Arc::new(Mutex::new(pending_buys)),
+            tracked_senders,
+            metadata,
+            pairs: Arc::new(Mutex::new(HashMap::new())),
+            reserve_cache: Arc::new(Mutex::new(ReserveCache::default())),
+            v3_pools: Arc::new(Mutex::new(HashMap::new())),
+            curve_pools: Arc::new(Mutex::new(HashMap::new())),
+            aerodrome_pools: Arc::new(Mutex::new(HashMap::new())),
+            throttle: Arc::new(Mutex::new(SellThrottle::default())),
+            orders: Arc::new(Mutex::new(ExecutionQueue::new(self.queue_depth, self.coalesce_sells, self.backlog_policy))),
+            order_ready: Arc::new(Notify::new()),
+            reached: Arc::new(Mutex::new(HashSet::new())),
+            cycle_start: Arc::new(Mutex::new(HashMap::new())),
+        })
+    }
+}
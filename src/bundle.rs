@@ -0,0 +1,219 @@
+use crate::bot::{TokenTarget, TradingBot};
+use crate::error::{MktmkrError, Result};
+use crate::receipts::InFlight;
+use ethers::{
+    prelude::*,
+    types::{transaction::eip2718::TypedTransaction, Bytes, TransactionReceipt, U256},
+    utils::{hex, keccak256},
+};
+use futures_util::future::join_all;
+use serde_json::{json, Value};
+
+// Flashbots' relay, which forwards bundles to the builders it works with
+pub const DEFAULT_BUNDLE_RELAY: &str = "https://relay.flashbots.net";
+
+// The buy a sell is bundled behind
+#[derive(Debug, Clone)]
+pub(crate) enum Backrun {
+    // Seen in full, so it can be put in the bundle itself
+    Signed(Bytes),
+    // Known only from a MEV-Share hint, so the bundle refers to it by hash
+    Hint(H256),
+}
+
+// A bundle relay reached over JSON-RPC. Requests are signed with a searcher
+// identity key, which only builds reputation with the relay and never holds
+// funds.
+#[derive(Debug, Clone)]
+pub struct BundleRelay {
+    pub name: String,
+    url: String,
+    identity: LocalWallet,
+    client: reqwest::Client,
+    // Share of a MEV-Share backrun's profit refunded to the buyer, and the
+    // blocks it may land in
+    pub refund_percent: u64,
+    pub mev_share_blocks: u64,
+}
+
+impl BundleRelay {
+    // A random identity when none is given
+    pub fn new(name: impl Into<String>, url: impl Into<String>, identity: Option<LocalWallet>) -> Self {
+        let identity = identity.unwrap_or_else(|| LocalWallet::new(&mut ethers::core::rand::thread_rng()));
+        Self {
+            name: name.into(),
+            url: url.into(),
+            identity,
+            client: reqwest::Client::new(),
+            refund_percent: 90,
+            mev_share_blocks: 3,
+        }
+    }
+
+    // Sends a request with the X-Flashbots-Signature header: our identity's
+    // signature over the hex keccak of the body
+    pub(crate) async fn request(&self, method: &str, params: Value) -> Result<Value> {
+        let body = json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params }).to_string();
+        let digest = format!("0x{}", hex::encode(keccak256(body.as_bytes())));
+        let signature = self.identity.sign_message(digest).await?;
+        let response: Value = self.client
+            .post(&self.url)
+            .header("content-type", "application/json")
+            .header("x-flashbots-signature", format!("{:?}:0x{}", self.identity.address(), signature))
+            .body(body)
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            .map_err(relay_error)?
+            .json()
+            .await
+            .map_err(relay_error)?;
+        if let Some(error) = response.get("error") {
+            return Err(relay_error(error));
+        }
+        Ok(response.get("result").cloned().unwrap_or_default())
+    }
+
+    // Simulates `txs` in order on top of the latest state as of `block`;
+    // the first failing transaction's error, if any
+    pub(crate) async fn call_bundle(&self, txs: &[Bytes], block: u64) -> Result<Option<String>> {
+        let params = json!([{ "txs": txs, "blockNumber": format!("0x{:x}", block), "stateBlockNumber": "latest" }]);
+        let result = self.request("eth_callBundle", params).await?;
+        let results = result.get("results").and_then(Value::as_array).cloned().unwrap_or_default();
+        Ok(results.iter().find_map(|tx| {
+            let failure = tx.get("error").or_else(|| tx.get("revert"))?;
+            Some(failure.as_str().map_or_else(|| failure.to_string(), str::to_string))
+        }))
+    }
+
+    pub(crate) async fn send_bundle(&self, txs: &[Bytes], block: u64) -> Result<()> {
+        let params = json!([{ "txs": txs, "blockNumber": format!("0x{:x}", block) }]);
+        self.request("eth_sendBundle", params).await.map(drop)
+    }
+
+    // Sends `tx` behind the hinted transaction `hint` through the MEV-Share
+    // bundle API, for blocks `block` to `max_block`. The hint's sender gets
+    // refund_percent of what the backrun pays.
+    pub(crate) async fn send_mev_share_bundle(&self, hint: H256, tx: &Bytes, block: u64, max_block: u64) -> Result<()> {
+        let params = json!([{
+            "version": "v0.1",
+            "inclusion": { "block": format!("0x{:x}", block), "maxBlock": format!("0x{:x}", max_block) },
+            "body": [{ "hash": hint }, { "tx": tx, "canRevert": false }],
+            "validity": { "refund": [{ "bodyIdx": 0, "percent": self.refund_percent }] },
+        }]);
+        self.request("mev_sendBundle", params).await.map(drop)
+    }
+}
+
+fn relay_error(e: impl std::fmt::Display) -> MktmkrError {
+    MktmkrError::Provider(ProviderError::CustomError(format!("bundle relay: {}", e)))
+}
+
+// Who built a block, from the name builders put in its extra data: the
+// configured builder it mentions, or else the text itself
+pub(crate) fn builder_name(extra_data: &[u8], relays: &[BundleRelay]) -> String {
+    let text = String::from_utf8_lossy(extra_data).trim_matches(char::from(0)).trim().to_string();
+    let lower = text.to_lowercase();
+    relays
+        .iter()
+        .find(|relay| lower.contains(&relay.name.to_lowercase()))
+        .map_or(text, |relay| relay.name.clone())
+}
+
+impl TradingBot {
+    // Logs and counts which builder included a bundled sell
+    pub(crate) async fn record_builder(&self, receipt: &TransactionReceipt) -> Result<()> {
+        let Some(block_hash) = receipt.block_hash else { return Ok(()) };
+        let Some(block) = self.provider.get_block(block_hash).await? else { return Ok(()) };
+        let name = builder_name(&block.extra_data, &self.bundle_relays);
+        println!("Bundled sell {:?} included by {}", receipt.transaction_hash, name);
+        *self.report.lock().await.builders.entry(name).or_default() += 1;
+        Ok(())
+    }
+
+    // Sends the sell bundled right behind the pending buy it answers. A
+    // signed buy is bundled for the next block only, once a simulation of
+    // the pair succeeds; a sell whose simulation fails (the buy already
+    // landed, or won't) is returned to be sent normally. A hinted buy can't
+    // be simulated, so its bundle goes to MEV-Share as is, for the next
+    // mev_share_blocks blocks. An unincluded bundle is dropped, freeing its
+    // nonce.
+    pub(crate) async fn send_backrun(
+        &self,
+        target: &TokenTarget,
+        sell_amount: U256,
+        mut tx: TypedTransaction,
+        deadline: U256,
+        venue: &str,
+        backrun: &Backrun,
+    ) -> Result<Option<(TypedTransaction, U256)>> {
+        let Some(relay) = self.bundle_relays.first() else { return Ok(Some((tx, deadline))) };
+        let token = target.address;
+        let nonce = self.next_nonce().await?;
+        tx.set_nonce(nonce);
+        let raw = match self.sign(&mut tx).await {
+            Ok(raw) => raw,
+            Err(e) => {
+                self.nonces.lock().await.release(nonce);
+                return Err(e);
+            }
+        };
+        let block = self.provider.get_block_number().await?.as_u64() + 1;
+        // Waited for like any other sell, until its last block has passed
+        let tx_hash = TxHash::from(keccak256(&raw));
+        let mut sell = InFlight {
+            tx: Some(tx.clone()),
+            raw: Some(raw.clone()),
+            deadline: Some(deadline),
+            venue: venue.to_string(),
+            ..InFlight::new(token, sell_amount, Some(nonce))
+        };
+
+        let buy = match backrun {
+            Backrun::Signed(buy) => buy,
+            Backrun::Hint(hint) => {
+                let last_block = block + relay.mev_share_blocks.max(1) - 1;
+                if let Err(e) = relay.send_mev_share_bundle(*hint, &raw, block, last_block).await {
+                    println!("MEV-Share refused the backrun of {}: {}", self.format_amount(token, sell_amount), e);
+                    self.nonces.lock().await.release(nonce);
+                    return Ok(None);
+                }
+                println!("Sent MEV-Share backrun of {} behind {:?}", self.format_amount(token, sell_amount), hint);
+                sell.bundle_block = Some(last_block);
+                self.in_flight.lock().await.insert(tx_hash, sell);
+                return Ok(None);
+            }
+        };
+        let bundle = [buy.clone(), raw];
+
+        let failure = match relay.call_bundle(&bundle, block).await {
+            Ok(failure) => failure,
+            Err(e) => Some(e.to_string()),
+        };
+        if let Some(failure) = failure {
+            println!("Backrun of {} failed simulation ({}); sending it alone", self.format_amount(token, sell_amount), failure);
+            self.nonces.lock().await.release(nonce);
+            return Ok(Some((tx, deadline)));
+        }
+        // Sent to every builder at once; any one of them including it is enough
+        let sent = join_all(self.bundle_relays.iter().map(|relay| relay.send_bundle(&bundle, block))).await;
+        let mut accepted = Vec::new();
+        for (relay, sent) in self.bundle_relays.iter().zip(sent) {
+            match sent {
+                Ok(()) => accepted.push(relay.name.as_str()),
+                Err(e) => println!("{} refused the backrun of {}: {}", relay.name, self.format_amount(token, sell_amount), e),
+            }
+        }
+        if accepted.is_empty() {
+            self.nonces.lock().await.release(nonce);
+            return Ok(None);
+        }
+        println!(
+            "Bundled sell of {} behind its buy for block {} via {}",
+            self.format_amount(token, sell_amount), block, accepted.join(", ")
+        );
+        sell.bundle_block = Some(block);
+        self.in_flight.lock().await.insert(tx_hash, sell);
+        Ok(None)
+    }
+}
@@ -0,0 +1,119 @@
+use crate::abi::{token_received, IUniswapV2Router02};
+use crate::bot::TradingBot;
+use crate::error::{MktmkrError, Result};
+use crate::math::less_bps;
+use crate::receipts::InFlight;
+use crate::strategy::{Filled, Side};
+use crate::tax::after_tax;
+use ethers::{
+    prelude::*,
+    types::{TransactionReceipt, U256},
+    utils::format_ether,
+};
+use std::sync::Arc;
+use std::time::SystemTime;
+
+impl TradingBot {
+    // Buys the token with `eth_amount` on the V2 router, accepting no less
+    // than the quote minus the token's slippage. Simulated in dry-run and
+    // paper modes; accounted for once the receipt watcher sees it confirmed.
+    pub(crate) async fn buy(&self, token: Address, eth_amount: U256) -> Result<()> {
+        if SystemTime::now() >= self.deadline {
+            return Err(MktmkrError::DeadlineExpired);
+        }
+        if eth_amount.is_zero() {
+            return Ok(());
+        }
+        if self.paper.is_some() {
+            return self.paper_buy(token, eth_amount).await;
+        }
+        let target = self.target_for(token)?;
+        let slippage_bps = target.slippage_bps.unwrap_or(self.slippage_bps);
+        let Some(gas_bid) = self.gas_bid(&target).await? else { return Ok(()) };
+
+        let wallet = self.wallet.address();
+        let router = IUniswapV2Router02::new(self.router, Arc::new(self.provider.clone()));
+        let path = vec![self.weth, token];
+        let quoted = router.get_amounts_out(eth_amount, path.clone()).call().await?.last().copied().unwrap_or_default();
+        let expected = after_tax(quoted, self.tax(token).await.buy_bps);
+        let amount_out_min = less_bps(expected, slippage_bps.into());
+        let deadline = self.swap_deadline_timestamp().await?;
+        let call = router.swap_exact_eth_for_tokens_supporting_fee_on_transfer_tokens(amount_out_min, path, wallet, deadline);
+        let mut tx = gas_bid.transaction(wallet, self.router, call.calldata().unwrap_or_default());
+        tx.set_value(eth_amount);
+
+        if self.dry_run {
+            let outcome = match self.provider.call(&tx, None).await {
+                Ok(_) => "simulation succeeded".to_string(),
+                Err(e) => format!("simulation reverted: {}", e),
+            };
+            println!(
+                "[dry-run] Would buy ~{} for {} ETH on {}; {}",
+                self.format_amount(token, expected), format_ether(eth_amount), self.v2_name, outcome
+            );
+            return Ok(());
+        }
+
+        let nonce = self.next_nonce().await?;
+        tx.set_nonce(nonce);
+        let mut raw = None;
+        let sent = match self.sign(&mut tx).await {
+            Ok(signed) => self.submit(raw.insert(signed).clone(), target.private).await,
+            Err(e) => Err(e),
+        };
+        let tx_hash = match sent {
+            Ok(tx_hash) => tx_hash,
+            Err(e) => {
+                let mut nonces = self.nonces.lock().await;
+                match e {
+                    MktmkrError::NonceConflict(_) => nonces.resync(),
+                    _ => nonces.release(nonce),
+                }
+                return Err(e);
+            }
+        };
+        let buy = InFlight {
+            tx: Some(tx),
+            raw,
+            deadline: Some(deadline),
+            private: target.private,
+            venue: self.v2_name.clone(),
+            side: Side::Buy,
+            ..InFlight::new(token, eth_amount, Some(nonce))
+        };
+        self.in_flight.lock().await.insert(tx_hash, buy);
+        Ok(())
+    }
+
+    pub(crate) async fn record_buy(&self, token: Address, eth_in: U256, venue: &str, receipt: &TransactionReceipt) {
+        let fee = receipt.gas_used.unwrap_or_default() * receipt.effective_gas_price.unwrap_or_default();
+        let succeeded = receipt.status == Some(1u64.into());
+        let bought = token_received(receipt, token, self.wallet.address());
+        {
+            let mut report = self.report.lock().await;
+            let entry = report.tokens.entry(token).or_default();
+            entry.fees_paid += fee;
+            if !succeeded {
+                entry.failed_buys += 1;
+                println!("Buy of {} reverted: {}", self.metadata(token).symbol, self.tx_link(receipt.transaction_hash));
+                return;
+            }
+            entry.buys += 1;
+            entry.tokens_bought += bought;
+            entry.eth_spent += eth_in;
+            println!(
+                "Bought {} for {} ETH on {}. Total: {} bought for {} ETH",
+                self.format_amount(token, bought),
+                format_ether(eth_in),
+                venue,
+                self.format_amount(token, entry.tokens_bought),
+                format_ether(entry.eth_spent)
+            );
+        }
+
+        let fill = Filled { token, side: Side::Buy, amount: bought, eth: eth_in, fee };
+        if let Err(e) = self.strategy.on_fill(self, fill).await {
+            println!("Strategy {} failed on a fill of {:?}: {}", self.strategy.name(), token, e);
+        }
+    }
+}
@@ -0,0 +1,50 @@
+use crate::bot::{TokenTarget, TradingBot};
+use crate::error::Result;
+use crate::math::{apply_bps, mul_div, percent_to_bps};
+use crate::target::{BuyBack, OnTarget};
+use ethers::{types::U256, utils::format_ether};
+
+impl TradingBot {
+    // Buys back tokens whose target was met once their price has dipped far
+    // enough below what they sold for, once a block
+    pub(crate) async fn check_buy_backs(&self) {
+        for target in self.tokens() {
+            let OnTarget::BuyBack(buy_back) = target.on_target else { continue };
+            if !self.reached.lock().await.contains(&target.address) {
+                continue;
+            }
+            if let Err(e) = self.check_buy_back(&target, buy_back).await {
+                println!("Could not check the buy-back of {:?}: {}", target.address, e);
+            }
+        }
+    }
+
+    async fn check_buy_back(&self, target: &TokenTarget, buy_back: BuyBack) -> Result<()> {
+        let token = target.address;
+        let cycle = self.cycle_progress(token).await;
+        if cycle.tokens_sold.is_zero() || cycle.eth_received.is_zero() {
+            return Ok(());
+        }
+        let average = self.whole_price(token, mul_div(cycle.eth_received, U256::exp10(18), cycle.tokens_sold));
+        let price = self.whole_price(token, self.spot(token).await?);
+        if price > average * (1.0 - buy_back.dip_percentage / 100.0) {
+            return Ok(());
+        }
+
+        // A new cycle starts now, so the buy can't fire twice and sells
+        // count towards the target afresh
+        let progress = self.progress(token).await;
+        self.cycle_start.lock().await.insert(token, progress);
+        self.reached.lock().await.remove(&token);
+
+        let eth = apply_bps(cycle.eth_received, percent_to_bps(buy_back.spend_percentage));
+        println!(
+            "Price of {} at {} ETH, below its average sell price of {} ETH; buying back with {} ETH",
+            self.metadata(token).symbol,
+            price,
+            average,
+            format_ether(eth)
+        );
+        self.buy(token, eth).await
+    }
+}
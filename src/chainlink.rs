@@ -0,0 +1,85 @@
+use crate::abi::IChainlinkAggregator;
+use crate::bot::{TokenTarget, TradingBot};
+use crate::error::{MktmkrError, Result};
+use ethers::{
+    types::{Address, U256},
+    utils::{format_ether, format_units, parse_ether},
+};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+// (chain id, Chainlink ETH/USD feed, or BNB/USD on BSC)
+pub const DEFAULT_ETH_USD_FEEDS: &[(u64, &str)] = &[
+    (1, "0x5f4eC3Df9cbd43714FE2740f5E3616155c5b8419"),
+    (10, "0x13e3Ee699D1909E989722E753853AE30b17e08c5"),
+    (56, "0x0567F2323251f0Aab15c8dFb1967E4e8A7D42aeE"),
+    (8453, "0x71041dddad3595F9CEd3DcCFBe3D1F4b0a16Bb70"),
+    (42161, "0x639Fe6ab55C921f74e7fac1ee960C0B6293ba612"),
+    (11155111, "0x694AA1769357215DE4FAc081bf1f309aDC325306"),
+];
+
+// Chainlink feeds valuing ETH, and tokens that name one, in USD for
+// targets, sell floors and the report. Unset, the chain's ETH/USD feed is
+// used.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PriceFeeds {
+    pub eth_usd: Option<Address>,
+    // Answers older than this are refused
+    pub max_age: Duration,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Chainlink {
+    pub eth_usd: Address,
+    pub max_age: Duration,
+}
+
+// `wei` in USD at `eth_usd` per ETH
+pub fn eth_to_usd(wei: U256, eth_usd: f64) -> f64 {
+    format_ether(wei).parse::<f64>().unwrap_or_default() * eth_usd
+}
+
+impl TradingBot {
+    // USD per ETH from the ETH/USD feed; None without Chainlink
+    pub async fn eth_usd(&self) -> Result<Option<f64>> {
+        let Some(chainlink) = self.chainlink else { return Ok(None) };
+        self.feed_price(chainlink.eth_usd, chainlink.max_age).await.map(Some)
+    }
+
+    // USD per whole token from the token's own feed, if it names one
+    pub async fn token_usd(&self, token: Address) -> Result<Option<f64>> {
+        let Some(chainlink) = self.chainlink else { return Ok(None) };
+        let Some(feed) = self.token(token).and_then(|target| target.usd_feed) else { return Ok(None) };
+        self.feed_price(feed, chainlink.max_age).await.map(Some)
+    }
+
+    // The least a sell must quote: the token's min_sell_output, or its
+    // min_sell_output_usd at the ETH/USD price if that's more
+    pub(crate) async fn sell_floor(&self, target: &TokenTarget) -> Result<U256> {
+        let Some(usd) = target.min_sell_output_usd else { return Ok(target.min_sell_output) };
+        let eth_usd = self.eth_usd().await?.ok_or_else(|| {
+            MktmkrError::Config("min_sell_output_usd needs Chainlink price feeds".into())
+        })?;
+        let floor = parse_ether(format!("{:.18}", usd / eth_usd))
+            .map_err(|e| MktmkrError::Contract(format!("USD sell floor: {}", e)))?;
+        Ok(floor.max(target.min_sell_output))
+    }
+
+    // The feed's latest answer, refusing stale or non-positive ones
+    async fn feed_price(&self, feed: Address, max_age: Duration) -> Result<f64> {
+        let aggregator = IChainlinkAggregator::new(feed, Arc::new(self.provider.clone()));
+        let (_, answer, _, updated_at, _) = aggregator.latest_round_data().call().await?;
+        let decimals = aggregator.decimals().call().await?;
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+        let age = now.saturating_sub(updated_at.low_u64());
+        if age > max_age.as_secs() {
+            return Err(MktmkrError::Contract(format!("Chainlink feed {:?} last updated {}s ago", feed, age)));
+        }
+        if answer.is_negative() || answer.is_zero() {
+            return Err(MktmkrError::Contract(format!("Chainlink feed {:?} answered {}", feed, answer)));
+        }
+        let price = format_units(answer.into_raw(), u32::from(decimals))
+            .map_err(|e| MktmkrError::Contract(format!("Chainlink feed {:?}: {}", feed, e)))?;
+        Ok(price.parse().unwrap_or_default())
+    }
+}
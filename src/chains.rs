@@ -0,0 +1,164 @@
+use crate::bot::TradingBot;
+use crate::fees::{FeeMode, L1Fee};
+use ethers::types::{Address, TxHash};
+use std::time::Duration;
+
+// A chain the bot knows: its wrapped native token, how often it makes
+// blocks, how it prices gas, whether it charges for L1 data and which V2
+// deployment it sells on by default.
+// Amounts called ETH elsewhere are the chain's native coin.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ChainPreset {
+    pub id: u64,
+    pub name: &'static str,
+    pub native: &'static str,
+    pub wrapped_native: &'static str,
+    pub block_time_ms: u64,
+    pub fee_mode: FeeMode,
+    pub l1_fee: L1Fee,
+    // Block explorer whose /tx/<hash> pages the log links to
+    pub explorer: &'static str,
+    pub v2_fork: &'static str,
+}
+
+impl ChainPreset {
+    pub fn block_time(&self) -> Duration {
+        Duration::from_millis(self.block_time_ms)
+    }
+}
+
+pub const CHAINS: &[ChainPreset] = &[
+    ChainPreset {
+        id: 1,
+        name: "ethereum",
+        native: "ETH",
+        wrapped_native: "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2",
+        block_time_ms: 12_000,
+        fee_mode: FeeMode::Eip1559,
+        l1_fee: L1Fee::None,
+        explorer: "https://etherscan.io",
+        v2_fork: "uniswap",
+    },
+    ChainPreset {
+        id: 10,
+        name: "optimism",
+        native: "ETH",
+        wrapped_native: "0x4200000000000000000000000000000000000006",
+        block_time_ms: 2_000,
+        fee_mode: FeeMode::Eip1559,
+        l1_fee: L1Fee::OpStack,
+        explorer: "https://optimistic.etherscan.io",
+        v2_fork: "uniswap",
+    },
+    ChainPreset {
+        id: 56,
+        name: "bsc",
+        native: "BNB",
+        wrapped_native: "0xbb4CdB9CBd36B01bD1cBaEBF2De08d9173bc095c",
+        block_time_ms: 750,
+        fee_mode: FeeMode::Legacy,
+        l1_fee: L1Fee::None,
+        explorer: "https://bscscan.com",
+        v2_fork: "pancakeswap",
+    },
+    ChainPreset {
+        id: 137,
+        name: "polygon",
+        native: "POL",
+        wrapped_native: "0x0d500B1d8E8eF31E21C99d1Db9A6444d3ADf1270",
+        block_time_ms: 2_000,
+        fee_mode: FeeMode::Eip1559,
+        l1_fee: L1Fee::None,
+        explorer: "https://polygonscan.com",
+        v2_fork: "uniswap",
+    },
+    ChainPreset {
+        id: 8453,
+        name: "base",
+        native: "ETH",
+        wrapped_native: "0x4200000000000000000000000000000000000006",
+        block_time_ms: 2_000,
+        fee_mode: FeeMode::Eip1559,
+        l1_fee: L1Fee::OpStack,
+        explorer: "https://basescan.org",
+        v2_fork: "uniswap",
+    },
+    ChainPreset {
+        id: 42161,
+        name: "arbitrum",
+        native: "ETH",
+        wrapped_native: "0x82aF49447D8a07e3bd95BD0d56f35241523fBab1",
+        block_time_ms: 250,
+        fee_mode: FeeMode::Eip1559,
+        l1_fee: L1Fee::Arbitrum,
+        explorer: "https://arbiscan.io",
+        v2_fork: "uniswap",
+    },
+    ChainPreset {
+        id: 43114,
+        name: "avalanche",
+        native: "AVAX",
+        wrapped_native: "0xB31f66AA3C1e785363F0875A1B74E27b85FD66c7",
+        block_time_ms: 2_000,
+        fee_mode: FeeMode::Eip1559,
+        l1_fee: L1Fee::None,
+        explorer: "https://snowtrace.io",
+        v2_fork: "uniswap",
+    },
+    ChainPreset {
+        id: 11155111,
+        name: "sepolia",
+        native: "ETH",
+        wrapped_native: "0xfFf9976782d46CC05630D1f6eBAb18b2324d6B14",
+        block_time_ms: 12_000,
+        fee_mode: FeeMode::Eip1559,
+        l1_fee: L1Fee::None,
+        explorer: "https://sepolia.etherscan.io",
+        v2_fork: "uniswap",
+    },
+];
+
+// A chain defined in the config: one off the registry, or a registry chain
+// with settings of its own. Anything unset falls back to the registry
+// preset for its id, if any.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CustomChain {
+    pub id: u64,
+    pub name: String,
+    // Endpoint for markets on it without one of their own
+    pub rpc_url: Option<String>,
+    pub wrapped_native: Option<Address>,
+    pub explorer_url: Option<String>,
+    pub fee_mode: Option<FeeMode>,
+    pub l1_fee: Option<L1Fee>,
+    pub block_time: Option<Duration>,
+}
+
+pub fn chain_preset(id: u64) -> Option<&'static ChainPreset> {
+    CHAINS.iter().find(|chain| chain.id == id)
+}
+
+// A chain by registry name ("base") or by id ("8453"); ids of chains not
+// in the registry are taken as they are
+pub fn parse_chain(chain: &str) -> Option<u64> {
+    CHAINS
+        .iter()
+        .find(|preset| preset.name.eq_ignore_ascii_case(chain))
+        .map(|preset| preset.id)
+        .or_else(|| chain.parse().ok())
+}
+
+// A transaction's page on the explorer at `explorer`
+pub fn tx_url(explorer: &str, hash: TxHash) -> String {
+    format!("{}/tx/{:?}", explorer.trim_end_matches('/'), hash)
+}
+
+impl TradingBot {
+    // The transaction's explorer page, or its hash without an explorer
+    pub(crate) fn tx_link(&self, hash: TxHash) -> String {
+        match &self.explorer_url {
+            Some(explorer) => tx_url(explorer, hash),
+            None => format!("{:?}", hash),
+        }
+    }
+}
@@ -0,0 +1,116 @@
+use crate::bot::{TokenTarget, TradingBot};
+use crate::error::Result;
+use crate::math::mul_div;
+use ethers::{
+    core::rand::{thread_rng, Rng},
+    types::{Address, U256},
+    utils::format_ether,
+};
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+// Splitting of large sells into randomized chunks spread over several blocks
+#[derive(Debug, Clone, PartialEq)]
+pub struct Chunking {
+    pub chunks: usize,
+    // Only sells worth at least this much ETH at spot are split
+    pub min_sell: U256,
+    // Each chunk follows the previous one after a random delay in this range
+    pub min_delay: Duration,
+    pub max_delay: Duration,
+}
+
+// A split sell's chunks still to go, with when each is due
+#[derive(Debug, Clone)]
+pub(crate) struct ChunkedSell {
+    pub token: Address,
+    pub schedule: VecDeque<(Instant, U256)>,
+}
+
+impl ChunkedSell {
+    pub fn remaining(&self) -> U256 {
+        self.schedule.iter().fold(U256::zero(), |acc, (_, amount)| acc + amount)
+    }
+}
+
+// `amount` in `chunking.chunks` parts, each weighted between half and one
+// and a half times the even share, with the last taking the rounding: the
+// first part, and the rest with when each is due
+fn split(amount: U256, chunking: &Chunking) -> (U256, VecDeque<(Instant, U256)>) {
+    let mut rng = thread_rng();
+    let weights: Vec<u64> = (0..chunking.chunks).map(|_| rng.gen_range(50..=150)).collect();
+    let total = U256::from(weights.iter().sum::<u64>());
+    let mut parts: Vec<U256> = weights.iter().map(|weight| mul_div(amount, U256::from(*weight), total)).collect();
+    let assigned = parts.iter().fold(U256::zero(), |acc, part| acc + part);
+    if let Some(last) = parts.last_mut() {
+        *last += amount - assigned;
+    }
+
+    let mut parts = parts.into_iter().filter(|part| !part.is_zero());
+    let first = parts.next().unwrap_or_default();
+    let mut due = Instant::now();
+    let schedule = parts
+        .map(|part| {
+            due += rng.gen_range(chunking.min_delay..=chunking.max_delay);
+            (due, part)
+        })
+        .collect();
+    (first, schedule)
+}
+
+impl TradingBot {
+    // Splits `sell_amount` into a chunked sell when chunking applies to it,
+    // returning the first chunk to sell now; otherwise returns it whole
+    pub(crate) async fn chunk(&self, target: &TokenTarget, sell_amount: U256) -> Result<U256> {
+        let Some(chunking) = &self.chunking else { return Ok(sell_amount) };
+        let (token_reserve, weth_reserve) = self.reserves(target.address).await?;
+        let value = mul_div(sell_amount, weth_reserve, token_reserve);
+        if chunking.chunks < 2 || value < chunking.min_sell {
+            return Ok(sell_amount);
+        }
+
+        let (first, schedule) = split(sell_amount, chunking);
+        let last_due = schedule.back().map_or(Instant::now(), |(due, _)| *due);
+        println!(
+            "Splitting sell of {} (~{} ETH) into {} chunks over {:?}",
+            self.format_amount(target.address, sell_amount),
+            format_ether(value),
+            schedule.len() + 1,
+            last_due.saturating_duration_since(Instant::now())
+        );
+        self.chunked.lock().await.push(ChunkedSell { token: target.address, schedule });
+        Ok(first)
+    }
+
+    // Sells every chunk that has come due
+    pub(crate) async fn sell_due_chunks(&self) -> Result<()> {
+        let now = Instant::now();
+        let due: Vec<_> = {
+            let mut chunked = self.chunked.lock().await;
+            let mut due = Vec::new();
+            for sell in chunked.iter_mut() {
+                while sell.schedule.front().is_some_and(|(at, _)| *at <= now) {
+                    let (_, amount) = sell.schedule.pop_front().expect("checked above");
+                    due.push((sell.token, amount, sell.schedule.len()));
+                }
+            }
+            chunked.retain(|sell| !sell.schedule.is_empty());
+            due
+        };
+
+        for (token, amount, left) in due {
+            println!("Selling chunk of {} ({} chunks left)", self.format_amount(token, amount), left);
+            let sold = match self.paper {
+                Some(_) => self.paper_fill(token, amount, None).await,
+                None => self.dispose(token, amount).await,
+            };
+            if let Err(e) = sold {
+                if e.ends_session() {
+                    return Err(e);
+                }
+                println!("Chunk of {} failed: {}", self.format_amount(token, amount), e);
+            }
+        }
+        Ok(())
+    }
+}
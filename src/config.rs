@@ -0,0 +1,2327 @@
+use crate::allowance::{ApprovalMode, Approvals};
+use crate::bot::TokenTarget;
+use crate::bundle::{BundleRelay, DEFAULT_BUNDLE_RELAY};
+use crate::chainlink::PriceFeeds;
+use crate::chains::{parse_chain, CustomChain, CHAINS};
+use crate::chunks::Chunking;
+use ethers::{
+    signers::LocalWallet,
+    types::{Address, Bytes, H256, U256},
+    utils::{parse_ether, parse_units, to_checksum},
+};
+use crate::cooldown::Cooldowns;
+use crate::counterparty::Counterparties;
+use crate::decode::PathMatch;
+use crate::error::{MktmkrError, Result};
+use crate::exits::{PriceExit, PriceLevel};
+use crate::fees::{FeeMode, L1Fee};
+use crate::flow::NetFlow;
+use crate::oracle::{TwapGate, TwapSide};
+use crate::intents::IntentOrders;
+use crate::forks::{is_v2_fork, V2Overrides, V2_FORKS};
+use crate::grid::{Grid, GridStrategy};
+use crate::jitter::Jitter;
+use crate::market_data::{Exchange, ReferenceFeed};
+use crate::market_making::{MarketMaker, MarketMaking, QuoteSide};
+use crate::mempool::PendingTxMode;
+use crate::momentum::{Momentum, MomentumStrategy};
+use crate::params::StrategyParams;
+use crate::paths::PathSearch;
+use crate::private::DEFAULT_PRIVATE_RPC;
+use crate::profile::{Profile, PRESETS};
+use crate::queue::BacklogPolicy;
+use crate::schedule::{Schedule, TradingWindow};
+use crate::signer::KeySource;
+use crate::sizing::{SellSizing, SizingCurve};
+use crate::skew::{InventorySkew, SkewCurve};
+use crate::sweep::Sweep;
+use crate::source::{BloxrouteSource, EthFeedSource, FiberSource, MempoolSource, MevShareSource};
+use crate::strategy::{SellIntoBuys, Strategy, StrategyKind};
+use crate::target::{BuyBack, OnTarget, Target};
+use crate::trigger::TriggerMode;
+use crate::twap::Twap;
+use crate::routing::{AggregatorApi, AggregatorKind, Venue, DEFAULT_ONE_INCH_API, DEFAULT_ZERO_EX_API};
+use crate::unwind::Unwind;
+use crate::v4::V4Pool;
+use crate::volatility::VolatilityScaling;
+use crate::watchlist::WatchedWallet;
+use crate::weth::WethProceeds;
+use serde::Deserialize;
+use chrono::DateTime;
+use std::{collections::{BTreeMap, BTreeSet, HashSet}, env, fs, path::Path, str::FromStr, sync::Arc, time::{Duration, SystemTime}};
+
+pub const DEFAULT_CONFIG_PATH: &str = "mktmkr.toml";
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub provider: ProviderConfig,
+    pub chains: Vec<ChainConfig>,
+    pub wallet: KeySource,
+    pub tokens: Vec<TokenConfig>,
+    pub markets: Vec<MarketConfig>,
+    pub strategy: StrategyConfig,
+    pub limits: LimitsConfig,
+    pub supervisor: SupervisorConfig,
+    pub schedule: Vec<WindowConfig>,
+    pub profiles: BTreeMap<String, ProfileConfig>,
+    pub watchlist: Vec<WatchConfig>,
+    pub counterparties: CounterpartyConfig,
+    pub approvals: ApprovalConfig,
+    pub submission: SubmissionConfig,
+    pub bundles: BundleConfig,
+    pub chunking: ChunkingConfig,
+    pub twap: Vec<TwapConfig>,
+    pub weth: WethConfig,
+    pub intents: IntentsConfig,
+    pub chainlink: ChainlinkConfig,
+    pub reference: ReferenceConfig,
+    pub sweep: SweepConfig,
+    pub lp: LpConfig,
+    pub routing: RoutingConfig,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ProviderConfig {
+    pub ws_url: String,
+    // Registry name ("base") or chain id the endpoints must be on; any
+    // chain is accepted unless set
+    pub chain: Option<String>,
+    // Overrides the chain's preset block time
+    pub block_time_ms: Option<u64>,
+    // "none", "op_stack" or "arbitrum"; overrides the chain's L1 data fee
+    pub l1_fee: Option<L1Fee>,
+    // More endpoints on the same chain, for markets using ws_url
+    pub fallback_urls: Vec<String>,
+    // Endpoints each sell must be accepted by
+    pub broadcast_quorum: usize,
+    // "hashes", "full", "alchemy" or "txpool"
+    pub pending_txs: PendingTxMode,
+    // Hash lookups in flight at once in "hashes" mode
+    pub fetch_concurrency: usize,
+    // Polling interval in "txpool" mode and over HTTP
+    pub txpool_poll_ms: u64,
+    // Provider-side filtering to the router, where supported ("alchemy")
+    pub filter_recipients: bool,
+    // Private feeds merged with the node's pending transactions
+    pub feeds: Vec<FeedConfig>,
+}
+
+// A [[chains]] entry, selected by name or id through provider.chain or
+// markets[].chain; settings left out come from the registry chain of the
+// same id, if any
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ChainConfig {
+    pub name: String,
+    pub id: u64,
+    // Endpoint for markets on the chain without their own ws_url
+    pub rpc_url: Option<String>,
+    pub wrapped_native: Option<String>,
+    // Transactions are logged as <explorer_url>/tx/<hash>
+    pub explorer_url: Option<String>,
+    // "eip1559", or "legacy" for chains without it
+    pub gas: Option<FeeMode>,
+    // "none", "op_stack" or "arbitrum"
+    pub l1_fee: Option<L1Fee>,
+    pub block_time_ms: Option<u64>,
+}
+
+// A [[provider.feeds]] entry
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FeedConfig {
+    // "bloxroute", "fiber", "eden" or "mev_share"
+    pub kind: String,
+    // Defaults to the public endpoint for bloXroute, Fiber and MEV-Share
+    pub url: Option<String>,
+    // Environment variable holding the API key or authorization header;
+    // MEV-Share needs none
+    pub auth_var: Option<String>,
+}
+
+impl Default for ProviderConfig {
+    fn default() -> Self {
+        Self {
+            ws_url: String::new(),
+            chain: None,
+            block_time_ms: None,
+            l1_fee: None,
+            fallback_urls: Vec::new(),
+            broadcast_quorum: 1,
+            pending_txs: PendingTxMode::default(),
+            fetch_concurrency: 16,
+            txpool_poll_ms: 1_000,
+            filter_recipients: true,
+            feeds: Vec::new(),
+        }
+    }
+}
+
+// Per-token settings; unset values fall back to [strategy] and [limits]
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TokenConfig {
+    pub address: String,
+    pub sell_percentage: Option<f64>,
+    pub target_eth: Option<f64>,
+    // Any target kind; use instead of target_eth
+    pub target: Option<TargetConfig>,
+    pub on_target: Option<String>,
+    pub reduced_sell_percentage: Option<f64>,
+    pub buy_back_dip_percentage: Option<f64>,
+    pub min_buy_eth: Option<f64>,
+    pub min_sell_output_eth: Option<f64>,
+    pub min_sell_output_usd: Option<f64>,
+    // Chainlink feed pricing the token in USD
+    pub usd_feed: Option<String>,
+    // Its market on the reference exchange, e.g. "PEPEUSDT" or "PEPE-USD"
+    pub cex_symbol: Option<String>,
+    // Overrides submission.private
+    pub private: Option<bool>,
+    // Sell limits in raw token units; excess is queued and sold later
+    pub max_sell_per_tx: Option<String>,
+    pub max_sell_per_hour: Option<String>,
+    // Named profile; explicit values above still take precedence over it
+    pub profile: Option<String>,
+    // Starting inventory in raw token units for paper trading
+    pub paper_inventory: Option<String>,
+    // "v2", "v3", "v4", "curve", "balancer", "aerodrome" or "best"; overrides
+    // routing.venue
+    pub venue: Option<Venue>,
+    // Tokens V2 sells pass through to WETH, e.g. [USDC]
+    pub sell_path: Option<Vec<String>>,
+    // Its Uniswap V4 pool against native ETH
+    pub v4_pool: Option<V4PoolConfig>,
+    // Its Curve pool holding ETH or WETH, by address
+    pub curve_pool: Option<String>,
+    // Its Balancer V2 pool holding WETH, by 32-byte pool id
+    pub balancer_pool: Option<String>,
+    // Price levels checked every block
+    pub take_profit: Option<ExitConfig>,
+    pub stop_loss: Option<ExitConfig>,
+    // Sell everything once the price retraces this far from its session high
+    pub trailing_stop_percentage: Option<f64>,
+}
+
+// take_profit = { price_eth = 0.002, percentage = 25.0 } or
+// stop_loss = { price_usd = 0.5 }: one price per whole token, and for a
+// take-profit the percentage of the inventory to sell; a stop-loss sells it
+// all
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ExitConfig {
+    pub price_eth: Option<f64>,
+    pub price_usd: Option<f64>,
+    pub percentage: Option<f64>,
+}
+
+impl ExitConfig {
+    fn resolve(&self, field: &str, take_profit: bool) -> Result<PriceExit> {
+        let level = match (self.price_eth, self.price_usd) {
+            (Some(price), None) => {
+                validate_target_eth(&format!("{}.price_eth", field), price)?;
+                PriceLevel::Eth(price)
+            }
+            (None, Some(price)) => {
+                validate_target_eth(&format!("{}.price_usd", field), price)?;
+                PriceLevel::Usd(price)
+            }
+            _ => return Err(MktmkrError::Config(format!("{} needs exactly one of price_eth or price_usd", field))),
+        };
+        let percentage = match (take_profit, self.percentage) {
+            (true, Some(percentage)) => percentage,
+            (true, None) => return Err(MktmkrError::Config(format!("{}.percentage is required", field))),
+            (false, None) => 100.0,
+            (false, Some(_)) => {
+                return Err(MktmkrError::Config(format!("{} sells everything; percentage isn't accepted", field)))
+            }
+        };
+        validate_sell_percentage(&format!("{}.percentage", field), percentage)?;
+        Ok(PriceExit { level, percentage })
+    }
+}
+
+// Exactly one of: target = { eth_received = 50.0 }, { tokens_sold =
+// "1000000" } (raw units) or { usd_received = 25000.0 }
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TargetConfig {
+    pub eth_received: Option<f64>,
+    pub tokens_sold: Option<String>,
+    pub usd_received: Option<f64>,
+}
+
+impl TargetConfig {
+    fn resolve(&self, field: &str) -> Result<Target> {
+        match (self.eth_received, &self.tokens_sold, self.usd_received) {
+            (Some(eth), None, None) => {
+                validate_target_eth(field, eth)?;
+                let wei = parse_ether(eth).map_err(|e| MktmkrError::Config(format!("{}: {}", field, e)))?;
+                Ok(Target::EthReceived(wei))
+            }
+            (None, Some(amount), None) => Ok(Target::TokensSold(parse_limit(field, amount)?)),
+            (None, None, Some(usd)) => {
+                validate_target_eth(field, usd)?;
+                Ok(Target::UsdReceived(usd))
+            }
+            _ => Err(MktmkrError::Config(format!(
+                "{} needs exactly one of eth_received, tokens_sold or usd_received",
+                field
+            ))),
+        }
+    }
+}
+
+// A token's v4_pool = { fee = 3000, tick_spacing = 60, hooks = "0x..." }
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct V4PoolConfig {
+    // In hundredths of a basis point, as in the pool key
+    pub fee: u32,
+    pub tick_spacing: i32,
+    pub hooks: Option<String>,
+    // Hex bytes passed to the hooks
+    pub hook_data: Option<String>,
+}
+
+impl V4PoolConfig {
+    fn resolve(&self, field: &str) -> Result<V4Pool> {
+        // 0x800000 marks a dynamic-fee pool
+        if self.fee > 1_000_000 && self.fee != 0x800000 {
+            return Err(MktmkrError::Config(format!("{}.fee must be at most 1000000, got {}", field, self.fee)));
+        }
+        if !(1..=32_767).contains(&self.tick_spacing) {
+            return Err(MktmkrError::Config(format!("{}.tick_spacing must be 1 to 32767, got {}", field, self.tick_spacing)));
+        }
+        Ok(V4Pool {
+            fee: self.fee,
+            tick_spacing: self.tick_spacing,
+            hooks: match &self.hooks {
+                Some(hooks) => parse_address(&format!("{}.hooks", field), hooks)?,
+                None => Address::zero(),
+            },
+            hook_data: match &self.hook_data {
+                Some(data) => data.parse().map_err(|e| MktmkrError::Config(format!("{}.hook_data: {}", field, e)))?,
+                None => Bytes::new(),
+            },
+        })
+    }
+}
+
+// Sender filters for detected buys
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CounterpartyConfig {
+    pub blacklist: Vec<String>,
+    // Empty means every sender not blacklisted
+    pub whitelist: Vec<String>,
+}
+
+// How sells reach block builders
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SubmissionConfig {
+    // Send sells through private_rpc_urls instead of the public mempool;
+    // tokens can override it
+    pub private: bool,
+    pub private_rpc_urls: Vec<String>,
+    // Private sells unmined after this long are also sent publicly
+    pub public_fallback_seconds: u64,
+}
+
+impl Default for SubmissionConfig {
+    fn default() -> Self {
+        Self {
+            private: false,
+            private_rpc_urls: vec![DEFAULT_PRIVATE_RPC.to_string()],
+            public_fallback_seconds: 60,
+        }
+    }
+}
+
+// A [[twap]] entry: sell `amount` (raw units) of a configured token evenly
+// over `duration` ("4h") alongside the reactive strategy
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TwapConfig {
+    pub token: String,
+    pub amount: String,
+    pub duration: String,
+    // One a minute when unset
+    pub slices: Option<usize>,
+}
+
+// Splitting of large sells
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ChunkingConfig {
+    pub enabled: bool,
+    pub chunks: usize,
+    // Sells worth less than this at spot go whole
+    pub min_sell_eth: f64,
+    pub min_delay_seconds: u64,
+    pub max_delay_seconds: u64,
+}
+
+impl Default for ChunkingConfig {
+    fn default() -> Self {
+        Self { enabled: false, chunks: 4, min_sell_eth: 1.0, min_delay_seconds: 12, max_delay_seconds: 60 }
+    }
+}
+
+// WETH proceeds
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WethConfig {
+    pub sell_to_weth: bool,
+    pub unwrap_threshold_eth: f64,
+}
+
+impl Default for WethConfig {
+    fn default() -> Self {
+        Self { sell_to_weth: false, unwrap_threshold_eth: 0.5 }
+    }
+}
+
+// CoW Protocol orders for sells nothing waits on
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct IntentsConfig {
+    pub enabled: bool,
+    // The chain's order book unless set
+    pub api_url: Option<String>,
+    pub validity_seconds: u64,
+}
+
+impl Default for IntentsConfig {
+    fn default() -> Self {
+        Self { enabled: false, api_url: None, validity_seconds: 1800 }
+    }
+}
+
+// Chainlink feeds valuing ETH and tokens in USD
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ChainlinkConfig {
+    pub enabled: bool,
+    // The chain's ETH/USD feed unless set
+    pub eth_usd_feed: Option<String>,
+    pub max_age_seconds: u64,
+}
+
+impl Default for ChainlinkConfig {
+    fn default() -> Self {
+        Self { enabled: false, eth_usd_feed: None, max_age_seconds: 3600 }
+    }
+}
+
+// Exchange tickers giving a reference price for tokens with a cex_symbol
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ReferenceConfig {
+    pub enabled: bool,
+    // "binance" or "coinbase"
+    pub exchange: Exchange,
+    // The exchange's public websocket unless set
+    pub url: Option<String>,
+    pub max_age_seconds: u64,
+    // Buys don't trigger sells while the pool's price is further than this
+    // from the reference
+    pub max_divergence_bps: Option<u64>,
+}
+
+impl Default for ReferenceConfig {
+    fn default() -> Self {
+        Self { enabled: false, exchange: Exchange::Binance, url: None, max_age_seconds: 60, max_divergence_bps: None }
+    }
+}
+
+// Venue selection and the aggregator APIs quoted for "best"
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RoutingConfig {
+    // For tokens that don't name their own
+    pub venue: Venue,
+    // V2 deployment preset, the chain's unless set; any contract set below
+    // overrides its own
+    pub v2_fork: Option<String>,
+    pub router: Option<String>,
+    pub factory: Option<String>,
+    pub init_code_hash: Option<String>,
+    pub weth: Option<String>,
+    // Tokens searched as hops when a direct WETH pair holds less than
+    // min_direct_liquidity_eth
+    pub hop_tokens: Vec<String>,
+    pub min_direct_liquidity_eth: f64,
+    // Sells pay out limits.usd_token instead of ETH
+    pub sell_to_usd: bool,
+    // Each API is quoted only with its key set
+    pub zero_ex_api_key: Option<String>,
+    pub zero_ex_api_url: String,
+    pub one_inch_api_key: Option<String>,
+    pub one_inch_api_url: String,
+}
+
+impl Default for RoutingConfig {
+    fn default() -> Self {
+        Self {
+            venue: Venue::V2,
+            v2_fork: None,
+            router: None,
+            factory: None,
+            init_code_hash: None,
+            weth: None,
+            hop_tokens: Vec::new(),
+            min_direct_liquidity_eth: 10.0,
+            sell_to_usd: false,
+            zero_ex_api_key: None,
+            zero_ex_api_url: DEFAULT_ZERO_EX_API.to_string(),
+            one_inch_api_key: None,
+            one_inch_api_url: DEFAULT_ONE_INCH_API.to_string(),
+        }
+    }
+}
+
+// Proceeds sweep
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SweepConfig {
+    // Off when unset
+    pub to: Option<String>,
+    pub float_eth: f64,
+}
+
+impl Default for SweepConfig {
+    fn default() -> Self {
+        Self { to: None, float_eth: 1.0 }
+    }
+}
+
+// Liquidity provision, through the lp commands
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LpConfig {
+    // Where deposits are recorded across runs
+    pub state_file: String,
+}
+
+impl Default for LpConfig {
+    fn default() -> Self {
+        Self { state_file: "mktmkr-lp.json".into() }
+    }
+}
+
+// Bundle mode
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BundleConfig {
+    pub enabled: bool,
+    pub relay_url: String,
+    // Hex key signing relay requests; random for each run when unset
+    pub identity_key: Option<String>,
+    // Backruns of MEV-Share hints: the share of their profit refunded to the
+    // buyer, and how many blocks they may land in
+    pub mev_share_refund_percent: u64,
+    pub mev_share_blocks: u64,
+    // More builders each bundle is sent to alongside relay_url
+    pub builders: Vec<BuilderConfig>,
+}
+
+// A [[bundles.builders]] entry
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BuilderConfig {
+    // Matched against the extra data of blocks including our bundles
+    pub name: String,
+    pub url: String,
+    // Defaults to bundles.identity_key
+    pub identity_key: Option<String>,
+}
+
+impl Default for BundleConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            relay_url: DEFAULT_BUNDLE_RELAY.to_string(),
+            identity_key: None,
+            mev_share_refund_percent: 90,
+            mev_share_blocks: 3,
+            builders: Vec::new(),
+        }
+    }
+}
+
+// Router allowance handling
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ApprovalConfig {
+    pub auto_approve: bool,
+    // "exact" approves the wallet's balance; "infinite" the maximum
+    pub mode: ApprovalMode,
+    pub gas_limit: Option<u64>,
+    pub max_fee_gwei: Option<f64>,
+    pub revoke_on_exit: bool,
+}
+
+// A [[watchlist]] entry
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WatchConfig {
+    pub address: String,
+    // Shown in logs; defaults to the address
+    pub label: Option<String>,
+    // Share of the wallet's sells to sell alongside it
+    pub mirror_percentage: Option<f64>,
+}
+
+// A daily UTC trading session, e.g. days = ["mon", "fri"], start = "14:00",
+// end = "18:00"; no days means every day
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WindowConfig {
+    pub days: Vec<String>,
+    pub start: String,
+    pub end: String,
+}
+
+// An independently supervised bot; `ws_url` falls back to [provider]
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MarketConfig {
+    pub name: String,
+    pub ws_url: Option<String>,
+    // Falls back to provider.chain
+    pub chain: Option<String>,
+    pub pending_txs: Option<PendingTxMode>,
+    pub tokens: Vec<TokenConfig>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SupervisorConfig {
+    pub max_restarts: u32,
+    pub initial_backoff_ms: u64,
+    pub max_backoff_ms: u64,
+}
+
+impl Default for SupervisorConfig {
+    fn default() -> Self {
+        Self { max_restarts: 5, initial_backoff_ms: 1_000, max_backoff_ms: 60_000 }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StrategyConfig {
+    pub sell_percentage: f64,
+    // Profile for tokens that don't name their own
+    pub profile: Option<String>,
+    // "mempool" sells into pending buys; "confirmed" waits for inclusion;
+    // "swap_logs" watches pair Swap logs instead of the mempool
+    pub trigger: TriggerMode,
+    // In "swap_logs" mode, wait this long after a buy before selling
+    pub swap_log_delay_ms: u64,
+    // "output" counts swaps ending in a token; "any_hop" also counts swaps
+    // routed through it
+    pub path_match: PathMatch,
+    // When non-zero, buys only trigger sells while pending buys minus
+    // pending sells over this many seconds exceed net_flow_threshold_eth
+    pub net_flow_window_seconds: u64,
+    pub net_flow_threshold_eth: f64,
+    // When non-zero, buys only trigger sells while spot is twap_margin_bps
+    // above (or below) the pool's TWAP over this many seconds
+    pub twap_window_seconds: u64,
+    pub twap_side: TwapSide,
+    pub twap_margin_bps: u64,
+    // "sell_into_buys", or "market_making" to also buy into sells
+    pub kind: StrategyKind,
+    pub market_making: MarketMakingConfig,
+    pub skew: SkewConfig,
+    pub grid: GridConfig,
+    pub volatility: VolatilityConfig,
+    pub momentum: MomentumConfig,
+    pub sizing: SizingConfig,
+}
+
+// [strategy.momentum]
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MomentumConfig {
+    // Either window, or both; zero leaves one unset
+    pub window_seconds: u64,
+    pub window_blocks: u64,
+    pub threshold_eth: f64,
+    pub cooldown_seconds: u64,
+}
+
+impl Default for MomentumConfig {
+    fn default() -> Self {
+        Self { window_seconds: 60, window_blocks: 0, threshold_eth: 1.0, cooldown_seconds: 30 }
+    }
+}
+
+// [strategy.sizing]
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SizingConfig {
+    // "linear", "sqrt" or "piecewise"
+    pub curve: SizingCurve,
+    pub pivot_eth: f64,
+    // [buy ETH, factor] pairs, for piecewise
+    pub points: Vec<[f64; 2]>,
+    pub floor_eth: f64,
+    pub cap_eth: Option<f64>,
+}
+
+impl Default for SizingConfig {
+    fn default() -> Self {
+        Self { curve: SizingCurve::default(), pivot_eth: 1.0, points: Vec::new(), floor_eth: 0.0, cap_eth: None }
+    }
+}
+
+// [strategy.volatility]
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct VolatilityConfig {
+    pub enabled: bool,
+    // Price moves, one per block the pool trades in, estimated over
+    pub window: usize,
+    pub baseline_bps: f64,
+    pub min_sell_percentage: f64,
+    pub max_sell_percentage: f64,
+}
+
+impl Default for VolatilityConfig {
+    fn default() -> Self {
+        Self { enabled: false, window: 30, baseline_bps: 50.0, min_sell_percentage: 2.0, max_sell_percentage: 50.0 }
+    }
+}
+
+// [strategy.grid]
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GridConfig {
+    pub spacing_bps: u64,
+    pub levels: u32,
+    pub order_eth: f64,
+    // ETH per whole token
+    pub center: Option<f64>,
+    pub state_file: String,
+}
+
+impl Default for GridConfig {
+    fn default() -> Self {
+        Self { spacing_bps: 200, levels: 5, order_eth: 0.05, center: None, state_file: "mktmkr-grid.json".into() }
+    }
+}
+
+// [strategy.skew]
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SkewConfig {
+    pub enabled: bool,
+    // market_making.target_ratio in market-making mode, else 0.5
+    pub target_ratio: Option<f64>,
+    // "linear", "quadratic" or "exponential"
+    pub curve: SkewCurve,
+    pub strength: f64,
+    pub max_factor: f64,
+}
+
+impl Default for SkewConfig {
+    fn default() -> Self {
+        Self { enabled: false, target_ratio: None, curve: SkewCurve::default(), strength: 1.0, max_factor: 4.0 }
+    }
+}
+
+// [strategy.market_making]
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MarketMakingConfig {
+    // Share of the wallet's token plus ETH value to keep in the token
+    pub target_ratio: f64,
+    // Blocks the spot price is averaged over without a [reference] feed
+    pub reference_blocks: u64,
+    pub max_buy_eth: Option<f64>,
+    pub buy: QuoteSideConfig,
+    pub sell: QuoteSideConfig,
+}
+
+impl Default for MarketMakingConfig {
+    fn default() -> Self {
+        Self {
+            target_ratio: 0.5,
+            reference_blocks: 20,
+            max_buy_eth: None,
+            buy: QuoteSideConfig::default(),
+            sell: QuoteSideConfig::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct QuoteSideConfig {
+    pub size_percentage: f64,
+    pub spread_bps: u64,
+}
+
+impl Default for QuoteSideConfig {
+    fn default() -> Self {
+        Self { size_percentage: 50.0, spread_bps: 100 }
+    }
+}
+
+impl Default for StrategyConfig {
+    fn default() -> Self {
+        Self {
+            sell_percentage: 10.0,
+            profile: None,
+            trigger: TriggerMode::default(),
+            swap_log_delay_ms: 0,
+            path_match: PathMatch::default(),
+            net_flow_window_seconds: 0,
+            net_flow_threshold_eth: 0.0,
+            twap_window_seconds: 0,
+            twap_side: TwapSide::Above,
+            twap_margin_bps: 0,
+            kind: StrategyKind::default(),
+            market_making: MarketMakingConfig::default(),
+            skew: SkewConfig::default(),
+            grid: GridConfig::default(),
+            volatility: VolatilityConfig::default(),
+            momentum: MomentumConfig::default(),
+            sizing: SizingConfig::default(),
+        }
+    }
+}
+
+// A [profiles.<name>] entry. Fields left unset come from the built-in preset
+// of the same name, or from [strategy] for custom profiles.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ProfileConfig {
+    pub sell_percentage: Option<f64>,
+    pub slippage_bps: Option<u32>,
+    pub gas_price_multiplier: Option<f64>,
+    // Raw token units
+    pub max_sell_per_tx: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LimitsConfig {
+    pub target_eth: f64,
+    // Default target of any kind; takes precedence over target_eth
+    pub target: Option<TargetConfig>,
+    // Stablecoin for USD targets; defaults to the chain's USDC
+    pub usd_token: Option<String>,
+    // After a token's target: "stop", "sweep" the remaining inventory,
+    // "reduce" to reduced_sell_percentage, or "buy_back" once the price is
+    // buy_back_dip_percentage below the average sell price
+    pub on_target: String,
+    pub reduced_sell_percentage: Option<f64>,
+    pub buy_back_dip_percentage: Option<f64>,
+    // Share of a cycle's ETH proceeds each buy-back spends
+    pub buy_back_spend_percentage: f64,
+    // At expiry, sell what's left of tokens short of their target in
+    // unwind_chunks sells, stopping if price impact exceeds the bound
+    pub unwind_at_expiry: bool,
+    pub unwind_chunks: u32,
+    pub unwind_max_slippage_bps: u32,
+    // Ignore buys smaller than this, e.g. dust and bot probes
+    pub min_buy_eth: f64,
+    // Ignore buys bidding an effective gas price below this
+    pub min_effective_gas_gwei: Option<f64>,
+    // Session end: a duration from startup ("90m", "4h") or an RFC 3339
+    // timestamp ("2026-10-15T18:00:00Z")
+    pub expiry: String,
+    pub swap_deadline_seconds: u64,
+    // Sells accept no less than the quote minus this, unless the token's
+    // profile sets its own slippage_bps
+    pub slippage_bps: u32,
+    // Sells quoted below this are skipped; the USD floor needs [chainlink]
+    pub min_sell_output_eth: f64,
+    pub min_sell_output_usd: Option<f64>,
+    // Buys included longer than this after being seen are only sold into
+    // if the pool's price hasn't fallen since
+    pub buy_ttl_seconds: u64,
+    pub max_gas_price_gwei: Option<f64>,
+    // Sells expected to cost more than this in fees, L1 data fee included,
+    // are skipped
+    pub max_sell_fee_eth: Option<f64>,
+    // "eip1559", or "legacy" for chains without it; the chain's unless set
+    pub fee_mode: Option<FeeMode>,
+    // Percentile of each recent block's tips sampled for the priority fee
+    pub priority_fee_percentile: f64,
+    // Sell orders waiting for execution at most; what happens to new ones
+    // beyond that, and whether triggers for a token merge into one order
+    pub queue_depth: usize,
+    pub backlog_policy: BacklogPolicy,
+    pub coalesce_sells: bool,
+    // Orders wait a random 0 to reaction_jitter_ms before executing, and
+    // skip_trigger_percentage of buys are ignored at random
+    pub reaction_jitter_ms: u64,
+    pub skip_trigger_percentage: f64,
+    // Buys within this long of the sender's last trigger, or of anyone's,
+    // are held back and sold into together once it passes
+    pub buyer_cooldown_seconds: u64,
+    pub global_cooldown_seconds: u64,
+    // Sells count toward progress once this many blocks deep; those unmined
+    // after receipt_timeout_seconds are no longer tracked
+    pub confirmations: u64,
+    pub receipt_timeout_seconds: u64,
+    // Sells unmined this many blocks after sending are rebid
+    // fee_bump_percent higher, at most max_fee_bumps times and never above
+    // max_escalated_fee_gwei (max_gas_price_gwei when unset); 0 disables
+    pub escalate_after_blocks: u64,
+    pub fee_bump_percent: u64,
+    pub max_fee_bumps: u32,
+    pub max_escalated_fee_gwei: Option<f64>,
+    // Simulate each sell against the pending block before sending it
+    pub preflight: bool,
+    // Measure each token's transfer taxes at startup
+    pub detect_taxes: bool,
+    // Attach an eth_createAccessList access list to every sell
+    pub access_lists: bool,
+    // Sells must quote this many percent more than their gas cost
+    pub gas_margin_percent: f64,
+    // Sells are shrunk to keep their price impact within this
+    pub max_price_impact_bps: Option<u64>,
+}
+
+impl Default for LimitsConfig {
+    fn default() -> Self {
+        Self {
+            target_eth: 100.0,
+            target: None,
+            usd_token: None,
+            on_target: "stop".to_string(),
+            reduced_sell_percentage: None,
+            buy_back_dip_percentage: None,
+            buy_back_spend_percentage: 100.0,
+            unwind_at_expiry: false,
+            unwind_chunks: 4,
+            unwind_max_slippage_bps: 100,
+            min_buy_eth: 0.0,
+            expiry: "1h".to_string(),
+            swap_deadline_seconds: 300,
+            slippage_bps: 100,
+            min_sell_output_eth: 0.0,
+            min_sell_output_usd: None,
+            buy_ttl_seconds: 120,
+            max_gas_price_gwei: None,
+            max_sell_fee_eth: None,
+            fee_mode: None,
+            priority_fee_percentile: 50.0,
+            queue_depth: 32,
+            backlog_policy: BacklogPolicy::default(),
+            coalesce_sells: true,
+            reaction_jitter_ms: 0,
+            skip_trigger_percentage: 0.0,
+            buyer_cooldown_seconds: 0,
+            global_cooldown_seconds: 0,
+            confirmations: 1,
+            receipt_timeout_seconds: 300,
+            escalate_after_blocks: 3,
+            fee_bump_percent: 15,
+            max_fee_bumps: 5,
+            max_escalated_fee_gwei: None,
+            preflight: true,
+            detect_taxes: true,
+            access_lists: false,
+            gas_margin_percent: 0.0,
+            max_price_impact_bps: None,
+            min_effective_gas_gwei: None,
+        }
+    }
+}
+
+impl Config {
+    // Parses a TOML or YAML file, chosen by extension (TOML unless .yaml/.yml)
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = fs::read_to_string(path)
+            .map_err(|e| MktmkrError::Config(format!("failed to read {}: {}", path.display(), e)))?;
+
+        let config = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("yaml") | Some("yml") => serde_yaml::from_str(&contents)
+                .map_err(|e| MktmkrError::Config(format!("{}: {}", path.display(), e)))?,
+            _ => toml::from_str(&contents)
+                .map_err(|e| MktmkrError::Config(format!("{}: {}", path.display(), e)))?,
+        };
+
+        Ok(config)
+    }
+
+    // MKTMKR_* variables take precedence over values from the file
+    pub fn apply_env_overrides(&mut self) -> Result<()> {
+        if let Ok(ws_url) = env::var("MKTMKR_WS_URL") {
+            self.provider.ws_url = ws_url;
+        }
+        if let Ok(tokens) = env::var("MKTMKR_TOKEN") {
+            self.tokens = tokens
+                .split(',')
+                .map(|address| TokenConfig { address: address.trim().to_string(), ..Default::default() })
+                .collect();
+        }
+        if let Ok(value) = env::var("MKTMKR_SELL_PERCENTAGE") {
+            self.strategy.sell_percentage = value.parse()
+                .map_err(|e| MktmkrError::Config(format!("MKTMKR_SELL_PERCENTAGE: {}", e)))?;
+        }
+        if let Ok(value) = env::var("MKTMKR_TARGET_ETH") {
+            self.limits.target_eth = value.parse()
+                .map_err(|e| MktmkrError::Config(format!("MKTMKR_TARGET_ETH: {}", e)))?;
+            self.limits.target = None;
+        }
+        if let Ok(value) = env::var("MKTMKR_MIN_BUY_ETH") {
+            self.limits.min_buy_eth = value.parse()
+                .map_err(|e| MktmkrError::Config(format!("MKTMKR_MIN_BUY_ETH: {}", e)))?;
+        }
+        if let Ok(expiry) = env::var("MKTMKR_EXPIRY") {
+            self.limits.expiry = expiry;
+        }
+        if let Ok(profile) = env::var("MKTMKR_PROFILE") {
+            self.strategy.profile = Some(profile);
+        }
+
+        Ok(())
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        self.validate_chains()?;
+        if !self.provider.ws_url.is_empty() {
+            validate_endpoint("provider.ws_url", &self.provider.ws_url)?;
+            validate_pending_txs("provider.pending_txs", &self.provider.ws_url, self.provider.pending_txs)?;
+        }
+        for (i, market) in self.markets().iter().enumerate() {
+            self.market_chain(market)?;
+            self.market_custom_chain(market)?;
+            validate_endpoint(&self.market_ws_field(i, market), self.market_ws_url(market))?;
+            validate_pending_txs("provider.pending_txs", self.market_ws_url(market), self.market_pending_txs(market))?;
+        }
+        if self.provider.block_time_ms == Some(0) {
+            return Err(MktmkrError::Config("provider.block_time_ms must be positive".into()));
+        }
+        for (i, url) in self.provider.fallback_urls.iter().enumerate() {
+            validate_endpoint(&format!("provider.fallback_urls[{}]", i), url)?;
+            validate_pending_txs("provider.pending_txs", url, self.provider.pending_txs)?;
+        }
+        if self.provider.broadcast_quorum == 0 || self.provider.broadcast_quorum > self.provider.fallback_urls.len() + 1 {
+            return Err(MktmkrError::Config(format!(
+                "provider.broadcast_quorum must be between 1 and {}, got {}",
+                self.provider.fallback_urls.len() + 1,
+                self.provider.broadcast_quorum
+            )));
+        }
+
+        self.wallet.validate()?;
+
+        for (i, feed) in self.provider.feeds.iter().enumerate() {
+            match (feed.kind.as_str(), &feed.url) {
+                ("bloxroute" | "fiber" | "mev_share", _) | ("eden", Some(_)) => {}
+                ("eden", None) => return Err(MktmkrError::Config(format!("provider.feeds[{}].url is required for eden", i))),
+                (kind, _) => {
+                    return Err(MktmkrError::Config(format!(
+                        "provider.feeds[{}].kind must be \"bloxroute\", \"fiber\", \"eden\" or \"mev_share\", got {:?}",
+                        i, kind
+                    )))
+                }
+            }
+            if feed.auth_var.is_none() && feed.kind != "mev_share" {
+                return Err(MktmkrError::Config(format!("provider.feeds[{}].auth_var is required", i)));
+            }
+        }
+        if self.provider.txpool_poll_ms == 0 {
+            return Err(MktmkrError::Config("provider.txpool_poll_ms must be greater than zero".into()));
+        }
+        if self.provider.fetch_concurrency == 0 {
+            return Err(MktmkrError::Config("provider.fetch_concurrency must be greater than zero".into()));
+        }
+
+        validate_sell_percentage("strategy.sell_percentage", self.strategy.sell_percentage)?;
+        validate_eth_amount("strategy.net_flow_threshold_eth", self.strategy.net_flow_threshold_eth)?;
+        validate_target_eth("limits.target_eth", self.limits.target_eth)?;
+        if let Some(target) = &self.limits.target {
+            target.resolve("limits.target")?;
+        }
+        if let Some(usd_token) = &self.limits.usd_token {
+            parse_address("limits.usd_token", usd_token)?;
+        }
+        parse_on_target(
+            "limits.on_target",
+            &self.limits.on_target,
+            self.limits.reduced_sell_percentage,
+            self.limits.buy_back_dip_percentage,
+            self.limits.buy_back_spend_percentage,
+        )?;
+        if self.limits.unwind_chunks == 0 {
+            return Err(MktmkrError::Config("limits.unwind_chunks must be greater than zero".into()));
+        }
+        if self.limits.unwind_max_slippage_bps > 10_000 {
+            return Err(MktmkrError::Config(format!(
+                "limits.unwind_max_slippage_bps must be at most 10000, got {}",
+                self.limits.unwind_max_slippage_bps
+            )));
+        }
+        validate_eth_amount("limits.min_buy_eth", self.limits.min_buy_eth)?;
+        if let Some(usd) = self.limits.min_sell_output_usd {
+            validate_eth_amount("limits.min_sell_output_usd", usd)?;
+        }
+
+        for name in self.profiles.keys() {
+            self.profile(name)?.validate()?;
+        }
+        if let Some(name) = &self.strategy.profile {
+            self.profile(name)?;
+        }
+
+        if self.markets.is_empty() {
+            self.validate_tokens("tokens", &self.tokens)?;
+        } else if !self.tokens.is_empty() {
+            return Err(MktmkrError::Config("use either top-level [[tokens]] or [[markets]], not both".into()));
+        }
+
+        let mut names = Vec::new();
+        for (i, market) in self.markets.iter().enumerate() {
+            if market.name.is_empty() || names.contains(&&market.name) {
+                return Err(MktmkrError::Config(format!("markets[{}].name must be unique and non-empty", i)));
+            }
+            names.push(&market.name);
+
+            validate_pending_txs(
+                &format!("markets[{}].pending_txs", i),
+                self.market_ws_url(market),
+                self.market_pending_txs(market),
+            )?;
+            self.validate_tokens(&format!("markets[{}].tokens", i), &market.tokens)?;
+        }
+
+        if let Some(max_gas_price_gwei) = self.limits.max_gas_price_gwei {
+            if !max_gas_price_gwei.is_finite() || max_gas_price_gwei <= 0.0 {
+                return Err(MktmkrError::Config(format!(
+                    "limits.max_gas_price_gwei must be positive, got {}",
+                    max_gas_price_gwei
+                )));
+            }
+        }
+
+        if !(0.0..=100.0).contains(&self.limits.priority_fee_percentile) {
+            return Err(MktmkrError::Config(format!(
+                "limits.priority_fee_percentile must be between 0 and 100, got {}",
+                self.limits.priority_fee_percentile
+            )));
+        }
+
+        if self.limits.confirmations == 0 {
+            return Err(MktmkrError::Config("limits.confirmations must be greater than zero".into()));
+        }
+
+        if !self.limits.gas_margin_percent.is_finite() || self.limits.gas_margin_percent < 0.0 {
+            return Err(MktmkrError::Config(format!(
+                "limits.gas_margin_percent must not be negative, got {}",
+                self.limits.gas_margin_percent
+            )));
+        }
+
+        if self.limits.max_price_impact_bps.is_some_and(|bps| bps == 0 || bps >= 10_000) {
+            return Err(MktmkrError::Config(format!(
+                "limits.max_price_impact_bps must be between 1 and 9999, got {}",
+                self.limits.max_price_impact_bps.unwrap_or_default()
+            )));
+        }
+
+        if self.limits.fee_bump_percent < 10 {
+            return Err(MktmkrError::Config(format!(
+                "limits.fee_bump_percent must be at least 10, the minimum bump nodes accept, got {}",
+                self.limits.fee_bump_percent
+            )));
+        }
+        if let Some(max_escalated_fee_gwei) = self.limits.max_escalated_fee_gwei {
+            if !max_escalated_fee_gwei.is_finite() || max_escalated_fee_gwei <= 0.0 {
+                return Err(MktmkrError::Config(format!(
+                    "limits.max_escalated_fee_gwei must be positive, got {}",
+                    max_escalated_fee_gwei
+                )));
+            }
+        }
+
+        if self.limits.queue_depth == 0 {
+            return Err(MktmkrError::Config("limits.queue_depth must be greater than zero".into()));
+        }
+
+        if let Some(max_sell_fee_eth) = self.limits.max_sell_fee_eth {
+            validate_eth_amount("limits.max_sell_fee_eth", max_sell_fee_eth)?;
+        }
+        if let Some(min_effective_gas_gwei) = self.limits.min_effective_gas_gwei {
+            if !min_effective_gas_gwei.is_finite() || min_effective_gas_gwei <= 0.0 {
+                return Err(MktmkrError::Config(format!(
+                    "limits.min_effective_gas_gwei must be positive, got {}",
+                    min_effective_gas_gwei
+                )));
+            }
+        }
+
+        if self.deadline()? <= SystemTime::now() {
+            return Err(MktmkrError::Config(format!("limits.expiry {:?} is in the past", self.limits.expiry)));
+        }
+
+        self.schedule()?;
+        self.watchlist()?;
+        self.counterparties()?;
+        self.approvals()?;
+        self.bundle_relays()?;
+        self.chunking()?;
+        self.twaps()?;
+        self.twap_gate()?;
+        self.weth_proceeds()?;
+        self.intents()?;
+        self.chainlink()?;
+        self.reference()?;
+        self.sweep()?;
+        self.aggregator_apis()?;
+        self.v2_overrides()?;
+        self.path_search()?;
+        if self.routing.sell_to_usd && self.weth.sell_to_weth {
+            return Err(MktmkrError::Config("routing.sell_to_usd and weth.sell_to_weth can't both be set".into()));
+        }
+        for (i, url) in self.submission.private_rpc_urls.iter().enumerate() {
+            validate_endpoint(&format!("submission.private_rpc_urls[{}]", i), url)?;
+        }
+        let private = self.submission.private
+            || self.tokens.iter().chain(self.markets.iter().flat_map(|market| &market.tokens)).any(|token| token.private == Some(true));
+        if private && self.submission.private_rpc_urls.is_empty() {
+            return Err(MktmkrError::Config("submission.private_rpc_urls is empty but sells are set to go privately".into()));
+        }
+
+        if self.limits.slippage_bps > 10_000 {
+            return Err(MktmkrError::Config(format!(
+                "limits.slippage_bps must be at most 10000, got {}",
+                self.limits.slippage_bps
+            )));
+        }
+
+        if self.limits.swap_deadline_seconds == 0 {
+            return Err(MktmkrError::Config("limits.swap_deadline_seconds must be greater than zero".into()));
+        }
+
+        Ok(())
+    }
+
+    // The configured [[markets]], or a single "default" market built from
+    // the top-level [provider] and [[tokens]]
+    pub fn markets(&self) -> Vec<MarketConfig> {
+        if !self.markets.is_empty() {
+            return self.markets.clone();
+        }
+        vec![MarketConfig {
+            name: "default".to_string(),
+            ws_url: None,
+            chain: None,
+            pending_txs: None,
+            tokens: self.tokens.clone(),
+        }]
+    }
+
+    // The market's own endpoint, else its [[chains]] entry's, else
+    // provider.ws_url
+    pub fn market_ws_url<'a>(&'a self, market: &'a MarketConfig) -> &'a str {
+        let chain_rpc = self.chain_entry(market).and_then(|(_, chain)| chain.rpc_url.as_deref());
+        market.ws_url.as_deref().or(chain_rpc).unwrap_or(&self.provider.ws_url)
+    }
+
+    // Names the setting market_ws_url took the endpoint from
+    fn market_ws_field(&self, i: usize, market: &MarketConfig) -> String {
+        match (&market.ws_url, self.chain_entry(market)) {
+            (Some(_), _) => format!("markets[{}].ws_url", i),
+            (None, Some((k, chain))) if chain.rpc_url.is_some() => format!("chains[{}].rpc_url", k),
+            (None, _) => "provider.ws_url".to_string(),
+        }
+    }
+
+    // Fallbacks belong to provider.ws_url, so markets with their own
+    // endpoint or their chain's get none
+    pub fn market_fallback_urls(&self, market: &MarketConfig) -> &[String] {
+        match self.market_ws_url(market) == self.provider.ws_url {
+            true => &self.provider.fallback_urls,
+            false => &[],
+        }
+    }
+
+    // The market's chain setting, its own or provider.chain
+    fn market_chain_setting<'a>(&'a self, market: &'a MarketConfig) -> Option<&'a str> {
+        market.chain.as_deref().or(self.provider.chain.as_deref())
+    }
+
+    // The [[chains]] entry the market's chain names, by name or id, with
+    // its index
+    fn chain_entry(&self, market: &MarketConfig) -> Option<(usize, &ChainConfig)> {
+        let chain = self.market_chain_setting(market)?;
+        self.chains
+            .iter()
+            .enumerate()
+            .find(|(_, entry)| entry.name.eq_ignore_ascii_case(chain) || chain.parse() == Ok(entry.id))
+    }
+
+    // The market's chain as defined in [[chains]], if it is
+    pub fn market_custom_chain(&self, market: &MarketConfig) -> Result<Option<CustomChain>> {
+        let Some((i, chain)) = self.chain_entry(market) else { return Ok(None) };
+        let wrapped_native = match &chain.wrapped_native {
+            Some(weth) => Some(parse_address(&format!("chains[{}].wrapped_native", i), weth)?),
+            None => None,
+        };
+        Ok(Some(CustomChain {
+            id: chain.id,
+            name: chain.name.clone(),
+            rpc_url: chain.rpc_url.clone(),
+            wrapped_native,
+            explorer_url: chain.explorer_url.clone(),
+            fee_mode: chain.gas,
+            l1_fee: chain.l1_fee,
+            block_time: chain.block_time_ms.map(Duration::from_millis),
+        }))
+    }
+
+    fn validate_chains(&self) -> Result<()> {
+        let mut names = Vec::new();
+        for (i, chain) in self.chains.iter().enumerate() {
+            let name = chain.name.to_lowercase();
+            if name.is_empty() || name.parse::<u64>().is_ok() || names.contains(&name) {
+                return Err(MktmkrError::Config(format!("chains[{}].name must be unique, non-empty and not a number", i)));
+            }
+            names.push(name);
+            if chain.id == 0 {
+                return Err(MktmkrError::Config(format!("chains[{}].id must be set", i)));
+            }
+            if self.chains[..i].iter().any(|other| other.id == chain.id) {
+                return Err(MktmkrError::Config(format!("chains[{}]: chain {} is defined twice", i, chain.id)));
+            }
+            if let Some(rpc_url) = &chain.rpc_url {
+                validate_endpoint(&format!("chains[{}].rpc_url", i), rpc_url)?;
+            }
+            if let Some(weth) = &chain.wrapped_native {
+                parse_address(&format!("chains[{}].wrapped_native", i), weth)?;
+            }
+            if let Some(explorer_url) = &chain.explorer_url {
+                if !explorer_url.starts_with("https://") && !explorer_url.starts_with("http://") {
+                    return Err(MktmkrError::Config(format!(
+                        "chains[{}].explorer_url must be an http:// or https:// URL, got {:?}",
+                        i, explorer_url
+                    )));
+                }
+            }
+            if chain.block_time_ms == Some(0) {
+                return Err(MktmkrError::Config(format!("chains[{}].block_time_ms must be positive", i)));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn market_pending_txs(&self, market: &MarketConfig) -> PendingTxMode {
+        market.pending_txs.unwrap_or(self.provider.pending_txs)
+    }
+
+    // The chain id the market's endpoints must be on, if pinned
+    pub fn market_chain(&self, market: &MarketConfig) -> Result<Option<u64>> {
+        if let Some((_, chain)) = self.chain_entry(market) {
+            return Ok(Some(chain.id));
+        }
+        let (field, chain) = match &market.chain {
+            Some(chain) => (format!("markets.{}.chain", market.name), chain),
+            None => match &self.provider.chain {
+                Some(chain) => ("provider.chain".to_string(), chain),
+                None => return Ok(None),
+            },
+        };
+        match parse_chain(chain) {
+            Some(id) => Ok(Some(id)),
+            None => {
+                let names: Vec<_> = CHAINS.iter().map(|chain| chain.name).chain(self.chains.iter().map(|chain| chain.name.as_str())).collect();
+                Err(MktmkrError::Config(format!("{} must be a chain id or one of {}, got {:?}", field, names.join(", "), chain)))
+            }
+        }
+    }
+
+    // Resolves limits.expiry to an absolute time; durations count from now
+    pub fn deadline(&self) -> Result<SystemTime> {
+        let expiry = self.limits.expiry.trim();
+        if let Ok(at) = DateTime::parse_from_rfc3339(expiry) {
+            return Ok(at.into());
+        }
+        let duration = humantime::parse_duration(expiry).map_err(|e| {
+            MktmkrError::Config(format!(
+                "limits.expiry {:?} is neither an RFC 3339 timestamp nor a duration: {}",
+                expiry, e
+            ))
+        })?;
+        Ok(SystemTime::now() + duration)
+    }
+
+    pub fn unwind(&self) -> Option<Unwind> {
+        self.limits.unwind_at_expiry.then_some(Unwind {
+            chunks: self.limits.unwind_chunks,
+            max_slippage_bps: self.limits.unwind_max_slippage_bps,
+        })
+    }
+
+    pub fn twap_gate(&self) -> Result<Option<TwapGate>> {
+        let strategy = &self.strategy;
+        if strategy.twap_window_seconds == 0 {
+            return Ok(None);
+        }
+        if strategy.twap_margin_bps >= 10_000 {
+            return Err(MktmkrError::Config(format!(
+                "strategy.twap_margin_bps must be below 10000, got {}",
+                strategy.twap_margin_bps
+            )));
+        }
+        Ok(Some(TwapGate {
+            window: Duration::from_secs(strategy.twap_window_seconds),
+            side: strategy.twap_side,
+            margin_bps: strategy.twap_margin_bps,
+        }))
+    }
+
+    pub fn trading_strategy(&self) -> Result<Arc<dyn Strategy>> {
+        match self.strategy.kind {
+            StrategyKind::SellIntoBuys => Ok(Arc::new(SellIntoBuys)),
+            StrategyKind::MarketMaking => Ok(Arc::new(MarketMaker::new(self.market_making()?))),
+            StrategyKind::Grid => Ok(Arc::new(GridStrategy::new(self.grid()?)?)),
+            StrategyKind::Momentum => Ok(Arc::new(MomentumStrategy::new(self.momentum()?))),
+        }
+    }
+
+    fn momentum(&self) -> Result<Momentum> {
+        let config = &self.strategy.momentum;
+        if config.window_seconds == 0 && config.window_blocks == 0 {
+            return Err(MktmkrError::Config(
+                "strategy.momentum needs a non-zero window_seconds or window_blocks".into(),
+            ));
+        }
+        validate_eth_amount("strategy.momentum.threshold_eth", config.threshold_eth)?;
+        Ok(Momentum {
+            window: (config.window_seconds > 0).then(|| Duration::from_secs(config.window_seconds)),
+            window_blocks: (config.window_blocks > 0).then_some(config.window_blocks),
+            threshold: parse_ether(config.threshold_eth)
+                .map_err(|e| MktmkrError::Config(format!("strategy.momentum.threshold_eth: {}", e)))?,
+            cooldown: Duration::from_secs(config.cooldown_seconds),
+        })
+    }
+
+    fn grid(&self) -> Result<Grid> {
+        let config = &self.strategy.grid;
+        if config.spacing_bps == 0 || config.spacing_bps >= 10_000 {
+            return Err(MktmkrError::Config(format!(
+                "strategy.grid.spacing_bps must be in [1, 9999], got {}",
+                config.spacing_bps
+            )));
+        }
+        if config.levels == 0 {
+            return Err(MktmkrError::Config("strategy.grid.levels must be greater than zero".into()));
+        }
+        validate_target_eth("strategy.grid.order_eth", config.order_eth)?;
+        if let Some(center) = config.center {
+            validate_target_eth("strategy.grid.center", center)?;
+        }
+        Ok(Grid {
+            spacing_bps: config.spacing_bps,
+            levels: config.levels,
+            order_eth: parse_ether(config.order_eth).map_err(|e| MktmkrError::Config(format!("strategy.grid.order_eth: {}", e)))?,
+            center: config.center,
+            state_path: config.state_file.clone().into(),
+        })
+    }
+
+    fn market_making(&self) -> Result<MarketMaking> {
+        let config = &self.strategy.market_making;
+        if self.strategy.trigger != TriggerMode::Mempool {
+            return Err(MktmkrError::Config("strategy.kind = \"market_making\" needs trigger = \"mempool\"".into()));
+        }
+        if !(config.target_ratio > 0.0 && config.target_ratio < 1.0) {
+            return Err(MktmkrError::Config(format!(
+                "strategy.market_making.target_ratio must be in (0, 1), got {}",
+                config.target_ratio
+            )));
+        }
+        if config.reference_blocks == 0 {
+            return Err(MktmkrError::Config("strategy.market_making.reference_blocks must be greater than zero".into()));
+        }
+        let mut sides = Vec::new();
+        for (name, side) in [("buy", &config.buy), ("sell", &config.sell)] {
+            validate_sell_percentage(&format!("strategy.market_making.{}.size_percentage", name), side.size_percentage)?;
+            if side.spread_bps >= 10_000 {
+                return Err(MktmkrError::Config(format!(
+                    "strategy.market_making.{}.spread_bps must be below 10000, got {}",
+                    name, side.spread_bps
+                )));
+            }
+            sides.push(QuoteSide { size_percentage: side.size_percentage, spread_bps: side.spread_bps });
+        }
+        let max_buy = match config.max_buy_eth {
+            Some(eth) => {
+                validate_target_eth("strategy.market_making.max_buy_eth", eth)?;
+                Some(parse_ether(eth).map_err(|e| MktmkrError::Config(format!("strategy.market_making.max_buy_eth: {}", e)))?)
+            }
+            None => None,
+        };
+        Ok(MarketMaking {
+            target_ratio: config.target_ratio,
+            buy: sides[0],
+            sell: sides[1],
+            max_buy,
+            reference_blocks: config.reference_blocks,
+        })
+    }
+
+    pub fn jitter(&self) -> Result<Option<Jitter>> {
+        let skip_percentage = self.limits.skip_trigger_percentage;
+        if !skip_percentage.is_finite() || !(0.0..100.0).contains(&skip_percentage) {
+            return Err(MktmkrError::Config(format!(
+                "limits.skip_trigger_percentage must be at least 0 and below 100, got {}",
+                skip_percentage
+            )));
+        }
+        if self.limits.reaction_jitter_ms == 0 && skip_percentage == 0.0 {
+            return Ok(None);
+        }
+        Ok(Some(Jitter { max_delay: Duration::from_millis(self.limits.reaction_jitter_ms), skip_percentage }))
+    }
+
+    pub fn sell_sizing(&self) -> Result<Option<SellSizing>> {
+        let config = &self.strategy.sizing;
+        if config.curve == SizingCurve::Linear && config.floor_eth == 0.0 && config.cap_eth.is_none() {
+            return Ok(None);
+        }
+        validate_target_eth("strategy.sizing.pivot_eth", config.pivot_eth)?;
+        if config.curve == SizingCurve::Piecewise && config.points.is_empty() {
+            return Err(MktmkrError::Config("strategy.sizing.curve = \"piecewise\" needs points".into()));
+        }
+        for (i, [eth, factor]) in config.points.iter().enumerate() {
+            validate_eth_amount(&format!("strategy.sizing.points[{}] buy ETH", i), *eth)?;
+            if !factor.is_finite() || *factor < 0.0 {
+                return Err(MktmkrError::Config(format!(
+                    "strategy.sizing.points[{}] factor must not be negative, got {}",
+                    i, factor
+                )));
+            }
+        }
+        if config.points.windows(2).any(|pair| pair[1][0] <= pair[0][0]) {
+            return Err(MktmkrError::Config("strategy.sizing.points must be in ascending order of buy ETH".into()));
+        }
+        validate_eth_amount("strategy.sizing.floor_eth", config.floor_eth)?;
+        let cap = match config.cap_eth {
+            Some(cap_eth) => {
+                validate_target_eth("strategy.sizing.cap_eth", cap_eth)?;
+                if cap_eth < config.floor_eth {
+                    return Err(MktmkrError::Config("strategy.sizing.cap_eth must not be below floor_eth".into()));
+                }
+                Some(parse_ether(cap_eth).map_err(|e| MktmkrError::Config(format!("strategy.sizing.cap_eth: {}", e)))?)
+            }
+            None => None,
+        };
+        Ok(Some(SellSizing {
+            curve: config.curve,
+            pivot_eth: config.pivot_eth,
+            points: config.points.iter().map(|[eth, factor]| (*eth, *factor)).collect(),
+            floor: parse_ether(config.floor_eth).map_err(|e| MktmkrError::Config(format!("strategy.sizing.floor_eth: {}", e)))?,
+            cap,
+        }))
+    }
+
+    pub fn cooldowns(&self) -> Option<Cooldowns> {
+        let (per_buyer, global) = (self.limits.buyer_cooldown_seconds, self.limits.global_cooldown_seconds);
+        if per_buyer == 0 && global == 0 {
+            return None;
+        }
+        Some(Cooldowns { per_buyer: Duration::from_secs(per_buyer), global: Duration::from_secs(global) })
+    }
+
+    pub fn volatility_scaling(&self) -> Result<Option<VolatilityScaling>> {
+        let config = &self.strategy.volatility;
+        if !config.enabled {
+            return Ok(None);
+        }
+        if config.window < 2 {
+            return Err(MktmkrError::Config(format!("strategy.volatility.window must be at least 2, got {}", config.window)));
+        }
+        if !config.baseline_bps.is_finite() || config.baseline_bps <= 0.0 {
+            return Err(MktmkrError::Config(format!(
+                "strategy.volatility.baseline_bps must be positive, got {}",
+                config.baseline_bps
+            )));
+        }
+        validate_sell_percentage("strategy.volatility.min_sell_percentage", config.min_sell_percentage)?;
+        validate_sell_percentage("strategy.volatility.max_sell_percentage", config.max_sell_percentage)?;
+        if config.min_sell_percentage > config.max_sell_percentage {
+            return Err(MktmkrError::Config(
+                "strategy.volatility.min_sell_percentage must not exceed max_sell_percentage".into(),
+            ));
+        }
+        Ok(Some(VolatilityScaling {
+            window: config.window,
+            baseline_bps: config.baseline_bps,
+            min_percentage: config.min_sell_percentage,
+            max_percentage: config.max_sell_percentage,
+        }))
+    }
+
+    pub fn inventory_skew(&self) -> Result<Option<InventorySkew>> {
+        let config = &self.strategy.skew;
+        if !config.enabled {
+            return Ok(None);
+        }
+        let target_ratio = match (config.target_ratio, self.strategy.kind) {
+            (Some(target_ratio), _) => target_ratio,
+            (None, StrategyKind::MarketMaking) => self.strategy.market_making.target_ratio,
+            (None, StrategyKind::SellIntoBuys | StrategyKind::Grid | StrategyKind::Momentum) => 0.5,
+        };
+        if !(target_ratio > 0.0 && target_ratio < 1.0) {
+            return Err(MktmkrError::Config(format!("strategy.skew.target_ratio must be in (0, 1), got {}", target_ratio)));
+        }
+        if !config.strength.is_finite() || config.strength < 0.0 {
+            return Err(MktmkrError::Config(format!("strategy.skew.strength must not be negative, got {}", config.strength)));
+        }
+        if !config.max_factor.is_finite() || config.max_factor < 1.0 {
+            return Err(MktmkrError::Config(format!("strategy.skew.max_factor must be at least 1, got {}", config.max_factor)));
+        }
+        Ok(Some(InventorySkew {
+            target_ratio,
+            curve: config.curve,
+            strength: config.strength,
+            max_factor: config.max_factor,
+        }))
+    }
+
+    pub fn net_flow(&self) -> Result<Option<NetFlow>> {
+        if self.strategy.net_flow_window_seconds == 0 {
+            return Ok(None);
+        }
+        let threshold = parse_ether(self.strategy.net_flow_threshold_eth)
+            .map_err(|e| MktmkrError::Config(format!("strategy.net_flow_threshold_eth: {}", e)))?;
+        Ok(Some(NetFlow::new(Duration::from_secs(self.strategy.net_flow_window_seconds), threshold)))
+    }
+
+    // Private feeds with their credentials read from the environment
+    pub fn feeds(&self) -> Result<Vec<Arc<dyn MempoolSource>>> {
+        self.provider
+            .feeds
+            .iter()
+            .enumerate()
+            .map(|(i, feed)| {
+                let url = |default: &str| feed.url.clone().unwrap_or_else(|| default.to_string());
+                if feed.kind == "mev_share" {
+                    return Ok(Arc::new(MevShareSource { url: url("https://mev-share.flashbots.net") }) as Arc<dyn MempoolSource>);
+                }
+                let auth_var = feed.auth_var.clone().unwrap_or_default();
+                let auth = env::var(&auth_var)
+                    .map_err(|_| MktmkrError::Config(format!("provider.feeds[{}]: {} is not set", i, auth_var)))?;
+                let source: Arc<dyn MempoolSource> = match feed.kind.as_str() {
+                    "bloxroute" => Arc::new(BloxrouteSource { url: url("wss://api.blxrbdn.com/ws"), auth }),
+                    "fiber" => Arc::new(FiberSource { url: url("https://beta.fiberapi.io:8080"), api_key: auth }),
+                    _ => Arc::new(EthFeedSource { name: feed.kind.clone(), url: url(""), auth: Some(auth) }),
+                };
+                Ok(source)
+            })
+            .collect()
+    }
+
+    pub fn max_sell_fee(&self) -> Result<Option<U256>> {
+        self.limits
+            .max_sell_fee_eth
+            .map(|eth| parse_ether(eth).map_err(|e| MktmkrError::Config(format!("limits.max_sell_fee_eth: {}", e))))
+            .transpose()
+    }
+
+    pub fn min_effective_gas_price(&self) -> Result<Option<U256>> {
+        self.limits
+            .min_effective_gas_gwei
+            .map(|gwei| {
+                parse_units(gwei, "gwei")
+                    .map(Into::into)
+                    .map_err(|e| MktmkrError::Config(format!("limits.min_effective_gas_gwei: {}", e)))
+            })
+            .transpose()
+    }
+
+    pub fn approvals(&self) -> Result<Approvals> {
+        let max_fee = self.approvals
+            .max_fee_gwei
+            .map(|gwei| {
+                if !gwei.is_finite() || gwei <= 0.0 {
+                    return Err(MktmkrError::Config(format!("approvals.max_fee_gwei must be positive, got {}", gwei)));
+                }
+                parse_units(gwei, "gwei")
+                    .map(Into::into)
+                    .map_err(|e| MktmkrError::Config(format!("approvals.max_fee_gwei: {}", e)))
+            })
+            .transpose()?;
+        Ok(Approvals {
+            auto_approve: self.approvals.auto_approve,
+            mode: self.approvals.mode,
+            gas_limit: self.approvals.gas_limit.map(U256::from),
+            max_fee,
+            revoke_on_exit: self.approvals.revoke_on_exit,
+        })
+    }
+
+    pub fn chunking(&self) -> Result<Option<Chunking>> {
+        let chunking = &self.chunking;
+        if !chunking.enabled {
+            return Ok(None);
+        }
+        if chunking.chunks < 2 {
+            return Err(MktmkrError::Config(format!("chunking.chunks must be at least 2, got {}", chunking.chunks)));
+        }
+        if chunking.min_delay_seconds > chunking.max_delay_seconds {
+            return Err(MktmkrError::Config("chunking.min_delay_seconds must not exceed max_delay_seconds".into()));
+        }
+        validate_eth_amount("chunking.min_sell_eth", chunking.min_sell_eth)?;
+        Ok(Some(Chunking {
+            chunks: chunking.chunks,
+            min_sell: parse_ether(chunking.min_sell_eth)
+                .map_err(|e| MktmkrError::Config(format!("chunking.min_sell_eth: {}", e)))?,
+            min_delay: Duration::from_secs(chunking.min_delay_seconds),
+            max_delay: Duration::from_secs(chunking.max_delay_seconds),
+        }))
+    }
+
+    pub fn weth_proceeds(&self) -> Result<Option<WethProceeds>> {
+        if !self.weth.sell_to_weth {
+            return Ok(None);
+        }
+        validate_eth_amount("weth.unwrap_threshold_eth", self.weth.unwrap_threshold_eth)?;
+        Ok(Some(WethProceeds {
+            unwrap_threshold: parse_ether(self.weth.unwrap_threshold_eth)
+                .map_err(|e| MktmkrError::Config(format!("weth.unwrap_threshold_eth: {}", e)))?,
+        }))
+    }
+
+    pub fn chainlink(&self) -> Result<Option<PriceFeeds>> {
+        let chainlink = &self.chainlink;
+        let all_tokens = || self.tokens.iter().chain(self.markets.iter().flat_map(|market| &market.tokens));
+        if !chainlink.enabled {
+            let usd = self.limits.min_sell_output_usd.is_some()
+                || all_tokens().any(|token| token.min_sell_output_usd.is_some() || token.usd_feed.is_some());
+            if usd {
+                return Err(MktmkrError::Config("min_sell_output_usd and usd_feed need chainlink.enabled".into()));
+            }
+            return Ok(None);
+        }
+        if chainlink.max_age_seconds == 0 {
+            return Err(MktmkrError::Config("chainlink.max_age_seconds must be positive".into()));
+        }
+        Ok(Some(PriceFeeds {
+            eth_usd: chainlink.eth_usd_feed.as_deref().map(|feed| parse_address("chainlink.eth_usd_feed", feed)).transpose()?,
+            max_age: Duration::from_secs(chainlink.max_age_seconds),
+        }))
+    }
+
+    pub fn reference(&self) -> Result<Option<ReferenceFeed>> {
+        let reference = &self.reference;
+        if !reference.enabled {
+            if self.tokens.iter().chain(self.markets.iter().flat_map(|market| &market.tokens)).any(|token| token.cex_symbol.is_some()) {
+                return Err(MktmkrError::Config("cex_symbol needs reference.enabled".into()));
+            }
+            return Ok(None);
+        }
+        if let Some(url) = &reference.url {
+            if !url.starts_with("ws://") && !url.starts_with("wss://") {
+                return Err(MktmkrError::Config(format!("reference.url must be a ws:// or wss:// URL, got {:?}", url)));
+            }
+        }
+        if reference.max_age_seconds == 0 {
+            return Err(MktmkrError::Config("reference.max_age_seconds must be positive".into()));
+        }
+        Ok(Some(ReferenceFeed {
+            exchange: reference.exchange,
+            url: reference.url.clone(),
+            max_age: Duration::from_secs(reference.max_age_seconds),
+            max_divergence_bps: reference.max_divergence_bps,
+        }))
+    }
+
+    pub fn intents(&self) -> Result<Option<IntentOrders>> {
+        let intents = &self.intents;
+        if !intents.enabled {
+            return Ok(None);
+        }
+        if let Some(url) = &intents.api_url {
+            if !url.starts_with("http://") && !url.starts_with("https://") {
+                return Err(MktmkrError::Config(format!("intents.api_url must be an http:// or https:// URL, got {:?}", url)));
+            }
+        }
+        if intents.validity_seconds < 60 {
+            return Err(MktmkrError::Config(format!(
+                "intents.validity_seconds must be at least 60, got {}",
+                intents.validity_seconds
+            )));
+        }
+        Ok(Some(IntentOrders {
+            api_url: intents.api_url.as_deref().map(|url| url.trim_end_matches('/').to_string()),
+            validity: Duration::from_secs(intents.validity_seconds),
+        }))
+    }
+
+    pub fn v2_overrides(&self) -> Result<V2Overrides> {
+        let routing = &self.routing;
+        if let Some(v2_fork) = routing.v2_fork.as_deref().filter(|v2_fork| !is_v2_fork(v2_fork)) {
+            let presets: Vec<&str> = V2_FORKS.iter().map(|(name, ..)| *name).collect::<BTreeSet<_>>().into_iter().collect();
+            return Err(MktmkrError::Config(format!(
+                "routing.v2_fork must be one of {}, got {:?}",
+                presets.join(", "), v2_fork
+            )));
+        }
+        let address = |field: &str, value: &Option<String>| value.as_deref().map(|value| parse_address(field, value)).transpose();
+        Ok(V2Overrides {
+            router: address("routing.router", &routing.router)?,
+            factory: address("routing.factory", &routing.factory)?,
+            init_code_hash: routing
+                .init_code_hash
+                .as_deref()
+                .map(|hash| hash.parse().map_err(|e| MktmkrError::Config(format!("routing.init_code_hash: {}", e))))
+                .transpose()?,
+            weth: address("routing.weth", &routing.weth)?,
+        })
+    }
+
+    pub fn path_search(&self) -> Result<Option<PathSearch>> {
+        if self.routing.hop_tokens.is_empty() {
+            return Ok(None);
+        }
+        validate_eth_amount("routing.min_direct_liquidity_eth", self.routing.min_direct_liquidity_eth)?;
+        Ok(Some(PathSearch {
+            hops: self
+                .routing
+                .hop_tokens
+                .iter()
+                .enumerate()
+                .map(|(i, hop)| parse_address(&format!("routing.hop_tokens[{}]", i), hop))
+                .collect::<Result<_>>()?,
+            min_direct_liquidity: parse_ether(self.routing.min_direct_liquidity_eth)
+                .map_err(|e| MktmkrError::Config(format!("routing.min_direct_liquidity_eth: {}", e)))?,
+        }))
+    }
+
+    pub fn aggregator_apis(&self) -> Result<Vec<AggregatorApi>> {
+        let routing = &self.routing;
+        let apis = [
+            (AggregatorKind::ZeroEx, "routing.zero_ex_api_url", &routing.zero_ex_api_url, &routing.zero_ex_api_key),
+            (AggregatorKind::OneInch, "routing.one_inch_api_url", &routing.one_inch_api_url, &routing.one_inch_api_key),
+        ];
+        let mut configured = Vec::new();
+        for (kind, field, url, api_key) in apis {
+            let Some(api_key) = api_key else { continue };
+            if !url.starts_with("http://") && !url.starts_with("https://") {
+                return Err(MktmkrError::Config(format!("{} must be an http:// or https:// URL, got {:?}", field, url)));
+            }
+            configured.push(AggregatorApi::new(kind, url.trim_end_matches('/'), api_key));
+        }
+        Ok(configured)
+    }
+
+    pub fn sweep(&self) -> Result<Option<Sweep>> {
+        let Some(to) = &self.sweep.to else { return Ok(None) };
+        validate_eth_amount("sweep.float_eth", self.sweep.float_eth)?;
+        Ok(Some(Sweep {
+            to: parse_address("sweep.to", to)?,
+            float: parse_ether(self.sweep.float_eth).map_err(|e| MktmkrError::Config(format!("sweep.float_eth: {}", e)))?,
+        }))
+    }
+
+    // The main relay, which also simulates bundles and takes MEV-Share
+    // backruns, followed by the extra builders; empty unless bundle mode is on
+    pub fn bundle_relays(&self) -> Result<Vec<BundleRelay>> {
+        if !self.bundles.enabled {
+            return Ok(Vec::new());
+        }
+        let http = |field: &str, url: &str| match url.starts_with("http://") || url.starts_with("https://") {
+            true => Ok(()),
+            false => Err(MktmkrError::Config(format!("{} must be an http:// or https:// URL, got {:?}", field, url))),
+        };
+        let key = |field: &str, key: &String| {
+            key.parse::<LocalWallet>().map_err(|e| MktmkrError::Config(format!("{}: {}", field, e)))
+        };
+        http("bundles.relay_url", &self.bundles.relay_url)?;
+        // Builders without their own key share the main identity, random for
+        // the run when none is set
+        let identity = match &self.bundles.identity_key {
+            Some(identity_key) => key("bundles.identity_key", identity_key)?,
+            None => LocalWallet::new(&mut ethers::core::rand::thread_rng()),
+        };
+        if self.bundles.mev_share_refund_percent > 100 {
+            return Err(MktmkrError::Config(format!(
+                "bundles.mev_share_refund_percent must be at most 100, got {}",
+                self.bundles.mev_share_refund_percent
+            )));
+        }
+        if self.bundles.mev_share_blocks == 0 {
+            return Err(MktmkrError::Config("bundles.mev_share_blocks must be greater than zero".into()));
+        }
+        let mut relay = BundleRelay::new("flashbots", &self.bundles.relay_url, Some(identity.clone()));
+        relay.refund_percent = self.bundles.mev_share_refund_percent;
+        relay.mev_share_blocks = self.bundles.mev_share_blocks;
+
+        let mut relays = vec![relay];
+        for (i, builder) in self.bundles.builders.iter().enumerate() {
+            if builder.name.is_empty() {
+                return Err(MktmkrError::Config(format!("bundles.builders[{}].name must not be empty", i)));
+            }
+            http(&format!("bundles.builders[{}].url", i), &builder.url)?;
+            let identity = match &builder.identity_key {
+                Some(identity_key) => key(&format!("bundles.builders[{}].identity_key", i), identity_key)?,
+                None => identity.clone(),
+            };
+            relays.push(BundleRelay::new(&builder.name, &builder.url, Some(identity)));
+        }
+        Ok(relays)
+    }
+
+    pub fn max_escalated_fee(&self) -> Result<Option<U256>> {
+        self.limits
+            .max_escalated_fee_gwei
+            .map(|gwei| {
+                parse_units(gwei, "gwei")
+                    .map(Into::into)
+                    .map_err(|e| MktmkrError::Config(format!("limits.max_escalated_fee_gwei: {}", e)))
+            })
+            .transpose()
+    }
+
+    pub fn schedule(&self) -> Result<Schedule> {
+        let windows = self.schedule
+            .iter()
+            .enumerate()
+            .map(|(i, window)| {
+                TradingWindow::parse(&window.days, &window.start, &window.end)
+                    .map_err(|e| MktmkrError::Config(format!("schedule[{}]: {}", i, e)))
+            })
+            .collect::<Result<_>>()?;
+        Ok(Schedule { windows })
+    }
+
+    pub fn counterparties(&self) -> Result<Counterparties> {
+        let parse = |field: &str, addresses: &[String]| {
+            addresses
+                .iter()
+                .enumerate()
+                .map(|(i, address)| parse_address(&format!("counterparties.{}[{}]", field, i), address))
+                .collect::<Result<HashSet<_>>>()
+        };
+        let whitelist = parse("whitelist", &self.counterparties.whitelist)?;
+        Ok(Counterparties {
+            blacklist: parse("blacklist", &self.counterparties.blacklist)?,
+            whitelist: (!whitelist.is_empty()).then_some(whitelist),
+        })
+    }
+
+    pub fn watchlist(&self) -> Result<Vec<WatchedWallet>> {
+        self.watchlist
+            .iter()
+            .enumerate()
+            .map(|(i, watch)| {
+                let address = parse_address(&format!("watchlist[{}].address", i), &watch.address)?;
+                if let Some(mirror_percentage) = watch.mirror_percentage {
+                    validate_sell_percentage(&format!("watchlist[{}].mirror_percentage", i), mirror_percentage)?;
+                }
+                Ok(WatchedWallet {
+                    address,
+                    label: watch.label.clone().unwrap_or_else(|| format!("{:?}", address)),
+                    mirror_percentage: watch.mirror_percentage,
+                })
+            })
+            .collect()
+    }
+
+    pub fn twaps(&self) -> Result<Vec<Twap>> {
+        self.twap
+            .iter()
+            .enumerate()
+            .map(|(i, twap)| {
+                let token = parse_address(&format!("twap[{}].token", i), &twap.token)?;
+                let amount = parse_limit(&format!("twap[{}].amount", i), &twap.amount)?;
+                let duration = humantime::parse_duration(&twap.duration)
+                    .map_err(|e| MktmkrError::Config(format!("twap[{}].duration {:?}: {}", i, twap.duration, e)))?;
+                if duration.is_zero() || twap.slices == Some(0) {
+                    return Err(MktmkrError::Config(format!("twap[{}] needs a positive duration and slice count", i)));
+                }
+                Ok(Twap::new(token, amount, duration, twap.slices))
+            })
+            .collect()
+    }
+
+    // The hot-reloadable part of a market's configuration
+    pub fn strategy_params(&self, market: &MarketConfig) -> Result<StrategyParams> {
+        let max_gas_price = match self.limits.max_gas_price_gwei {
+            Some(gwei) => Some(
+                parse_units(gwei, "gwei")
+                    .map_err(|e| MktmkrError::Config(format!("limits.max_gas_price_gwei: {}", e)))?
+                    .into(),
+            ),
+            None => None,
+        };
+
+        Ok(StrategyParams {
+            tokens: self.token_targets(&market.tokens)?,
+            max_gas_price,
+        })
+    }
+
+    // (token, starting inventory) overrides for paper trading
+    pub fn paper_inventories(&self, market: &MarketConfig) -> Result<Vec<(Address, U256)>> {
+        let mut inventories = Vec::new();
+        for (i, token) in market.tokens.iter().enumerate() {
+            if let Some(inventory) = &token.paper_inventory {
+                let address = parse_address(&format!("tokens[{}].address", i), &token.address)?;
+                inventories.push((address, parse_amount(&format!("tokens[{}].paper_inventory", i), inventory)?));
+            }
+        }
+        Ok(inventories)
+    }
+
+    // Resolves token entries against the global defaults
+    pub fn token_targets(&self, tokens: &[TokenConfig]) -> Result<Vec<TokenTarget>> {
+        tokens
+            .iter()
+            .enumerate()
+            .map(|(i, token)| {
+                let mut target = TokenTarget::new(
+                    parse_address(&format!("tokens[{}].address", i), &token.address)?,
+                    self.strategy.sell_percentage,
+                    self.token_goal(i, token)?,
+                );
+                if let Some(name) = token.profile.as_ref().or(self.strategy.profile.as_ref()) {
+                    target.apply_profile(&self.profile(name)?);
+                }
+                if let Some(sell_percentage) = token.sell_percentage {
+                    target.sell_percentage = sell_percentage;
+                }
+                if let Some(max_sell_per_tx) = &token.max_sell_per_tx {
+                    let field = format!("tokens[{}].max_sell_per_tx", i);
+                    target.max_sell_per_tx = Some(parse_limit(&field, max_sell_per_tx)?);
+                }
+                if let Some(max_sell_per_hour) = &token.max_sell_per_hour {
+                    let field = format!("tokens[{}].max_sell_per_hour", i);
+                    target.max_sell_per_hour = Some(parse_limit(&field, max_sell_per_hour)?);
+                }
+                target.on_target = self.token_on_target(&format!("tokens[{}].on_target", i), token)?;
+                let min_buy_eth = token.min_buy_eth.unwrap_or(self.limits.min_buy_eth);
+                target.min_buy_eth = parse_ether(min_buy_eth)
+                    .map_err(|e| MktmkrError::Config(format!("tokens[{}].min_buy_eth: {}", i, e)))?;
+                let min_sell_output_eth = token.min_sell_output_eth.unwrap_or(self.limits.min_sell_output_eth);
+                target.min_sell_output = parse_ether(min_sell_output_eth)
+                    .map_err(|e| MktmkrError::Config(format!("tokens[{}].min_sell_output_eth: {}", i, e)))?;
+                target.min_sell_output_usd = token.min_sell_output_usd.or(self.limits.min_sell_output_usd);
+                if let Some(feed) = &token.usd_feed {
+                    target.usd_feed = Some(parse_address(&format!("tokens[{}].usd_feed", i), feed)?);
+                }
+                target.cex_symbol = token.cex_symbol.clone();
+                target.private = token.private.unwrap_or(self.submission.private);
+                target.venue = token.venue.unwrap_or(self.routing.venue);
+                if let Some(pool) = &token.v4_pool {
+                    target.v4_pool = Some(pool.resolve(&format!("tokens[{}].v4_pool", i))?);
+                }
+                if let Some(pool) = &token.curve_pool {
+                    target.curve_pool = Some(parse_address(&format!("tokens[{}].curve_pool", i), pool)?);
+                }
+                if let Some(pool) = &token.balancer_pool {
+                    target.balancer_pool = Some(parse_pool_id(&format!("tokens[{}].balancer_pool", i), pool)?);
+                }
+                if let Some(exit) = &token.take_profit {
+                    target.take_profit = Some(exit.resolve(&format!("tokens[{}].take_profit", i), true)?);
+                }
+                if let Some(exit) = &token.stop_loss {
+                    target.stop_loss = Some(exit.resolve(&format!("tokens[{}].stop_loss", i), false)?);
+                }
+                if let Some(retrace) = token.trailing_stop_percentage {
+                    validate_sell_percentage(&format!("tokens[{}].trailing_stop_percentage", i), retrace)?;
+                    target.trailing_stop_percentage = Some(retrace);
+                }
+                if let Some(path) = &token.sell_path {
+                    target.hops = path
+                        .iter()
+                        .enumerate()
+                        .map(|(j, hop)| parse_address(&format!("tokens[{}].sell_path[{}]", i, j), hop))
+                        .collect::<Result<_>>()?;
+                }
+                Ok(target)
+            })
+            .collect()
+    }
+
+    // Token target, then token target_eth, then the [limits] defaults
+    fn token_goal(&self, i: usize, token: &TokenConfig) -> Result<Target> {
+        if let Some(target) = &token.target {
+            return target.resolve(&format!("tokens[{}].target", i));
+        }
+        if let Some(target_eth) = token.target_eth {
+            let target = TargetConfig { eth_received: Some(target_eth), ..Default::default() };
+            return target.resolve(&format!("tokens[{}].target_eth", i));
+        }
+        match &self.limits.target {
+            Some(target) => target.resolve("limits.target"),
+            None => TargetConfig { eth_received: Some(self.limits.target_eth), ..Default::default() }
+                .resolve("limits.target_eth"),
+        }
+    }
+
+    fn token_on_target(&self, field: &str, token: &TokenConfig) -> Result<OnTarget> {
+        parse_on_target(
+            field,
+            token.on_target.as_deref().unwrap_or(&self.limits.on_target),
+            token.reduced_sell_percentage.or(self.limits.reduced_sell_percentage),
+            token.buy_back_dip_percentage.or(self.limits.buy_back_dip_percentage),
+            self.limits.buy_back_spend_percentage,
+        )
+    }
+
+    pub fn usd_token(&self) -> Result<Option<Address>> {
+        self.limits.usd_token.as_deref().map(|token| parse_address("limits.usd_token", token)).transpose()
+    }
+
+    // Resolves a profile name against [profiles] and the built-in presets
+    pub fn profile(&self, name: &str) -> Result<Profile> {
+        let overrides = self.profiles.get(name);
+        let mut profile = match (Profile::preset(name), overrides) {
+            (Some(preset), _) => preset,
+            (None, Some(_)) => Profile {
+                name: name.to_string(),
+                sell_percentage: self.strategy.sell_percentage,
+                slippage_bps: None,
+                gas_price_multiplier: 1.0,
+                max_sell_per_tx: None,
+            },
+            (None, None) => {
+                return Err(MktmkrError::Config(format!(
+                    "unknown profile {:?}; define it under [profiles] or use one of {}",
+                    name,
+                    PRESETS.join(", ")
+                )))
+            }
+        };
+
+        if let Some(overrides) = overrides {
+            let field = |key: &str| format!("profiles.{}.{}", name, key);
+            if let Some(sell_percentage) = overrides.sell_percentage {
+                validate_sell_percentage(&field("sell_percentage"), sell_percentage)?;
+                profile.sell_percentage = sell_percentage;
+            }
+            if let Some(slippage_bps) = overrides.slippage_bps {
+                profile.slippage_bps = Some(slippage_bps);
+            }
+            if let Some(multiplier) = overrides.gas_price_multiplier {
+                profile.gas_price_multiplier = multiplier;
+            }
+            if let Some(max_sell_per_tx) = &overrides.max_sell_per_tx {
+                profile.max_sell_per_tx = Some(parse_limit(&field("max_sell_per_tx"), max_sell_per_tx)?);
+            }
+        }
+
+        Ok(profile)
+    }
+
+    fn validate_tokens(&self, field: &str, tokens: &[TokenConfig]) -> Result<()> {
+        validate_tokens(field, tokens)?;
+        for (i, token) in tokens.iter().enumerate() {
+            if let Some(name) = &token.profile {
+                self.profile(name)?;
+            }
+            let on_target = self.token_on_target(&format!("{}[{}].on_target", field, i), token)?;
+            if self.routing.sell_to_usd && matches!(on_target, OnTarget::BuyBack(_)) {
+                return Err(MktmkrError::Config(format!(
+                    "{}[{}]: on_target = \"buy_back\" needs ETH proceeds, not routing.sell_to_usd",
+                    field, i
+                )));
+            }
+            if self.routing.sell_to_usd && matches!(self.token_goal(i, token)?, Target::EthReceived(_)) {
+                return Err(MktmkrError::Config(format!(
+                    "{}[{}] needs a usd_received or tokens_sold target with routing.sell_to_usd",
+                    field, i
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+fn parse_amount(field: &str, value: &str) -> Result<U256> {
+    U256::from_dec_str(value)
+        .map_err(|e| MktmkrError::Config(format!("{}: invalid amount {:?}: {}", field, value, e)))
+}
+
+fn parse_on_target(
+    field: &str,
+    action: &str,
+    reduced_sell_percentage: Option<f64>,
+    buy_back_dip_percentage: Option<f64>,
+    buy_back_spend_percentage: f64,
+) -> Result<OnTarget> {
+    match action {
+        "stop" => Ok(OnTarget::Stop),
+        "sweep" => Ok(OnTarget::Sweep),
+        "reduce" => {
+            let sell_percentage = reduced_sell_percentage.ok_or_else(|| {
+                MktmkrError::Config(format!("{} = \"reduce\" needs reduced_sell_percentage", field))
+            })?;
+            validate_sell_percentage("reduced_sell_percentage", sell_percentage)?;
+            Ok(OnTarget::Reduce(sell_percentage))
+        }
+        "buy_back" => {
+            let dip_percentage = buy_back_dip_percentage.ok_or_else(|| {
+                MktmkrError::Config(format!("{} = \"buy_back\" needs buy_back_dip_percentage", field))
+            })?;
+            validate_sell_percentage("buy_back_dip_percentage", dip_percentage)?;
+            validate_sell_percentage("limits.buy_back_spend_percentage", buy_back_spend_percentage)?;
+            Ok(OnTarget::BuyBack(BuyBack { dip_percentage, spend_percentage: buy_back_spend_percentage }))
+        }
+        other => Err(MktmkrError::Config(format!(
+            "{} must be \"stop\", \"reduce\", \"sweep\" or \"buy_back\", got {:?}",
+            field, other
+        ))),
+    }
+}
+
+fn parse_limit(field: &str, value: &str) -> Result<U256> {
+    let limit = parse_amount(field, value)?;
+    if limit.is_zero() {
+        return Err(MktmkrError::Config(format!("{} must be positive", field)));
+    }
+    Ok(limit)
+}
+
+// A ws://, wss://, http:// or https:// URL, or an IPC socket path
+fn validate_endpoint(field: &str, url: &str) -> Result<()> {
+    let scheme = url.split_once("://").map(|(scheme, _)| scheme);
+    if url.is_empty() || scheme.is_some_and(|scheme| !matches!(scheme, "ws" | "wss" | "http" | "https")) {
+        return Err(MktmkrError::Config(format!(
+            "{} must be a ws://, wss://, http:// or https:// URL or an IPC socket path, got {:?}",
+            field, url
+        )));
+    }
+    Ok(())
+}
+
+// HTTP endpoints can't push pending bodies; hashes are polled from a filter
+// and "txpool" polls the pool
+fn validate_pending_txs(field: &str, url: &str, mode: PendingTxMode) -> Result<()> {
+    let http = url.starts_with("http://") || url.starts_with("https://");
+    if http && matches!(mode, PendingTxMode::Full | PendingTxMode::Alchemy) {
+        return Err(MktmkrError::Config(format!("{} must be \"hashes\" or \"txpool\" over HTTP", field)));
+    }
+    Ok(())
+}
+
+fn validate_tokens(field: &str, tokens: &[TokenConfig]) -> Result<()> {
+    if tokens.is_empty() {
+        return Err(MktmkrError::Config(format!("{}: at least one token is required", field)));
+    }
+    let mut seen = Vec::new();
+    for (i, token) in tokens.iter().enumerate() {
+        let address = parse_address(&format!("{}[{}].address", field, i), &token.address)?;
+        if seen.contains(&address) {
+            return Err(MktmkrError::Config(format!("{}[{}]: {:?} is listed twice", field, i, address)));
+        }
+        seen.push(address);
+
+        if let Some(sell_percentage) = token.sell_percentage {
+            validate_sell_percentage(&format!("{}[{}].sell_percentage", field, i), sell_percentage)?;
+        }
+        if let Some(target_eth) = token.target_eth {
+            validate_target_eth(&format!("{}[{}].target_eth", field, i), target_eth)?;
+        }
+        if let Some(target) = &token.target {
+            if token.target_eth.is_some() {
+                return Err(MktmkrError::Config(format!("{}[{}]: set target or target_eth, not both", field, i)));
+            }
+            target.resolve(&format!("{}[{}].target", field, i))?;
+        }
+        if let Some(min_buy_eth) = token.min_buy_eth {
+            validate_eth_amount(&format!("{}[{}].min_buy_eth", field, i), min_buy_eth)?;
+        }
+        match &token.v4_pool {
+            Some(pool) => {
+                pool.resolve(&format!("{}[{}].v4_pool", field, i))?;
+            }
+            None if token.venue == Some(Venue::V4) => {
+                return Err(MktmkrError::Config(format!("{}[{}]: venue \"v4\" needs a v4_pool", field, i)));
+            }
+            None => {}
+        }
+        if let Some(usd) = token.min_sell_output_usd {
+            validate_eth_amount(&format!("{}[{}].min_sell_output_usd", field, i), usd)?;
+        }
+        if let Some(feed) = &token.usd_feed {
+            parse_address(&format!("{}[{}].usd_feed", field, i), feed)?;
+        }
+        match &token.curve_pool {
+            Some(pool) => {
+                parse_address(&format!("{}[{}].curve_pool", field, i), pool)?;
+            }
+            None if token.venue == Some(Venue::Curve) => {
+                return Err(MktmkrError::Config(format!("{}[{}]: venue \"curve\" needs a curve_pool", field, i)));
+            }
+            None => {}
+        }
+        match &token.balancer_pool {
+            Some(pool) => {
+                parse_pool_id(&format!("{}[{}].balancer_pool", field, i), pool)?;
+            }
+            None if token.venue == Some(Venue::Balancer) => {
+                return Err(MktmkrError::Config(format!("{}[{}]: venue \"balancer\" needs a balancer_pool", field, i)));
+            }
+            None => {}
+        }
+        if let Some(max_sell_per_tx) = &token.max_sell_per_tx {
+            parse_limit(&format!("{}[{}].max_sell_per_tx", field, i), max_sell_per_tx)?;
+        }
+        if let Some(max_sell_per_hour) = &token.max_sell_per_hour {
+            parse_limit(&format!("{}[{}].max_sell_per_hour", field, i), max_sell_per_hour)?;
+        }
+        if let Some(inventory) = &token.paper_inventory {
+            parse_amount(&format!("{}[{}].paper_inventory", field, i), inventory)?;
+        }
+    }
+    Ok(())
+}
+
+fn validate_sell_percentage(field: &str, value: f64) -> Result<()> {
+    if !(value > 0.0 && value <= 100.0) {
+        return Err(MktmkrError::Config(format!("{} must be in (0, 100], got {}", field, value)));
+    }
+    Ok(())
+}
+
+fn validate_target_eth(field: &str, value: f64) -> Result<()> {
+    if !value.is_finite() || value <= 0.0 {
+        return Err(MktmkrError::Config(format!("{} must be positive, got {}", field, value)));
+    }
+    Ok(())
+}
+
+fn validate_eth_amount(field: &str, value: f64) -> Result<()> {
+    if !value.is_finite() || value < 0.0 {
+        return Err(MktmkrError::Config(format!("{} must not be negative, got {}", field, value)));
+    }
+    Ok(())
+}
+
+// Rejects mixed-case addresses that fail the EIP-55 checksum; all-lower or
+// all-upper hex carries no checksum and is accepted as-is
+pub fn parse_address(field: &str, value: &str) -> Result<Address> {
+    let address = Address::from_str(value)
+        .map_err(|e| MktmkrError::Config(format!("{}: invalid address {:?}: {}", field, value, e)))?;
+
+    let hex = value.trim_start_matches("0x");
+    let mixed_case = hex.chars().any(|c| c.is_ascii_uppercase())
+        && hex.chars().any(|c| c.is_ascii_lowercase());
+    if mixed_case && to_checksum(&address, None).trim_start_matches("0x") != hex {
+        return Err(MktmkrError::Config(format!("{}: {} fails EIP-55 checksum", field, value)));
+    }
+
+    Ok(address)
+}
+
+// A Balancer pool id, 32 bytes of hex
+pub fn parse_pool_id(field: &str, value: &str) -> Result<H256> {
+    H256::from_str(value).map_err(|e| MktmkrError::Config(format!("{}: invalid pool id {:?}: {}", field, value, e)))
+}
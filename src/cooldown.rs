@@ -0,0 +1,149 @@
+use crate::bot::TradingBot;
+use crate::error::Result;
+use crate::strategy::{Side, Trade};
+use crate::trigger::{Seen, TriggerMode};
+use ethers::{
+    providers::Middleware,
+    types::{Address, H256, U256},
+    utils::format_ether,
+};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+// How soon buys may trigger again: from the same sender, and from anyone.
+// Buys arriving sooner are held back and sold into together once both have
+// passed, so a sender spamming small buys gets one larger sell.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Cooldowns {
+    pub per_buyer: Duration,
+    pub global: Duration,
+}
+
+// Buys of a token held back by cooldowns, by hash and ETH. Swap-log buys
+// have no hash and were included already.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct HeldBuys {
+    pub buys: Vec<(Option<H256>, U256)>,
+    // When they may trigger
+    pub ready: Instant,
+    // When the first of them was seen
+    pub seen: Seen,
+}
+
+impl HeldBuys {
+    pub fn eth(&self) -> U256 {
+        self.buys.iter().fold(U256::zero(), |acc, (_, eth)| acc + eth)
+    }
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct CooldownState {
+    buyers: HashMap<Address, Instant>,
+    last_trigger: Option<Instant>,
+    held: HashMap<Address, HeldBuys>,
+}
+
+impl CooldownState {
+    // Whether the buy may trigger now; otherwise it is held back. Hinted
+    // transactions hide their sender as zero and only cool down globally.
+    pub fn admit(
+        &mut self,
+        cooldowns: Cooldowns,
+        hash: Option<H256>,
+        sender: Address,
+        token: Address,
+        eth: U256,
+        seen: Seen,
+    ) -> bool {
+        let now = seen.at;
+        self.buyers.retain(|_, at| now.duration_since(*at) < cooldowns.per_buyer);
+        let buyer_ready = self.buyers.get(&sender).filter(|_| !sender.is_zero()).map(|at| *at + cooldowns.per_buyer);
+        let global_ready = self.last_trigger.map(|at| at + cooldowns.global).filter(|ready| *ready > now);
+        if buyer_ready.is_some() || global_ready.is_some() {
+            let ready = buyer_ready.into_iter().chain(global_ready).max().unwrap_or(now);
+            let held = self.held.entry(token).or_insert(HeldBuys { buys: Vec::new(), ready, seen });
+            held.buys.push((hash, eth));
+            held.ready = held.ready.max(ready);
+            return false;
+        }
+        if !sender.is_zero() {
+            self.buyers.insert(sender, now);
+        }
+        self.last_trigger = Some(now);
+        true
+    }
+
+    // Held-back buys per token whose cooldown has passed
+    pub fn release(&mut self, cooldowns: Cooldowns, now: Instant) -> Vec<(Address, HeldBuys)> {
+        if self.last_trigger.is_some_and(|at| now.duration_since(at) < cooldowns.global) {
+            return Vec::new();
+        }
+        let due: Vec<Address> = self.held.iter().filter(|(_, held)| held.ready <= now).map(|(token, _)| *token).collect();
+        if !due.is_empty() {
+            self.last_trigger = Some(now);
+        }
+        due.into_iter().filter_map(|token| self.held.remove(&token).map(|held| (token, held))).collect()
+    }
+
+    // Stops holding a cancelled or replaced buy; its ETH if it was held
+    pub fn forget(&mut self, hash: H256) -> Option<U256> {
+        let (token, held) = self.held.iter_mut().find(|(_, held)| held.buys.iter().any(|(held, _)| *held == Some(hash)))?;
+        let token = *token;
+        let at = held.buys.iter().position(|(held, _)| *held == Some(hash))?;
+        let (_, eth) = held.buys.remove(at);
+        if held.buys.is_empty() {
+            self.held.remove(&token);
+        }
+        Some(eth)
+    }
+}
+
+impl TradingBot {
+    // False when the buy is held back by a cooldown
+    pub(crate) async fn cooled_down(&self, hash: Option<H256>, sender: Address, token: Address, eth: U256, seen: Seen) -> bool {
+        let Some(cooldowns) = self.cooldowns else { return true };
+        // A held buy is re-checked against the price it was seen at
+        let seen = match seen.spot {
+            Some(_) => seen,
+            None => Seen { spot: self.spot(token).await.ok(), ..seen },
+        };
+        let admitted = self.cooldown_state.lock().await.admit(cooldowns, hash, sender, token, eth, seen);
+        if !admitted && self.dry_run {
+            println!("[dry-run] Holding back a buy of {:?} from {:?} until its cooldown passes", token, sender);
+        }
+        admitted
+    }
+
+    // Sells into buys held back by cooldowns, one merged trigger per token.
+    // In confirmed mode only included buys merge; those still pending are
+    // awaited like any other.
+    pub(crate) async fn release_cooled_down(&self) -> Result<()> {
+        let Some(cooldowns) = self.cooldowns else { return Ok(()) };
+        let released = self.cooldown_state.lock().await.release(cooldowns, Instant::now());
+        for (token, held) in released {
+            println!("Cooldown over; selling into {} ETH of held-back buys of {:?}", format_ether(held.eth()), token);
+            let seen = held.seen;
+            let mut eth = U256::zero();
+            for (hash, buy_eth) in held.buys {
+                let Some(hash) = hash.filter(|_| self.trigger == TriggerMode::Confirmed) else {
+                    eth += buy_eth;
+                    continue;
+                };
+                match self.provider.get_transaction_receipt(hash).await? {
+                    Some(receipt) if receipt.status == Some(1u64.into()) => eth += buy_eth,
+                    Some(_) => println!("Buy {:?} reverted; not selling into it", hash),
+                    None => {
+                        let trade = Trade { hash: Some(hash), token, side: Side::Buy, eth: buy_eth, amount: None, pending: true, seen };
+                        self.strategy.on_pending_tx(self, trade).await?;
+                    }
+                }
+            }
+            if eth.is_zero() {
+                continue;
+            }
+            let trade = Trade { hash: None, token, side: Side::Buy, eth, amount: None, pending: false, seen };
+            self.strategy.on_pending_tx(self, trade).await?;
+        }
+        Ok(())
+    }
+}
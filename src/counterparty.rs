@@ -0,0 +1,20 @@
+use ethers::types::Address;
+use std::collections::HashSet;
+
+// Which senders' buys are sold into. Other bots' probes and sandwich legs
+// look like retail buys but are usually unwound at our expense.
+#[derive(Debug, Clone, Default)]
+pub struct Counterparties {
+    pub blacklist: HashSet<Address>,
+    // When set, only these senders' buys are sold into
+    pub whitelist: Option<HashSet<Address>>,
+}
+
+impl Counterparties {
+    // Hinted transactions hide their sender as zero: never blacklisted, and
+    // never on a whitelist
+    pub fn allows(&self, sender: Address) -> bool {
+        !self.blacklist.contains(&sender)
+            && self.whitelist.as_ref().is_none_or(|whitelist| whitelist.contains(&sender))
+    }
+}
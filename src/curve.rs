@@ -0,0 +1,143 @@
+use crate::abi::{ICurveCryptoPool, ICurveLegacyPool, ICurvePool};
+use crate::bot::TradingBot;
+use crate::error::{MktmkrError, Result};
+use crate::math::mul_div;
+use ethers::{
+    signers::Signer,
+    types::{Address, Bytes, TransactionReceipt, H256, U256},
+    utils::keccak256,
+};
+use std::sync::Arc;
+
+// Gas a Curve sell is assumed to take
+pub(crate) const CURVE_SELL_GAS: u64 = 250_000;
+// How Curve pools list native ETH among their coins
+const CURVE_ETH: Address = Address::repeat_byte(0xee);
+// Most coins a Curve pool holds
+const MAX_COINS: u64 = 8;
+
+// A token's place in its Curve pool, read from the pool's coins
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CurvePool {
+    pub address: Address,
+    // Indices of the token and of the ETH or WETH it sells for
+    pub i: u64,
+    pub j: u64,
+    // Pays out native ETH rather than WETH
+    pub native: bool,
+    // Older pools index coins with int128
+    pub legacy: bool,
+}
+
+impl TradingBot {
+    // The token's configured Curve pool, looked up on first use
+    pub(crate) async fn curve_pool(&self, token: Address) -> Result<CurvePool> {
+        if let Some(pool) = self.curve_pools.lock().await.get(&token) {
+            return Ok(*pool);
+        }
+        let address = self
+            .token(token)
+            .and_then(|target| target.curve_pool)
+            .ok_or_else(|| MktmkrError::Config(format!("{:?} has no Curve pool configured", token)))?;
+        let client = Arc::new(self.provider.clone());
+        let contract = ICurvePool::new(address, client.clone());
+        let mut coins = Vec::new();
+        for k in 0..MAX_COINS {
+            match contract.coins(k.into()).call().await {
+                Ok(coin) => coins.push(coin),
+                Err(_) => break,
+            }
+        }
+        let i = coins.iter().position(|coin| *coin == token);
+        let j = coins.iter().position(|coin| *coin == self.weth || *coin == CURVE_ETH);
+        let (Some(i), Some(j)) = (i, j) else {
+            return Err(MktmkrError::Contract(format!(
+                "Curve pool {:?} doesn't hold both {:?} and ETH or WETH",
+                address, token
+            )));
+        };
+
+        // Quoting a sliver of the pool's balance tells the two indexings apart
+        let probe = (contract.balances(i.into()).call().await? / 1000).max(U256::one());
+        let legacy = match contract.get_dy(i.into(), j.into(), probe).call().await {
+            Ok(_) => false,
+            Err(_) => {
+                ICurveLegacyPool::new(address, client).get_dy(i as i128, j as i128, probe).call().await?;
+                true
+            }
+        };
+        let pool = CurvePool { address, i: i as u64, j: j as u64, native: coins[j] == CURVE_ETH, legacy };
+        self.curve_pools.lock().await.insert(token, pool);
+        Ok(pool)
+    }
+
+    async fn curve_dy(&self, pool: CurvePool, amount: U256) -> Result<U256> {
+        let client = Arc::new(self.provider.clone());
+        let amount_out = match pool.legacy {
+            true => ICurveLegacyPool::new(pool.address, client).get_dy(pool.i as i128, pool.j as i128, amount).call().await?,
+            false => ICurvePool::new(pool.address, client).get_dy(pool.i.into(), pool.j.into(), amount).call().await?,
+        };
+        Ok(amount_out)
+    }
+
+    // ETH or WETH the token's pool pays for `amount`, erring when it can't
+    // pay what sells are set to take. Pools holding WETH unwrap it when
+    // asked, which only those indexed by uint256 can be; pools holding
+    // native ETH can't pay WETH.
+    pub(crate) async fn curve_quote(&self, token: Address, amount: U256) -> Result<U256> {
+        let pool = self.curve_pool(token).await?;
+        match (pool.native, pool.legacy, self.weth_proceeds.is_none()) {
+            (true, _, false) => Err(MktmkrError::Config(format!("{:?}'s Curve pool pays out native ETH, not WETH", token))),
+            (false, true, true) => Err(MktmkrError::Config(format!("{:?}'s Curve pool only pays out WETH; sell to WETH", token))),
+            _ => self.curve_dy(pool, amount).await,
+        }
+    }
+
+    // Calldata selling `amount` into the token's pool, once quoted; None if
+    // it doesn't encode
+    pub(crate) async fn curve_sell_call(&self, token: Address, amount: U256, amount_out_min: U256) -> Result<Option<Bytes>> {
+        let pool = self.curve_pool(token).await?;
+        let client = Arc::new(self.provider.clone());
+        let call = match (pool.native, pool.legacy) {
+            (false, false) if self.weth_proceeds.is_none() => ICurveCryptoPool::new(pool.address, client)
+                .exchange(pool.i.into(), pool.j.into(), amount, amount_out_min, true)
+                .calldata(),
+            (_, true) => ICurveLegacyPool::new(pool.address, client)
+                .exchange(pool.i as i128, pool.j as i128, amount, amount_out_min)
+                .calldata(),
+            (_, false) => ICurvePool::new(pool.address, client)
+                .exchange(pool.i.into(), pool.j.into(), amount, amount_out_min)
+                .calldata(),
+        };
+        Ok(call)
+    }
+
+    // (token, ETH) reserves of a constant-product pool holding the Curve
+    // pool's token balance at its current price, for sizing and valuation
+    pub(crate) async fn curve_reserves(&self, token: Address) -> Result<(U256, U256)> {
+        let pool = self.curve_pool(token).await?;
+        let balance = ICurvePool::new(pool.address, Arc::new(self.provider.clone())).balances(pool.i.into()).call().await?;
+        let probe = (balance / 1000).max(U256::one());
+        let amount_out = self.curve_dy(pool, probe).await?;
+        Ok((balance, mul_div(amount_out, balance, probe)))
+    }
+
+    // Native ETH a sell's receipt took out of our Curve pools paying it,
+    // from their TokenExchange events; pools paying WETH unwrap it through
+    // the WETH contract instead
+    pub(crate) async fn curve_eth_received(&self, receipt: &TransactionReceipt) -> U256 {
+        let pools: Vec<Address> =
+            self.curve_pools.lock().await.values().filter(|pool| pool.native).map(|pool| pool.address).collect();
+        let exchanges = [
+            H256::from(keccak256("TokenExchange(address,int128,uint256,int128,uint256)")),
+            H256::from(keccak256("TokenExchange(address,uint256,uint256,uint256,uint256)")),
+        ];
+        let buyer = H256::from(self.wallet.address());
+        receipt
+            .logs
+            .iter()
+            .filter(|log| pools.contains(&log.address) && log.topics.len() == 2 && exchanges.contains(&log.topics[0]))
+            .filter(|log| log.topics[1] == buyer && log.data.len() >= 128)
+            .fold(U256::zero(), |acc, log| acc + U256::from_big_endian(&log.data[96..128]))
+    }
+}
@@ -0,0 +1,308 @@
+use crate::abi::{ICamelotRouterCalls, IMulticallCalls, IUniswapV2Router02Calls};
+use ethers::{
+    abi::AbiDecode,
+    types::{Address, Bytes, U256},
+};
+use serde::Deserialize;
+
+// Where in a swap's path one of our tokens has to appear for it to count
+// as a buy
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PathMatch {
+    // Only as the final output
+    #[default]
+    Output,
+    // Anywhere after the input, including intermediate hops
+    AnyHop,
+}
+
+// How much a swap spends and receives. `None` inputs are paid with the
+// call's ETH value.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SwapAmounts {
+    ExactIn { amount_in: Option<U256>, amount_out_min: U256 },
+    ExactOut { amount_out: U256, amount_in_max: Option<U256> },
+}
+
+// A swap decoded from router calldata
+#[derive(Debug, Clone, PartialEq)]
+pub struct RouterSwap {
+    pub amounts: SwapAmounts,
+    pub path: Vec<Address>,
+    pub to: Address,
+    pub deadline: U256,
+}
+
+impl RouterSwap {
+    pub fn token_in(&self) -> Option<Address> {
+        self.path.first().copied()
+    }
+
+    pub fn token_out(&self) -> Option<Address> {
+        self.path.last().copied()
+    }
+
+    // The token of ours this swap buys, if any. A token it starts from is
+    // being sold, so only later positions count.
+    pub fn bought(&self, path_match: PathMatch, ours: impl Fn(Address) -> bool) -> Option<Address> {
+        let token_in = self.token_in()?;
+        let bought = match path_match {
+            PathMatch::Output => self.path.last().copied().filter(|token| ours(*token)),
+            PathMatch::AnyHop => self.path[1..].iter().copied().find(|token| ours(*token)),
+        };
+        bought.filter(|token| *token != token_in)
+    }
+
+    // The token of ours this swap sells, if any
+    pub fn sold(&self, ours: impl Fn(Address) -> bool) -> Option<Address> {
+        self.token_in().filter(|token| ours(*token))
+    }
+
+    // Input tokens spent, or the most that may be spent for exact-output
+    // swaps; None when paid with ETH
+    pub fn max_amount_in(&self) -> Option<U256> {
+        match self.amounts {
+            SwapAmounts::ExactIn { amount_in, .. } => amount_in,
+            SwapAmounts::ExactOut { amount_in_max, .. } => amount_in_max,
+        }
+    }
+
+    // True when the input is the call's ETH value rather than a token
+    pub fn pays_eth(&self) -> bool {
+        matches!(
+            self.amounts,
+            SwapAmounts::ExactIn { amount_in: None, .. } | SwapAmounts::ExactOut { amount_in_max: None, .. }
+        )
+    }
+}
+
+// Decodes Uniswap V2 router calldata, or Camelot's, for every swap that can
+// buy a token; None for anything else
+pub fn decode_router_swap(input: &[u8]) -> Option<RouterSwap> {
+    use IUniswapV2Router02Calls as Call;
+
+    let Ok(call) = Call::decode(input) else { return decode_camelot_swap(input) };
+    let (amounts, path, to, deadline) = match call {
+        Call::SwapExactETHForTokens(c) => {
+            (SwapAmounts::ExactIn { amount_in: None, amount_out_min: c.amount_out_min }, c.path, c.to, c.deadline)
+        }
+        Call::SwapExactETHForTokensSupportingFeeOnTransferTokens(c) => {
+            (SwapAmounts::ExactIn { amount_in: None, amount_out_min: c.amount_out_min }, c.path, c.to, c.deadline)
+        }
+        Call::SwapETHForExactTokens(c) => {
+            (SwapAmounts::ExactOut { amount_out: c.amount_out, amount_in_max: None }, c.path, c.to, c.deadline)
+        }
+        Call::SwapExactTokensForTokens(c) => (
+            SwapAmounts::ExactIn { amount_in: Some(c.amount_in), amount_out_min: c.amount_out_min },
+            c.path,
+            c.to,
+            c.deadline,
+        ),
+        Call::SwapExactTokensForTokensSupportingFeeOnTransferTokens(c) => (
+            SwapAmounts::ExactIn { amount_in: Some(c.amount_in), amount_out_min: c.amount_out_min },
+            c.path,
+            c.to,
+            c.deadline,
+        ),
+        Call::SwapTokensForExactTokens(c) => (
+            SwapAmounts::ExactOut { amount_out: c.amount_out, amount_in_max: Some(c.amount_in_max) },
+            c.path,
+            c.to,
+            c.deadline,
+        ),
+        _ => return None,
+    };
+
+    (path.len() >= 2).then_some(RouterSwap { amounts, path, to, deadline })
+}
+
+// Camelot's router only has fee-on-transfer swaps, each with a referrer
+fn decode_camelot_swap(input: &[u8]) -> Option<RouterSwap> {
+    use ICamelotRouterCalls as Call;
+
+    let (amounts, path, to, deadline) = match Call::decode(input).ok()? {
+        Call::SwapExactETHForTokensSupportingFeeOnTransferTokens(c) => {
+            (SwapAmounts::ExactIn { amount_in: None, amount_out_min: c.amount_out_min }, c.path, c.to, c.deadline)
+        }
+        Call::SwapExactTokensForTokensSupportingFeeOnTransferTokens(c) => (
+            SwapAmounts::ExactIn { amount_in: Some(c.amount_in), amount_out_min: c.amount_out_min },
+            c.path,
+            c.to,
+            c.deadline,
+        ),
+        _ => return None,
+    };
+
+    (path.len() >= 2).then_some(RouterSwap { amounts, path, to, deadline })
+}
+
+// Calls nested deeper than this are not looked at
+const MAX_MULTICALL_DEPTH: usize = 4;
+
+// The calls `input` makes on its target: itself, or for a multicall (plain,
+// deadline- or blockhash-guarded) every call it bundles, expanded recursively
+pub fn unwrap_multicall(input: &[u8]) -> Vec<Bytes> {
+    let mut calls = Vec::new();
+    collect_calls(input, 0, &mut calls);
+    calls
+}
+
+fn collect_calls(input: &[u8], depth: usize, calls: &mut Vec<Bytes>) {
+    let inner = match IMulticallCalls::decode(input) {
+        Ok(IMulticallCalls::Multicall(c)) => c.data,
+        Ok(IMulticallCalls::MulticallWithDeadline(c)) => c.data,
+        Ok(IMulticallCalls::MulticallWithPreviousBlockhash(c)) => c.data,
+        Err(_) => Vec::new(),
+    };
+    if inner.is_empty() || depth >= MAX_MULTICALL_DEPTH {
+        calls.push(Bytes::from(input.to_vec()));
+        return;
+    }
+    for call in inner {
+        collect_calls(&call, depth + 1, calls);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::utils::hex;
+
+    const WETH: &str = "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2";
+    const PEPE: &str = "0x6982508145454Ce325dDbE47a25d4ec3d2311933";
+
+    // swapExactETHForTokens(1000 PEPE min, [WETH, PEPE], to, 1700000000)
+    // as sent to the mainnet router
+    const SWAP_EXACT_ETH_FOR_TOKENS: &str = concat!(
+        "7ff36ab5",
+        "00000000000000000000000000000000000000000000003635c9adc5dea00000",
+        "0000000000000000000000000000000000000000000000000000000000000080",
+        "0000000000000000000000003fc91a3afd70395cd496c647d5a6cc9d4b2b7fad",
+        "000000000000000000000000000000000000000000000000000000006553f100",
+        "0000000000000000000000000000000000000000000000000000000000000002",
+        "000000000000000000000000c02aaa39b223fe8d0a0e5c4f27ead9083c756cc2",
+        "0000000000000000000000006982508145454ce325ddbe47a25d4ec3d2311933",
+    );
+
+    fn address(s: &str) -> Address {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn decodes_mainnet_swap_exact_eth_for_tokens() {
+        let swap = decode_router_swap(&hex::decode(SWAP_EXACT_ETH_FOR_TOKENS).unwrap()).unwrap();
+        assert_eq!(swap.amounts, SwapAmounts::ExactIn { amount_in: None, amount_out_min: U256::exp10(21) });
+        assert_eq!(swap.path, vec![address(WETH), address(PEPE)]);
+        assert_eq!(swap.to, address("0x3fC91A3afd70395Cd496C647d5a6CC9D4B2b7FAD"));
+        assert_eq!(swap.deadline, U256::from(1_700_000_000u64));
+    }
+
+    #[test]
+    fn rejects_empty_short_and_foreign_calldata() {
+        let calldata = hex::decode(SWAP_EXACT_ETH_FOR_TOKENS).unwrap();
+        assert_eq!(decode_router_swap(&[]), None);
+        assert_eq!(decode_router_swap(&calldata[..4]), None);
+        assert_eq!(decode_router_swap(&calldata[..100]), None);
+        // approve(address,uint256) shares no selector with the router
+        let mut approve = hex::decode("095ea7b3").unwrap();
+        approve.extend_from_slice(&calldata[4..68]);
+        assert_eq!(decode_router_swap(&approve), None);
+    }
+
+    fn encode(call: IUniswapV2Router02Calls) -> Vec<u8> {
+        use ethers::abi::AbiEncode;
+        call.encode()
+    }
+
+    #[test]
+    fn decodes_every_buy_variant() {
+        use crate::abi::i_uniswap_v2_router_02::*;
+
+        let (weth, pepe, to) = (address(WETH), address(PEPE), Address::repeat_byte(1));
+        let (amount, limit, deadline) = (U256::from(5), U256::from(7), U256::MAX);
+        let path = vec![weth, pepe];
+        let cases = [
+            (
+                "b6f9de95",
+                IUniswapV2Router02Calls::SwapExactETHForTokensSupportingFeeOnTransferTokens(
+                    SwapExactETHForTokensSupportingFeeOnTransferTokensCall { amount_out_min: limit, path: path.clone(), to, deadline },
+                ),
+                SwapAmounts::ExactIn { amount_in: None, amount_out_min: limit },
+            ),
+            (
+                "fb3bdb41",
+                IUniswapV2Router02Calls::SwapETHForExactTokens(SwapETHForExactTokensCall {
+                    amount_out: amount, path: path.clone(), to, deadline,
+                }),
+                SwapAmounts::ExactOut { amount_out: amount, amount_in_max: None },
+            ),
+            (
+                "38ed1739",
+                IUniswapV2Router02Calls::SwapExactTokensForTokens(SwapExactTokensForTokensCall {
+                    amount_in: amount, amount_out_min: limit, path: path.clone(), to, deadline,
+                }),
+                SwapAmounts::ExactIn { amount_in: Some(amount), amount_out_min: limit },
+            ),
+            (
+                "5c11d795",
+                IUniswapV2Router02Calls::SwapExactTokensForTokensSupportingFeeOnTransferTokens(
+                    SwapExactTokensForTokensSupportingFeeOnTransferTokensCall {
+                        amount_in: amount, amount_out_min: limit, path: path.clone(), to, deadline,
+                    },
+                ),
+                SwapAmounts::ExactIn { amount_in: Some(amount), amount_out_min: limit },
+            ),
+            (
+                "8803dbee",
+                IUniswapV2Router02Calls::SwapTokensForExactTokens(SwapTokensForExactTokensCall {
+                    amount_out: amount, amount_in_max: limit, path: path.clone(), to, deadline,
+                }),
+                SwapAmounts::ExactOut { amount_out: amount, amount_in_max: Some(limit) },
+            ),
+        ];
+        for (selector, call, amounts) in cases {
+            let calldata = encode(call);
+            assert_eq!(hex::encode(&calldata[..4]), selector);
+            let swap = decode_router_swap(&calldata).unwrap();
+            assert_eq!(swap, RouterSwap { amounts, path: path.clone(), to, deadline });
+        }
+    }
+
+    #[test]
+    fn skips_single_token_paths_and_non_buys() {
+        use crate::abi::i_uniswap_v2_router_02::*;
+
+        let call = SwapExactETHForTokensCall {
+            amount_out_min: U256::zero(), path: vec![address(PEPE)], to: Address::zero(), deadline: U256::zero(),
+        };
+        assert_eq!(decode_router_swap(&encode(IUniswapV2Router02Calls::SwapExactETHForTokens(call))), None);
+        assert_eq!(decode_router_swap(&encode(IUniswapV2Router02Calls::Factory(FactoryCall))), None);
+    }
+
+    #[test]
+    fn finds_bought_and_sold_tokens() {
+        let (weth, pepe, usdc) = (address(WETH), address(PEPE), Address::repeat_byte(2));
+        let swap = |path: Vec<Address>, amount_in: Option<U256>| RouterSwap {
+            amounts: SwapAmounts::ExactIn { amount_in, amount_out_min: U256::zero() },
+            path,
+            to: Address::zero(),
+            deadline: U256::MAX,
+        };
+        let ours = |token: Address| token == pepe;
+
+        let buy = swap(vec![weth, pepe, usdc], None);
+        assert_eq!(buy.bought(PathMatch::Output, ours), None);
+        assert_eq!(buy.bought(PathMatch::AnyHop, ours), Some(pepe));
+        assert_eq!(buy.sold(ours), None);
+        assert!(buy.pays_eth());
+        assert_eq!(buy.max_amount_in(), None);
+
+        // A token the path starts and ends with is sold, not bought
+        let round_trip = swap(vec![pepe, weth, pepe], Some(U256::MAX));
+        assert_eq!(round_trip.bought(PathMatch::Output, ours), None);
+        assert_eq!(round_trip.sold(ours), Some(pepe));
+        assert!(!round_trip.pays_eth());
+        assert_eq!(round_trip.max_amount_in(), Some(U256::MAX));
+    }
+}
@@ -0,0 +1,94 @@
+use crate::transport::Transport;
+use ethers::{
+    abi::AbiError,
+    contract::ContractError,
+    middleware::signer::SignerMiddlewareError,
+    providers::{Middleware, Provider, ProviderError},
+    signers::{LocalWallet, WalletError},
+    types::U256,
+};
+use thiserror::Error;
+
+pub type Result<T, E = MktmkrError> = std::result::Result<T, E>;
+
+#[derive(Debug, Error)]
+pub enum MktmkrError {
+    #[error("provider error: {0}")]
+    Provider(#[from] ProviderError),
+
+    #[error("contract call failed: {0}")]
+    Contract(String),
+
+    #[error("ABI encoding failed: {0}")]
+    AbiEncode(#[from] ethers::abi::Error),
+
+    #[error("ABI decoding failed: {0}")]
+    AbiDecode(#[from] AbiError),
+
+    #[error("wallet error: {0}")]
+    Wallet(#[from] WalletError),
+
+    #[error("insufficient {asset} balance: need {required}, have {available}")]
+    InsufficientBalance { asset: String, required: U256, available: U256 },
+
+    #[error("nonce conflict: {0}")]
+    NonceConflict(String),
+
+    #[error("session deadline expired")]
+    DeadlineExpired,
+
+    #[error("invalid configuration: {0}")]
+    Config(String),
+
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+impl MktmkrError {
+    // Transient failures are worth retrying as-is; everything else needs a
+    // change of inputs or operator attention
+    pub fn is_transient(&self) -> bool {
+        match self {
+            MktmkrError::Provider(ProviderError::JsonRpcClientError(e)) => e.as_error_response().is_none(),
+            MktmkrError::Provider(ProviderError::HTTPError(_)) => true,
+            MktmkrError::NonceConflict(_) => true,
+            _ => false,
+        }
+    }
+
+    // A lost connection ends the session, for the supervisor to reconnect;
+    // any other failure of a sell only costs that sell
+    pub fn ends_session(&self) -> bool {
+        matches!(self, MktmkrError::Provider(_)) && self.is_transient()
+    }
+
+    // Classifies a failed submission: nodes report nonce races as JSON-RPC
+    // errors, which callers want to handle separately from other rejections
+    pub(crate) fn from_send(error: ProviderError) -> Self {
+        let message = error.to_string().to_lowercase();
+        if message.contains("nonce too low")
+            || message.contains("already known")
+            || message.contains("replacement transaction underpriced")
+        {
+            MktmkrError::NonceConflict(error.to_string())
+        } else {
+            MktmkrError::Provider(error)
+        }
+    }
+}
+
+impl From<SignerMiddlewareError<Provider<Transport>, LocalWallet>> for MktmkrError {
+    fn from(error: SignerMiddlewareError<Provider<Transport>, LocalWallet>) -> Self {
+        match error {
+            SignerMiddlewareError::MiddlewareError(e) => MktmkrError::Provider(e),
+            SignerMiddlewareError::SignerError(e) => MktmkrError::Wallet(e),
+            other => MktmkrError::Provider(ProviderError::CustomError(other.to_string())),
+        }
+    }
+}
+
+impl<M: Middleware> From<ContractError<M>> for MktmkrError {
+    fn from(error: ContractError<M>) -> Self {
+        MktmkrError::Contract(error.to_string())
+    }
+}
@@ -0,0 +1,107 @@
+use crate::bot::TradingBot;
+use crate::error::Result;
+use crate::math::plus_bps;
+use crate::receipts::InFlight;
+use ethers::{
+    prelude::*,
+    types::{transaction::eip2718::TypedTransaction, Bytes, TxHash, U256},
+};
+
+// Nodes only accept a replacement bidding at least this much more, on both
+// the fee cap and the tip
+const MIN_REPLACEMENT_BUMP_PERCENT: u64 = 10;
+const TRANSFER_GAS: u64 = 21_000;
+
+fn min_replacement(fee: U256) -> U256 {
+    plus_bps(fee, MIN_REPLACEMENT_BUMP_PERCENT * 100)
+}
+
+// `base` priced `bump_percent` above `previous`, clamped to `cap`; None when
+// the clamp leaves too little of a bump for nodes to accept
+fn bumped(base: &TypedTransaction, previous: &TypedTransaction, bump_percent: u64, cap: Option<U256>) -> Option<TypedTransaction> {
+    let raise = |fee: U256| {
+        let raised = plus_bps(fee, bump_percent * 100);
+        let raised = cap.map_or(raised, |cap| raised.min(cap));
+        (raised >= min_replacement(fee) && !raised.is_zero()).then_some(raised)
+    };
+
+    let mut replacement = base.clone();
+    match (&mut replacement, previous) {
+        (TypedTransaction::Eip1559(request), TypedTransaction::Eip1559(previous)) => {
+            let max_fee = raise(previous.max_fee_per_gas?)?;
+            let max_priority_fee = raise(previous.max_priority_fee_per_gas?)?.min(max_fee);
+            if max_priority_fee < min_replacement(previous.max_priority_fee_per_gas?) {
+                return None;
+            }
+            request.max_fee_per_gas = Some(max_fee);
+            request.max_priority_fee_per_gas = Some(max_priority_fee);
+        }
+        (replacement, previous) => {
+            replacement.set_gas_price(raise(previous.gas_price()?)?);
+        }
+    }
+    Some(replacement)
+}
+
+impl TradingBot {
+    // Rebids a sell that has sat unmined for escalate_after_blocks, fee_bump
+    // percent higher and no higher than the escalation cap. A sell whose
+    // swap deadline has passed would only revert, so its nonce is taken by a
+    // zero-value transfer to ourselves instead. The sell being replaced stays
+    // tracked in case it lands first.
+    pub(crate) async fn escalate(&self, tx_hash: TxHash, sell: &InFlight, head: u64, timestamp: U256) -> Result<()> {
+        let (Some(previous), Some(nonce)) = (&sell.tx, sell.nonce) else { return Ok(()) };
+        // A rebid of a bundled sell would go out without its buy
+        if sell.cancel || sell.bundle_block.is_some() || sell.bumps >= self.max_fee_bumps {
+            return Ok(());
+        }
+
+        let expired = sell.deadline.is_some_and(|deadline| timestamp >= deadline);
+        let base = match expired {
+            true => {
+                let mut cancel = previous.clone();
+                cancel.set_to(self.wallet.address());
+                cancel.set_value(U256::zero());
+                cancel.set_data(Bytes::default());
+                cancel.set_gas(TRANSFER_GAS);
+                cancel
+            }
+            false => previous.clone(),
+        };
+        let cap = self.max_escalated_fee.or(self.params.read().max_gas_price);
+        let Some(replacement) = bumped(&base, previous, self.fee_bump_percent, cap) else {
+            println!("Sell {:?} of {:?} is stuck at the fee cap", tx_hash, sell.token);
+            return Ok(());
+        };
+
+        let signature = self.client.signer().sign_transaction(&replacement).await?;
+        let raw = replacement.rlp_signed(&signature);
+        let replaced_by = match self.submit(raw.clone(), sell.private).await {
+            Ok(replaced_by) => replaced_by,
+            Err(e) => {
+                println!("Could not replace sell {:?} of {:?} (nonce {}): {}", tx_hash, sell.token, nonce, e);
+                return Ok(());
+            }
+        };
+        match expired {
+            true => println!("Sell {:?} of {:?} expired; cancelling it with {:?}", tx_hash, sell.token, replaced_by),
+            false => println!("Sell {:?} of {:?} stuck; rebid as {:?}", tx_hash, sell.token, replaced_by),
+        }
+
+        let mut in_flight = self.in_flight.lock().await;
+        if let Some(replaced) = in_flight.get_mut(&tx_hash) {
+            replaced.superseded = true;
+        }
+        let escalated = InFlight {
+            tx: Some(replacement),
+            raw: Some(raw),
+            sent_block: Some(head),
+            bumps: sell.bumps + 1,
+            cancel: expired,
+            superseded: false,
+            ..sell.clone()
+        };
+        in_flight.insert(replaced_by, escalated);
+        Ok(())
+    }
+}
@@ -0,0 +1,510 @@
+use crate::abi::{encode_function_data, encode_referrer_function_data, token_received, weth_withdrawn};
+use crate::bot::{TokenTarget, TradingBot};
+use crate::bundle::Backrun;
+use crate::error::{MktmkrError, Result};
+use crate::fees::GasBid;
+use crate::math::{apply_bps, less_bps, mul_div, percent_to_bps, plus_bps};
+use crate::pool::spot_price;
+use crate::queue::{OrderPart, SellOrder};
+use crate::receipts::InFlight;
+use crate::reorg::Fill;
+use crate::strategy::{Filled, Side};
+use crate::target::{OnTarget, Target};
+use crate::tax::after_tax;
+use crate::trigger::Seen;
+use crate::routing::{Route, RouteCall};
+use ethers::{
+    abi::Token,
+    prelude::*,
+    types::{transaction::eip2718::TypedTransaction, TransactionReceipt, U256},
+    utils::format_ether,
+};
+use std::time::{Instant, SystemTime};
+
+impl TradingBot {
+    // Queues a sell into a detected buy, still pending or already included:
+    // `sell_percentage` of the tokens the buy's ETH is worth at spot. Without
+    // a spot price from when the buy was seen, today's stands in.
+    pub(crate) async fn enqueue_sell(
+        &self,
+        buy: Option<H256>,
+        token: Address,
+        buy_amount: U256,
+        buy_pending: bool,
+        seen: Option<Seen>,
+    ) -> Result<()> {
+        let target = self.target_for(token)?;
+        let Some(sell_percentage) = self.sell_percentage(&target).await else { return Ok(()) };
+        let sell_percentage = self.volatility_scaled(token, sell_percentage).await;
+        if !self.twap_allows(token).await || !self.reference_allows(token).await {
+            return Ok(());
+        }
+        if self.skip_trigger() {
+            println!("Skipping a trigger for {:?} at random", token);
+            return Ok(());
+        }
+        let (token_reserve, weth_reserve) = self.reserves(token).await?;
+        let sell_bps = (percent_to_bps(sell_percentage) as f64 * self.skew_factor(token).await) as u64;
+        let desired = match &self.sizing {
+            Some(sizing) => mul_div(sizing.sell_eth(buy_amount, sell_bps), token_reserve, weth_reserve),
+            None => apply_bps(mul_div(buy_amount, token_reserve, weth_reserve), sell_bps),
+        };
+        let seen = seen.map(|seen| Seen { spot: seen.spot.or(Some(spot_price(token_reserve, weth_reserve))), ..seen });
+        let part = OrderPart { buy, desired, pending_buy: buy_pending.then_some(buy_amount), seen };
+        self.enqueue_order(SellOrder { token, parts: vec![part] }).await;
+        Ok(())
+    }
+
+    // Queues a sell of `mirror_percentage` of the `amount` of tokens a
+    // watched wallet is selling
+    pub(crate) async fn mirror_sell(&self, token: Address, amount: U256, mirror_percentage: f64) -> Result<()> {
+        let target = self.target_for(token)?;
+        if self.sell_percentage(&target).await.is_none() {
+            return Ok(());
+        }
+        let desired = apply_bps(amount, percent_to_bps(mirror_percentage));
+        let part = OrderPart { buy: None, desired, pending_buy: None, seen: None };
+        self.enqueue_order(SellOrder { token, parts: vec![part] }).await;
+        Ok(())
+    }
+
+    // Sells as much of `desired` as sell limits allow, paper-filling against
+    // `pending_buy` when given and bundling behind the `backrun`
+    // buy in bundle mode; returns the part queued
+    pub(crate) async fn reserve_and_sell(
+        &self,
+        target: &TokenTarget,
+        desired: U256,
+        pending_buy: Option<U256>,
+        backrun: Option<&Backrun>,
+    ) -> Result<U256> {
+        let token = target.address;
+        let sell_amount = self.throttle.lock().await.reserve(target, desired, Instant::now());
+        let mut queued = desired - sell_amount;
+        if !queued.is_zero() {
+            println!("Queued {} behind sell limits", self.format_amount(token, queued));
+        }
+        let sell_amount = self.impact_limited(token, sell_amount).await?;
+        if sell_amount.is_zero() {
+            return Ok(queued);
+        }
+        // A bundled sell has to go whole, in the buy's block
+        let sell_amount = match backrun {
+            Some(_) => sell_amount,
+            None => self.chunk(target, sell_amount).await?,
+        };
+        if self.paper.is_some() {
+            self.paper_fill(token, sell_amount, pending_buy).await?;
+        } else {
+            // The unsent half of a halved sell goes back behind the limits,
+            // no longer counted as sold
+            let unsent = self.sell_behind(token, sell_amount, backrun).await?;
+            if !unsent.is_zero() {
+                let mut throttle = self.throttle.lock().await;
+                throttle.unreserve(target, unsent);
+                throttle.enqueue(token, unsent);
+                println!("Queued the unsent {} behind sell limits", self.format_amount(token, unsent));
+                queued += unsent;
+            }
+        }
+        Ok(queued)
+    }
+
+    pub async fn sell(&self, token: Address, sell_amount: U256) -> Result<()> {
+        self.sell_behind(token, sell_amount, None).await.map(|_| ())
+    }
+
+    // Sells nothing is waiting on: queued, sliced and chunked ones. With
+    // intents configured they're posted as CoW orders instead of swapped.
+    pub(crate) async fn dispose(&self, token: Address, sell_amount: U256) -> Result<()> {
+        if self.intents.is_none() || self.paper.is_some() {
+            return self.sell(token, sell_amount).await;
+        }
+        let Some((target, sell_amount, slippage_bps)) = self.sized(token, sell_amount).await? else { return Ok(()) };
+        self.post_intent(&target, sell_amount, slippage_bps).await
+    }
+
+    // Returns what a halved sell left unsent
+    async fn sell_behind(&self, token: Address, sell_amount: U256, backrun: Option<&Backrun>) -> Result<U256> {
+        let Some((target, sell_amount, slippage_bps)) = self.sized(token, sell_amount).await? else { return Ok(U256::zero()) };
+        self.submit_sell(&target, sell_amount, slippage_bps, backrun).await
+    }
+
+    // The token, what of `sell_amount` its limits and holdings allow, and its
+    // slippage; None when that's nothing
+    async fn sized(&self, token: Address, sell_amount: U256) -> Result<Option<(TokenTarget, U256, u32)>> {
+        if SystemTime::now() >= self.deadline {
+            return Err(MktmkrError::DeadlineExpired);
+        }
+        let target = self.target_for(token)?;
+        let slippage_bps = target.slippage_bps.unwrap_or(self.slippage_bps);
+        let desired = capped(&target, sell_amount);
+        let sell_amount = desired.min(self.sellable(&target).await?);
+        if sell_amount.is_zero() {
+            println!("Skipping sell of {}: nothing left to sell", self.format_amount(token, desired));
+            return Ok(None);
+        }
+        if sell_amount < desired {
+            println!("Clamped sell of {} to {}", self.format_amount(token, desired), self.format_amount(token, sell_amount));
+        }
+        Ok(Some((target, sell_amount, slippage_bps)))
+    }
+
+    // What the wallet holds beyond its unconfirmed sells and open CoW orders,
+    // and for tokens stopping at a tokens-sold target, no more than is left
+    // of it
+    async fn sellable(&self, target: &TokenTarget) -> Result<U256> {
+        let token = target.address;
+        let posted = self.open_intents
+            .lock()
+            .await
+            .values()
+            .filter(|order| order.token == token)
+            .fold(U256::zero(), |total, order| total + order.sell_amount);
+        let in_flight = self.in_flight
+            .lock()
+            .await
+            .values()
+            .filter(|sell| sell.token == token && !sell.superseded && !sell.cancel)
+            .fold(posted, |total, sell| total + sell.sell_amount);
+        let held = self.inventory(token).await?.saturating_sub(in_flight);
+        match (target.target, target.on_target) {
+            (Target::TokensSold(amount), OnTarget::Stop) => {
+                let sold = self.progress(token).await.tokens_sold;
+                Ok(held.min(amount.saturating_sub(sold + in_flight)))
+            }
+            _ => Ok(held),
+        }
+    }
+
+    // Builds, signs and sends one sell, or simulates it in dry-run and paper
+    // modes. No deadline check, so unwinding can run after expiry.
+    pub(crate) async fn submit_sell(
+        &self,
+        target: &TokenTarget,
+        sell_amount: U256,
+        slippage_bps: u32,
+        backrun: Option<&Backrun>,
+    ) -> Result<U256> {
+        let token = target.address;
+
+        let Some(gas_bid) = self.gas_bid(target).await? else { return Ok(U256::zero()) };
+        let Some((tx, deadline, route)) = self.sell_transaction(target, sell_amount, slippage_bps, gas_bid).await? else {
+            return Ok(U256::zero());
+        };
+
+        if self.paper.is_some() {
+            return self.paper_fill(token, sell_amount, None).await.map(|_| U256::zero());
+        }
+        if self.dry_run {
+            return self.simulate_sell(token, sell_amount, &tx, &route).await.map(|_| U256::zero());
+        }
+
+        let mut approved = self.ensure_allowance(token, route.spender, sell_amount).await?;
+        if approved && route.call == RouteCall::V4 {
+            approved = self.ensure_permit2(token, route.router, sell_amount).await?;
+        }
+        if !approved {
+            println!("Skipping sell of {}: router not approved", self.format_amount(token, sell_amount));
+            return Ok(U256::zero());
+        }
+
+        // A sell that would revert is retried once at half the size, which
+        // gets under per-transaction limits, and skipped if it still would.
+        // The other half is left to the caller.
+        let requested = sell_amount;
+        let (mut tx, mut deadline, route, sell_amount) = match self.preflight(&tx).await? {
+            None => (tx, deadline, route, sell_amount),
+            Some(reason) => {
+                let half = sell_amount / 2;
+                if half.is_zero() {
+                    println!("Skipping sell of {:?}: simulation reverted ({})", token, reason);
+                    return Ok(U256::zero());
+                }
+                println!(
+                    "Sell of {} would revert ({}); retrying with {}",
+                    self.format_amount(token, sell_amount), reason, self.format_amount(token, half)
+                );
+                let Some((tx, deadline, route)) = self.sell_transaction(target, half, slippage_bps, gas_bid).await? else {
+                    return Ok(U256::zero());
+                };
+                if let Some(reason) = self.preflight(&tx).await? {
+                    println!("Skipping sell of {:?}: simulation reverted ({})", token, reason);
+                    return Ok(U256::zero());
+                }
+                (tx, deadline, route, half)
+            }
+        };
+
+        self.attach_access_list(&mut tx).await;
+        if let Some(backrun) = backrun {
+            let Some(unbundled) = self.send_backrun(target, sell_amount, tx, deadline, &route.venue, backrun).await? else {
+                return Ok(U256::zero());
+            };
+            (tx, deadline) = unbundled;
+        }
+
+        let nonce = self.next_nonce().await?;
+        tx.set_nonce(nonce);
+        let mut raw = None;
+        let sent = match self.sign(&mut tx).await {
+            Ok(signed) => self.submit(raw.insert(signed).clone(), target.private).await,
+            Err(e) => Err(e),
+        };
+        let tx_hash = match sent {
+            Ok(tx_hash) => tx_hash,
+            Err(e) => {
+                // A nonce the node disputes means our count is off; any other
+                // failure leaves the nonce unused, for the next sell to fill
+                let mut nonces = self.nonces.lock().await;
+                match e {
+                    MktmkrError::NonceConflict(_) => nonces.resync(),
+                    _ => nonces.release(nonce),
+                }
+                return Err(e);
+            }
+        };
+        // Accounted for once the receipt watcher sees it confirmed
+        let sell = InFlight {
+            tx: Some(tx),
+            raw,
+            deadline: Some(deadline),
+            private: target.private,
+            venue: route.venue,
+            ..InFlight::new(token, sell_amount, Some(nonce))
+        };
+        self.in_flight.lock().await.insert(tx_hash, sell);
+
+        Ok(requested - sell_amount)
+    }
+
+    // The swap for selling `sell_amount` of the token, its deadline and the
+    // route it takes, accepting no less than the current quote minus
+    // `slippage_bps`; None when the quote is below the token's
+    // sell floor or doesn't cover the gas
+    async fn sell_transaction(
+        &self,
+        target: &TokenTarget,
+        sell_amount: U256,
+        slippage_bps: u32,
+        gas_bid: GasBid,
+    ) -> Result<Option<(TypedTransaction, U256, Route)>> {
+        let token = target.address;
+        let route = self.route_sell(target, sell_amount, slippage_bps, gas_bid).await?;
+        // The router's quote assumes the pool receives every token sent
+        let sell_bps = self.tax(token).await.sell_bps;
+        let expected = after_tax(route.amount_out, sell_bps);
+        let floor = self.sell_floor(target).await?;
+        if expected < floor {
+            println!(
+                "Skipping sell of {}: quoted {} ETH, below the {} ETH floor",
+                self.format_amount(token, sell_amount), format_ether(expected), format_ether(floor)
+            );
+            return Ok(None);
+        }
+        let amount_out_min = less_bps(after_tax(route.proceeds, sell_bps), slippage_bps.into());
+
+        let deadline = self.swap_deadline_timestamp().await?;
+
+        // Both take the same arguments; WETH proceeds are unwrapped in batches
+        let function = match self.weth_proceeds.is_some() || self.stable.is_some() {
+            true => "swapExactTokensForTokensSupportingFeeOnTransferTokens",
+            false => "swapExactTokensForETHSupportingFeeOnTransferTokens",
+        };
+        let swap_call = match &route.call {
+            RouteCall::Prebuilt(data) => Some(data.clone()),
+            RouteCall::V3 { fee } => self.v3_sell_call(token, *fee, sell_amount, amount_out_min, deadline)?,
+            RouteCall::V4 => Some(self.v4_sell_call(token, sell_amount, amount_out_min, deadline)?),
+            RouteCall::Curve => self.curve_sell_call(token, sell_amount, amount_out_min).await?,
+            RouteCall::Balancer => self.balancer_sell_call(token, sell_amount, amount_out_min, deadline)?,
+            RouteCall::Aerodrome { stable } => self.aerodrome_sell_call(token, *stable, sell_amount, amount_out_min, deadline)?,
+            RouteCall::V2 { path } => Some(self.v2_sell_call(function, sell_amount, amount_out_min, path, deadline)?),
+        };
+        let Some(swap_call) = swap_call else {
+            println!("Skipping sell of {}: couldn't encode the {} swap", self.format_amount(token, sell_amount), route.venue);
+            return Ok(None);
+        };
+
+        // Micro-sells whose proceeds don't clear their own gas by the margin
+        // would lose money on fees
+        let mut tx = gas_bid.transaction(self.wallet.address(), route.router, swap_call);
+        let gas_cost = self.gas_cost(&mut tx, gas_bid).await?;
+        if let Some(max_sell_fee) = self.max_sell_fee.filter(|max_sell_fee| gas_cost > *max_sell_fee) {
+            println!(
+                "Skipping sell of {}: {} ETH of fees above the {} ETH cap",
+                self.format_amount(token, sell_amount), format_ether(gas_cost), format_ether(max_sell_fee)
+            );
+            return Ok(None);
+        }
+        let required = plus_bps(gas_cost, self.gas_margin_bps);
+        if expected <= required {
+            println!(
+                "Skipping sell of {}: quoted {} ETH doesn't clear {} ETH of gas plus margin",
+                self.format_amount(token, sell_amount), format_ether(expected), format_ether(gas_cost)
+            );
+            return Ok(None);
+        }
+
+        Ok(Some((tx, deadline, route)))
+    }
+
+    // Calldata selling `amount` along the V2 `path` through `function`, with
+    // an empty referrer on routers like Camelot's that take one
+    pub(crate) fn v2_sell_call(
+        &self,
+        function: &str,
+        amount: U256,
+        amount_out_min: U256,
+        path: &[Address],
+        deadline: U256,
+    ) -> Result<Bytes> {
+        let mut tokens = vec![
+            Token::Uint(amount),
+            Token::Uint(amount_out_min),
+            Token::Array(path.iter().copied().map(Token::Address).collect()),
+            Token::Address(self.wallet.address()),
+        ];
+        if !self.v2_referrer {
+            tokens.push(Token::Uint(deadline));
+            return encode_function_data(function, &tokens);
+        }
+        tokens.extend([Token::Address(Address::zero()), Token::Uint(deadline)]);
+        encode_referrer_function_data(function, &tokens)
+    }
+
+    // Gas limit and chain id come from the node; the signature then commits
+    // to the chain id (EIP-155)
+    pub(crate) async fn sign_and_send(&self, tx: &mut TypedTransaction) -> Result<TxHash> {
+        let raw = self.sign(tx).await?;
+        self.broadcast(raw).await
+    }
+
+    pub(crate) async fn sign(&self, tx: &mut TypedTransaction) -> Result<Bytes> {
+        self.client.fill_transaction(tx, None).await?;
+        let signature = self.client.signer().sign_transaction(tx).await?;
+        Ok(tx.rlp_signed(&signature))
+    }
+
+    // The router compares deadlines with block.timestamp, so count from the
+    // chain's clock rather than ours
+    pub(crate) async fn swap_deadline_timestamp(&self) -> Result<U256> {
+        let latest = self.provider
+            .get_block(BlockNumber::Latest)
+            .await?
+            .ok_or_else(|| MktmkrError::Provider(ProviderError::CustomError("latest block unavailable".into())))?;
+        Ok(latest.timestamp + self.swap_deadline.as_secs())
+    }
+
+    // Dry-run stand-in for broadcasting: executes the quoted sell with
+    // eth_call so calldata and sizing can be checked against live traffic
+    async fn simulate_sell(&self, token: Address, sell_amount: U256, tx: &TypedTransaction, route: &Route) -> Result<()> {
+        let expected_eth = after_tax(route.amount_out, self.tax(token).await.sell_bps);
+
+        match self.provider.call(tx, None).await {
+            Ok(_) => println!(
+                "[dry-run] Would sell {} on {} for ~{} ETH; simulation succeeded",
+                self.format_amount(token, sell_amount), route.venue, format_ether(expected_eth)
+            ),
+            Err(e) => println!(
+                "[dry-run] Would sell {} on {} for ~{} ETH; simulation reverted: {}",
+                self.format_amount(token, sell_amount), route.venue, format_ether(expected_eth), e
+            ),
+        }
+
+        Ok(())
+    }
+
+    pub(crate) async fn record_fill(&self, token: Address, sell_amount: U256, venue: &str, receipt: &TransactionReceipt) {
+        let fee = receipt.gas_used.unwrap_or_default() * receipt.effective_gas_price.unwrap_or_default();
+        let succeeded = receipt.status == Some(1u64.into());
+        let wallet = self.wallet.address();
+        let (eth_out, stable_out) = match (succeeded, self.stable, self.weth_proceeds) {
+            (false, ..) => (U256::zero(), U256::zero()),
+            (true, Some(stable), _) => (U256::zero(), token_received(receipt, stable, wallet)),
+            (true, None, Some(_)) => (token_received(receipt, self.weth, wallet), U256::zero()),
+            // V4 and some Curve sells take native ETH, with no unwrap to read
+            (true, None, None) => {
+                let native = self.v4_eth_received(receipt) + self.curve_eth_received(receipt).await;
+                (weth_withdrawn(receipt, self.weth) + native, U256::zero())
+            }
+        };
+        if let (Some(block_hash), Some(block_number)) = (receipt.block_hash, receipt.block_number) {
+            let fill = Fill {
+                token,
+                sell_amount,
+                eth_out,
+                stable_out,
+                fee,
+                succeeded,
+                venue: venue.to_string(),
+                block_hash,
+                block_number: block_number.as_u64(),
+            };
+            self.recent_fills.lock().await.insert(receipt.transaction_hash, fill);
+        }
+
+        if !succeeded {
+            let mut report = self.report.lock().await;
+            let sold = report.tokens.entry(token).or_default();
+            sold.fees_paid += fee;
+            sold.failed_sells += 1;
+            println!("Sell of {} reverted: {}", self.metadata(token).symbol, self.tx_link(receipt.transaction_hash));
+            return;
+        }
+        if self.explorer_url.is_some() {
+            println!("Sell confirmed: {}", self.tx_link(receipt.transaction_hash));
+        }
+        self.record_sale(token, sell_amount, eth_out, stable_out, fee, venue).await;
+    }
+
+    // Adds a completed sell to the report and checks the token's target
+    pub(crate) async fn record_sale(
+        &self,
+        token: Address,
+        sell_amount: U256,
+        eth_out: U256,
+        stable_out: U256,
+        fee: U256,
+        venue: &str,
+    ) {
+        {
+            let mut report = self.report.lock().await;
+            let sold = report.tokens.entry(token).or_default();
+            sold.fees_paid += fee;
+            sold.sells += 1;
+            sold.tokens_sold += sell_amount;
+            sold.eth_received += eth_out;
+            sold.stable_received += stable_out;
+            let (received, total) = match self.stable {
+                Some(stable) => (self.format_amount(stable, stable_out), self.format_amount(stable, sold.stable_received)),
+                None => (format!("{} ETH", format_ether(eth_out)), format!("{} ETH", format_ether(sold.eth_received))),
+            };
+            println!(
+                "Sold {} for {} on {}. Total: {} sold for {}",
+                self.format_amount(token, sell_amount),
+                received,
+                venue,
+                self.format_amount(token, sold.tokens_sold),
+                total
+            );
+        }
+
+        if let Err(e) = self.check_target(token).await {
+            println!("Could not check target for {:?}: {}", token, e);
+        }
+        let fill = Filled { token, side: Side::Sell, amount: sell_amount, eth: eth_out, fee };
+        if let Err(e) = self.strategy.on_fill(self, fill).await {
+            println!("Strategy {} failed on a fill of {:?}: {}", self.strategy.name(), token, e);
+        }
+    }
+
+    pub(crate) fn target_for(&self, token: Address) -> Result<TokenTarget> {
+        self.token(token)
+            .ok_or_else(|| MktmkrError::Config(format!("{:?} is not a configured token", token)))
+    }
+}
+
+// Applies the token's per-transaction size limit
+fn capped(target: &TokenTarget, sell_amount: U256) -> U256 {
+    target.max_sell_per_tx.map_or(sell_amount, |max| sell_amount.min(max))
+}
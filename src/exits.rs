@@ -0,0 +1,122 @@
+use crate::bot::{TokenTarget, TradingBot};
+use crate::error::{MktmkrError, Result};
+use crate::math::{apply_bps, percent_to_bps};
+use crate::queue::{OrderPart, SellOrder};
+use ethers::types::Address;
+use std::fmt;
+
+// A price per whole token, in ETH or in USD at the Chainlink ETH/USD price
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PriceLevel {
+    Eth(f64),
+    Usd(f64),
+}
+
+impl fmt::Display for PriceLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PriceLevel::Eth(price) => write!(f, "{} ETH", price),
+            PriceLevel::Usd(price) => write!(f, "${}", price),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ExitKind {
+    TakeProfit,
+    StopLoss,
+    TrailingStop,
+}
+
+impl fmt::Display for ExitKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExitKind::TakeProfit => write!(f, "Take-profit"),
+            ExitKind::StopLoss => write!(f, "Stop-loss"),
+            ExitKind::TrailingStop => write!(f, "Trailing stop"),
+        }
+    }
+}
+
+// Sells `percentage` of the inventory once the pool price reaches `level`:
+// at or above it for a take-profit, at or below it for a stop-loss
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PriceExit {
+    pub level: PriceLevel,
+    pub percentage: f64,
+}
+
+impl TradingBot {
+    // Checks every token's take-profit, stop-loss and trailing stop against
+    // its pool price, once a block whatever the mempool is doing. Each fires
+    // once a session.
+    pub(crate) async fn check_exits(&self) {
+        for target in self.tokens() {
+            if target.take_profit.is_none() && target.stop_loss.is_none() && target.trailing_stop_percentage.is_none() {
+                continue;
+            }
+            if let Err(e) = self.check_token_exits(&target).await {
+                println!("Could not check price exits for {:?}: {}", target.address, e);
+            }
+        }
+    }
+
+    async fn check_token_exits(&self, target: &TokenTarget) -> Result<()> {
+        let token = target.address;
+        let price = self.whole_price(token, self.spot(token).await?);
+        if let Some(retrace) = target.trailing_stop_percentage {
+            let peak = {
+                let mut peaks = self.price_peaks.lock().await;
+                let peak = peaks.entry(token).or_insert(price);
+                *peak = peak.max(price);
+                *peak
+            };
+            if price <= peak * (1.0 - retrace / 100.0) {
+                let level = PriceLevel::Eth(peak * (1.0 - retrace / 100.0));
+                self.fire_exit(token, ExitKind::TrailingStop, PriceExit { level, percentage: 100.0 }).await?;
+            }
+        }
+
+        let exits = [(ExitKind::TakeProfit, target.take_profit), (ExitKind::StopLoss, target.stop_loss)];
+        for (kind, exit) in exits {
+            let Some(exit) = exit else { continue };
+            let above = kind == ExitKind::TakeProfit;
+            let (price, level) = match exit.level {
+                PriceLevel::Eth(level) => (price, level),
+                PriceLevel::Usd(level) => {
+                    let eth_usd = self.eth_usd().await?.ok_or_else(|| {
+                        MktmkrError::Config("USD price exits need Chainlink price feeds".into())
+                    })?;
+                    (price * eth_usd, level)
+                }
+            };
+            if (above && price < level) || (!above && price > level) {
+                continue;
+            }
+            self.fire_exit(token, kind, exit).await?;
+        }
+        Ok(())
+    }
+
+    // Queues the exit's share of the inventory for sale, unless it already
+    // fired this session
+    async fn fire_exit(&self, token: Address, kind: ExitKind, exit: PriceExit) -> Result<()> {
+        if !self.exits_fired.lock().await.insert((token, kind)) {
+            return Ok(());
+        }
+        let inventory = self.inventory(token).await?;
+        let desired = apply_bps(inventory, percent_to_bps(exit.percentage));
+        println!(
+            "{} for {} hit at {}: selling {}",
+            kind,
+            self.metadata(token).symbol,
+            exit.level,
+            self.format_amount(token, desired)
+        );
+        if !desired.is_zero() {
+            let part = OrderPart { buy: None, desired, pending_buy: None, seen: None };
+            self.enqueue_order(SellOrder { token, parts: vec![part] }).await;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,79 @@
+use crate::bot::TradingBot;
+use crate::error::{MktmkrError, Result};
+use crate::transport::Transport;
+use ethers::{
+    providers::{Middleware, Provider},
+    types::{Bytes, TxHash},
+    utils::keccak256,
+};
+use std::time::Instant;
+
+// Blocks an endpoint may trail the best head by before sells skip it
+const MAX_LAG_BLOCKS: u64 = 2;
+
+impl TradingBot {
+    // Endpoints fit to take a transaction, fastest first: those up at the
+    // latest probe and no more than MAX_LAG_BLOCKS behind the best head.
+    // Returns (health table index, endpoint).
+    async fn healthy_endpoints(&self) -> Vec<(usize, &Provider<Transport>)> {
+        let endpoints = self.endpoints();
+        let health = self.health.lock().await;
+        let best = health.iter().filter_map(|endpoint| endpoint.head).max().unwrap_or_default();
+        let mut healthy: Vec<_> = health
+            .iter()
+            .enumerate()
+            .filter(|(_, endpoint)| endpoint.up && endpoint.head.is_some_and(|head| head + MAX_LAG_BLOCKS >= best))
+            .map(|(i, endpoint)| (i, endpoint.rtt_ms.unwrap_or(f64::MAX)))
+            .collect();
+        healthy.sort_by(|a, b| a.1.total_cmp(&b.1));
+
+        // Before the first probe, or with none answering, trying them all
+        // in order beats not sending
+        if healthy.is_empty() {
+            return endpoints.into_iter().enumerate().collect();
+        }
+        healthy.into_iter().map(|(i, _)| (i, endpoints[i])).collect()
+    }
+
+    // Sends a signed transaction through healthy endpoints until
+    // `broadcast_quorum` of them accept it, failing over past any that
+    // error. The first error is returned when none accept.
+    pub(crate) async fn broadcast(&self, raw: Bytes) -> Result<TxHash> {
+        let endpoints = match self.fallbacks.is_empty() {
+            true => vec![(0, &self.provider)],
+            false => self.healthy_endpoints().await,
+        };
+        let tx_hash = TxHash::from(keccak256(&raw));
+        let mut accepted = 0;
+        let mut first_error = None;
+
+        for (i, provider) in endpoints {
+            let started = Instant::now();
+            let sent = provider.send_raw_transaction(raw.clone()).await.map_err(MktmkrError::from_send);
+            let mut health = self.health.lock().await;
+            match sent {
+                Ok(_) => {
+                    health[i].record_rtt(started.elapsed());
+                    accepted += 1;
+                }
+                // An earlier endpoint that errored may have propagated it anyway
+                Err(MktmkrError::NonceConflict(e)) if e.to_lowercase().contains("already known") => accepted += 1,
+                Err(e) => {
+                    health[i].record_error();
+                    if !self.fallbacks.is_empty() {
+                        println!("Endpoint {} failed to send {:?}: {}", health[i].name, tx_hash, e);
+                    }
+                    first_error.get_or_insert(e);
+                }
+            }
+            if accepted >= self.broadcast_quorum {
+                break;
+            }
+        }
+
+        match (accepted, first_error) {
+            (0, Some(e)) => Err(e),
+            _ => Ok(tx_hash),
+        }
+    }
+}
@@ -0,0 +1,179 @@
+use crate::abi::{IGasPriceOracle, INodeInterface};
+use crate::bot::{TokenTarget, TradingBot};
+use crate::error::Result;
+use crate::math::{apply_bps, ratio_to_bps};
+use ethers::{
+    providers::Middleware,
+    types::{transaction::eip2718::TypedTransaction, Address, BlockNumber, Bytes, Eip1559TransactionRequest, TransactionRequest, H160, U256},
+};
+use serde::Deserialize;
+use std::sync::Arc;
+
+// Recent blocks whose tips are sampled for the priority fee
+const FEE_HISTORY_BLOCKS: u64 = 10;
+// Gas a fee-on-transfer V2 sell is assumed to use when it can't be estimated
+pub(crate) const SELL_GAS_FALLBACK: u64 = 200_000;
+// OP Stack predeploy pricing a transaction's L1 data, and Arbitrum's
+// virtual contract estimating its L1 posting cost
+const GAS_PRICE_ORACLE: Address = H160([0x42, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x0f]);
+const NODE_INTERFACE: Address = H160([0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0xc8]);
+
+// How sells price their gas
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FeeMode {
+    // Type-2 transactions tipping the median of recent blocks' tips at the
+    // configured percentile, capped at twice the next base fee plus the tip
+    #[default]
+    Eip1559,
+    // gasPrice transactions, for chains without EIP-1559
+    Legacy,
+}
+
+// What rollups charge on top of execution gas for posting a transaction to
+// L1, which dwarfs it on cheap blocks
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum L1Fee {
+    #[default]
+    None,
+    // A separate fee the GasPriceOracle predeploy prices from the
+    // transaction's data (Optimism, Base and other OP Stack chains)
+    OpStack,
+    // Extra gas, which gas estimates already include; NodeInterface prices
+    // it otherwise
+    Arbitrum,
+}
+
+// Gas pricing for one sell
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum GasBid {
+    // None leaves the price to the node
+    Legacy(Option<U256>),
+    Eip1559 { max_fee: U256, max_priority_fee: U256 },
+}
+
+impl GasBid {
+    // A call from `from` to `to`, priced by the bid
+    pub fn transaction(self, from: Address, to: Address, data: Bytes) -> TypedTransaction {
+        match self {
+            GasBid::Legacy(gas_price) => {
+                let mut request = TransactionRequest::new().to(to).data(data).from(from);
+                if let Some(gas_price) = gas_price {
+                    request = request.gas_price(gas_price);
+                }
+                request.into()
+            }
+            GasBid::Eip1559 { max_fee, max_priority_fee } => Eip1559TransactionRequest::new()
+                .to(to)
+                .data(data)
+                .from(from)
+                .max_fee_per_gas(max_fee)
+                .max_priority_fee_per_gas(max_priority_fee)
+                .into(),
+        }
+    }
+
+    // What the bid is expected to pay per gas at `base_fee`: the fee cap or
+    // base fee plus tip, whichever is lower; None leaves it to the node
+    fn expected_price(self, base_fee: Option<U256>) -> Option<U256> {
+        match self {
+            GasBid::Legacy(gas_price) => gas_price,
+            GasBid::Eip1559 { max_fee, max_priority_fee } => {
+                Some(base_fee.map_or(max_fee, |base_fee| max_fee.min(base_fee + max_priority_fee)))
+            }
+        }
+    }
+}
+
+impl TradingBot {
+    // Expected fee for sending `tx` at `bid`, L1 data fee included. The gas
+    // estimate is kept on the transaction; one that can't be estimated yet
+    // (no allowance in dry-run) is costed at SELL_GAS_FALLBACK.
+    pub(crate) async fn gas_cost(&self, tx: &mut TypedTransaction, bid: GasBid) -> Result<U256> {
+        let (gas, estimated) = match self.provider.estimate_gas(tx, None).await {
+            Ok(gas) => {
+                tx.set_gas(gas);
+                (gas, true)
+            }
+            Err(_) => (U256::from(SELL_GAS_FALLBACK), false),
+        };
+        let price = self.expected_gas_price(bid).await?;
+        Ok(gas * price + self.l1_data_fee(tx, estimated, price).await?)
+    }
+
+    // The L1 posting cost of `tx` beyond its execution gas at `price`
+    async fn l1_data_fee(&self, tx: &TypedTransaction, estimated: bool, price: U256) -> Result<U256> {
+        let client = Arc::new(self.provider.clone());
+        match self.l1_fee {
+            L1Fee::None => Ok(U256::zero()),
+            L1Fee::OpStack => Ok(IGasPriceOracle::new(GAS_PRICE_ORACLE, client).get_l1_fee(tx.rlp()).call().await?),
+            L1Fee::Arbitrum if estimated => Ok(U256::zero()),
+            L1Fee::Arbitrum => {
+                let to = tx.to_addr().copied().unwrap_or_default();
+                let data = tx.data().cloned().unwrap_or_default();
+                let (l1_gas, _, _) =
+                    INodeInterface::new(NODE_INTERFACE, client).gas_estimate_l1_component(to, false, data).call().await?;
+                Ok(U256::from(l1_gas) * price)
+            }
+        }
+    }
+
+    // What `bid` is expected to pay per gas in the next block
+    pub(crate) async fn expected_gas_price(&self, bid: GasBid) -> Result<U256> {
+        match bid.expected_price(*self.base_fee.lock().await) {
+            Some(price) => Ok(price),
+            None => Ok(self.provider.get_gas_price().await?),
+        }
+    }
+
+    // Prices a sell of the token, or returns None when the network price is
+    // above max_gas_price. The cap applies to the network price; the
+    // token's multiplier only shapes our bid.
+    pub(crate) async fn gas_bid(&self, target: &TokenTarget) -> Result<Option<GasBid>> {
+        let max_gas_price = self.params.read().max_gas_price;
+        let multiplied = |price: U256| apply_bps(price, ratio_to_bps(target.gas_price_multiplier));
+        let over_cap = |price: U256| {
+            let over = max_gas_price.is_some_and(|max_gas_price| price > max_gas_price);
+            if over {
+                println!(
+                    "Skipping sell of {:?}: gas price {} above cap {}",
+                    target.address, price, max_gas_price.unwrap_or_default()
+                );
+            }
+            over
+        };
+
+        match self.fee_mode {
+            FeeMode::Legacy => {
+                if max_gas_price.is_none() && target.gas_price_multiplier == 1.0 {
+                    return Ok(Some(GasBid::Legacy(None)));
+                }
+                let network_gas_price = self.provider.get_gas_price().await?;
+                if over_cap(network_gas_price) {
+                    return Ok(None);
+                }
+                let bid = multiplied(network_gas_price);
+                Ok(Some(GasBid::Legacy(Some(max_gas_price.map_or(bid, |max_gas_price| bid.min(max_gas_price))))))
+            }
+            FeeMode::Eip1559 => {
+                let history = self.provider
+                    .fee_history(FEE_HISTORY_BLOCKS, BlockNumber::Latest, &[self.priority_fee_percentile])
+                    .await?;
+                // The last base fee is the next block's
+                let base_fee = history.base_fee_per_gas.last().copied().unwrap_or_default();
+                if over_cap(base_fee) {
+                    return Ok(None);
+                }
+                let mut tips: Vec<U256> = history.reward.iter().filter_map(|rewards| rewards.first().copied()).collect();
+                tips.sort();
+                let tip = tips.get(tips.len() / 2).copied().unwrap_or_default();
+
+                let max_priority_fee = multiplied(tip);
+                let max_fee = base_fee * U256::from(2) + max_priority_fee;
+                let max_fee = max_gas_price.map_or(max_fee, |max_gas_price| max_fee.min(max_gas_price));
+                Ok(Some(GasBid::Eip1559 { max_fee, max_priority_fee: max_priority_fee.min(max_fee) }))
+            }
+        }
+    }
+}
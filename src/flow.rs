@@ -0,0 +1,46 @@
+use crate::bot::TradingBot;
+use ethers::types::{Address, I256, U256};
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+// Net ETH flowing into each token from pending buys less pending sells over
+// a rolling window. Buys only trigger sells while it is above the threshold.
+#[derive(Debug, Clone)]
+pub struct NetFlow {
+    pub window: Duration,
+    // Wei of net buy pressure required
+    pub threshold: U256,
+    flows: HashMap<Address, VecDeque<(Instant, I256)>>,
+}
+
+impl NetFlow {
+    pub fn new(window: Duration, threshold: U256) -> Self {
+        Self { window, threshold, flows: HashMap::new() }
+    }
+
+    // Adds a buy (positive) or sell (negative) and returns the token's net
+    // flow over the window
+    pub fn record(&mut self, token: Address, flow: I256, now: Instant) -> I256 {
+        let flows = self.flows.entry(token).or_default();
+        flows.push_back((now, flow));
+        while flows.front().is_some_and(|(at, _)| now.duration_since(*at) >= self.window) {
+            flows.pop_front();
+        }
+        flows.iter().fold(I256::zero(), |net, (_, flow)| net + *flow)
+    }
+}
+
+impl TradingBot {
+    // Records a pending trade's flow and says whether net buy pressure now
+    // clears the threshold; always true without a net-flow window
+    pub(crate) async fn record_flow(&self, token: Address, flow: I256) -> bool {
+        let Some(net_flow) = &self.net_flow else { return true };
+        let mut net_flow = net_flow.lock().await;
+        let net = net_flow.record(token, flow, Instant::now());
+        let clears = net > I256::from_raw(net_flow.threshold);
+        if !clears && flow.is_positive() && self.dry_run {
+            println!("[dry-run] Not selling into {:?}: net buy pressure {} wei is below the threshold", token, net);
+        }
+        clears
+    }
+}
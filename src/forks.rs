@@ -0,0 +1,122 @@
+use ethers::{
+    types::{Address, H256},
+    utils::{get_create2_address_from_hash, keccak256},
+};
+
+// Uniswap V2 deployments and forks sharing its router interface:
+// (preset, chain id, name in the trade log, router, factory, pair init code
+// hash). Camelot's router takes a referrer before each swap's deadline.
+pub const V2_FORKS: &[(&str, u64, &str, &str, &str, &str)] = &[
+    (
+        "uniswap", 1, "Uniswap V2",
+        "0x7a250d5630B4cF539739dF2C5dAcb4c659F2488D", "0x5C69bEe701ef814a2B6a3EDD4B1652CB9cc5aA6f",
+        "0x96e8ac4277198ff8b6f785478aa9a39f403cb768dd02cbee326c3e7da348845f",
+    ),
+    (
+        "uniswap", 10, "Uniswap V2",
+        "0x4A7b5Da61326A6379179b40d00F57E5bbDC962c2", "0x0c3c1c532F1e39EdF36BE9Fe0bE1410313E074Bf",
+        "0x96e8ac4277198ff8b6f785478aa9a39f403cb768dd02cbee326c3e7da348845f",
+    ),
+    (
+        "uniswap", 56, "Uniswap V2",
+        "0x4752ba5DBc23f44D87826276BF6Fd6b1C372aD24", "0x8909Dc15e40173Ff4699343b6eB8132c65e18eC6",
+        "0x96e8ac4277198ff8b6f785478aa9a39f403cb768dd02cbee326c3e7da348845f",
+    ),
+    (
+        "uniswap", 137, "Uniswap V2",
+        "0xedf6066a2b290C185783862C7F4776A2C8077AD1", "0x9e5A52f57b3038F1B8EeE45F28b3C1967e22799C",
+        "0x96e8ac4277198ff8b6f785478aa9a39f403cb768dd02cbee326c3e7da348845f",
+    ),
+    (
+        "uniswap", 8453, "Uniswap V2",
+        "0x4752ba5DBc23f44D87826276BF6Fd6b1C372aD24", "0x8909Dc15e40173Ff4699343b6eB8132c65e18eC6",
+        "0x96e8ac4277198ff8b6f785478aa9a39f403cb768dd02cbee326c3e7da348845f",
+    ),
+    (
+        "uniswap", 42161, "Uniswap V2",
+        "0x4752ba5DBc23f44D87826276BF6Fd6b1C372aD24", "0xf1D7CC64Fb4452F05c498126312eBE29f30Fbcf9",
+        "0x96e8ac4277198ff8b6f785478aa9a39f403cb768dd02cbee326c3e7da348845f",
+    ),
+    (
+        "uniswap", 43114, "Uniswap V2",
+        "0x4752ba5DBc23f44D87826276BF6Fd6b1C372aD24", "0x9e5A52f57b3038F1B8EeE45F28b3C1967e22799C",
+        "0x96e8ac4277198ff8b6f785478aa9a39f403cb768dd02cbee326c3e7da348845f",
+    ),
+    (
+        "uniswap", 11155111, "Uniswap V2",
+        "0xeE567Fe1712Faf6149d80dA1E6934E354124CfE3", "0xF62c03E08ada871A0bEb309762E260a7a6a880E6",
+        "0x96e8ac4277198ff8b6f785478aa9a39f403cb768dd02cbee326c3e7da348845f",
+    ),
+    (
+        "sushiswap", 1, "SushiSwap",
+        "0xd9e1cE17f2641f24aE83637ab66a2cca9C378B9F", "0xC0AEe478e3658e2610c5F7A4A2E1777cE9e4f2Ac",
+        "0xe18a34eb0e04b04f7a0ac29a6e80748dca96319b42c54d679cb821dca90c6303",
+    ),
+    (
+        "sushiswap", 42161, "SushiSwap",
+        "0x1b02dA8Cb0d097eB8D57A175b88c7D8b47997506", "0xc35DADB65012eC5796536bD9864eD8773aBc74C4",
+        "0xe18a34eb0e04b04f7a0ac29a6e80748dca96319b42c54d679cb821dca90c6303",
+    ),
+    (
+        "camelot", 42161, "Camelot",
+        "0xc873fEcbd354f5A56E00E710B90EF4201db2448d", "0x6EcCab422D763aC031210895C81787E87B43A652",
+        "0xa856464ae65f7619087bc369daaf7e387dae1e5af69cfa7935850ebf754b04c1",
+    ),
+    (
+        "pancakeswap", 1, "PancakeSwap V2",
+        "0xEfF92A263d31888d860bD50809A8D171709b7b1c", "0x1097053Fd2ea711dad45caCcc45EfF7548fCB362",
+        "0x57224589c67f3f30a6b0d7a1b54cf3153ab84563bc609ef41dfb34f8b2974d2d",
+    ),
+    (
+        "pancakeswap", 56, "PancakeSwap V2",
+        "0x10ED43C718714eb63d5aA57B78B54704E256024E", "0xcA143Ce32Fe78f1f7019d7d551a6402fC5350c73",
+        "0x00fb7f630766e6a796048ea87d01acd3068e8ff67d078148a3fa3f4a84f69bd5",
+    ),
+];
+
+// A V2 router and, when known, the factory its pairs come from and the hash
+// that fixes their addresses
+#[derive(Debug, Clone, PartialEq)]
+pub struct V2Venue {
+    pub name: String,
+    pub router: Address,
+    pub factory: Option<Address>,
+    pub init_code_hash: Option<H256>,
+}
+
+// Contracts set in the config over the preset's
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct V2Overrides {
+    pub router: Option<Address>,
+    pub factory: Option<Address>,
+    pub init_code_hash: Option<H256>,
+    pub weth: Option<Address>,
+}
+
+pub fn is_v2_fork(preset: &str) -> bool {
+    V2_FORKS.iter().any(|(name, ..)| *name == preset)
+}
+
+// Whether the preset's router swaps take a referrer address
+pub fn takes_referrer(preset: &str) -> bool {
+    preset == "camelot"
+}
+
+// The preset's deployment on the chain
+pub fn v2_preset(preset: &str, chain_id: u64) -> Option<V2Venue> {
+    let (_, _, name, router, factory, init_code_hash) =
+        V2_FORKS.iter().find(|(name, id, ..)| *name == preset && *id == chain_id)?;
+    Some(V2Venue {
+        name: name.to_string(),
+        router: router.parse().expect("valid preset address"),
+        factory: Some(factory.parse().expect("valid preset address")),
+        init_code_hash: Some(init_code_hash.parse().expect("valid preset hash")),
+    })
+}
+
+// Where the factory deploys the pair of two tokens, without asking it
+pub fn pair_address(factory: Address, init_code_hash: H256, a: Address, b: Address) -> Address {
+    let (token0, token1) = if a < b { (a, b) } else { (b, a) };
+    let salt = keccak256([token0.as_bytes(), token1.as_bytes()].concat());
+    get_create2_address_from_hash(factory, salt, init_code_hash)
+}
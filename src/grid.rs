@@ -0,0 +1,140 @@
+use crate::bot::TradingBot;
+use crate::error::{MktmkrError, Result};
+use crate::math::mul_div;
+use crate::pool::spot_price;
+use crate::queue::{OrderPart, SellOrder};
+use crate::strategy::{Strategy, Trade};
+use ethers::{
+    types::{Address, H256, U256},
+    utils::format_ether,
+};
+use futures_util::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use tokio::sync::Mutex;
+
+// Virtual buy and sell levels spaced spacing_bps apart around a center
+// price. Each level the price rises through sells order_eth worth of the
+// token; each level it falls through buys that much.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Grid {
+    pub spacing_bps: u64,
+    // Levels on each side of the center; the price beyond them trades no more
+    pub levels: u32,
+    pub order_eth: U256,
+    // ETH per whole token; the exchange reference or the pool's spot price
+    // when the grid is first laid out, unless set
+    pub center: Option<f64>,
+    // Where each token's center and level are kept across restarts
+    pub state_path: PathBuf,
+}
+
+// A token's grid: its center and the level the price was last seen at
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct GridLevel {
+    pub center: f64,
+    pub level: i64,
+}
+
+pub struct GridStrategy {
+    params: Grid,
+    // Keyed by "<chain id>:<token>", so markets can share the state file
+    levels: Mutex<BTreeMap<String, GridLevel>>,
+}
+
+impl GridStrategy {
+    // Picks up the grids left by the last run, if any
+    pub fn new(params: Grid) -> Result<Self> {
+        let levels = read_state(&params.state_path)?;
+        Ok(Self { params, levels: Mutex::new(levels) })
+    }
+
+    // The level `price` sits at, floored and clamped to the grid
+    fn level(&self, center: f64, price: f64) -> i64 {
+        let spacing = (1.0 + self.params.spacing_bps as f64 / 10_000.0).ln();
+        let levels = i64::from(self.params.levels);
+        ((price / center).ln() / spacing).floor().clamp(-levels as f64, levels as f64) as i64
+    }
+
+    async fn step(&self, bot: &TradingBot, token: Address) -> Result<()> {
+        let (token_reserve, weth_reserve) = bot.reserves(token).await?;
+        let price = bot.whole_price(token, spot_price(token_reserve, weth_reserve));
+        if price <= 0.0 {
+            return Ok(());
+        }
+        let key = format!("{}:{:?}", bot.chain_id(), token);
+
+        let crossed = {
+            let mut levels = self.levels.lock().await;
+            let Some(grid) = levels.get(&key).copied() else {
+                let center = match self.params.center {
+                    Some(center) => center,
+                    None => bot.reference_price(token).await.unwrap_or(price),
+                };
+                let level = self.level(center, price);
+                println!("Grid for {:?} laid out around {} ETH, starting at level {}", token, center, level);
+                levels.insert(key, GridLevel { center, level });
+                drop(levels);
+                return self.save().await;
+            };
+            let level = self.level(grid.center, price);
+            if level == grid.level {
+                return Ok(());
+            }
+            levels.insert(key, GridLevel { level, ..grid });
+            level - grid.level
+        };
+        self.save().await?;
+
+        let eth = self.params.order_eth * U256::from(crossed.unsigned_abs());
+        if crossed > 0 {
+            println!("Price of {:?} rose through {} grid levels; selling {} ETH worth", token, crossed, format_ether(eth));
+            let part = OrderPart { buy: None, desired: mul_div(eth, token_reserve, weth_reserve), pending_buy: None, seen: None };
+            bot.enqueue_order(SellOrder { token, parts: vec![part] }).await;
+        } else {
+            println!("Price of {:?} fell through {} grid levels; buying with {} ETH", token, -crossed, format_ether(eth));
+            bot.buy(token, eth).await?;
+        }
+        Ok(())
+    }
+
+    // Writes our grids over the state file's, keeping other markets' entries
+    async fn save(&self) -> Result<()> {
+        let mut state = read_state(&self.params.state_path)?;
+        state.extend(self.levels.lock().await.iter().map(|(key, grid)| (key.clone(), *grid)));
+        let json = serde_json::to_string_pretty(&state).expect("JSON values always serialize");
+        std::fs::write(&self.params.state_path, json)?;
+        Ok(())
+    }
+}
+
+fn read_state(path: &Path) -> Result<BTreeMap<String, GridLevel>> {
+    match std::fs::read_to_string(path) {
+        Ok(json) => serde_json::from_str(&json).map_err(|e| MktmkrError::Config(format!("grid state {}: {}", path.display(), e))),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(BTreeMap::new()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+impl Strategy for GridStrategy {
+    fn name(&self) -> &str {
+        "grid"
+    }
+
+    // The grid trades on price alone
+    fn on_pending_tx<'a>(&'a self, _bot: &'a TradingBot, _trade: Trade) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async { Ok(()) })
+    }
+
+    fn on_block<'a>(&'a self, bot: &'a TradingBot, _block: H256) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            for token in bot.tokens() {
+                if let Err(e) = self.step(bot, token.address).await {
+                    println!("Grid step for {:?} failed: {}", token.address, e);
+                }
+            }
+            Ok(())
+        })
+    }
+}
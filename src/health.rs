@@ -0,0 +1,124 @@
+use crate::bot::TradingBot;
+use crate::transport::Transport;
+use ethers::{
+    providers::{Middleware, Provider},
+    types::{BlockNumber, U256},
+};
+use futures_util::future::join_all;
+use std::fmt;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+// Weight of the newest sample in the moving averages
+const SMOOTHING: f64 = 0.2;
+// How long a probe waits for an endpoint before counting it down
+const PROBE_TIMEOUT: Duration = Duration::from_secs(2);
+
+// Latency and error figures for one endpoint
+#[derive(Debug, Clone, Default)]
+pub struct EndpointHealth {
+    pub name: String,
+    // Moving averages in milliseconds: request round trip, and the time from
+    // a block's timestamp to its header arriving over the subscription
+    pub rtt_ms: Option<f64>,
+    pub head_lag_ms: Option<f64>,
+    pub head: Option<u64>,
+    pub requests: u64,
+    pub errors: u64,
+    // Whether the latest probe was answered
+    pub up: bool,
+}
+
+impl EndpointHealth {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self { name: name.into(), ..Self::default() }
+    }
+
+    pub fn error_rate(&self) -> f64 {
+        match self.requests {
+            0 => 0.0,
+            requests => self.errors as f64 / requests as f64,
+        }
+    }
+
+    pub(crate) fn record_rtt(&mut self, rtt: Duration) {
+        self.requests += 1;
+        self.rtt_ms = Some(average(self.rtt_ms, rtt.as_secs_f64() * 1000.0));
+    }
+
+    pub(crate) fn record_error(&mut self) {
+        self.requests += 1;
+        self.errors += 1;
+    }
+
+    // Block timestamps have one-second resolution, so lags under a second
+    // read as zero
+    pub(crate) fn record_head(&mut self, number: u64, timestamp: U256) {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as f64;
+        let lag = (now - timestamp.low_u64() as f64 * 1000.0).max(0.0);
+        self.head_lag_ms = Some(average(self.head_lag_ms, lag));
+        self.head = Some(self.head.map_or(number, |head| head.max(number)));
+    }
+}
+
+fn average(average: Option<f64>, sample: f64) -> f64 {
+    average.map_or(sample, |average| average + SMOOTHING * (sample - average))
+}
+
+impl fmt::Display for EndpointHealth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let ms = |value: Option<f64>| value.map_or("-".to_string(), |value| format!("{:.0} ms", value));
+        write!(
+            f,
+            "{}: {}, head {}, rtt {}, head lag {}, {} of {} requests failed",
+            self.name,
+            if self.up { "up" } else { "down" },
+            self.head.map_or("-".to_string(), |head| head.to_string()),
+            ms(self.rtt_ms),
+            ms(self.head_lag_ms),
+            self.errors,
+            self.requests
+        )
+    }
+}
+
+impl TradingBot {
+    // The primary endpoint followed by the fallbacks, in the order of the
+    // health table
+    pub(crate) fn endpoints(&self) -> Vec<&Provider<Transport>> {
+        std::iter::once(&self.provider).chain(&self.fallbacks).collect()
+    }
+
+    // Fetches every endpoint's latest block at once, timing the round trips
+    pub(crate) async fn probe_endpoints(&self) {
+        let probes = join_all(self.endpoints().into_iter().map(|provider| async move {
+            let started = Instant::now();
+            let block = tokio::time::timeout(PROBE_TIMEOUT, provider.get_block(BlockNumber::Latest)).await;
+            (started.elapsed(), block)
+        }))
+        .await;
+
+        let mut health = self.health.lock().await;
+        for (endpoint, (rtt, block)) in health.iter_mut().zip(probes) {
+            match block {
+                Ok(Ok(Some(block))) => {
+                    endpoint.up = true;
+                    endpoint.record_rtt(rtt);
+                    if let Some(number) = block.number {
+                        endpoint.head = Some(endpoint.head.map_or(number.as_u64(), |head| head.max(number.as_u64())));
+                    }
+                }
+                _ => {
+                    if endpoint.up {
+                        println!("Endpoint {} stopped responding", endpoint.name);
+                    }
+                    endpoint.up = false;
+                    endpoint.record_error();
+                }
+            }
+        }
+    }
+
+    pub async fn health(&self) -> Vec<EndpointHealth> {
+        self.health.lock().await.clone()
+    }
+}
@@ -0,0 +1,280 @@
+use crate::bot::{TokenTarget, TradingBot};
+use crate::error::{MktmkrError, Result};
+use crate::math::less_bps;
+use crate::tax::after_tax;
+use ethers::{
+    abi::{encode, Token},
+    signers::Signer,
+    types::{Address, H160, H256, U256},
+    utils::{format_ether, keccak256},
+};
+use serde_json::{json, Value};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+// CoW Protocol's settlement contract, which orders are signed for, and the
+// relayer it pulls sold tokens through
+pub const GPV2_SETTLEMENT: Address = H160([
+    0x90, 0x08, 0xd1, 0x9f, 0x58, 0xaa, 0xbd, 0x9e, 0xd0, 0xd6, 0x09, 0x71, 0x56, 0x5a, 0xa8, 0x51, 0x05, 0x60, 0xab, 0x41,
+]);
+pub const GPV2_VAULT_RELAYER: Address = H160([
+    0xc9, 0x2e, 0x8b, 0xdf, 0x79, 0xf0, 0x50, 0x7f, 0x65, 0xa3, 0x92, 0xb0, 0xab, 0x46, 0x67, 0x71, 0x6b, 0xfe, 0x01, 0x10,
+]);
+
+// (chain id, CoW Protocol order book API)
+pub const DEFAULT_COW_APIS: &[(u64, &str)] = &[
+    (1, "https://api.cow.fi/mainnet"),
+    (100, "https://api.cow.fi/xdai"),
+    (8453, "https://api.cow.fi/base"),
+    (42161, "https://api.cow.fi/arbitrum_one"),
+    (11155111, "https://api.cow.fi/sepolia"),
+];
+
+// What the order book takes for native ETH
+const NATIVE_ETH: Address = Address::repeat_byte(0xee);
+// Orders carry no app metadata
+const APP_DATA: &str = "{}";
+const API_TIMEOUT: Duration = Duration::from_secs(10);
+
+// Background sells posted as signed CoW Protocol orders instead of swaps:
+// solvers settle them in batches, off the public mempool, and pay the gas
+// out of the proceeds. Unset, the chain's order book is used.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IntentOrders {
+    pub api_url: Option<String>,
+    pub validity: Duration,
+}
+
+#[derive(Debug, Clone)]
+pub struct CowApi {
+    url: String,
+    // How long each order stays open for solvers
+    pub validity: Duration,
+    client: reqwest::Client,
+}
+
+impl CowApi {
+    pub fn new(url: impl Into<String>, validity: Duration) -> Self {
+        Self { url: url.into(), validity, client: reqwest::Client::new() }
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    // What selling `amount` pays out once the protocol's fee is taken
+    async fn quote(&self, token: Address, buy_token: Address, amount: U256, from: Address) -> Result<U256> {
+        let body = json!({
+            "sellToken": format!("{:?}", token),
+            "buyToken": format!("{:?}", buy_token),
+            "from": format!("{:?}", from),
+            "receiver": format!("{:?}", from),
+            "kind": "sell",
+            "sellAmountBeforeFee": amount.to_string(),
+            "appData": APP_DATA,
+            "validFor": self.validity.as_secs(),
+            "signingScheme": "eip712",
+        });
+        let response = self.request(self.client.post(format!("{}/api/v1/quote", self.url)).json(&body)).await?;
+        response["quote"]["buyAmount"]
+            .as_str()
+            .and_then(|amount| U256::from_dec_str(amount).ok())
+            .ok_or_else(|| self.error(format!("unexpected quote {}", response)))
+    }
+
+    // The posted order's uid
+    async fn post(&self, order: &Value) -> Result<String> {
+        let response = self.request(self.client.post(format!("{}/api/v1/orders", self.url)).json(order)).await?;
+        response.as_str().map(str::to_string).ok_or_else(|| self.error(format!("unexpected response {}", response)))
+    }
+
+    async fn order(&self, uid: &str) -> Result<Value> {
+        self.request(self.client.get(format!("{}/api/v1/orders/{}", self.url, uid))).await
+    }
+
+    async fn request(&self, request: reqwest::RequestBuilder) -> Result<Value> {
+        let response = request.timeout(API_TIMEOUT).send().await.map_err(|e| self.error(e))?;
+        let status = response.status();
+        let body: Value = response.json().await.map_err(|e| self.error(e))?;
+        // Rejections explain themselves in the body
+        if !status.is_success() {
+            return Err(self.error(format!("{} {}", status, body)));
+        }
+        Ok(body)
+    }
+
+    fn error(&self, error: impl std::fmt::Display) -> MktmkrError {
+        MktmkrError::Contract(format!("CoW order book: {}", error))
+    }
+}
+
+// A posted order not yet filled, expired or cancelled
+#[derive(Debug, Clone)]
+pub(crate) struct OpenIntent {
+    pub token: Address,
+    pub sell_amount: U256,
+    pub valid_to: u64,
+}
+
+// A fill-or-kill sell order, as the settlement contract hashes it
+struct Order {
+    sell_token: Address,
+    buy_token: Address,
+    receiver: Address,
+    sell_amount: U256,
+    buy_amount: U256,
+    valid_to: u32,
+}
+
+impl Order {
+    // EIP-712 digest the owner signs, under the settlement contract's domain
+    fn digest(&self, chain_id: u64) -> H256 {
+        let domain = keccak256(encode(&[
+            Token::FixedBytes(keccak256("EIP712Domain(string name,string version,uint256 chainId,address verifyingContract)").to_vec()),
+            Token::FixedBytes(keccak256("Gnosis Protocol").to_vec()),
+            Token::FixedBytes(keccak256("v2").to_vec()),
+            Token::Uint(chain_id.into()),
+            Token::Address(GPV2_SETTLEMENT),
+        ]));
+        let order = keccak256(encode(&[
+            Token::FixedBytes(keccak256(
+                "Order(address sellToken,address buyToken,address receiver,uint256 sellAmount,uint256 buyAmount,\
+                 uint32 validTo,bytes32 appData,uint256 feeAmount,string kind,bool partiallyFillable,\
+                 string sellTokenBalance,string buyTokenBalance)",
+            ).to_vec()),
+            Token::Address(self.sell_token),
+            Token::Address(self.buy_token),
+            Token::Address(self.receiver),
+            Token::Uint(self.sell_amount),
+            Token::Uint(self.buy_amount),
+            Token::Uint(self.valid_to.into()),
+            Token::FixedBytes(keccak256(APP_DATA).to_vec()),
+            Token::Uint(U256::zero()),
+            Token::FixedBytes(keccak256("sell").to_vec()),
+            Token::Bool(false),
+            Token::FixedBytes(keccak256("erc20").to_vec()),
+            Token::FixedBytes(keccak256("erc20").to_vec()),
+        ]));
+        H256::from(keccak256([&[0x19, 0x01], &domain[..], &order[..]].concat()))
+    }
+}
+
+impl TradingBot {
+    // Posts a sell as a CoW order paying no less than the order book's quote
+    // minus `slippage_bps`, after any sell tax; the quote already has the
+    // protocol's fee taken out. Skipped below the token's sell floor.
+    pub(crate) async fn post_intent(&self, target: &TokenTarget, sell_amount: U256, slippage_bps: u32) -> Result<()> {
+        let Some(cow) = &self.intents else { return Ok(()) };
+        let token = target.address;
+        let buy_token = match (self.stable, self.weth_proceeds) {
+            (Some(stable), _) => stable,
+            (None, Some(_)) => self.weth,
+            (None, None) => NATIVE_ETH,
+        };
+        let wallet = self.wallet.address();
+
+        let quoted = after_tax(cow.quote(token, buy_token, sell_amount, wallet).await?, self.tax(token).await.sell_bps);
+        let expected = match self.stable {
+            Some(stable) => self.quote_path(&[stable, self.weth], quoted).await?,
+            None => quoted,
+        };
+        let floor = self.sell_floor(target).await?;
+        if expected < floor {
+            println!(
+                "Skipping sell of {}: quoted {} ETH, below the {} ETH floor",
+                self.format_amount(token, sell_amount), format_ether(expected), format_ether(floor)
+            );
+            return Ok(());
+        }
+        let buy_amount = less_bps(quoted, slippage_bps.into());
+        let minimum = match self.stable {
+            Some(stable) => self.format_amount(stable, buy_amount),
+            None => format!("{} ETH", format_ether(buy_amount)),
+        };
+        if self.dry_run {
+            println!("[dry-run] Would post a CoW order selling {} for at least {}", self.format_amount(token, sell_amount), minimum);
+            return Ok(());
+        }
+
+        if !self.ensure_allowance(token, GPV2_VAULT_RELAYER, sell_amount).await? {
+            println!("Skipping sell of {}: CoW vault relayer not approved", self.format_amount(token, sell_amount));
+            return Ok(());
+        }
+
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+        let valid_to = now + cow.validity.as_secs();
+        let order = Order {
+            sell_token: token,
+            buy_token,
+            receiver: wallet,
+            sell_amount,
+            buy_amount,
+            valid_to: valid_to.min(u32::MAX.into()) as u32,
+        };
+        let signature = self.wallet.sign_hash(order.digest(self.wallet.chain_id()))?;
+        let uid = cow
+            .post(&json!({
+                "sellToken": format!("{:?}", order.sell_token),
+                "buyToken": format!("{:?}", order.buy_token),
+                "receiver": format!("{:?}", order.receiver),
+                "sellAmount": order.sell_amount.to_string(),
+                "buyAmount": order.buy_amount.to_string(),
+                "validTo": order.valid_to,
+                "appData": APP_DATA,
+                "appDataHash": format!("{:?}", H256::from(keccak256(APP_DATA))),
+                "feeAmount": "0",
+                "kind": "sell",
+                "partiallyFillable": false,
+                "sellTokenBalance": "erc20",
+                "buyTokenBalance": "erc20",
+                "signingScheme": "eip712",
+                "signature": format!("0x{}", signature),
+                "from": format!("{:?}", wallet),
+            }))
+            .await?;
+        println!("Posted CoW order {} selling {} for at least {}", uid, self.format_amount(token, sell_amount), minimum);
+        self.open_intents.lock().await.insert(uid, OpenIntent { token, sell_amount, valid_to });
+        Ok(())
+    }
+
+    // Records open orders the solvers have filled and forgets those expired
+    // or cancelled; returns how many are still open. Orders the order book
+    // can't be asked about are kept until they expire.
+    pub(crate) async fn poll_intents(&self) -> usize {
+        let Some(cow) = &self.intents else { return 0 };
+        let open: Vec<_> = self.open_intents.lock().await.iter().map(|(uid, order)| (uid.clone(), order.clone())).collect();
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+        for (uid, order) in open {
+            let status = match cow.order(&uid).await {
+                Ok(status) => status,
+                Err(e) if now > order.valid_to => {
+                    println!("Dropping CoW order {} past its expiry: {}", uid, e);
+                    self.open_intents.lock().await.remove(&uid);
+                    continue;
+                }
+                Err(e) => {
+                    println!("Could not check CoW order {}: {}", uid, e);
+                    continue;
+                }
+            };
+            let amount = |field: &str| status[field].as_str().and_then(|amount| U256::from_dec_str(amount).ok()).unwrap_or_default();
+            match status["status"].as_str().unwrap_or_default() {
+                "fulfilled" => {
+                    self.open_intents.lock().await.remove(&uid);
+                    let received = amount("executedBuyAmount");
+                    let (eth_out, stable_out) = match self.stable {
+                        Some(_) => (U256::zero(), received),
+                        None => (received, U256::zero()),
+                    };
+                    self.record_sale(order.token, amount("executedSellAmount"), eth_out, stable_out, U256::zero(), "CoW Protocol")
+                        .await;
+                }
+                state @ ("expired" | "cancelled") => {
+                    self.open_intents.lock().await.remove(&uid);
+                    println!("CoW order {} selling {} {} unfilled", uid, self.format_amount(order.token, order.sell_amount), state);
+                }
+                _ => {}
+            }
+        }
+        self.open_intents.lock().await.len()
+    }
+}
@@ -0,0 +1,24 @@
+use crate::bot::TradingBot;
+use ethers::core::rand::{thread_rng, Rng};
+use std::time::Duration;
+
+// Makes reactions harder to predict: each order waits a random delay up to
+// max_delay before executing, and skip_percentage of triggers are ignored
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Jitter {
+    pub max_delay: Duration,
+    pub skip_percentage: f64,
+}
+
+impl TradingBot {
+    // How long the executor waits before taking the next order
+    pub(crate) fn reaction_delay(&self) -> Option<Duration> {
+        let jitter = self.jitter.filter(|jitter| !jitter.max_delay.is_zero())?;
+        Some(thread_rng().gen_range(Duration::ZERO..=jitter.max_delay))
+    }
+
+    // Whether to let this trigger pass
+    pub(crate) fn skip_trigger(&self) -> bool {
+        self.jitter.is_some_and(|jitter| thread_rng().gen_bool(jitter.skip_percentage / 100.0))
+    }
+}
@@ -0,0 +1,90 @@
+pub mod abi;
+pub mod access_list;
+pub mod aerodrome;
+pub mod aggregator;
+pub mod allowance;
+pub mod balancer;
+pub mod bot;
+pub mod builder;
+pub mod bundle;
+pub mod buy;
+pub mod buy_back;
+pub mod chainlink;
+pub mod chains;
+pub mod chunks;
+pub mod config;
+pub mod cooldown;
+pub mod counterparty;
+pub mod curve;
+pub mod decode;
+pub mod error;
+pub mod escalator;
+pub mod execution;
+pub mod exits;
+pub mod failover;
+pub mod fees;
+pub mod flow;
+pub mod forks;
+pub mod grid;
+pub mod health;
+pub mod intents;
+pub mod jitter;
+pub mod lp;
+pub mod market_data;
+pub mod market_making;
+pub mod math;
+pub mod mempool;
+pub mod metadata;
+pub mod momentum;
+pub mod nonce;
+pub mod oracle;
+pub mod paper;
+pub mod params;
+pub mod paths;
+pub mod pipeline;
+pub mod pool;
+pub mod preflight;
+pub mod private;
+pub mod profile;
+pub mod queue;
+pub mod receipts;
+pub mod reload;
+pub mod reorg;
+pub mod replacement;
+pub mod report;
+pub mod reserves;
+pub mod routing;
+pub mod schedule;
+pub mod seen;
+pub mod shutdown;
+pub mod signer;
+pub mod sizing;
+pub mod skew;
+pub mod source;
+pub mod strategy;
+pub mod supervisor;
+pub mod sweep;
+pub mod target;
+pub mod tax;
+pub mod throttle;
+pub mod transport;
+pub mod trigger;
+pub mod twap;
+pub mod universal;
+pub mod unwind;
+pub mod v3;
+pub mod v4;
+pub mod volatility;
+pub mod watchlist;
+pub mod weth;
+
+pub use bot::{TokenTarget, TradingBot};
+pub use builder::TradingBotBuilder;
+pub use config::Config;
+pub use error::{MktmkrError, Result};
+pub use params::{ParamsHandle, StrategyParams};
+pub use profile::Profile;
+pub use report::SessionReport;
+pub use signer::KeySource;
+pub use supervisor::{MarketStatus, Supervisor};
+pub use target::{OnTarget, Target};
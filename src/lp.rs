@@ -0,0 +1,274 @@
+use crate::abi::{IUniswapV2Pair, IUniswapV2Router02, IERC20};
+use crate::bot::{TradingBot, SETTLE_TIMEOUT};
+use crate::error::{MktmkrError, Result};
+use crate::math::{apply_bps, less_bps, mul_div, percent_to_bps};
+use ethers::{
+    prelude::*,
+    types::{transaction::eip2718::TypedTransaction, Address, U256},
+    utils::format_ether,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::Arc;
+
+// What was put into a token's WETH pair, for fee and impermanent loss
+// accounting. Removals shrink it in proportion.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct LpPosition {
+    // LP tokens minted by our adds
+    pub liquidity: U256,
+    pub tokens_deposited: U256,
+    pub eth_deposited: U256,
+    // sqrt(k) per LP token when deposited, averaged over adds; the pool's
+    // fees grow it
+    pub entry_root_k: f64,
+}
+
+// A pair's reserves and LP token supply
+struct PairState {
+    pair: Address,
+    token_reserve: U256,
+    weth_reserve: U256,
+    total_supply: U256,
+}
+
+impl PairState {
+    fn root_k(&self) -> f64 {
+        (to_f64(self.token_reserve) * to_f64(self.weth_reserve)).sqrt() / to_f64(self.total_supply)
+    }
+
+    // The token and ETH `liquidity` LP tokens redeem for
+    fn underlying(&self, liquidity: U256) -> (U256, U256) {
+        (
+            mul_div(liquidity, self.token_reserve, self.total_supply),
+            mul_div(liquidity, self.weth_reserve, self.total_supply),
+        )
+    }
+}
+
+// Ratios of raw amounts, so the scale cancels out
+fn to_f64(amount: U256) -> f64 {
+    format_ether(amount).parse().unwrap_or_default()
+}
+
+impl TradingBot {
+    // Deposits `eth` and the matching amount of the token into its WETH pair
+    // on the V2 router, and waits for it to be mined
+    pub async fn add_liquidity(&self, token: Address, eth: U256) -> Result<()> {
+        self.lp_not_on_paper()?;
+        let target = self.target_for(token)?;
+        let slippage_bps = target.slippage_bps.unwrap_or(self.slippage_bps);
+        let before = self.pair_state(token).await?;
+        let tokens = mul_div(eth, before.token_reserve, before.weth_reserve);
+        let inventory = self.inventory(token).await?;
+        if tokens > inventory {
+            return Err(MktmkrError::Config(format!(
+                "adding {} ETH of liquidity needs {}, but the wallet holds {}",
+                format_ether(eth), self.format_amount(token, tokens), self.format_amount(token, inventory)
+            )));
+        }
+        if !self.dry_run && !self.ensure_allowance(token, self.router, tokens).await? {
+            return Ok(());
+        }
+        let held = self.lp_balance(before.pair).await?;
+
+        let wallet = self.wallet.address();
+        let router = IUniswapV2Router02::new(self.router, Arc::new(self.provider.clone()));
+        let deadline = self.swap_deadline_timestamp().await?;
+        let call = router.add_liquidity_eth(
+            token,
+            tokens,
+            less_bps(tokens, slippage_bps.into()),
+            less_bps(eth, slippage_bps.into()),
+            wallet,
+            deadline,
+        );
+        let mut tx = self.approval_bid().await?.transaction(wallet, self.router, call.calldata().unwrap_or_default());
+        tx.set_value(eth);
+        let description = format!("adding {} and {} ETH to {:?}", self.format_amount(token, tokens), format_ether(eth), before.pair);
+        if !self.send_lp(&mut tx, &description).await? {
+            return Ok(());
+        }
+
+        let after = self.pair_state(token).await?;
+        let mut positions = read_positions(&self.lp_state_path)?;
+        let position = positions.entry(self.lp_key(token)).or_default();
+        let minted = self.lp_balance(after.pair).await?.saturating_sub(held);
+        let (tokens_in, eth_in) = after.underlying(minted);
+        let liquidity = to_f64(position.liquidity) + to_f64(minted);
+        if liquidity > 0.0 {
+            position.entry_root_k =
+                (position.entry_root_k * to_f64(position.liquidity) + after.root_k() * to_f64(minted)) / liquidity;
+        }
+        position.liquidity += minted;
+        position.tokens_deposited += tokens_in;
+        position.eth_deposited += eth_in;
+        write_positions(&self.lp_state_path, positions)?;
+        println!("Minted {} LP tokens of {:?}", format_ether(minted), after.pair);
+        Ok(())
+    }
+
+    // Withdraws `percentage` of the wallet's LP tokens in the token's WETH
+    // pair, and waits for it to be mined
+    pub async fn remove_liquidity(&self, token: Address, percentage: f64) -> Result<()> {
+        self.lp_not_on_paper()?;
+        let target = self.target_for(token)?;
+        let slippage_bps = target.slippage_bps.unwrap_or(self.slippage_bps);
+        let state = self.pair_state(token).await?;
+        let balance = self.lp_balance(state.pair).await?;
+        let liquidity = apply_bps(balance, percent_to_bps(percentage));
+        if liquidity.is_zero() {
+            println!("No liquidity in {:?} to remove", state.pair);
+            return Ok(());
+        }
+        if !self.dry_run && !self.ensure_allowance(state.pair, self.router, liquidity).await? {
+            return Ok(());
+        }
+
+        let (tokens, eth) = state.underlying(liquidity);
+        let wallet = self.wallet.address();
+        let router = IUniswapV2Router02::new(self.router, Arc::new(self.provider.clone()));
+        let deadline = self.swap_deadline_timestamp().await?;
+        let call = router.remove_liquidity_eth_supporting_fee_on_transfer_tokens(
+            token,
+            liquidity,
+            less_bps(tokens, slippage_bps.into()),
+            less_bps(eth, slippage_bps.into()),
+            wallet,
+            deadline,
+        );
+        let mut tx = self.approval_bid().await?.transaction(wallet, self.router, call.calldata().unwrap_or_default());
+        let description = format!(
+            "removing {} LP tokens of {:?} for ~{} and ~{} ETH",
+            format_ether(liquidity), state.pair, self.format_amount(token, tokens), format_ether(eth)
+        );
+        if !self.send_lp(&mut tx, &description).await? {
+            return Ok(());
+        }
+
+        let mut positions = read_positions(&self.lp_state_path)?;
+        if let Some(position) = positions.get_mut(&self.lp_key(token)) {
+            let removed = liquidity.min(position.liquidity);
+            if !position.liquidity.is_zero() {
+                position.tokens_deposited -= mul_div(position.tokens_deposited, removed, position.liquidity);
+                position.eth_deposited -= mul_div(position.eth_deposited, removed, position.liquidity);
+            }
+            position.liquidity -= removed;
+            write_positions(&self.lp_state_path, positions)?;
+        }
+        Ok(())
+    }
+
+    // Prints the wallet's LP balance in the token's WETH pair, what it
+    // redeems for, and against what was deposited, the fees earned and the
+    // impermanent loss
+    pub async fn lp_status(&self, token: Address) -> Result<()> {
+        let state = self.pair_state(token).await?;
+        let balance = self.lp_balance(state.pair).await?;
+        let symbol = &self.metadata(token).symbol;
+        let (tokens, eth) = state.underlying(balance);
+        let value = eth * 2;
+        println!("{}/WETH pair {:?}", symbol, state.pair);
+        println!(
+            "  LP tokens: {} ({:.4}% of supply)",
+            format_ether(balance),
+            to_f64(balance) / to_f64(state.total_supply) * 100.0
+        );
+        println!("  Redeems for {} and {} ETH, worth {} ETH", self.format_amount(token, tokens), format_ether(eth), format_ether(value));
+
+        let positions = read_positions(&self.lp_state_path)?;
+        let Some(position) = positions.get(&self.lp_key(token)).filter(|p| !p.liquidity.is_zero()) else {
+            println!("  No deposits recorded in {}", self.lp_state_path.display());
+            return Ok(());
+        };
+        // Deposits and fees of the recorded LP tokens still held
+        let held = position.liquidity.min(balance);
+        let tokens_deposited = mul_div(position.tokens_deposited, held, position.liquidity);
+        let eth_deposited = mul_div(position.eth_deposited, held, position.liquidity);
+        let value = to_f64(state.underlying(held).1) * 2.0;
+        let hodl = to_f64(eth_deposited + mul_div(tokens_deposited, state.weth_reserve, state.token_reserve));
+        let fees = value * (1.0 - position.entry_root_k / state.root_k());
+        let impermanent_loss = if hodl > 0.0 { ((value - fees) / hodl - 1.0) * 100.0 } else { 0.0 };
+        println!(
+            "  Deposited {} and {} ETH, worth {:.6} ETH if held instead",
+            self.format_amount(token, tokens_deposited),
+            format_ether(eth_deposited),
+            hodl
+        );
+        println!("  Fees earned: {:.6} ETH", fees);
+        println!("  Impermanent loss: {:.2}%", impermanent_loss);
+        Ok(())
+    }
+
+    fn lp_not_on_paper(&self) -> Result<()> {
+        match self.paper.is_some() {
+            true => Err(MktmkrError::Config("liquidity can't be added or removed in paper-trading mode".into())),
+            false => Ok(()),
+        }
+    }
+
+    fn lp_key(&self, token: Address) -> String {
+        format!("{}:{:?}", self.chain_id(), token)
+    }
+
+    async fn pair_state(&self, token: Address) -> Result<PairState> {
+        let pair = self.pair_for(token).await?;
+        let (token_reserve, weth_reserve) = self.reserves(token).await?;
+        let total_supply = IUniswapV2Pair::new(pair, Arc::new(self.provider.clone())).total_supply().call().await?;
+        if token_reserve.is_zero() || weth_reserve.is_zero() || total_supply.is_zero() {
+            return Err(MktmkrError::Config(format!("pair {:?} has no liquidity", pair)));
+        }
+        Ok(PairState { pair, token_reserve, weth_reserve, total_supply })
+    }
+
+    async fn lp_balance(&self, pair: Address) -> Result<U256> {
+        Ok(IERC20::new(pair, Arc::new(self.provider.clone())).balance_of(self.wallet.address()).call().await?)
+    }
+
+    // Simulates the transaction in dry-run mode; otherwise sends it and
+    // waits for it to be mined. True once it succeeded.
+    async fn send_lp(&self, tx: &mut TypedTransaction, description: &str) -> Result<bool> {
+        if self.dry_run {
+            let outcome = match self.provider.call(tx, None).await {
+                Ok(_) => "simulation succeeded".to_string(),
+                Err(e) => format!("simulation reverted: {}", e),
+            };
+            println!("[dry-run] Would be {}; {}", description, outcome);
+            return Ok(false);
+        }
+
+        let nonce = self.next_nonce().await?;
+        tx.set_nonce(nonce);
+        let tx_hash = match self.sign_and_send(tx).await {
+            Ok(tx_hash) => tx_hash,
+            Err(e) => {
+                self.nonces.lock().await.release(nonce);
+                return Err(e);
+            }
+        };
+        println!("{} in {}", description, self.tx_link(tx_hash));
+        match tokio::time::timeout(SETTLE_TIMEOUT, PendingTransaction::new(tx_hash, &self.provider)).await {
+            Ok(Ok(Some(receipt))) if receipt.status == Some(1u64.into()) => Ok(true),
+            Ok(Ok(Some(_))) => Err(MktmkrError::Contract(format!("{} reverted", description))),
+            Ok(Ok(None)) => Err(MktmkrError::Contract(format!("{} was dropped", description))),
+            Ok(Err(e)) => Err(e.into()),
+            Err(_) => Err(MktmkrError::Contract(format!("{} not mined within {:?}", description, SETTLE_TIMEOUT))),
+        }
+    }
+}
+
+// Positions keyed by "<chain id>:<token>"
+fn read_positions(path: &Path) -> Result<BTreeMap<String, LpPosition>> {
+    match std::fs::read_to_string(path) {
+        Ok(json) => serde_json::from_str(&json).map_err(|e| MktmkrError::Config(format!("LP state {}: {}", path.display(), e))),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(BTreeMap::new()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+fn write_positions(path: &Path, positions: BTreeMap<String, LpPosition>) -> Result<()> {
+    let json = serde_json::to_string_pretty(&positions).expect("JSON values always serialize");
+    std::fs::write(path, json)?;
+    Ok(())
+}
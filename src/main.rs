@@ -1,192 +1,501 @@
-use ethers::{
-    prelude::*,
-    providers::{Provider, Ws, StreamExt},
-    types::{Transaction, H256, U256, Bytes},
-    utils::keccak256,
-};
-use std::time::{Duration, Instant};
-use std::str::FromStr;
-use std::sync::Arc;
-use tokio::sync::Mutex;
-
-const UNISWAP_V2_ROUTER: &str = "0x7a250d5630B4cF539739dF2C5dAcb4c659F2488D";
-const WETH_ADDRESS: &str = "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2";
-
-struct TradingBot {
-    provider: Provider<Ws>,
-    wallet: LocalWallet,
-    token_address: Address,
-    router: Address,
-    weth: Address,
-    sell_percentage: f64,
-    target_eth: U256,
-    expiry_time: Instant,
-    total_sold: Arc<Mutex<U256>>,
-}
+use clap::{Parser, Subcommand};
+use ethers::types::U256;
+use mktmkr::chains::chain_preset;
+use mktmkr::config::{Config, TokenConfig, DEFAULT_CONFIG_PATH};
+use mktmkr::reload::ConfigWatcher;
+use mktmkr::shutdown;
+use mktmkr::twap::Twap;
+use mktmkr::{MarketStatus, ParamsHandle, Supervisor, TradingBot};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
-impl TradingBot {
-    async fn new(
-        ws_url: &str,
-        private_key: &str,
-        token_address: &str,
-        sell_percentage: f64,
-        target_eth: U256,
-        expiry_seconds: u64,
-    ) -> Result<Self, Box<dyn std::error::Error>> {
-        let provider = Provider::<Ws>::connect(ws_url).await?;
-        let wallet: LocalWallet = private_key.parse()?;
-        let token_address = Address::from_str(token_address)?;
-        let router = Address::from_str(UNISWAP_V2_ROUTER)?;
-        let weth = Address::from_str(WETH_ADDRESS)?;
-        let expiry_time = Instant::now() + Duration::from_secs(expiry_seconds);
-
-        Ok(Self {
-            provider,
-            wallet,
-            token_address,
-            router,
-            weth,
-            sell_percentage,
-            target_eth,
-            expiry_time,
-            total_sold: Arc::new(Mutex::new(U256::zero())),
-        })
-    }
+#[derive(Parser, Clone)]
+#[command(name = "mktmkr", version, about = "Mempool-reactive market maker for Uniswap V2 tokens")]
+struct Cli {
+    /// Config file (TOML or YAML); defaults to ./mktmkr.toml when present
+    #[arg(long, global = true)]
+    config: Option<PathBuf>,
 
-    async fn monitor_mempool(&self) -> Result<(), Box<dyn std::error::Error>> {
-        let mut pending_txs = self.provider.subscribe_pending_txs().await?;
-        
-        while let Some(tx_hash) = pending_txs.next().await {
-            if Instant::now() >= self.expiry_time {
-                break;
-            }
+    /// RPC endpoint: ws(s):// or http(s):// URL, or IPC socket path
+    #[arg(long, global = true)]
+    ws_url: Option<String>,
 
-            if let Some(tx) = self.provider.get_transaction(tx_hash).await? {
-                if let Some(buy_amount) = self.is_token_buy(&tx) {
-                    self.execute_sell(buy_amount).await?;
-                }
-            }
-        }
+    /// ERC-20 token to market-make; repeat to trade several, replacing [[tokens]] and [[markets]]
+    #[arg(long = "token", global = true)]
+    tokens: Vec<String>,
 
-        Ok(())
-    }
+    /// Default percentage of each detected buy to sell back into the pool
+    #[arg(long, global = true)]
+    sell_percentage: Option<f64>,
 
-    fn is_token_buy(&self, tx: &Transaction) -> Option<U256> {
-        // Check if the transaction is to the Uniswap V2 Router
-        if tx.to != Some(self.router) {
-            return None;
-        }
+    /// Default stop target per token, in ETH
+    #[arg(long, global = true)]
+    target_eth: Option<f64>,
 
-        // Function selector for swapExactETHForTokensSupportingFeeOnTransferTokens
-        const SWAP_ETH_FOR_TOKENS: [u8; 4] = [0x7f, 0xf3, 0x6a, 0xb5];
-        // Function selector for swapExactTokensForTokensSupportingFeeOnTransferTokens
-        const SWAP_TOKENS_FOR_TOKENS: [u8; 4] = [0x38, 0xed, 0x17, 0x39];
+    /// Ignore buys spending less than this many ETH
+    #[arg(long, global = true)]
+    min_buy_eth: Option<f64>,
 
-        if tx.input.starts_with(&SWAP_ETH_FOR_TOKENS) || tx.input.starts_with(&SWAP_TOKENS_FOR_TOKENS) {
-            // Check if our token is in the path (should be the last address)
-            let path_offset = if tx.input.starts_with(&SWAP_ETH_FOR_TOKENS) { 164 } else { 196 };
-            let path_length = U256::from_big_endian(&tx.input[path_offset..path_offset + 32]).as_usize();
-            let last_token = Address::from_slice(&tx.input[tx.input.len() - 20..]);
+    /// Profile for every token ("aggressive", "passive", "unwind" or a [profiles] entry)
+    #[arg(long, global = true)]
+    profile: Option<String>,
 
-            if last_token == self.token_address {
-                return Some(tx.value);
-            }
-        }
+    /// Session end: a duration ("90m", "4h") or an RFC 3339 timestamp
+    #[arg(long, global = true)]
+    expiry: Option<String>,
+
+    /// Log detected buys and simulate sells with eth_call; nothing is broadcast
+    #[arg(long, global = true)]
+    dry_run: bool,
 
-        None
+    /// Fill sells virtually against live pool reserves and track P&L
+    #[arg(long, global = true)]
+    paper: bool,
+
+    /// Where to write the paper-trading JSON report (implies --paper)
+    #[arg(long, global = true)]
+    paper_report: Option<PathBuf>,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Clone)]
+enum Command {
+    /// Watch the mempool and sell into detected buys
+    Run,
+    /// Validate the configuration and provider connectivity, then exit
+    CheckConfig,
+    /// Sell a fixed amount of a token immediately (pick one with --token)
+    SellNow {
+        /// Amount to sell, in raw token units
+        #[arg(long, value_parser = parse_amount)]
+        amount: U256,
+    },
+    /// Sell a fixed amount of a token evenly over a window, without watching for buys
+    Twap {
+        /// Amount to sell, in raw token units
+        #[arg(long, value_parser = parse_amount)]
+        amount: U256,
+        /// Window to spread it over, e.g. "4h"
+        #[arg(long, value_parser = humantime::parse_duration)]
+        duration: Duration,
+        /// Number of slices; one a minute by default
+        #[arg(long)]
+        slices: Option<usize>,
+    },
+    /// Manage liquidity in a token's WETH pair on the V2 router (pick one with --token)
+    Lp {
+        #[command(subcommand)]
+        action: LpAction,
+    },
+}
+
+#[derive(Subcommand, Clone)]
+enum LpAction {
+    /// Deposit ETH and the matching amount of the token
+    Add {
+        /// ETH to deposit
+        #[arg(long, value_parser = parse_eth)]
+        eth: U256,
+    },
+    /// Withdraw a percentage of the wallet's LP tokens
+    Remove {
+        #[arg(long, default_value_t = 100.0)]
+        percentage: f64,
+    },
+    /// Show the position, fees earned and impermanent loss
+    Status,
+}
+
+impl Cli {
+    fn config_path(&self) -> Option<PathBuf> {
+        match &self.config {
+            Some(path) => Some(path.clone()),
+            None if Path::new(DEFAULT_CONFIG_PATH).exists() => Some(PathBuf::from(DEFAULT_CONFIG_PATH)),
+            None => None,
+        }
     }
 
-    async fn execute_sell(&self, buy_amount: U256) -> Result<(), Box<dyn std::error::Error>> {
-        let sell_amount = (buy_amount.as_u128() as f64 * self.sell_percentage / 100.0) as u128;
-        let sell_amount = U256::from(sell_amount);
-
-        let deadline = U256::from(Instant::now().elapsed().as_secs() + 300); // 5 minutes from now
-
-        let swap_call = encode_function_data(
-            "swapExactTokensForETHSupportingFeeOnTransferTokens",
-            &[
-                Token::Uint(sell_amount),
-                Token::Uint(U256::zero()),  // We accept any amount of ETH
-                Token::Array(vec![
-                    Token::Address(self.token_address),
-                    Token::Address(self.weth),
-                ]),
-                Token::Address(self.wallet.address()),
-                Token::Uint(deadline),
-            ],
-        )?;
-
-        let tx = TransactionRequest::new()
-            .to(self.router)
-            .data(swap_call)
-            .from(self.wallet.address());
-
-        let pending_tx = self.wallet.sign_transaction(&tx).await?;
-        let receipt = self.provider.send_raw_transaction(pending_tx).await?;
-
-        if let Some(receipt) = self.provider.get_transaction_receipt(receipt).await? {
-            let mut total_sold = self.total_sold.lock().await;
-            *total_sold += sell_amount;
-            println!("Sold {} tokens. Total sold: {} ETH", sell_amount, *total_sold);
-
-            if *total_sold >= self.target_eth {
-                println!("Target reached: {} ETH sold", *total_sold);
+    // File values, then MKTMKR_* env vars, then explicit flags
+    fn load_config(&self) -> mktmkr::Result<Config> {
+        let mut config = match self.config_path() {
+            Some(path) => Config::load(&path)?,
+            None => Config::default(),
+        };
+        config.apply_env_overrides()?;
+
+        if let Some(ws_url) = &self.ws_url {
+            config.provider.ws_url = ws_url.clone();
+        }
+        if !self.tokens.is_empty() {
+            config.markets.clear();
+            config.tokens = self.tokens
+                .iter()
+                .map(|address| TokenConfig { address: address.clone(), ..Default::default() })
+                .collect();
+        }
+        if let Some(sell_percentage) = self.sell_percentage {
+            config.strategy.sell_percentage = sell_percentage;
+        }
+        if let Some(target_eth) = self.target_eth {
+            config.limits.target_eth = target_eth;
+            config.limits.target = None;
+        }
+        if let Some(min_buy_eth) = self.min_buy_eth {
+            config.limits.min_buy_eth = min_buy_eth;
+        }
+        if let Some(expiry) = &self.expiry {
+            config.limits.expiry = expiry.clone();
+        }
+        if let Some(profile) = &self.profile {
+            config.strategy.profile = Some(profile.clone());
+            let market_tokens = config.markets.iter_mut().flat_map(|market| market.tokens.iter_mut());
+            for token in config.tokens.iter_mut().chain(market_tokens) {
+                token.profile = None;
             }
         }
 
-        Ok(())
+        config.validate()?;
+        Ok(config)
     }
+}
 
-    async fn run(&self) -> Result<(), Box<dyn std::error::Error>> {
-        self.monitor_mempool().await?;
+fn file_name(path: &Path) -> String {
+    path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default()
+}
 
-        let total_sold = self.total_sold.lock().await;
-        if *total_sold < self.target_eth {
-            println!("Time expired. Total sold: {} ETH", *total_sold);
-        }
+fn parse_amount(value: &str) -> Result<U256, String> {
+    U256::from_dec_str(value).map_err(|e| e.to_string())
+}
 
-        Ok(())
-    }
+fn parse_eth(value: &str) -> Result<U256, String> {
+    ethers::utils::parse_ether(value).map_err(|e| e.to_string())
 }
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let ws_url = "wss://mainnet.infura.io/ws/v3/YOUR-PROJECT-ID";
-    let private_key = "your_private_key_here";
-    let token_address = "0x..."; // The address of the ERC-20 token you're trading
-    let sell_percentage = 10.0;
-    let target_eth = U256::from(100_000_000_000_000_000_000u128); // 100 ETH
-    let expiry_seconds = 3600; // 1 hour
-
-    let bot = TradingBot::new(
-        ws_url,
-        private_key,
-        token_address,
-        sell_percentage,
-        target_eth,
-        expiry_seconds,
-    ).await?;
-
-    bot.run().await?;
+    let cli = Cli::parse();
+    let config = cli.load_config()?;
+
+    let wallet = config.wallet.load()?;
+    let mut markets = Vec::new();
+    let mut handles = Vec::new();
+    // Resolved once so supervisor restarts don't extend the session
+    let deadline = config.deadline()?;
+    let market_count = config.markets().len();
+    for market in config.markets() {
+        let params = ParamsHandle::new(config.strategy_params(&market)?);
+        let mut builder = TradingBot::builder()
+            .ws_url(config.market_ws_url(&market))
+            .pending_txs(config.market_pending_txs(&market))
+            .broadcast_quorum(config.provider.broadcast_quorum.min(config.market_fallback_urls(&market).len() + 1))
+            .fetch_concurrency(config.provider.fetch_concurrency)
+            .txpool_poll_interval(Duration::from_millis(config.provider.txpool_poll_ms))
+            .filter_recipients(config.provider.filter_recipients)
+            .wallet(wallet.clone())
+            .params(params.clone())
+            .trigger(config.strategy.trigger)
+            .swap_log_delay(Duration::from_millis(config.strategy.swap_log_delay_ms))
+            .path_match(config.strategy.path_match)
+            .dry_run(cli.dry_run)
+            .deadline(deadline)
+            .swap_deadline(Duration::from_secs(config.limits.swap_deadline_seconds))
+            .slippage_bps(config.limits.slippage_bps)
+            .buy_ttl(Duration::from_secs(config.limits.buy_ttl_seconds))
+            .priority_fee_percentile(config.limits.priority_fee_percentile)
+            .queue_depth(config.limits.queue_depth)
+            .backlog_policy(config.limits.backlog_policy)
+            .coalesce_sells(config.limits.coalesce_sells)
+            .confirmations(config.limits.confirmations)
+            .receipt_timeout(Duration::from_secs(config.limits.receipt_timeout_seconds))
+            .escalate_after_blocks(config.limits.escalate_after_blocks)
+            .fee_bump_percent(config.limits.fee_bump_percent)
+            .max_fee_bumps(config.limits.max_fee_bumps)
+            .preflight(config.limits.preflight)
+            .detect_taxes(config.limits.detect_taxes)
+            .public_fallback(Duration::from_secs(config.submission.public_fallback_seconds))
+            .access_lists(config.limits.access_lists)
+            .gas_margin_percent(config.limits.gas_margin_percent)
+            .schedule(config.schedule()?);
+        builder = builder.counterparties(config.counterparties()?).approvals(config.approvals()?);
+        if let Some(chain_id) = config.market_chain(&market)? {
+            builder = builder.chain_id(chain_id);
+        }
+        if let Some(block_time_ms) = config.provider.block_time_ms {
+            builder = builder.block_time(Duration::from_millis(block_time_ms));
+        }
+        if let Some(fee_mode) = config.limits.fee_mode {
+            builder = builder.fee_mode(fee_mode);
+        }
+        if let Some(l1_fee) = config.provider.l1_fee {
+            builder = builder.l1_fee(l1_fee);
+        }
+        // A chain defined in the config takes precedence over the settings
+        // above, which apply to every market
+        if let Some(chain) = config.market_custom_chain(&market)? {
+            if let Some(block_time) = chain.block_time {
+                builder = builder.block_time(block_time);
+            }
+            if let Some(fee_mode) = chain.fee_mode {
+                builder = builder.fee_mode(fee_mode);
+            }
+            if let Some(l1_fee) = chain.l1_fee {
+                builder = builder.l1_fee(l1_fee);
+            }
+            if let Some(explorer_url) = chain.explorer_url {
+                builder = builder.explorer_url(explorer_url);
+            }
+        }
+        if let Some(max_sell_fee) = config.max_sell_fee()? {
+            builder = builder.max_sell_fee(max_sell_fee);
+        }
+        for wallet in config.watchlist()? {
+            builder = builder.watch(wallet);
+        }
+        for url in config.market_fallback_urls(&market) {
+            builder = builder.fallback_url(url);
+        }
+        for url in &config.submission.private_rpc_urls {
+            builder = builder.private_rpc_url(url);
+        }
+        for source in config.feeds()? {
+            builder = builder.mempool_source(source);
+        }
+        // Each TWAP runs in the market trading its token
+        let market_tokens = params.read().tokens.iter().map(|token| token.address).collect::<Vec<_>>();
+        for twap in config.twaps()?.into_iter().filter(|twap| market_tokens.contains(&twap.token)) {
+            builder = builder.twap(twap);
+        }
+        if let Some(chunking) = config.chunking()? {
+            builder = builder.chunking(chunking);
+        }
+        if let Some(proceeds) = config.weth_proceeds()? {
+            builder = builder.weth_proceeds(proceeds);
+        }
+        let v2 = config.v2_overrides()?;
+        if let Some(v2_fork) = &config.routing.v2_fork {
+            builder = builder.v2_fork(v2_fork);
+        }
+        if let Some(router) = v2.router {
+            builder = builder.router(router);
+        }
+        if let Some(factory) = v2.factory {
+            builder = builder.factory(factory);
+        }
+        if let Some(init_code_hash) = v2.init_code_hash {
+            builder = builder.init_code_hash(init_code_hash);
+        }
+        if let Some(weth) = v2.weth {
+            builder = builder.weth(weth);
+        }
+        if let Some(weth) = config.market_custom_chain(&market)?.and_then(|chain| chain.wrapped_native) {
+            builder = builder.weth(weth);
+        }
+        builder = builder.stable_proceeds(config.routing.sell_to_usd);
+        if let Some(search) = config.path_search()? {
+            builder = builder.path_search(search);
+        }
+        for api in config.aggregator_apis()? {
+            builder = builder.aggregator_api(api);
+        }
+        if let Some(intents) = config.intents()? {
+            builder = builder.intents(intents);
+        }
+        if let Some(sweep) = config.sweep()? {
+            builder = builder.sweep(sweep);
+        }
+        builder = builder.lp_state_path(&config.lp.state_file);
+        for relay in config.bundle_relays()? {
+            builder = builder.bundle_relay(relay);
+        }
+        if let Some(max_price_impact_bps) = config.limits.max_price_impact_bps {
+            builder = builder.max_price_impact_bps(max_price_impact_bps);
+        }
+        if let Some(max_escalated_fee) = config.max_escalated_fee()? {
+            builder = builder.max_escalated_fee(max_escalated_fee);
+        }
+        if let Some(min_effective_gas_price) = config.min_effective_gas_price()? {
+            builder = builder.min_effective_gas_price(min_effective_gas_price);
+        }
+        builder = builder.strategy(config.trading_strategy()?);
+        if let Some(skew) = config.inventory_skew()? {
+            builder = builder.inventory_skew(skew);
+        }
+        if let Some(scaling) = config.volatility_scaling()? {
+            builder = builder.volatility_scaling(scaling);
+        }
+        if let Some(sizing) = config.sell_sizing()? {
+            builder = builder.sell_sizing(sizing);
+        }
+        if let Some(jitter) = config.jitter()? {
+            builder = builder.jitter(jitter);
+        }
+        if let Some(cooldowns) = config.cooldowns() {
+            builder = builder.cooldowns(cooldowns);
+        }
+        if let Some(net_flow) = config.net_flow()? {
+            builder = builder.net_flow(net_flow);
+        }
+        if let Some(gate) = config.twap_gate()? {
+            builder = builder.twap_gate(gate);
+        }
+        if let Some(feed) = config.reference()? {
+            builder = builder.reference(feed);
+        }
+        if let Some(unwind) = config.unwind() {
+            builder = builder.unwind_at_expiry(unwind);
+        }
+        if let Some(usd_token) = config.usd_token()? {
+            builder = builder.usd_token(usd_token);
+        }
+        if let Some(feeds) = config.chainlink()? {
+            builder = builder.chainlink(feeds);
+        }
+        if cli.paper || cli.paper_report.is_some() {
+            // One report per market, prefixed with its name when there are several
+            let report_path = cli.paper_report.as_ref().map(|path| match market_count {
+                1 => path.clone(),
+                _ => path.with_file_name(format!("{}-{}", market.name, file_name(path))),
+            });
+            builder = builder.paper_trading(report_path);
+            for (token, amount) in config.paper_inventories(&market)? {
+                builder = builder.paper_inventory(token, amount);
+            }
+        }
+        handles.push((market.name.clone(), params));
+        markets.push((market.name, builder));
+    }
+
+    match &cli.command {
+        Command::Run => {
+            let _watcher = match cli.config_path() {
+                Some(path) => {
+                    let reload_cli = cli.clone();
+                    let watcher = ConfigWatcher::spawn(&path, move || reload_cli.load_config(), handles)?;
+                    println!("Watching {} for parameter changes", path.display());
+                    Some(watcher)
+                }
+                None => None,
+            };
+
+            let mut supervisor = Supervisor::new()
+                .shutdown(shutdown::on_signals())
+                .max_restarts(config.supervisor.max_restarts)
+                .backoff(
+                    Duration::from_millis(config.supervisor.initial_backoff_ms),
+                    Duration::from_millis(config.supervisor.max_backoff_ms),
+                );
+            for (name, builder) in markets {
+                supervisor = supervisor.market(name, builder);
+            }
+
+            let statuses = supervisor.run().await;
+            let mut failed = false;
+            for (name, status) in &statuses {
+                println!("[{}] {:?}", name, status);
+                failed |= matches!(status, MarketStatus::Failed { .. });
+            }
+            // Each market printed its own summary; several get a combined one
+            if market_count > 1 {
+                print!("{}", supervisor.consolidated().await);
+            }
+            if failed {
+                return Err("one or more markets failed".into());
+            }
+        }
+        Command::CheckConfig => {
+            println!("Configuration OK");
+            for (name, builder) in markets {
+                let bot = builder.build().await?;
+                let custom = config.chains.iter().find(|chain| chain.id == bot.chain_id()).map(|chain| chain.name.as_str());
+                match custom.or(chain_preset(bot.chain_id()).map(|chain| chain.name)) {
+                    Some(chain) => println!("[{}] Connected to {} (chain {})", name, chain, bot.chain_id()),
+                    None => println!("[{}] Connected to chain {}", name, bot.chain_id()),
+                }
+                if let Some(explorer_url) = bot.explorer_url() {
+                    println!("[{}] Explorer: {}", name, explorer_url);
+                }
+                println!("[{}] Wallet: {:?}", name, bot.wallet_address());
+                println!("[{}] Strategy: {}", name, bot.strategy_name());
+                println!("[{}] Router: {:?} ({})", name, bot.router(), bot.v2_name());
+                if let Some(universal_router) = bot.universal_router() {
+                    println!("[{}] Universal Router: {:?}", name, universal_router);
+                }
+                if let Some(v3_router) = bot.v3_router() {
+                    println!("[{}] {} router: {:?}", name, bot.v3_name().unwrap_or_default(), v3_router);
+                }
+                if let Some(v4_router) = bot.v4_router() {
+                    println!("[{}] Uniswap V4 router: {:?}", name, v4_router);
+                }
+                if let Some(url) = bot.intent_api() {
+                    println!("[{}] CoW order book: {}", name, url);
+                }
+                if !bot.aggregators().is_empty() {
+                    println!("[{}] Aggregators: {:?}", name, bot.aggregators());
+                }
+                println!("[{}] WETH: {:?}", name, bot.weth());
+                if let Some(usd_token) = bot.usd_token() {
+                    println!("[{}] USD token: {:?}", name, usd_token);
+                }
+                if let Some(chainlink) = bot.chainlink() {
+                    println!("[{}] Chainlink ETH/USD feed: {:?}", name, chainlink.eth_usd);
+                }
+                for token in bot.tokens() {
+                    let metadata = bot.metadata(token.address);
+                    println!(
+                        "[{}] Token {} ({:?}, {} decimals): profile {}, sell {}%, target {}, then {}",
+                        name,
+                        metadata.symbol,
+                        token.address,
+                        metadata.decimals,
+                        token.profile.as_deref().unwrap_or("none"),
+                        token.sell_percentage,
+                        token.target,
+                        token.on_target
+                    );
+                }
+            }
+            println!("Session ends: {}", humantime::format_rfc3339_seconds(deadline));
+        }
+        Command::SellNow { amount } => {
+            let [(_, builder)] = <[_; 1]>::try_from(markets)
+                .map_err(|_| "sell-now needs exactly one market")?;
+            let bot = builder.build().await?;
+            match bot.tokens().as_slice() {
+                [token] => bot.sell(token.address, *amount).await?,
+                _ => return Err("sell-now needs exactly one token; select it with --token".into()),
+            }
+        }
+        Command::Twap { amount, duration, slices } => {
+            let [(_, builder)] = <[_; 1]>::try_from(markets)
+                .map_err(|_| "twap needs exactly one market")?;
+            let bot = builder.shutdown(shutdown::on_signals()).build().await?;
+            match bot.tokens().as_slice() {
+                [token] => bot.run_twap(Twap::new(token.address, *amount, *duration, *slices)).await?,
+                _ => return Err("twap needs exactly one token; select it with --token".into()),
+            }
+        }
+        Command::Lp { action } => {
+            let [(_, builder)] = <[_; 1]>::try_from(markets)
+                .map_err(|_| "lp needs exactly one market")?;
+            let bot = builder.build().await?;
+            let token = match bot.tokens().as_slice() {
+                [token] => token.address,
+                _ => return Err("lp needs exactly one token; select it with --token".into()),
+            };
+            match action {
+                LpAction::Add { eth } => bot.add_liquidity(token, *eth).await?,
+                LpAction::Remove { percentage } => {
+                    if !(*percentage > 0.0 && *percentage <= 100.0) {
+                        return Err(format!("--percentage must be in (0, 100], got {}", percentage).into());
+                    }
+                    bot.remove_liquidity(token, *percentage).await?
+                }
+                LpAction::Status => bot.lp_status(token).await?,
+            }
+        }
+    }
 
     Ok(())
 }
-
-fn encode_function_data(function_name: &str, tokens: &[Token]) -> Result<Bytes, Box<dyn std::error::Error>> {
-    let function = ethers::abi::Function {
-        name: function_name.to_string(),
-        inputs: vec![
-            ethers::abi::Param { name: "amountIn".to_string(), kind: ethers::abi::ParamType::Uint(256), internal_type: None },
-            ethers::abi::Param { name: "amountOutMin".to_string(), kind: ethers::abi::ParamType::Uint(256), internal_type: None },
-            ethers::abi::Param { name: "path".to_string(), kind: ethers::abi::ParamType::Array(Box::new(ethers::abi::ParamType::Address)), internal_type: None },
-            ethers::abi::Param { name: "to".to_string(), kind: ethers::abi::ParamType::Address, internal_type: None },
-            ethers::abi::Param { name: "deadline".to_string(), kind: ethers::abi::ParamType::Uint(256), internal_type: None },
-        ],
-        outputs: vec![],
-        constant: None,
-        state_mutability: ethers::abi::StateMutability::NonPayable,
-    };
-
-    let encoded = function.encode_input(tokens)?;
-    Ok(encoded.into())
-}
\ No newline at end of file
@@ -0,0 +1,181 @@
+use crate::bot::TradingBot;
+use crate::error::{MktmkrError, Result};
+use crate::shutdown::Shutdown;
+use ethers::{
+    providers::ProviderError,
+    types::{Address, U256},
+    utils::format_ether,
+};
+use futures_util::{SinkExt, StreamExt};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::fmt;
+use std::time::{Duration, Instant};
+use tokio_tungstenite::tungstenite::Message;
+
+const BINANCE_STREAMS: &str = "wss://stream.binance.com:9443/stream";
+const COINBASE_FEED: &str = "wss://ws-feed.exchange.coinbase.com";
+// Wait between reconnects to a dropped ticker feed
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Exchange {
+    #[default]
+    Binance,
+    Coinbase,
+}
+
+impl Exchange {
+    // Its ETH market quoted in the same dollar as token markets
+    pub fn eth_symbol(&self) -> &'static str {
+        match self {
+            Exchange::Binance => "ETHUSDT",
+            Exchange::Coinbase => "ETH-USD",
+        }
+    }
+}
+
+impl fmt::Display for Exchange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Exchange::Binance => write!(f, "Binance"),
+            Exchange::Coinbase => write!(f, "Coinbase"),
+        }
+    }
+}
+
+// Exchange tickers for ETH and the tokens listed there, dividing into a
+// reference price in ETH per whole token. With `max_divergence_bps` set,
+// buys don't trigger sells while the pool's spot price is further than that
+// from the reference either way.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReferenceFeed {
+    pub exchange: Exchange,
+    // The exchange's public endpoint unless set
+    pub url: Option<String>,
+    // Tickers older than this are ignored
+    pub max_age: Duration,
+    pub max_divergence_bps: Option<u64>,
+}
+
+impl TradingBot {
+    // ETH per whole token at the exchange's latest tickers; None without a
+    // listing or fresh tickers for both sides
+    pub async fn reference_price(&self, token: Address) -> Option<f64> {
+        let feed = self.reference.as_ref()?;
+        let symbol = self.token(token)?.cex_symbol?;
+        let tickers = self.tickers.lock().await;
+        let fresh = |symbol: &str| tickers.get(symbol).filter(|(_, at)| at.elapsed() <= feed.max_age).map(|(price, _)| *price);
+        let eth = fresh(feed.exchange.eth_symbol()).filter(|eth| *eth > 0.0)?;
+        Some(fresh(&symbol)? / eth)
+    }
+
+    // How far the pool's spot price is above (positive) or below the
+    // reference price, in basis points
+    pub async fn divergence_bps(&self, token: Address) -> Result<Option<f64>> {
+        let Some(reference) = self.reference_price(token).await.filter(|price| *price > 0.0) else { return Ok(None) };
+        let whole = self.whole_price(token, self.spot(token).await?);
+        Ok(Some((whole / reference - 1.0) * 10_000.0))
+    }
+
+    // A spot price, in wei per 1e18 raw units, as ETH per whole token
+    pub(crate) fn whole_price(&self, token: Address, spot: U256) -> f64 {
+        let spot: f64 = format_ether(spot).parse().unwrap_or_default();
+        spot * 10f64.powi(i32::from(self.metadata(token).decimals) - 18)
+    }
+
+    // Whether the pool's price is within max_divergence_bps of the
+    // reference; true without a bound or a reference to compare with
+    pub(crate) async fn reference_allows(&self, token: Address) -> bool {
+        let Some(max) = self.reference.as_ref().and_then(|feed| feed.max_divergence_bps) else { return true };
+        match self.divergence_bps(token).await {
+            Ok(Some(divergence)) if divergence.abs() > max as f64 => {
+                println!("Not selling into {:?}: pool price {:+.0} bps from the exchange's", token, divergence);
+                false
+            }
+            Ok(_) => true,
+            Err(e) => {
+                println!("Could not compare {:?} with the exchange's price, selling ungated: {}", token, e);
+                true
+            }
+        }
+    }
+
+    // Keeps the tickers current until `closing`, reconnecting whenever the
+    // exchange drops the feed
+    pub(crate) async fn watch_reference(&self, mut closing: Shutdown) -> Result<()> {
+        let Some(feed) = &self.reference else { return Ok(()) };
+        loop {
+            tokio::select! {
+                result = self.follow_tickers(feed) => {
+                    if let Err(e) = result {
+                        println!("{} tickers dropped: {}; reconnecting", feed.exchange, e);
+                    }
+                }
+                _ = closing.wait() => return Ok(()),
+            }
+            tokio::select! {
+                _ = tokio::time::sleep(RECONNECT_DELAY) => {}
+                _ = closing.wait() => return Ok(()),
+            }
+        }
+    }
+
+    // Subscribes to ETH's and each listed token's ticker and records their
+    // last prices until the socket closes. Tokens listed by a reload are
+    // picked up on the next reconnect.
+    async fn follow_tickers(&self, feed: &ReferenceFeed) -> Result<()> {
+        let mut symbols = vec![feed.exchange.eth_symbol().to_string()];
+        symbols.extend(self.tokens().into_iter().filter_map(|token| token.cex_symbol));
+        let url = match (&feed.url, feed.exchange) {
+            (Some(url), _) => url.clone(),
+            (None, Exchange::Binance) => BINANCE_STREAMS.to_string(),
+            (None, Exchange::Coinbase) => COINBASE_FEED.to_string(),
+        };
+        let url = match feed.exchange {
+            Exchange::Binance => {
+                let streams: Vec<_> = symbols.iter().map(|symbol| format!("{}@miniTicker", symbol.to_lowercase())).collect();
+                format!("{}?streams={}", url, streams.join("/"))
+            }
+            Exchange::Coinbase => url,
+        };
+
+        let (mut socket, _) = tokio_tungstenite::connect_async(url).await.map_err(ticker_error)?;
+        if feed.exchange == Exchange::Coinbase {
+            let subscribe = json!({ "type": "subscribe", "product_ids": symbols, "channels": ["ticker"] });
+            socket.send(Message::Text(subscribe.to_string())).await.map_err(ticker_error)?;
+        }
+
+        while let Some(message) = socket.next().await {
+            let text = match message.map_err(ticker_error)? {
+                Message::Text(text) => text,
+                Message::Ping(payload) => {
+                    socket.send(Message::Pong(payload)).await.map_err(ticker_error)?;
+                    continue;
+                }
+                _ => continue,
+            };
+            let Ok(message) = serde_json::from_str::<Value>(&text) else { continue };
+            let ticker = match feed.exchange {
+                Exchange::Binance => message.get("data").and_then(|data| Some((data["s"].as_str()?, data["c"].as_str()?))),
+                Exchange::Coinbase => {
+                    if message["type"] == "error" {
+                        return Err(ticker_error(message["message"].as_str().unwrap_or_default()));
+                    }
+                    (message["type"] == "ticker").then(|| Some((message["product_id"].as_str()?, message["price"].as_str()?))).flatten()
+                }
+            };
+            let Some((symbol, price)) = ticker else { continue };
+            let Ok(price) = price.parse::<f64>() else { continue };
+            // Binance names streams in lower case but symbols in upper
+            let symbol = symbols.iter().find(|s| s.eq_ignore_ascii_case(symbol)).cloned().unwrap_or_else(|| symbol.to_string());
+            self.tickers.lock().await.insert(symbol, (price, Instant::now()));
+        }
+        Err(ticker_error("connection closed"))
+    }
+}
+
+fn ticker_error(error: impl fmt::Display) -> MktmkrError {
+    MktmkrError::Provider(ProviderError::CustomError(error.to_string()))
+}
@@ -0,0 +1,156 @@
+use crate::bot::TradingBot;
+use crate::error::Result;
+use crate::math::{apply_bps, mul_div, percent_to_bps, BPS};
+use crate::pool::{get_amount_out, spot_price};
+use crate::queue::{OrderPart, SellOrder};
+use crate::strategy::{Side, Strategy, Trade};
+use crate::trigger::Seen;
+use ethers::{
+    providers::Middleware,
+    signers::Signer,
+    types::{Address, H256, U256},
+};
+use futures_util::future::BoxFuture;
+use std::collections::HashMap;
+use tokio::sync::Mutex;
+
+// How one side answers the flow it trades against
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct QuoteSide {
+    // Percentage of the flow matched: of the tokens a buy's ETH is worth
+    // when selling, of the ETH a sell takes out when buying
+    pub size_percentage: f64,
+    // How far the price must move past the reference first: above it for
+    // sells, below it for buys
+    pub spread_bps: u64,
+}
+
+// Two-sided market making: sells into buys that lift the price above the
+// reference while the wallet holds more than target_ratio of its value in
+// the token, and buys into sells that push it below while it holds less
+#[derive(Debug, Clone, PartialEq)]
+pub struct MarketMaking {
+    // Share of the wallet's token plus ETH value to keep in the token
+    pub target_ratio: f64,
+    pub buy: QuoteSide,
+    pub sell: QuoteSide,
+    // Largest single buy
+    pub max_buy: Option<U256>,
+    // Without an exchange reference, the pool's spot price averaged over
+    // about this many blocks stands in
+    pub reference_blocks: u64,
+}
+
+pub struct MarketMaker {
+    params: MarketMaking,
+    // Averaged spot price per token, in ETH per whole token
+    averages: Mutex<HashMap<Address, f64>>,
+}
+
+impl MarketMaker {
+    pub fn new(params: MarketMaking) -> Self {
+        Self { params, averages: Mutex::new(HashMap::new()) }
+    }
+
+    // The exchange's price if there is one, else the averaged spot price
+    async fn reference(&self, bot: &TradingBot, token: Address) -> Option<f64> {
+        match bot.reference_price(token).await {
+            Some(price) => Some(price),
+            None => self.averages.lock().await.get(&token).copied(),
+        }
+        .filter(|price| *price > 0.0)
+    }
+
+    async fn on_trade(&self, bot: &TradingBot, trade: Trade) -> Result<()> {
+        let Some(reference) = self.reference(bot, trade.token).await else { return Ok(()) };
+        let (token_reserve, weth_reserve) = bot.reserves(trade.token).await?;
+        let price = bot.whole_price(trade.token, price_after(trade, token_reserve, weth_reserve));
+        let moved_bps = (price / reference - 1.0) * BPS as f64;
+        let (token_value, eth_balance) = bot.holdings(trade.token).await?;
+        let total = token_value + eth_balance;
+        let target = apply_bps(total, (self.params.target_ratio * BPS as f64) as u64);
+        // Overweight in the token, sells grow and trigger sooner while buys
+        // shrink and wait for a deeper dip
+        let skew = bot.skew_factor(trade.token).await;
+        let (sell, buy) = (self.params.sell, self.params.buy);
+
+        match trade.side {
+            Side::Buy if moved_bps >= sell.spread_bps as f64 / skew && token_value > target => {
+                let matched = apply_bps(trade.eth, (percent_to_bps(sell.size_percentage) as f64 * skew) as u64);
+                let eth = matched.min(token_value - target);
+                let desired = mul_div(eth, token_reserve, weth_reserve);
+                let seen = Seen { spot: trade.seen.spot.or(Some(spot_price(token_reserve, weth_reserve))), ..trade.seen };
+                let part =
+                    OrderPart { buy: trade.hash, desired, pending_buy: trade.pending.then_some(trade.eth), seen: Some(seen) };
+                bot.enqueue_order(SellOrder { token: trade.token, parts: vec![part] }).await;
+            }
+            Side::Sell if -moved_bps >= buy.spread_bps as f64 * skew && token_value < target => {
+                let matched = apply_bps(trade.eth, (percent_to_bps(buy.size_percentage) as f64 / skew) as u64);
+                let eth = matched.min(target - token_value);
+                let eth = self.params.max_buy.map_or(eth, |max_buy| eth.min(max_buy));
+                bot.buy(trade.token, eth).await?;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+// The pool's spot price once a pending trade has executed
+fn price_after(trade: Trade, token_reserve: U256, weth_reserve: U256) -> U256 {
+    match (trade.pending, trade.side, trade.amount) {
+        (true, Side::Buy, _) => {
+            let bought = get_amount_out(trade.eth, weth_reserve, token_reserve);
+            spot_price(token_reserve - bought, weth_reserve + trade.eth)
+        }
+        (true, Side::Sell, Some(amount)) => {
+            let out = get_amount_out(amount, token_reserve, weth_reserve);
+            spot_price(token_reserve + amount, weth_reserve - out)
+        }
+        _ => spot_price(token_reserve, weth_reserve),
+    }
+}
+
+impl Strategy for MarketMaker {
+    fn name(&self) -> &str {
+        "market_making"
+    }
+
+    fn wants_sells(&self) -> bool {
+        true
+    }
+
+    fn on_pending_tx<'a>(&'a self, bot: &'a TradingBot, trade: Trade) -> BoxFuture<'a, Result<()>> {
+        Box::pin(self.on_trade(bot, trade))
+    }
+
+    // Folds each token's spot price into its average
+    fn on_block<'a>(&'a self, bot: &'a TradingBot, _block: H256) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            let weight = 2.0 / (self.params.reference_blocks as f64 + 1.0);
+            for token in bot.tokens() {
+                let spot = match bot.spot(token.address).await {
+                    Ok(spot) => bot.whole_price(token.address, spot),
+                    Err(e) => {
+                        println!("Could not sample the price of {:?}: {}", token.address, e);
+                        continue;
+                    }
+                };
+                let mut averages = self.averages.lock().await;
+                let average = averages.entry(token.address).or_insert(spot);
+                *average += (spot - *average) * weight;
+            }
+            Ok(())
+        })
+    }
+}
+
+impl TradingBot {
+    // The ETH value of the wallet's token inventory at spot, and its ETH
+    pub(crate) async fn holdings(&self, token: Address) -> Result<(U256, U256)> {
+        let inventory = self.inventory(token).await?;
+        let token_value = self.eth_value(token, inventory).await?;
+        let eth = self.provider.get_balance(self.wallet.address(), None).await?;
+        Ok((token_value, eth))
+    }
+}
@@ -0,0 +1,102 @@
+use ethers::types::{U256, U512};
+
+// Basis points in a whole
+pub const BPS: u64 = 10_000;
+
+// A configured percentage or ratio in basis points, rounded. Only the
+// percentage passes through f64, never the amount it is applied to.
+pub fn percent_to_bps(percent: f64) -> u64 {
+    (percent * 100.0).round().max(0.0) as u64
+}
+
+pub fn ratio_to_bps(ratio: f64) -> u64 {
+    percent_to_bps(ratio * 100.0)
+}
+
+// `a` × `b` / `c`, rounded down. The product is taken at 512 bits, so
+// nothing overflows; results beyond U256 saturate, as does division by zero.
+pub fn mul_div(a: U256, b: U256, c: U256) -> U256 {
+    if c.is_zero() {
+        return U256::MAX;
+    }
+    U256::try_from(a.full_mul(b) / U512::from(c)).unwrap_or(U256::MAX)
+}
+
+// `amount` × `bps` / 10 000
+pub fn apply_bps(amount: U256, bps: u64) -> U256 {
+    mul_div(amount, U256::from(bps), U256::from(BPS))
+}
+
+// `amount` less `bps` of it, for slippage bounds
+pub fn less_bps(amount: U256, bps: u64) -> U256 {
+    apply_bps(amount, BPS.saturating_sub(bps))
+}
+
+// `amount` plus `bps` of it, for fee bumps
+pub fn plus_bps(amount: U256, bps: u64) -> U256 {
+    apply_bps(amount, BPS.saturating_add(bps))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mul_div_rounds_down() {
+        assert_eq!(mul_div(U256::from(7), U256::from(3), U256::from(2)), U256::from(10));
+        assert_eq!(mul_div(U256::zero(), U256::MAX, U256::one()), U256::zero());
+        assert_eq!(mul_div(U256::one(), U256::one(), U256::MAX), U256::zero());
+    }
+
+    #[test]
+    fn mul_div_keeps_the_full_product() {
+        assert_eq!(mul_div(U256::MAX, U256::MAX, U256::MAX), U256::MAX);
+        assert_eq!(mul_div(U256::MAX, U256::from(2), U256::from(4)), U256::MAX / 2);
+    }
+
+    #[test]
+    fn mul_div_saturates() {
+        assert_eq!(mul_div(U256::MAX, U256::from(2), U256::one()), U256::MAX);
+        assert_eq!(mul_div(U256::one(), U256::one(), U256::zero()), U256::MAX);
+        assert_eq!(mul_div(U256::zero(), U256::zero(), U256::zero()), U256::MAX);
+    }
+
+    #[test]
+    fn applies_bps() {
+        let amount = U256::exp10(18);
+        assert_eq!(apply_bps(amount, 0), U256::zero());
+        assert_eq!(apply_bps(amount, 1), U256::exp10(14));
+        assert_eq!(apply_bps(amount, BPS), amount);
+        assert_eq!(apply_bps(U256::from(9_999), 1), U256::zero());
+        assert_eq!(apply_bps(U256::MAX, BPS), U256::MAX);
+        assert_eq!(apply_bps(U256::MAX, 2 * BPS), U256::MAX);
+        assert_eq!(apply_bps(U256::MAX, u64::MAX), U256::MAX);
+    }
+
+    #[test]
+    fn less_bps_bottoms_out_at_zero() {
+        let amount = U256::from(1_000);
+        assert_eq!(less_bps(amount, 0), amount);
+        assert_eq!(less_bps(amount, 50), U256::from(995));
+        assert_eq!(less_bps(amount, BPS), U256::zero());
+        assert_eq!(less_bps(amount, u64::MAX), U256::zero());
+    }
+
+    #[test]
+    fn plus_bps_saturates() {
+        let amount = U256::from(1_000);
+        assert_eq!(plus_bps(amount, 0), amount);
+        assert_eq!(plus_bps(amount, 1_250), U256::from(1_125));
+        assert_eq!(plus_bps(U256::zero(), u64::MAX), U256::zero());
+        assert_eq!(plus_bps(U256::MAX, 1), U256::MAX);
+        assert_eq!(plus_bps(amount, u64::MAX), mul_div(amount, U256::from(u64::MAX), U256::from(BPS)));
+    }
+
+    #[test]
+    fn converts_percentages_to_bps() {
+        assert_eq!(percent_to_bps(0.0), 0);
+        assert_eq!(percent_to_bps(12.345), 1_235);
+        assert_eq!(percent_to_bps(-5.0), 0);
+        assert_eq!(ratio_to_bps(1.0), BPS);
+    }
+}
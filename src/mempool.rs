@@ -0,0 +1,445 @@
+use crate::aerodrome::decode_aerodrome_swap;
+use crate::aggregator::decode_aggregator_swap;
+use crate::bot::TradingBot;
+use crate::decode::{decode_router_swap, unwrap_multicall, RouterSwap};
+use crate::error::Result;
+use crate::math::{apply_bps, BPS};
+use crate::replacement::{PendingBuy, Replacement};
+use crate::shutdown;
+use crate::source::PendingFilter;
+use crate::strategy::{Side, Trade};
+use crate::transport::heads;
+use crate::trigger::{Seen, TriggerMode};
+use crate::universal::decode_universal_swap;
+use crate::v3::decode_v3_swap;
+use ethers::{
+    prelude::*,
+    providers::StreamExt,
+    types::{Transaction, I256, U256},
+    utils::format_ether,
+};
+use futures_util::stream::{self, BoxStream};
+use serde::Deserialize;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, PoisonError};
+use std::time::{Duration, Instant, SystemTime};
+
+// How often sells held back by limits are retried
+const QUEUE_INTERVAL: Duration = Duration::from_secs(60);
+// How often split sells' chunks and TWAP slices are checked for being due
+pub(crate) const SCHEDULE_INTERVAL: Duration = Duration::from_secs(1);
+// How often every endpoint's round trip and head are probed
+const PROBE_INTERVAL: Duration = Duration::from_secs(10);
+
+// How pending transactions are pushed by the provider
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PendingTxMode {
+    // Standard newPendingTransactions: hashes, fetched concurrently with
+    // eth_getTransactionByHash
+    #[default]
+    Hashes,
+    // newPendingTransactions with full bodies (Geth, Reth, Erigon)
+    Full,
+    // Alchemy's alchemy_pendingTransactions with full bodies
+    Alchemy,
+    // No subscription: txpool_content polled and diffed, for nodes without
+    // websocket pending-transaction support
+    Txpool,
+}
+
+// The gas price `tx` pays at `base_fee`: its fee cap or base fee plus tip,
+// whichever is lower, or its legacy gas price
+fn effective_gas_price(tx: &Transaction, base_fee: Option<U256>) -> U256 {
+    match (tx.max_fee_per_gas, tx.max_priority_fee_per_gas, base_fee) {
+        (Some(max_fee), Some(tip), Some(base_fee)) => max_fee.min(base_fee + tip),
+        (Some(max_fee), _, _) => max_fee,
+        _ => tx.gas_price.unwrap_or_default(),
+    }
+}
+
+// The signed encoding of `tx`; None for hinted transactions, which arrive
+// without a signature
+fn signed(tx: &Transaction) -> Option<Bytes> {
+    (!tx.r.is_zero()).then(|| tx.rlp())
+}
+
+// The V2 router, then every other router and aggregator a buy can go
+// through; shared with the builder, whose node sources filter on it
+pub(crate) fn watched_contracts(
+    router: Address,
+    universal_router: Option<Address>,
+    v3_router: Option<Address>,
+    v4_router: Option<Address>,
+    aerodrome_router: Option<Address>,
+    aggregators: &[Address],
+) -> Vec<Address> {
+    std::iter::once(router)
+        .chain(universal_router)
+        .chain(v3_router)
+        .chain(v4_router)
+        .chain(aerodrome_router)
+        .chain(aggregators.iter().copied())
+        .collect()
+}
+
+impl TradingBot {
+    // Contracts whose pending transactions can contain a buy
+    pub(crate) fn watched_contracts(&self) -> Vec<Address> {
+        watched_contracts(
+            self.router,
+            self.universal_router,
+            self.v3_router(),
+            self.v4_router(),
+            self.aerodrome_router(),
+            &self.aggregators,
+        )
+    }
+
+    // Passes transactions to a watched contract, and any from a sender whose
+    // buy is tracked so replacements of it are noticed
+    fn pending_filter(&self) -> impl Fn(&Transaction) -> bool + Clone + Send + Sync + 'static {
+        let watched = self.watched_contracts();
+        let senders = self.tracked_senders.clone();
+        move |tx| {
+            tx.to.is_some_and(|to| watched.contains(&to))
+                || senders.read().unwrap_or_else(PoisonError::into_inner).contains(&tx.from)
+        }
+    }
+
+    // Pending transactions that may matter to us, merged from every source;
+    // a transaction seen by several is dropped by the seen cache
+    pub(crate) async fn subscribe_pending(&self) -> Result<BoxStream<'_, Transaction>> {
+        let filter: PendingFilter = Arc::new(self.pending_filter());
+        let mut streams = Vec::new();
+        for source in &self.sources {
+            streams.push(source.subscribe(filter.clone()).await?);
+            if self.sources.len() > 1 {
+                println!("Subscribed to pending transactions from {}", source.name());
+            }
+        }
+        Ok(stream::select_all(streams).boxed())
+    }
+
+    // Reacts to pending buys, or to swap logs in swap-log trigger mode,
+    // until `until`, shutdown, or the end of the stream. Sells triggered
+    // along the way are queued for the executor running alongside, and
+    // their receipts watched; orders not yet started are kept for the next
+    // session.
+    pub(crate) async fn monitor_mempool(&self, until: SystemTime) -> Result<()> {
+        let (close, closing) = shutdown::channel();
+        let watch = async {
+            let result = self.watch_mempool(until).await;
+            close.trigger();
+            result
+        };
+        let result = tokio::try_join!(
+            watch,
+            self.execute_orders(closing.clone()),
+            self.watch_receipts(closing.clone()),
+            self.watch_reference(closing),
+        )
+        .map(|_| ());
+        self.reserve_cache.lock().await.stop();
+        result
+    }
+
+    async fn watch_mempool(&self, until: SystemTime) -> Result<()> {
+        let (mut pending_txs, (mut swaps, pairs)) = match self.trigger {
+            TriggerMode::SwapLogs => (stream::pending().boxed(), self.subscribe_swaps().await?),
+            _ => (self.subscribe_pending().await?, (stream::pending().boxed(), HashMap::new())),
+        };
+        // Buys seen in swap logs, waiting out swap_log_delay
+        let mut delayed: VecDeque<(Instant, Address, U256)> = VecDeque::new();
+        // The primary's heads keep the base fee current and, in
+        // confirmed-trigger mode, release sells into buys they include.
+        // Every endpoint's heads are timed for its health.
+        let mut endpoint_heads = vec![heads(&self.provider).await?.map(|head| (0, head)).boxed()];
+        for (i, fallback) in self.fallbacks.iter().enumerate() {
+            match heads(fallback).await {
+                Ok(heads) => endpoint_heads.push(heads.map(move |head| (i + 1, head)).boxed()),
+                Err(e) => println!("Could not follow heads of fallback {}: {}", i + 1, e),
+            }
+        }
+        let mut heads = stream::select_all(endpoint_heads);
+        let mut syncs = self.subscribe_syncs().await;
+        let mut shutdown = self.shutdown.clone();
+        let stop = tokio::time::sleep(until.duration_since(SystemTime::now()).unwrap_or_default());
+        tokio::pin!(stop);
+        let mut queue_tick = tokio::time::interval(QUEUE_INTERVAL);
+        let mut schedule_tick = tokio::time::interval(SCHEDULE_INTERVAL);
+        let mut probe_tick = tokio::time::interval(PROBE_INTERVAL);
+
+        loop {
+            // Sells held back by limits run inline, so a shutdown is only
+            // observed between them
+            tokio::select! {
+                biased;
+                _ = shutdown.wait() => break,
+                _ = &mut stop => break,
+                _ = queue_tick.tick() => {
+                    self.drain_queued_sells().await?;
+                    self.unwrap_weth(false).await?;
+                    self.observe_prices().await;
+                }
+                _ = schedule_tick.tick() => {
+                    self.sell_due_chunks().await?;
+                    self.sell_due_twap_slices().await?;
+                    self.release_cooled_down().await?;
+                }
+                _ = probe_tick.tick() => self.probe_endpoints().await,
+                Some((i, head)) = heads.next() => {
+                    if let Some(number) = head.number {
+                        self.health.lock().await[i].record_head(number.as_u64(), head.timestamp);
+                    }
+                    if i > 0 {
+                        continue;
+                    }
+                    self.track_head(&head).await?;
+                    *self.base_fee.lock().await = head.base_fee_per_gas;
+                    self.refresh_skew().await;
+                    self.sample_volatility().await;
+                    self.check_exits().await;
+                    self.check_buy_backs().await;
+                    if let Some(hash) = head.hash {
+                        self.strategy.on_block(self, hash).await?;
+                    }
+                }
+                _ = tokio::time::sleep_until(delayed.front().map_or_else(Instant::now, |d| d.0).into()), if !delayed.is_empty() => {
+                    if let Some((_, token, buy_amount)) = delayed.pop_front() {
+                        let seen = Seen::now();
+                        if self.cooled_down(None, Address::zero(), token, buy_amount, seen).await {
+                            let trade =
+                                Trade { hash: None, token, side: Side::Buy, eth: buy_amount, amount: None, pending: false, seen };
+                            self.strategy.on_pending_tx(self, trade).await?;
+                        }
+                    }
+                }
+                log = syncs.next() => match log {
+                    Some(log) => self.reserve_cache.lock().await.apply(&log),
+                    None => {
+                        println!("Pair reserve logs ended; reading reserves per quote");
+                        self.reserve_cache.lock().await.stop();
+                        syncs = stream::pending().boxed();
+                    }
+                },
+                log = swaps.next() => match log {
+                    Some(log) if SystemTime::now() < until => {
+                        if let Some((token, buy_amount)) = self.swap_buy(&pairs, &log).await {
+                            if self.dry_run {
+                                println!("[dry-run] Detected buy of {:?} for {} wei in {:?}", token, buy_amount, log.transaction_hash);
+                            }
+                            delayed.push_back((Instant::now() + self.swap_log_delay, token, buy_amount));
+                        }
+                    }
+                    _ => break,
+                },
+                tx = pending_txs.next() => match tx {
+                    Some(tx) if SystemTime::now() < until => self.handle_pending(tx).await?,
+                    _ => break,
+                },
+            }
+
+            if self.targets_done().await {
+                println!("All targets reached");
+                break;
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn handle_pending(&self, tx: Transaction) -> Result<()> {
+        let now = Instant::now();
+        if !self.seen.lock().await.insert(tx.hash, now) {
+            return Ok(());
+        }
+
+        // Hinted transactions hide their sender, so never match
+        let watched = self.watchlist.get(&tx.from).filter(|_| !tx.from.is_zero());
+        let lands_soon = self.lands_soon(&tx).await;
+        let buy = self.token_buy(&tx).await?;
+        if let (Some(wallet), Some((token, buy_amount))) = (watched, buy) {
+            println!(
+                "Watched wallet {} buying {:?} for {} ETH in {:?}",
+                wallet.label, token, format_ether(buy_amount), tx.hash
+            );
+        }
+        // Overweight in the token, smaller buys trigger sells
+        let skew = match buy {
+            Some((token, _)) => self.skew_factor(token).await,
+            None => 1.0,
+        };
+        let buy = buy.filter(|(token, buy_amount)| {
+            if !lands_soon && self.dry_run {
+                println!("[dry-run] Ignoring buy of {:?} in {:?}: gas price too low to land soon", token, tx.hash);
+            }
+            let allowed = self.counterparties.allows(tx.from);
+            if !allowed && self.dry_run {
+                println!("[dry-run] Ignoring buy of {:?} in {:?}: sender {:?} filtered", token, tx.hash, tx.from);
+            }
+            let min_buy_eth = self.token(*token).map(|t| t.min_buy_eth).unwrap_or_default();
+            let min_buy_eth = apply_bps(min_buy_eth, (BPS as f64 / skew) as u64);
+            if *buy_amount < min_buy_eth && self.dry_run {
+                println!("[dry-run] Ignoring buy of {:?} for {} wei in {:?}: below minimum", token, buy_amount, tx.hash);
+            }
+            lands_soon && allowed && (watched.is_some() || *buy_amount >= min_buy_eth)
+        });
+        if buy.is_none() && (self.net_flow.is_some() || watched.is_some() || self.strategy.wants_sells()) {
+            if let Some((token, sold, value)) = self.token_sell(&tx).await? {
+                self.record_flow(token, -I256::from_raw(value)).await;
+                if self.strategy.wants_sells() && lands_soon {
+                    let trade = Trade {
+                        hash: Some(tx.hash),
+                        token,
+                        side: Side::Sell,
+                        eth: value,
+                        amount: Some(sold),
+                        pending: true,
+                        seen: Seen { at: now, spot: None },
+                    };
+                    self.strategy.on_pending_tx(self, trade).await?;
+                }
+                if let Some(wallet) = watched {
+                    println!(
+                        "Watched wallet {} selling {} of {:?} (~{} ETH) in {:?}",
+                        wallet.label, sold, token, format_ether(value), tx.hash
+                    );
+                    if let Some(mirror_percentage) = wallet.mirror_percentage {
+                        self.mirror_sell(token, sold, mirror_percentage).await?;
+                    }
+                }
+            }
+        }
+
+        // A replacement only sells the part of the buy not already sold into;
+        // sells still waiting for the replaced buy's inclusion or for the
+        // executor are dropped
+        let replacement = self.pending_buys.lock().await.classify(&tx, buy.is_some(), now);
+        let unsold = match &replacement {
+            Replacement::Buy(previous) | Replacement::Cancel(previous) => {
+                let awaiting = self.awaiting.lock().await.remove(&previous.hash).map(|awaiting| awaiting.buy_amount);
+                let ordered = self.orders.lock().await.cancel(previous.hash);
+                let held = self.cooldown_state.lock().await.forget(previous.hash);
+                awaiting.or(ordered).or(held).unwrap_or_default()
+            }
+            Replacement::Fresh => U256::zero(),
+        };
+        let (token, buy_amount, tracked) = match (replacement, buy) {
+            (Replacement::Cancel(previous), _) => {
+                println!("Buy {:?} cancelled by {:?}", previous.hash, tx.hash);
+                self.throttle.lock().await.dequeue(previous.token, previous.queued);
+                return Ok(());
+            }
+            (_, None) => return Ok(()),
+            (Replacement::Fresh, Some((token, buy_amount))) => {
+                let tracked =
+                    PendingBuy { hash: tx.hash, token, buy_amount, queued: U256::zero(), seen_at: now, raw: signed(&tx) };
+                (token, buy_amount, tracked)
+            }
+            (Replacement::Buy(previous), Some((token, buy_amount))) => {
+                println!("Buy {:?} replaced by {:?}", previous.hash, tx.hash);
+                let mut tracked =
+                    PendingBuy { hash: tx.hash, token, buy_amount, queued: U256::zero(), seen_at: now, raw: signed(&tx) };
+                if token != previous.token {
+                    self.throttle.lock().await.dequeue(previous.token, previous.queued);
+                    (token, buy_amount, tracked)
+                } else {
+                    let sold = previous.buy_amount.saturating_sub(unsold);
+                    tracked.buy_amount = buy_amount.max(sold);
+                    tracked.queued = previous.queued;
+                    (token, buy_amount.saturating_sub(sold), tracked)
+                }
+            }
+        };
+
+        // Tracked first, so the executor can attribute a held-back part of
+        // the sell to it. Watched wallets' buys are sold into whatever the
+        // net flow says.
+        self.pending_buys.lock().await.track(&tx, tracked);
+        if !buy_amount.is_zero() && (self.record_flow(token, I256::from_raw(buy_amount)).await || watched.is_some()) {
+            if self.dry_run {
+                println!("[dry-run] Detected buy of {:?} for {} wei in {:?}", token, buy_amount, tx.hash);
+            }
+            let seen = Seen { at: now, spot: None };
+            if !self.cooled_down(Some(tx.hash), tx.from, token, buy_amount, seen).await {
+                return Ok(());
+            }
+            let trade =
+                Trade { hash: Some(tx.hash), token, side: Side::Buy, eth: buy_amount, amount: None, pending: true, seen };
+            self.strategy.on_pending_tx(self, trade).await?;
+        }
+        Ok(())
+    }
+
+    // False for transactions bidding below min_effective_gas_price, or
+    // below the lowest base fee the next block can have. Hints from private
+    // flow don't reveal their bid and pass.
+    async fn lands_soon(&self, tx: &Transaction) -> bool {
+        if tx.gas_price.is_none() && tx.max_fee_per_gas.is_none() {
+            return true;
+        }
+        let base_fee = *self.base_fee.lock().await;
+        let bid = effective_gas_price(tx, base_fee);
+        if self.min_effective_gas_price.is_some_and(|min| bid < min) {
+            return false;
+        }
+        // The base fee falls by at most 1/8 per block
+        base_fee.is_none_or(|base_fee| bid >= base_fee - base_fee / 8)
+    }
+
+    // Swaps made by `tx` on a watched contract, including any bundled into
+    // a multicall
+    fn swaps(&self, tx: &Transaction) -> Vec<RouterSwap> {
+        let decode: fn(&[u8]) -> Option<RouterSwap> = match tx.to {
+            Some(to) if to == self.router => decode_router_swap,
+            Some(to) if Some(to) == self.universal_router || Some(to) == self.v4_router() => decode_universal_swap,
+            Some(to) if Some(to) == self.v3_router() => decode_v3_swap,
+            Some(to) if Some(to) == self.aerodrome_router() => decode_aerodrome_swap,
+            Some(to) if self.aggregators.contains(&to) => decode_aggregator_swap,
+            _ => return Vec::new(),
+        };
+        unwrap_multicall(&tx.input).iter().filter_map(|call| decode(call)).collect()
+    }
+
+    // Returns the bought token and the ETH (or ETH-equivalent) spent when
+    // `tx` buys one of ours; the first such swap is the one acted on
+    pub async fn token_buy(&self, tx: &Transaction) -> Result<Option<(Address, U256)>> {
+        let Some((token, swap)) = self.swaps(tx).into_iter().find_map(|swap| {
+            let token = swap.bought(self.path_match, |token| self.token(token).is_some())?;
+            Some((token, swap))
+        }) else {
+            return Ok(None);
+        };
+
+        match self.eth_equivalent(&swap, tx.value).await {
+            Ok(spent) => Ok(Some((token, spent))),
+            Err(e) => {
+                println!("Could not value buy {:?} of {:?}: {}", tx.hash, token, e);
+                Ok(None)
+            }
+        }
+    }
+
+    // Returns the sold token, the amount sold and its ETH value at spot
+    // when `tx` sells one of ours. Our own sells don't count.
+    pub async fn token_sell(&self, tx: &Transaction) -> Result<Option<(Address, U256, U256)>> {
+        if tx.from == self.wallet.address() {
+            return Ok(None);
+        }
+        let Some((token, sold)) = self.swaps(tx).into_iter().find_map(|swap| {
+            let token = swap.sold(|token| self.token(token).is_some())?;
+            Some((token, swap.max_amount_in()?))
+        }) else {
+            return Ok(None);
+        };
+
+        match self.eth_value(token, sold).await {
+            Ok(value) => Ok(Some((token, sold, value))),
+            Err(e) => {
+                println!("Could not value sell {:?} of {:?}: {}", tx.hash, token, e);
+                Ok(None)
+            }
+        }
+    }
+}
@@ -0,0 +1,54 @@
+use crate::abi::IERC20;
+use crate::bot::TradingBot;
+use crate::transport::Transport;
+use ethers::{
+    providers::Provider,
+    types::{Address, U256},
+    utils::format_units,
+};
+use std::sync::Arc;
+
+// A token's ERC-20 metadata, read once at startup
+#[derive(Debug, Clone, PartialEq)]
+pub struct TokenMetadata {
+    pub name: String,
+    pub symbol: String,
+    pub decimals: u8,
+}
+
+impl TokenMetadata {
+    // Stands in for tokens whose metadata calls fail or don't decode, as
+    // with bytes32 symbols: the address as symbol and 18 decimals
+    pub fn unknown(token: Address) -> Self {
+        let symbol = format!("{:?}", token);
+        Self { name: symbol.clone(), symbol, decimals: 18 }
+    }
+
+    pub async fn fetch(provider: &Provider<Transport>, token: Address) -> Self {
+        let erc20 = IERC20::new(token, Arc::new(provider.clone()));
+        let unknown = Self::unknown(token);
+        Self {
+            name: erc20.name().call().await.unwrap_or(unknown.name),
+            symbol: erc20.symbol().call().await.unwrap_or(unknown.symbol),
+            decimals: erc20.decimals().call().await.unwrap_or(unknown.decimals),
+        }
+    }
+
+    // `amount` raw units in whole tokens, with the symbol
+    pub fn format(&self, amount: U256) -> String {
+        match format_units(amount, u32::from(self.decimals)) {
+            Ok(whole) => format!("{} {}", whole, self.symbol),
+            Err(_) => format!("{} {}", amount, self.symbol),
+        }
+    }
+}
+
+impl TradingBot {
+    pub fn metadata(&self, token: Address) -> TokenMetadata {
+        self.metadata.get(&token).cloned().unwrap_or_else(|| TokenMetadata::unknown(token))
+    }
+
+    pub(crate) fn format_amount(&self, token: Address, amount: U256) -> String {
+        self.metadata(token).format(amount)
+    }
+}
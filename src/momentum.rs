@@ -0,0 +1,100 @@
+use crate::bot::TradingBot;
+use crate::error::Result;
+use crate::strategy::{Side, Strategy, Trade};
+use ethers::{
+    types::{Address, H256, I256, U256},
+    utils::format_ether,
+};
+use futures_util::future::BoxFuture;
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+// Sells on momentum rather than on each buy: once net ETH buy flow over the
+// window clears the threshold, sell_percentage of that flow is sold and the
+// token cools down before it can trigger again
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Momentum {
+    // Flow counts while within both windows that are set
+    pub window: Option<Duration>,
+    pub window_blocks: Option<u64>,
+    // Wei of net buy flow required
+    pub threshold: U256,
+    pub cooldown: Duration,
+}
+
+#[derive(Debug, Default)]
+struct Flows {
+    // Blocks seen so far, for the block window
+    block: u64,
+    // Trades per token: when, at which block, and their signed ETH flow
+    trades: HashMap<Address, VecDeque<(Instant, u64, I256)>>,
+    last_trigger: HashMap<Address, Instant>,
+}
+
+pub struct MomentumStrategy {
+    params: Momentum,
+    flows: Mutex<Flows>,
+}
+
+impl MomentumStrategy {
+    pub fn new(params: Momentum) -> Self {
+        Self { params, flows: Mutex::new(Flows::default()) }
+    }
+
+    // Adds the trade and returns the net flow to sell into if it triggers
+    async fn record(&self, trade: &Trade) -> Option<U256> {
+        let now = Instant::now();
+        let flow = match trade.side {
+            Side::Buy => I256::from_raw(trade.eth),
+            Side::Sell => -I256::from_raw(trade.eth),
+        };
+        let mut flows = self.flows.lock().await;
+        let block = flows.block;
+        let trades = flows.trades.entry(trade.token).or_default();
+        trades.push_back((now, block, flow));
+        let Momentum { window, window_blocks, threshold, cooldown } = self.params;
+        while trades.front().is_some_and(|(at, at_block, _)| {
+            window.is_some_and(|window| now.duration_since(*at) >= window)
+                || window_blocks.is_some_and(|blocks| block - at_block >= blocks)
+        }) {
+            trades.pop_front();
+        }
+        let net = trades.iter().fold(I256::zero(), |net, (_, _, flow)| net + *flow);
+        if net <= I256::from_raw(threshold) {
+            return None;
+        }
+        if flows.last_trigger.get(&trade.token).is_some_and(|at| at.elapsed() < cooldown) {
+            return None;
+        }
+        // Flow that triggered a sell doesn't count toward the next one
+        flows.trades.remove(&trade.token);
+        flows.last_trigger.insert(trade.token, now);
+        Some(net.into_raw())
+    }
+}
+
+impl Strategy for MomentumStrategy {
+    fn name(&self) -> &str {
+        "momentum"
+    }
+
+    fn wants_sells(&self) -> bool {
+        true
+    }
+
+    fn on_pending_tx<'a>(&'a self, bot: &'a TradingBot, trade: Trade) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            let Some(net) = self.record(&trade).await else { return Ok(()) };
+            println!("Net buy flow into {:?} reached {} ETH; selling into it", trade.token, format_ether(net));
+            bot.enqueue_sell(trade.hash, trade.token, net, false, Some(trade.seen)).await
+        })
+    }
+
+    fn on_block<'a>(&'a self, _bot: &'a TradingBot, _block: H256) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            self.flows.lock().await.block += 1;
+            Ok(())
+        })
+    }
+}
@@ -0,0 +1,54 @@
+use crate::bot::TradingBot;
+use crate::error::Result;
+use ethers::{
+    providers::Middleware,
+    signers::Signer,
+    types::{BlockNumber, U256},
+};
+use std::collections::BTreeSet;
+
+// Our wallet's next nonces, cached so back-to-back sells neither wait on
+// nor race each other's eth_getTransactionCount
+#[derive(Debug, Default)]
+pub(crate) struct NonceManager {
+    // Unset until the first sell, and after a resync
+    next: Option<U256>,
+    // Nonces handed out whose sell never reached a node; reused first so
+    // later sells aren't stuck behind the gap
+    released: BTreeSet<U256>,
+}
+
+impl NonceManager {
+    // Returns a nonce whose sell failed to send
+    pub fn release(&mut self, nonce: U256) {
+        if self.next == Some(nonce + 1) {
+            self.next = Some(nonce);
+        } else {
+            self.released.insert(nonce);
+        }
+    }
+
+    // Forgets everything, so the next sell starts from the node's count;
+    // for when a node reports our nonces out of step
+    pub fn resync(&mut self) {
+        self.next = None;
+        self.released.clear();
+    }
+}
+
+impl TradingBot {
+    // Allocates the nonce for a sell. The lock is held across the first
+    // lookup so concurrent sells can't both start from the same count.
+    pub(crate) async fn next_nonce(&self) -> Result<U256> {
+        let mut nonces = self.nonces.lock().await;
+        if let Some(nonce) = nonces.released.pop_first() {
+            return Ok(nonce);
+        }
+        let nonce = match nonces.next {
+            Some(next) => next,
+            None => self.provider.get_transaction_count(self.wallet.address(), Some(BlockNumber::Pending.into())).await?,
+        };
+        nonces.next = Some(nonce + 1);
+        Ok(nonce)
+    }
+}
@@ -0,0 +1,159 @@
+use crate::abi::{IUniswapV2Pair, IUniswapV3Pool};
+use crate::bot::TradingBot;
+use crate::error::Result;
+use crate::math::{apply_bps, BPS};
+use crate::routing::Venue;
+use ethers::types::{Address, U256};
+use serde::Deserialize;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+// Which side of the TWAP spot must be on for buys to trigger sells
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TwapSide {
+    // Selling into strength: spot above the average
+    #[default]
+    Above,
+    Below,
+}
+
+// Buys only trigger sells while the pool's spot price is `margin_bps`
+// beyond its time-weighted average over `window`, on the given side
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TwapGate {
+    pub window: Duration,
+    pub side: TwapSide,
+    pub margin_bps: u64,
+}
+
+// Price cumulatives sampled from each token's V2 pair, which keeps only its
+// latest: (unix time, cumulative WETH per token as UQ112x112 seconds)
+#[derive(Debug, Default)]
+pub(crate) struct Observations {
+    by_token: HashMap<Address, VecDeque<(u64, U256)>>,
+}
+
+impl Observations {
+    pub fn record(&mut self, token: Address, at: u64, cumulative: U256, keep: Duration) {
+        let observations = self.by_token.entry(token).or_default();
+        observations.push_back((at, cumulative));
+        // One observation older than the window is enough to average from
+        while observations.get(1).is_some_and(|(second, _)| at.saturating_sub(*second) >= keep.as_secs()) {
+            observations.pop_front();
+        }
+    }
+
+    // The newest observation at least `window` before `at`
+    fn at_least(&self, token: Address, at: u64, window: Duration) -> Option<(u64, U256)> {
+        self.by_token
+            .get(&token)?
+            .iter()
+            .rev()
+            .find(|(observed, _)| at.saturating_sub(*observed) >= window.as_secs())
+            .copied()
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs()
+}
+
+impl TradingBot {
+    // The token's time-weighted average price over `window`, as wei per
+    // 1e18 units like `spot`: from its V3 pool's observations, or from its
+    // V2 pair's price cumulative sampled at least `window` ago. None while
+    // too little V2 history has been sampled, and for tokens sold on other
+    // venues.
+    pub async fn twap(&self, token: Address, window: Duration) -> Result<Option<U256>> {
+        match self.venue(token) {
+            Venue::V3 => self.v3_twap(token, window).await.map(Some),
+            Venue::V2 | Venue::Best => {
+                let (at, cumulative) = self.v2_cumulative(token).await?;
+                self.observations.lock().await.record(token, at, cumulative, window);
+                let Some((then, earlier)) = self.observations.lock().await.at_least(token, at, window) else {
+                    return Ok(None);
+                };
+                // Cumulatives are meant to wrap
+                let (elapsed, _) = cumulative.overflowing_sub(earlier);
+                Ok(Some(((elapsed / (at - then)) * U256::exp10(18)) >> 112))
+            }
+            Venue::V4 | Venue::Curve | Venue::Balancer | Venue::Aerodrome => Ok(None),
+        }
+    }
+
+    // Samples the price cumulatives the TWAP gate averages from
+    pub(crate) async fn observe_prices(&self) {
+        let Some(gate) = self.twap_gate else { return };
+        for token in self.tokens().into_iter().filter(|token| matches!(token.venue, Venue::V2 | Venue::Best)) {
+            match self.v2_cumulative(token.address).await {
+                Ok((at, cumulative)) => self.observations.lock().await.record(token.address, at, cumulative, gate.window),
+                Err(e) => println!("Could not sample the price of {:?}: {}", token.address, e),
+            }
+        }
+    }
+
+    // Whether spot is on the gate's side of the TWAP; true without a gate
+    // or a TWAP to compare with
+    pub(crate) async fn twap_allows(&self, token: Address) -> bool {
+        let Some(gate) = self.twap_gate else { return true };
+        let prices = match self.twap(token, gate.window).await {
+            Ok(Some(twap)) => self.spot(token).await.map(|spot| (spot, twap)),
+            Ok(None) => return true,
+            Err(e) => Err(e),
+        };
+        let (spot, twap) = match prices {
+            Ok(prices) => prices,
+            Err(e) => {
+                println!("Could not compare {:?} with its TWAP, selling ungated: {}", token, e);
+                return true;
+            }
+        };
+        let allows = match gate.side {
+            TwapSide::Above => spot >= apply_bps(twap, BPS + gate.margin_bps),
+            TwapSide::Below => spot <= apply_bps(twap, BPS.saturating_sub(gate.margin_bps)),
+        };
+        if !allows && self.dry_run {
+            println!(
+                "[dry-run] Not selling into {:?}: spot {} isn't {:?} the TWAP {}",
+                token, spot, gate.side, twap
+            );
+        }
+        allows
+    }
+
+    // The pair's WETH-per-token price cumulative brought up to now, as the
+    // pair itself would on its next trade
+    async fn v2_cumulative(&self, token: Address) -> Result<(u64, U256)> {
+        let pair = IUniswapV2Pair::new(self.pair_for(token).await?, Arc::new(self.provider.clone()));
+        let (reserve0, reserve1, last) = pair.get_reserves().call().await?;
+        let (cumulative, token_reserve, weth_reserve) = match token < self.weth {
+            true => (pair.price_0_cumulative_last().call().await?, reserve0, reserve1),
+            false => (pair.price_1_cumulative_last().call().await?, reserve1, reserve0),
+        };
+        let now = unix_now();
+        // Pairs keep the time mod 2^32
+        let elapsed = (now as u32).wrapping_sub(last);
+        if token_reserve == 0 || elapsed == 0 {
+            return Ok((now, cumulative));
+        }
+        let price = (U256::from(weth_reserve) << 112) / token_reserve;
+        Ok((now, cumulative.overflowing_add(price * elapsed).0))
+    }
+
+    // From the mean tick over the window, which the pool must hold enough
+    // observations for
+    async fn v3_twap(&self, token: Address, window: Duration) -> Result<U256> {
+        let pool = IUniswapV3Pool::new(self.v3_pool(token).await?, Arc::new(self.provider.clone()));
+        let seconds = window.as_secs().min(u32::MAX.into()) as u32;
+        let (ticks, _) = pool.observe(vec![seconds, 0]).call().await?;
+        let mean_tick = (ticks[1] - ticks[0]) as f64 / seconds.max(1) as f64;
+        // The tick prices token1 in token0
+        let price = match token < self.weth {
+            true => 1.0001f64.powf(mean_tick),
+            false => 1.0001f64.powf(-mean_tick),
+        };
+        Ok(U256::from((price * 1e18) as u128))
+    }
+}
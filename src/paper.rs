@@ -0,0 +1,213 @@
+use crate::abi::IERC20;
+use crate::bot::TradingBot;
+use crate::error::Result;
+use crate::pool::{get_amount_out, spot_price};
+use crate::tax::after_tax;
+use ethers::{
+    signers::Signer,
+    types::{Address, I256, U256},
+    utils::format_ether,
+};
+use serde_json::json;
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+use std::sync::Arc;
+
+// A virtual position, marked against the pool price when it was opened
+#[derive(Debug, Clone, Default)]
+pub struct PaperPosition {
+    pub start_inventory: U256,
+    pub inventory: U256,
+    pub tokens_sold: U256,
+    pub eth_received: U256,
+    pub tokens_bought: U256,
+    pub eth_spent: U256,
+    pub fills: u32,
+    // Wei of ETH per 1e18 token units
+    pub start_price: U256,
+    pub last_price: U256,
+}
+
+impl PaperPosition {
+    // Proceeds versus what the sold tokens were worth at the opening price,
+    // and likewise for buys
+    pub fn realized_pnl(&self) -> I256 {
+        let cost = self.tokens_sold * self.start_price / U256::exp10(18);
+        let bought = self.tokens_bought * self.start_price / U256::exp10(18);
+        I256::from_raw(self.eth_received) - I256::from_raw(cost) + I256::from_raw(bought) - I256::from_raw(self.eth_spent)
+    }
+
+    // Price change since opening on the inventory still held
+    pub fn unrealized_pnl(&self) -> I256 {
+        let now = self.inventory * self.last_price / U256::exp10(18);
+        let then = self.inventory * self.start_price / U256::exp10(18);
+        I256::from_raw(now) - I256::from_raw(then)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct PaperBook {
+    pub positions: HashMap<Address, PaperPosition>,
+    // Starting inventory overrides; otherwise the wallet's real balance is used
+    pub inventory_overrides: HashMap<Address, U256>,
+}
+
+impl PaperBook {
+    pub fn write_json(&self, path: &Path) -> Result<()> {
+        let positions: serde_json::Map<_, _> = self.positions
+            .iter()
+            .map(|(token, p)| {
+                (format!("{:?}", token), json!({
+                    "start_inventory": p.start_inventory.to_string(),
+                    "inventory": p.inventory.to_string(),
+                    "tokens_sold": p.tokens_sold.to_string(),
+                    "eth_received": format_ether(p.eth_received),
+                    "tokens_bought": p.tokens_bought.to_string(),
+                    "eth_spent": format_ether(p.eth_spent),
+                    "fills": p.fills,
+                    "start_price": format_ether(p.start_price),
+                    "last_price": format_ether(p.last_price),
+                    "realized_pnl_eth": format_signed_ether(p.realized_pnl()),
+                    "unrealized_pnl_eth": format_signed_ether(p.unrealized_pnl()),
+                }))
+            })
+            .collect();
+
+        let report = serde_json::to_string_pretty(&json!({ "positions": positions }))
+            .expect("JSON values always serialize");
+        std::fs::write(path, report)?;
+        Ok(())
+    }
+}
+
+impl fmt::Display for PaperBook {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Paper trading summary")?;
+        for (token, p) in &self.positions {
+            writeln!(
+                f,
+                "  {:?}: {} fills, sold {} of {}, {} ETH received, realized {} ETH, unrealized {} ETH",
+                token,
+                p.fills,
+                p.tokens_sold,
+                p.start_inventory,
+                format_ether(p.eth_received),
+                format_signed_ether(p.realized_pnl()),
+                format_signed_ether(p.unrealized_pnl()),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+fn format_signed_ether(value: I256) -> String {
+    let sign = if value.is_negative() { "-" } else { "" };
+    format!("{}{}", sign, format_ether(value.unsigned_abs()))
+}
+
+impl TradingBot {
+    // Fills a sell against the pool as it will look once `pending_buy` (ETH
+    // in) has executed, clamped to the virtual inventory
+    pub(crate) async fn paper_fill(&self, token: Address, sell_amount: U256, pending_buy: Option<U256>) -> Result<()> {
+        let Some(book) = &self.paper else { return Ok(()) };
+        self.open_paper_position(token).await?;
+
+        let (mut token_reserve, mut weth_reserve) = self.reserves(token).await?;
+        if let Some(buy) = pending_buy {
+            let bought = get_amount_out(buy, weth_reserve, token_reserve);
+            weth_reserve += buy;
+            token_reserve -= bought;
+        }
+
+        let mut book = book.lock().await;
+        let position = book.positions.entry(token).or_default();
+        let sell_amount = sell_amount.min(position.inventory);
+        if sell_amount.is_zero() {
+            println!("[paper] No virtual inventory left for {}", self.metadata(token).symbol);
+            return Ok(());
+        }
+
+        // The tax is taken before the tokens reach the pool
+        let received = after_tax(sell_amount, self.tax(token).await.sell_bps);
+        let eth_out = get_amount_out(received, token_reserve, weth_reserve);
+        position.inventory -= sell_amount;
+        position.tokens_sold += sell_amount;
+        position.eth_received += eth_out;
+        position.fills += 1;
+        position.last_price = spot_price(token_reserve + received, weth_reserve - eth_out);
+
+        println!(
+            "[paper] Sold {} for {} ETH ({} left)",
+            self.format_amount(token, sell_amount), format_ether(eth_out), self.format_amount(token, position.inventory)
+        );
+        drop(book);
+
+        self.check_target(token).await
+    }
+
+    // Fills a buy of `eth_amount` against the pool into the virtual inventory
+    pub(crate) async fn paper_buy(&self, token: Address, eth_amount: U256) -> Result<()> {
+        let Some(book) = &self.paper else { return Ok(()) };
+        self.open_paper_position(token).await?;
+
+        let (token_reserve, weth_reserve) = self.reserves(token).await?;
+        let bought = get_amount_out(eth_amount, weth_reserve, token_reserve);
+        let received = after_tax(bought, self.tax(token).await.buy_bps);
+
+        let mut book = book.lock().await;
+        let position = book.positions.entry(token).or_default();
+        position.inventory += received;
+        position.tokens_bought += received;
+        position.eth_spent += eth_amount;
+        position.fills += 1;
+        position.last_price = spot_price(token_reserve - bought, weth_reserve + eth_amount);
+
+        println!(
+            "[paper] Bought {} for {} ETH ({} held)",
+            self.format_amount(token, received), format_ether(eth_amount), self.format_amount(token, position.inventory)
+        );
+        Ok(())
+    }
+
+    pub(crate) async fn open_paper_position(&self, token: Address) -> Result<()> {
+        let Some(book) = &self.paper else { return Ok(()) };
+        if book.lock().await.positions.contains_key(&token) {
+            return Ok(());
+        }
+
+        let inventory = match book.lock().await.inventory_overrides.get(&token) {
+            Some(amount) => *amount,
+            None => IERC20::new(token, Arc::new(self.provider.clone()))
+                .balance_of(self.wallet.address())
+                .call()
+                .await?,
+        };
+        let (token_reserve, weth_reserve) = self.reserves(token).await?;
+        let price = spot_price(token_reserve, weth_reserve);
+
+        book.lock().await.positions.entry(token).or_insert(PaperPosition {
+            start_inventory: inventory,
+            inventory,
+            start_price: price,
+            last_price: price,
+            ..Default::default()
+        });
+        Ok(())
+    }
+
+    // Re-marks every position at the current pool price
+    pub(crate) async fn paper_report(&self) -> Result<Option<PaperBook>> {
+        let Some(book) = &self.paper else { return Ok(None) };
+
+        for token in self.tokens() {
+            self.open_paper_position(token.address).await?;
+            let (token_reserve, weth_reserve) = self.reserves(token.address).await?;
+            if let Some(position) = book.lock().await.positions.get_mut(&token.address) {
+                position.last_price = spot_price(token_reserve, weth_reserve);
+            }
+        }
+
+        Ok(Some(book.lock().await.clone()))
+    }
+}
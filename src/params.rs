@@ -0,0 +1,118 @@
+use crate::bot::TokenTarget;
+use crate::error::{MktmkrError, Result};
+use crate::profile::Profile;
+use ethers::types::{Address, U256};
+use std::sync::{Arc, PoisonError, RwLock, RwLockReadGuard};
+
+// Strategy settings that can be retuned while the bot is running
+#[derive(Debug, Clone, Default)]
+pub struct StrategyParams {
+    pub tokens: Vec<TokenTarget>,
+    // Sells are skipped while the network gas price is above this
+    pub max_gas_price: Option<U256>,
+}
+
+impl StrategyParams {
+    pub fn token(&self, address: Address) -> Option<&TokenTarget> {
+        self.tokens.iter().find(|token| token.address == address)
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        if self.tokens.is_empty() {
+            return Err(MktmkrError::Config("at least one token is required".into()));
+        }
+        for token in &self.tokens {
+            if !(token.sell_percentage > 0.0 && token.sell_percentage <= 100.0) {
+                return Err(MktmkrError::Config(format!(
+                    "sell_percentage for {:?} must be in (0, 100], got {}",
+                    token.address, token.sell_percentage
+                )));
+            }
+            if token.slippage_bps.is_some_and(|bps| bps > 10_000) {
+                return Err(MktmkrError::Config(format!("slippage_bps for {:?} must be at most 10000", token.address)));
+            }
+            if !token.gas_price_multiplier.is_finite() || token.gas_price_multiplier <= 0.0 {
+                return Err(MktmkrError::Config(format!(
+                    "gas_price_multiplier for {:?} must be positive, got {}",
+                    token.address, token.gas_price_multiplier
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+// Shared, cloneable view of a bot's StrategyParams. The same handle survives
+// supervisor restarts, so reloaded values aren't lost when a bot is rebuilt.
+#[derive(Debug, Clone, Default)]
+pub struct ParamsHandle(Arc<RwLock<StrategyParams>>);
+
+impl ParamsHandle {
+    pub fn new(params: StrategyParams) -> Self {
+        Self(Arc::new(RwLock::new(params)))
+    }
+
+    pub fn read(&self) -> RwLockReadGuard<'_, StrategyParams> {
+        self.0.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub fn snapshot(&self) -> StrategyParams {
+        self.read().clone()
+    }
+
+    // Updates tunables for tokens already being traded. The token set itself
+    // is fixed at startup (balances were checked for it), so additions and
+    // removals in `update` are reported and ignored.
+    pub fn apply(&self, update: StrategyParams) -> Result<()> {
+        update.validate()?;
+
+        let mut params = self.0.write().unwrap_or_else(PoisonError::into_inner);
+        for new in &update.tokens {
+            match params.tokens.iter_mut().find(|token| token.address == new.address) {
+                Some(token) => {
+                    if token != new {
+                        println!(
+                            "Reloaded {:?}: profile {} -> {}, sell {}% -> {}%, target {} -> {}",
+                            token.address,
+                            token.profile.as_deref().unwrap_or("none"),
+                            new.profile.as_deref().unwrap_or("none"),
+                            token.sell_percentage,
+                            new.sell_percentage,
+                            token.target,
+                            new.target
+                        );
+                    }
+                    *token = new.clone();
+                }
+                None => println!("Ignoring new token {:?}; restart to start trading it", new.address),
+            }
+        }
+        for token in &params.tokens {
+            if update.token(token.address).is_none() {
+                println!("Token {:?} removed from config; restart to stop trading it", token.address);
+            }
+        }
+        params.max_gas_price = update.max_gas_price;
+
+        Ok(())
+    }
+
+    // Switches one token, or every token when `token` is None, to `profile`
+    // without touching the rest of the config
+    pub fn apply_profile(&self, profile: &Profile, token: Option<Address>) -> Result<()> {
+        profile.validate()?;
+
+        let mut params = self.0.write().unwrap_or_else(PoisonError::into_inner);
+        if let Some(address) = token {
+            if params.token(address).is_none() {
+                return Err(MktmkrError::Config(format!("{:?} is not a configured token", address)));
+            }
+        }
+        for target in params.tokens.iter_mut().filter(|t| token.is_none_or(|address| t.address == address)) {
+            target.apply_profile(profile);
+            println!("Switched {:?} to profile {}", target.address, profile.name);
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,84 @@
+use crate::abi::IUniswapV2Router02;
+use crate::bot::{TokenTarget, TradingBot};
+use crate::error::{MktmkrError, Result};
+use ethers::types::{Address, U256};
+use futures_util::future::join_all;
+use std::sync::Arc;
+
+// Gas each pool past the first adds to a sell
+pub(crate) const HOP_GAS: u64 = 60_000;
+
+// Tokens V2 sells may pass through when the direct WETH pair is shallow
+#[derive(Debug, Clone, PartialEq)]
+pub struct PathSearch {
+    pub hops: Vec<Address>,
+    // WETH the direct pair must hold for sells to skip the search
+    pub min_direct_liquidity: U256,
+}
+
+// Direct paths, then through each hop token, then through each ordered
+// pair of them
+pub fn candidate_paths(token: Address, weth: Address, hops: &[Address]) -> Vec<Vec<Address>> {
+    let hops: Vec<Address> = hops.iter().copied().filter(|hop| *hop != token && *hop != weth).collect();
+    let mut paths = vec![vec![token, weth]];
+    paths.extend(hops.iter().map(|hop| vec![token, *hop, weth]));
+    for first in &hops {
+        paths.extend(hops.iter().filter(|second| *second != first).map(|second| vec![token, *first, *second, weth]));
+    }
+    paths
+}
+
+impl TradingBot {
+    // The V2 path a sell of `amount` takes and the router's quote along it:
+    // the token's own path when it names one, else the direct pair unless
+    // it's shallow, when whichever candidate path quotes most wins. Paths
+    // through pairs the factory doesn't have fail to quote.
+    pub(crate) async fn sell_path(&self, target: &TokenTarget, amount: U256) -> Result<(Vec<Address>, U256)> {
+        let token = target.address;
+        if !target.hops.is_empty() {
+            let path: Vec<Address> = std::iter::once(token).chain(target.hops.iter().copied()).chain([self.weth]).collect();
+            let amount_out = self.quote_path(&path, amount).await?;
+            return Ok((path, amount_out));
+        }
+        let direct = vec![token, self.weth];
+        let Some(search) = &self.path_search else {
+            let amount_out = self.quote_path(&direct, amount).await?;
+            return Ok((direct, amount_out));
+        };
+        let deep = match self.pair_for(token).await {
+            Ok(_) => self.reserves(token).await?.1 >= search.min_direct_liquidity,
+            Err(_) => false,
+        };
+        if deep {
+            let amount_out = self.quote_path(&direct, amount).await?;
+            return Ok((direct, amount_out));
+        }
+
+        let paths = candidate_paths(token, self.weth, &search.hops);
+        let quotes = join_all(paths.iter().map(|path| self.quote_path(path, amount))).await;
+        let best = paths
+            .into_iter()
+            .zip(quotes)
+            .filter_map(|(path, quote)| Some((path, quote.ok()?)))
+            .max_by_key(|(_, amount_out)| *amount_out);
+        best.ok_or_else(|| MktmkrError::Contract(format!("no V2 path quotes a sell of {:?}", token)))
+    }
+
+    pub(crate) async fn quote_path(&self, path: &[Address], amount: U256) -> Result<U256> {
+        let router = IUniswapV2Router02::new(self.router, Arc::new(self.provider.clone()));
+        let amounts = router.get_amounts_out(amount, path.to_vec()).call().await?;
+        Ok(amounts.last().copied().unwrap_or_default())
+    }
+
+    // "TOKEN → USDC → WETH", by symbol
+    pub(crate) fn format_path(&self, path: &[Address]) -> String {
+        let symbols: Vec<String> = path
+            .iter()
+            .map(|token| match *token == self.weth {
+                true => "WETH".to_string(),
+                false => self.metadata(*token).symbol,
+            })
+            .collect();
+        symbols.join(" → ")
+    }
+}
@@ -0,0 +1,144 @@
+use crate::error::{MktmkrError, Result};
+use crate::transport::{pending_hashes, Transport};
+use ethers::{
+    providers::{Middleware, Provider, ProviderError, StreamExt},
+    types::Transaction,
+};
+use futures_util::stream::{self, BoxStream};
+use std::collections::{BTreeMap, HashSet};
+use std::time::Duration;
+use tokio::sync::{mpsc, oneshot};
+use tokio::task::JoinHandle;
+
+// Fetched transactions waiting for the decoder. Anything beyond this is
+// dropped: a buy seen late is not worth reacting to.
+pub(crate) const PENDING_BUFFER: usize = 1024;
+
+// Aborts the fetch task once the stream reading from it is dropped
+struct AbortOnDrop(JoinHandle<()>);
+
+impl Drop for AbortOnDrop {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+// Subscribes to pending hashes and resolves up to `concurrency` of them at
+// once on a separate task, so a burst of announcements doesn't queue behind
+// one eth_getTransactionByHash round trip after another. Only transactions
+// passing `filter` are forwarded.
+pub(crate) async fn fetch_pending(
+    provider: Provider<Transport>,
+    concurrency: usize,
+    filter: impl Fn(&Transaction) -> bool + Send + 'static,
+) -> Result<BoxStream<'static, Transaction>> {
+    let (ready_tx, ready_rx) = oneshot::channel();
+    let (bodies_tx, bodies_rx) = mpsc::channel(PENDING_BUFFER);
+
+    let task = tokio::spawn(async move {
+        let hashes = match pending_hashes(&provider).await {
+            Ok(hashes) => {
+                let _ = ready_tx.send(Ok(()));
+                hashes
+            }
+            Err(e) => {
+                let _ = ready_tx.send(Err(e));
+                return;
+            }
+        };
+
+        let mut bodies = hashes
+            .map(|hash| provider.get_transaction(hash))
+            .buffer_unordered(concurrency.max(1));
+        let mut dropped = 0u64;
+        while let Some(body) = bodies.next().await {
+            let Ok(Some(tx)) = body else { continue };
+            if filter(&tx) && !forward(&bodies_tx, tx, &mut dropped) {
+                return;
+            }
+        }
+    });
+
+    receive(task, ready_rx, bodies_rx).await
+}
+
+// Polls txpool_content every `interval`, for nodes that can't push pending
+// transactions over a subscription, and forwards transactions passing
+// `filter` that weren't in the previous poll
+pub(crate) async fn poll_txpool(
+    provider: Provider<Transport>,
+    interval: Duration,
+    filter: impl Fn(&Transaction) -> bool + Send + 'static,
+) -> Result<BoxStream<'static, Transaction>> {
+    let (ready_tx, ready_rx) = oneshot::channel();
+    let (bodies_tx, bodies_rx) = mpsc::channel(PENDING_BUFFER);
+
+    let task = tokio::spawn(async move {
+        let mut ready_tx = Some(ready_tx);
+        let mut known = HashSet::new();
+        let mut ticker = tokio::time::interval(interval);
+        let mut dropped = 0u64;
+        loop {
+            ticker.tick().await;
+            let content = match provider.txpool_content().await {
+                Ok(content) => content,
+                Err(e) => match ready_tx.take() {
+                    Some(ready_tx) => {
+                        let _ = ready_tx.send(Err(e));
+                        return;
+                    }
+                    None => continue,
+                },
+            };
+            if let Some(ready_tx) = ready_tx.take() {
+                let _ = ready_tx.send(Ok(()));
+            }
+
+            // The pool's current hashes replace the previous set, so a
+            // transaction that leaves and comes back is forwarded again
+            let pending: Vec<Transaction> = content.pending.into_values().flat_map(BTreeMap::into_values).collect();
+            let previous = std::mem::replace(&mut known, pending.iter().map(|tx| tx.hash).collect());
+            for tx in pending {
+                if !previous.contains(&tx.hash) && filter(&tx) && !forward(&bodies_tx, tx, &mut dropped) {
+                    return;
+                }
+            }
+        }
+    });
+
+    receive(task, ready_rx, bodies_rx).await
+}
+
+// Hands a transaction to the decoder, dropping it if the decoder is behind;
+// false once the decoder is gone
+pub(crate) fn forward(bodies_tx: &mpsc::Sender<Transaction>, tx: Transaction, dropped: &mut u64) -> bool {
+    match bodies_tx.try_send(tx) {
+        Ok(()) => true,
+        Err(mpsc::error::TrySendError::Full(_)) => {
+            *dropped += 1;
+            if *dropped % 100 == 1 {
+                println!("Decoder falling behind; {} pending transactions dropped so far", dropped);
+            }
+            true
+        }
+        Err(mpsc::error::TrySendError::Closed(_)) => false,
+    }
+}
+
+// Waits for the task's source to be up, then streams what it forwards for
+// as long as the stream is held
+pub(crate) async fn receive(
+    task: JoinHandle<()>,
+    ready_rx: oneshot::Receiver<std::result::Result<(), ProviderError>>,
+    bodies_rx: mpsc::Receiver<Transaction>,
+) -> Result<BoxStream<'static, Transaction>> {
+    let guard = AbortOnDrop(task);
+    ready_rx
+        .await
+        .map_err(|_| MktmkrError::Provider(ProviderError::CustomError("pending fetcher exited".into())))??;
+
+    Ok(stream::unfold((bodies_rx, guard), |(mut rx, guard)| async move {
+        rx.recv().await.map(|tx| (tx, (rx, guard)))
+    })
+    .boxed())
+}
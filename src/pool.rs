@@ -0,0 +1,168 @@
+use crate::abi::{IUniswapV2Factory, IUniswapV2Pair, IUniswapV2Router02};
+use crate::bot::TradingBot;
+use crate::decode::{RouterSwap, SwapAmounts};
+use crate::error::{MktmkrError, Result};
+use crate::forks::pair_address;
+use crate::math::{mul_div, BPS};
+use crate::routing::Venue;
+use ethers::{
+    providers::Middleware,
+    types::{Address, U256},
+};
+use std::sync::Arc;
+
+// Uniswap V2 constant-product output for an exact input, 0.3% fee included
+pub fn get_amount_out(amount_in: U256, reserve_in: U256, reserve_out: U256) -> U256 {
+    if amount_in.is_zero() || reserve_in.is_zero() || reserve_out.is_zero() {
+        return U256::zero();
+    }
+    let amount_in_with_fee = amount_in * 997;
+    amount_in_with_fee * reserve_out / (reserve_in * 1000 + amount_in_with_fee)
+}
+
+// Largest sell into a pool holding `token_reserve` whose execution price,
+// fee aside, falls at most `impact_bps` short of spot: the fee-adjusted
+// input may reach impact / (1 - impact) of the reserve
+pub fn max_sell_for_impact(token_reserve: U256, impact_bps: u64) -> U256 {
+    if impact_bps >= BPS {
+        return U256::MAX;
+    }
+    mul_div(token_reserve, U256::from(impact_bps * 1000), U256::from((BPS - impact_bps) * 997))
+}
+
+// Spot price as wei of ETH per 1e18 token units
+pub fn spot_price(token_reserve: U256, weth_reserve: U256) -> U256 {
+    if token_reserve.is_zero() {
+        return U256::zero();
+    }
+    weth_reserve * U256::exp10(18) / token_reserve
+}
+
+impl TradingBot {
+    // Shrinks a sell so its price impact stays within max_price_impact_bps,
+    // queueing the rest to be released with the sells held back by limits
+    pub(crate) async fn impact_limited(&self, token: Address, sell_amount: U256) -> Result<U256> {
+        let Some(impact_bps) = self.max_price_impact_bps else { return Ok(sell_amount) };
+        let (token_reserve, _) = self.reserves(token).await?;
+        let allowed = sell_amount.min(max_sell_for_impact(token_reserve, impact_bps));
+        if allowed < sell_amount {
+            self.throttle.lock().await.enqueue(token, sell_amount - allowed);
+            println!(
+                "Deferred {} to keep price impact within {} bps",
+                self.format_amount(token, sell_amount - allowed), impact_bps
+            );
+        }
+        Ok(allowed)
+    }
+
+    pub(crate) async fn pair_for(&self, token: Address) -> Result<Address> {
+        if let Some(pair) = self.pairs.lock().await.get(&token) {
+            return Ok(*pair);
+        }
+
+        let client = Arc::new(self.provider.clone());
+        let factory = match self.factory {
+            Some(factory) => factory,
+            None => IUniswapV2Router02::new(self.router, client.clone()).factory().call().await?,
+        };
+        let pair = match self.init_code_hash {
+            // A derived address is only a pair once deployed
+            Some(init_code_hash) => {
+                let pair = pair_address(factory, init_code_hash, token, self.weth);
+                match self.provider.get_code(pair, None).await?.is_empty() {
+                    true => Address::zero(),
+                    false => pair,
+                }
+            }
+            None => IUniswapV2Factory::new(factory, client).get_pair(token, self.weth).call().await?,
+        };
+        if pair.is_zero() {
+            return Err(MktmkrError::Config(format!("no {:?}/WETH pair on router {:?}", token, self.router)));
+        }
+
+        self.pairs.lock().await.insert(token, pair);
+        Ok(pair)
+    }
+
+    // Current (token reserve, WETH reserve) of the token's WETH pair, or
+    // the equivalent for tokens sold elsewhere
+    pub(crate) async fn reserves(&self, token: Address) -> Result<(U256, U256)> {
+        match self.venue(token) {
+            Venue::V3 => return self.v3_reserves(token).await,
+            Venue::V4 => return self.v4_reserves(token).await,
+            Venue::Curve => return self.curve_reserves(token).await,
+            Venue::Balancer => return self.balancer_reserves(token).await,
+            Venue::Aerodrome => return self.aerodrome_reserves(token).await,
+            Venue::V2 | Venue::Best => {}
+        }
+        let pair = self.pair_for(token).await?;
+        let cached = self.reserve_cache.lock().await.get(pair);
+        let (reserve0, reserve1) = match cached {
+            Some(reserves) => reserves,
+            None => {
+                let (reserve0, reserve1, _) = IUniswapV2Pair::new(pair, Arc::new(self.provider.clone())).get_reserves().call().await?;
+                let reserves = (U256::from(reserve0), U256::from(reserve1));
+                self.reserve_cache.lock().await.seed(pair, reserves);
+                reserves
+            }
+        };
+
+        // Pairs sort their tokens by address
+        if token < self.weth {
+            Ok((reserve0, reserve1))
+        } else {
+            Ok((reserve1, reserve0))
+        }
+    }
+
+    // ETH spent by a buy: the call value for plain ETH swaps, otherwise the
+    // WETH leg of its path quoted through the router, or the bought tokens
+    // at our pool's spot price when the path never touches WETH. Hinted
+    // transactions hide their value, so ETH swaps fall back to the least
+    // they can spend: the input needed for their minimum output.
+    pub(crate) async fn eth_equivalent(&self, swap: &RouterSwap, value: U256) -> Result<U256> {
+        // V4 swaps name native ETH as the zero address
+        if swap.path.iter().any(|token| !matches!(self.venue(*token), Venue::V2 | Venue::Best) || token.is_zero()) {
+            return self.v3_eth_equivalent(swap, value).await;
+        }
+        let router = IUniswapV2Router02::new(self.router, Arc::new(self.provider.clone()));
+        let path = &swap.path;
+        let weth_at = path.iter().position(|address| *address == self.weth);
+
+        let bought = match (swap.amounts, weth_at) {
+            (SwapAmounts::ExactIn { amount_in: None, amount_out_min }, _) if value.is_zero() => {
+                let amounts = router.get_amounts_in(amount_out_min, path.clone()).call().await?;
+                return Ok(amounts.first().copied().unwrap_or_default());
+            }
+            (SwapAmounts::ExactIn { amount_in: None, .. }, _) => return Ok(value),
+            (SwapAmounts::ExactIn { amount_in: Some(amount_in), .. }, Some(0)) => return Ok(amount_in),
+            (SwapAmounts::ExactIn { amount_in: Some(amount_in), .. }, Some(i)) => {
+                let amounts = router.get_amounts_out(amount_in, path[..=i].to_vec()).call().await?;
+                return Ok(amounts.last().copied().unwrap_or_default());
+            }
+            (SwapAmounts::ExactOut { amount_out, .. }, Some(i)) => {
+                let amounts = router.get_amounts_in(amount_out, path[i..].to_vec()).call().await?;
+                let spent = amounts.first().copied().unwrap_or_default();
+                return Ok(if swap.pays_eth() && !value.is_zero() { spent.min(value) } else { spent });
+            }
+            (SwapAmounts::ExactIn { amount_in: Some(amount_in), .. }, None) => {
+                let amounts = router.get_amounts_out(amount_in, path.clone()).call().await?;
+                amounts.last().copied().unwrap_or_default()
+            }
+            (SwapAmounts::ExactOut { amount_out, .. }, None) => amount_out,
+        };
+
+        self.eth_value(swap.token_out().unwrap_or_default(), bought).await
+    }
+
+    // Current spot price of the token's pool, as wei per 1e18 units
+    pub(crate) async fn spot(&self, token: Address) -> Result<U256> {
+        let (token_reserve, weth_reserve) = self.reserves(token).await?;
+        Ok(spot_price(token_reserve, weth_reserve))
+    }
+
+    // `amount` of a token valued at its pool's spot price
+    pub(crate) async fn eth_value(&self, token: Address, amount: U256) -> Result<U256> {
+        Ok(self.spot(token).await? * amount / U256::exp10(18))
+    }
+}
@@ -0,0 +1,54 @@
+use crate::bot::TradingBot;
+use crate::error::Result;
+use ethers::{
+    abi::{self, ParamType, Token},
+    prelude::*,
+    types::{transaction::eip2718::TypedTransaction, BlockNumber},
+    utils::hex,
+};
+
+// Error(string) and Panic(uint256)
+const ERROR_SELECTOR: [u8; 4] = [0x08, 0xc3, 0x79, 0xa0];
+const PANIC_SELECTOR: [u8; 4] = [0x4e, 0x48, 0x7b, 0x71];
+
+// A readable reason from revert data: the message of a require, the code of
+// a panic, or the raw bytes of a custom error
+pub(crate) fn revert_reason(data: &[u8]) -> String {
+    let (selector, args) = match data.len() {
+        0 => return "no reason given".to_string(),
+        1..=3 => return format!("0x{}", hex::encode(data)),
+        _ => data.split_at(4),
+    };
+    match selector {
+        s if s == ERROR_SELECTOR => match abi::decode(&[ParamType::String], args).ok().as_deref() {
+            Some([Token::String(message)]) => message.clone(),
+            _ => format!("0x{}", hex::encode(data)),
+        },
+        s if s == PANIC_SELECTOR => match abi::decode(&[ParamType::Uint(256)], args).ok().as_deref() {
+            Some([Token::Uint(code)]) => format!("panic 0x{:x}", code),
+            _ => format!("0x{}", hex::encode(data)),
+        },
+        _ => format!("custom error 0x{}", hex::encode(data)),
+    }
+}
+
+impl TradingBot {
+    // Runs a sell against the pending block; returns why it reverted, or
+    // None if it went through. Catches missing allowances, transfer taxes
+    // and paused tokens before they cost gas.
+    pub(crate) async fn preflight(&self, tx: &TypedTransaction) -> Result<Option<String>> {
+        if !self.preflight {
+            return Ok(None);
+        }
+        match self.provider.call(tx, Some(BlockNumber::Pending.into())).await {
+            Ok(_) => Ok(None),
+            Err(e) => match RpcError::as_error_response(&e) {
+                Some(response) => Ok(Some(match response.as_revert_data() {
+                    Some(data) => revert_reason(&data),
+                    None => response.message.clone(),
+                })),
+                None => Err(e.into()),
+            },
+        }
+    }
+}
@@ -0,0 +1,28 @@
+use crate::bot::TradingBot;
+use crate::error::Result;
+use ethers::{
+    providers::Middleware,
+    types::{Bytes, TxHash},
+    utils::keccak256,
+};
+
+// Flashbots Protect's fast endpoint, which shares with every builder it works with
+pub const DEFAULT_PRIVATE_RPC: &str = "https://rpc.flashbots.net/fast";
+
+impl TradingBot {
+    // Sends a signed sell privately or through the public mempool. A private
+    // sell goes to the first relay that takes it, and to the public mempool
+    // when none does.
+    pub(crate) async fn submit(&self, raw: Bytes, private: bool) -> Result<TxHash> {
+        if private {
+            for (i, relay) in self.private_relays.iter().enumerate() {
+                match relay.send_raw_transaction(raw.clone()).await {
+                    Ok(_) => return Ok(TxHash::from(keccak256(&raw))),
+                    Err(e) => println!("Private relay {} refused {:?}: {}", i + 1, TxHash::from(keccak256(&raw)), e),
+                }
+            }
+            println!("No private relay took the sell; sending it publicly");
+        }
+        self.broadcast(raw).await
+    }
+}
@@ -0,0 +1,64 @@
+use crate::error::{MktmkrError, Result};
+use ethers::types::U256;
+
+// A named bundle of per-token tunables, switched as a unit
+#[derive(Debug, Clone, PartialEq)]
+pub struct Profile {
+    pub name: String,
+    pub sell_percentage: f64,
+    // Minimum output is the quoted amount less this; None accepts any output
+    pub slippage_bps: Option<u32>,
+    // Bid this multiple of the network gas price
+    pub gas_price_multiplier: f64,
+    // Largest single sell, in raw token units
+    pub max_sell_per_tx: Option<U256>,
+}
+
+pub const PRESETS: &[&str] = &["aggressive", "passive", "unwind"];
+
+impl Profile {
+    // Built-in profiles; [profiles.<name>] entries in the config override
+    // individual fields
+    pub fn preset(name: &str) -> Option<Self> {
+        let (sell_percentage, slippage_bps, gas_price_multiplier) = match name {
+            "aggressive" => (25.0, 300, 1.25),
+            "passive" => (5.0, 50, 1.0),
+            "unwind" => (50.0, 1_000, 1.5),
+            _ => return None,
+        };
+        Some(Self {
+            name: name.to_string(),
+            sell_percentage,
+            slippage_bps: Some(slippage_bps),
+            gas_price_multiplier,
+            max_sell_per_tx: None,
+        })
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        if !(self.sell_percentage > 0.0 && self.sell_percentage <= 100.0) {
+            return Err(MktmkrError::Config(format!(
+                "profile {}: sell_percentage must be in (0, 100], got {}",
+                self.name, self.sell_percentage
+            )));
+        }
+        if let Some(slippage_bps) = self.slippage_bps {
+            if slippage_bps > 10_000 {
+                return Err(MktmkrError::Config(format!(
+                    "profile {}: slippage_bps must be at most 10000, got {}",
+                    self.name, slippage_bps
+                )));
+            }
+        }
+        if !self.gas_price_multiplier.is_finite() || self.gas_price_multiplier <= 0.0 {
+            return Err(MktmkrError::Config(format!(
+                "profile {}: gas_price_multiplier must be positive, got {}",
+                self.name, self.gas_price_multiplier
+            )));
+        }
+        if self.max_sell_per_tx == Some(U256::zero()) {
+            return Err(MktmkrError::Config(format!("profile {}: max_sell_per_tx must be positive", self.name)));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,200 @@
+use crate::bot::TradingBot;
+use crate::bundle::Backrun;
+use crate::error::Result;
+use crate::shutdown::Shutdown;
+use crate::trigger::Seen;
+use ethers::types::{Address, H256, U256};
+use serde::Deserialize;
+use std::collections::VecDeque;
+
+// What happens to a new sell order when the execution queue is full
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BacklogPolicy {
+    // Fold it into a waiting order for the same token, or drop it if there
+    // is none
+    #[default]
+    Merge,
+    DropNewest,
+    DropOldest,
+}
+
+// One trigger's share of a sell order
+#[derive(Debug, Clone)]
+pub(crate) struct OrderPart {
+    // The buy it answers; none for mirrored sells
+    pub buy: Option<H256>,
+    pub desired: U256,
+    // The buy's ETH amount while it is still pending, for paper fills
+    pub pending_buy: Option<U256>,
+    // When the buy was seen, for those answering one
+    pub seen: Option<Seen>,
+}
+
+// A sell waiting for the executor; coalesced triggers add parts
+#[derive(Debug, Clone)]
+pub(crate) struct SellOrder {
+    pub token: Address,
+    pub parts: Vec<OrderPart>,
+}
+
+impl SellOrder {
+    pub fn desired(&self) -> U256 {
+        self.parts.iter().fold(U256::zero(), |acc, part| acc + part.desired)
+    }
+
+    pub fn pending_buy(&self) -> Option<U256> {
+        self.parts.iter().filter_map(|part| part.pending_buy).reduce(|a, b| a + b)
+    }
+}
+
+// Sell orders waiting to be executed one at a time, oldest first
+#[derive(Debug)]
+pub struct ExecutionQueue {
+    orders: VecDeque<SellOrder>,
+    max_depth: usize,
+    // Fold every trigger into a waiting order for the same token, so a
+    // burst of buys becomes one larger sell
+    coalesce: bool,
+    policy: BacklogPolicy,
+}
+
+impl Default for ExecutionQueue {
+    fn default() -> Self {
+        Self::new(32, true, BacklogPolicy::default())
+    }
+}
+
+impl ExecutionQueue {
+    pub fn new(max_depth: usize, coalesce: bool, policy: BacklogPolicy) -> Self {
+        Self { orders: VecDeque::new(), max_depth: max_depth.max(1), coalesce, policy }
+    }
+
+    pub fn len(&self) -> usize {
+        self.orders.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.orders.is_empty()
+    }
+
+    pub(crate) fn push(&mut self, order: SellOrder) {
+        let full = self.orders.len() >= self.max_depth;
+        if self.coalesce || (full && self.policy == BacklogPolicy::Merge) {
+            if let Some(waiting) = self.orders.iter_mut().rev().find(|waiting| waiting.token == order.token) {
+                waiting.parts.extend(order.parts);
+                return;
+            }
+        }
+        if full {
+            match self.policy {
+                BacklogPolicy::Merge | BacklogPolicy::DropNewest => {
+                    println!("Execution queue full; dropping a sell of {} of {:?}", order.desired(), order.token);
+                    return;
+                }
+                BacklogPolicy::DropOldest => {
+                    if let Some(oldest) = self.orders.pop_front() {
+                        println!("Execution queue full; dropping a sell of {} of {:?}", oldest.desired(), oldest.token);
+                    }
+                }
+            }
+        }
+        self.orders.push_back(order);
+    }
+
+    pub(crate) fn pop(&mut self) -> Option<SellOrder> {
+        self.orders.pop_front()
+    }
+
+    // Withdraws a replaced or cancelled buy's part of any order still
+    // waiting; returns the pending buy amount it was sized from
+    pub(crate) fn cancel(&mut self, buy: H256) -> Option<U256> {
+        let mut withdrawn = None;
+        for order in &mut self.orders {
+            order.parts.retain(|part| {
+                if part.buy != Some(buy) {
+                    return true;
+                }
+                withdrawn = Some(withdrawn.unwrap_or_default() + part.pending_buy.unwrap_or_default());
+                false
+            });
+        }
+        self.orders.retain(|order| !order.parts.is_empty());
+        withdrawn
+    }
+
+    pub(crate) fn clear(&mut self) -> Vec<SellOrder> {
+        self.orders.drain(..).collect()
+    }
+}
+
+impl TradingBot {
+    pub(crate) async fn enqueue_order(&self, order: SellOrder) {
+        self.orders.lock().await.push(order);
+        self.order_ready.notify_one();
+    }
+
+    // Executes queued orders one at a time until `closing` fires; an order
+    // already executing is finished first. Runs alongside the mempool loop,
+    // so triggers arriving during a sell queue up and coalesce behind it.
+    pub(crate) async fn execute_orders(&self, mut closing: Shutdown) -> Result<()> {
+        loop {
+            if closing.is_triggered() {
+                return Ok(());
+            }
+            if self.orders.lock().await.is_empty() {
+                tokio::select! {
+                    _ = self.order_ready.notified() => {}
+                    _ = closing.wait() => return Ok(()),
+                }
+                continue;
+            }
+            // Triggers arriving meanwhile still merge into the waiting order
+            if let Some(delay) = self.reaction_delay() {
+                tokio::select! {
+                    _ = tokio::time::sleep(delay) => {}
+                    _ = closing.wait() => return Ok(()),
+                }
+            }
+            let next = self.orders.lock().await.pop();
+            let Some(order) = next else { continue };
+            let token = order.token;
+            if let Err(e) = self.execute_order(order).await {
+                if e.ends_session() {
+                    return Err(e);
+                }
+                println!("Sell order for {:?} failed: {}", token, e);
+            }
+        }
+    }
+
+    // Sells as much of the order as sell limits allow. Whatever they hold
+    // back is tracked against the order's last buy, so cancelling it
+    // withdraws that too.
+    pub(crate) async fn execute_order(&self, mut order: SellOrder) -> Result<()> {
+        let target = self.target_for(order.token)?;
+        if self.sell_percentage(&target).await.is_none() {
+            return Ok(());
+        }
+        self.drop_stale_parts(&mut order).await?;
+        if order.parts.is_empty() {
+            return Ok(());
+        }
+        // Only a lone pending buy can have a sell bundled behind it
+        let backrun = match order.parts.as_slice() {
+            [OrderPart { buy: Some(buy), pending_buy: Some(_), .. }] if !self.bundle_relays.is_empty() => {
+                let pending_buys = self.pending_buys.lock().await;
+                match pending_buys.raw(*buy) {
+                    Some(raw) => Some(Backrun::Signed(raw)),
+                    None => pending_buys.is_hint(*buy).then_some(Backrun::Hint(*buy)),
+                }
+            }
+            _ => None,
+        };
+        let queued = self.reserve_and_sell(&target, order.desired(), order.pending_buy(), backrun.as_ref()).await?;
+        if let Some(buy) = order.parts.iter().rev().find_map(|part| part.buy) {
+            self.pending_buys.lock().await.add_queued(buy, queued);
+        }
+        Ok(())
+    }
+}
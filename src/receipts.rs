@@ -0,0 +1,221 @@
+use crate::bot::TradingBot;
+use crate::error::Result;
+use crate::shutdown::Shutdown;
+use crate::strategy::Side;
+use ethers::{
+    prelude::*,
+    types::{transaction::eip2718::TypedTransaction, Address, BlockNumber, Bytes, TransactionReceipt, TxHash, U256},
+};
+use std::time::{Duration, Instant};
+
+// How often receipts of submitted sells are checked while trading
+const RECEIPT_INTERVAL: Duration = Duration::from_secs(5);
+// Fast chains are polled once a block, but no more often than this
+const MIN_RECEIPT_INTERVAL: Duration = Duration::from_secs(1);
+
+// A submitted sell whose receipt hasn't reached the confirmation depth
+#[derive(Debug, Clone)]
+pub(crate) struct InFlight {
+    pub token: Address,
+    pub sell_amount: U256,
+    // Unknown for sells picked back up after a reorg
+    pub nonce: Option<U256>,
+    pub sent_at: Instant,
+    // The signed transaction's fields and the swap's deadline, kept so a
+    // stuck sell can be rebid or cancelled
+    pub tx: Option<TypedTransaction>,
+    pub raw: Option<Bytes>,
+    pub deadline: Option<U256>,
+    // Sent only to private relays so far; public nodes won't see it pending
+    pub private: bool,
+    // Bundled behind a buy for blocks up to this one; never in the public
+    // mempool
+    pub bundle_block: Option<u64>,
+    // Head when the sell was first seen pending
+    pub sent_block: Option<u64>,
+    pub bumps: u32,
+    // A zero-value transfer taking an expired sell's nonce
+    pub cancel: bool,
+    // Rebid under another hash; still tracked in case it lands first
+    pub superseded: bool,
+    // Where it was routed, for the trade log
+    pub venue: String,
+    // Buys made by market-making strategies are tracked alongside sells,
+    // with the ETH they spend as their amount
+    pub side: Side,
+}
+
+impl InFlight {
+    pub fn new(token: Address, sell_amount: U256, nonce: Option<U256>) -> Self {
+        Self {
+            token,
+            sell_amount,
+            nonce,
+            sent_at: Instant::now(),
+            tx: None,
+            raw: None,
+            deadline: None,
+            private: false,
+            bundle_block: None,
+            sent_block: None,
+            bumps: 0,
+            cancel: false,
+            superseded: false,
+            venue: String::new(),
+            side: Side::Sell,
+        }
+    }
+}
+
+impl TradingBot {
+    // Checks receipts every block, within MIN_RECEIPT_INTERVAL and
+    // RECEIPT_INTERVAL, until `closing` fires
+    pub(crate) async fn watch_receipts(&self, mut closing: Shutdown) -> Result<()> {
+        let interval = self.block_time.clamp(MIN_RECEIPT_INTERVAL, RECEIPT_INTERVAL);
+        loop {
+            tokio::select! {
+                _ = tokio::time::sleep(interval) => {}
+                _ = closing.wait() => return Ok(()),
+            }
+            self.poll_in_flight().await?;
+            self.poll_intents().await;
+        }
+    }
+
+    // Records every unconfirmed sell mined at least `confirmations` blocks
+    // deep, rebids those stuck, and stops tracking those dropped, replaced
+    // or past receipt_timeout; returns how many are still pending
+    pub(crate) async fn poll_in_flight(&self) -> Result<usize> {
+        let pending: Vec<_> = self.in_flight.lock().await.iter().map(|(hash, sell)| (*hash, sell.clone())).collect();
+        if pending.is_empty() {
+            return Ok(0);
+        }
+        let latest = self.provider.get_block(BlockNumber::Latest).await?;
+        let head = latest.as_ref().and_then(|block| block.number).map_or(0, |number| number.as_u64());
+        let timestamp = latest.map(|block| block.timestamp).unwrap_or_default();
+        let mut mined_nonce = None;
+
+        for (tx_hash, sell) in pending {
+            if let Some(receipt) = self.provider.get_transaction_receipt(tx_hash).await? {
+                let depth = receipt.block_number.map_or(0, |number| (head + 1).saturating_sub(number.as_u64()));
+                if depth >= self.confirmations {
+                    // Rebids of the same nonce can no longer land
+                    let mut in_flight = self.in_flight.lock().await;
+                    in_flight.remove(&tx_hash);
+                    if sell.nonce.is_some() {
+                        in_flight.retain(|_, other| other.nonce != sell.nonce);
+                    }
+                    drop(in_flight);
+                    match (sell.cancel, sell.side) {
+                        (true, _) => self.record_cancel(&sell, &receipt).await,
+                        (false, Side::Sell) => self.record_fill(sell.token, sell.sell_amount, &sell.venue, &receipt).await,
+                        (false, Side::Buy) => self.record_buy(sell.token, sell.sell_amount, &sell.venue, &receipt).await,
+                    }
+                    if sell.bundle_block.is_some() {
+                        self.record_builder(&receipt).await?;
+                    }
+                }
+                continue;
+            }
+
+            if sell.private {
+                if sell.sent_at.elapsed() >= self.public_fallback {
+                    self.fall_back_to_public(tx_hash, &sell).await;
+                }
+            } else if sell.bundle_block.is_some_and(|block| head <= block) {
+                // Not yet known whether the bundle made it in
+            } else if self.provider.get_transaction(tx_hash).await?.is_none() {
+                // Gone from the pool unmined: replaced if another transaction
+                // has since used its nonce, otherwise dropped
+                let nonce = match mined_nonce {
+                    Some(nonce) => nonce,
+                    None => {
+                        let address = self.wallet.address();
+                        let nonce = self.provider.get_transaction_count(address, Some(BlockNumber::Latest.into())).await?;
+                        *mined_nonce.insert(nonce)
+                    }
+                };
+                self.in_flight.lock().await.remove(&tx_hash);
+                match sell.nonce {
+                    _ if sell.superseded => {}
+                    Some(sent) if sent < nonce => {
+                        println!("Sell {:?} of {:?} was replaced; not recording it", tx_hash, sell.token);
+                    }
+                    sent => {
+                        println!("Sell {:?} of {:?} was dropped from the mempool", tx_hash, sell.token);
+                        if let Some(sent) = sent {
+                            self.nonces.lock().await.release(sent);
+                        }
+                    }
+                }
+                continue;
+            }
+
+            let Some(sent_block) = sell.sent_block else {
+                if let Some(sell) = self.in_flight.lock().await.get_mut(&tx_hash) {
+                    sell.sent_block = Some(head);
+                }
+                continue;
+            };
+            if !sell.superseded && self.escalate_after_blocks > 0 && head >= sent_block + self.escalate_after_blocks {
+                self.escalate(tx_hash, &sell, head, timestamp).await?;
+            }
+
+            if sell.sent_at.elapsed() >= self.receipt_timeout {
+                if !sell.superseded {
+                    println!(
+                        "Sell {:?} of {:?} still unmined after {:?}; no longer tracking it",
+                        tx_hash, sell.token, self.receipt_timeout
+                    );
+                }
+                self.in_flight.lock().await.remove(&tx_hash);
+            }
+        }
+        Ok(self.unconfirmed().await)
+    }
+
+    // Sends a private sell that relays haven't got mined to the public
+    // mempool too
+    async fn fall_back_to_public(&self, tx_hash: TxHash, sell: &InFlight) {
+        let Some(raw) = sell.raw.clone() else { return };
+        println!("Private sell {:?} of {:?} unmined after {:?}; sending it publicly", tx_hash, sell.token, self.public_fallback);
+        match self.broadcast(raw).await {
+            Ok(_) => {
+                if let Some(sell) = self.in_flight.lock().await.get_mut(&tx_hash) {
+                    sell.private = false;
+                }
+            }
+            Err(e) => println!("Could not send {:?} publicly: {}", tx_hash, e),
+        }
+    }
+
+    // Sells still pending, not counting rebids' predecessors
+    pub(crate) async fn unconfirmed(&self) -> usize {
+        self.in_flight.lock().await.values().filter(|sell| !sell.superseded).count() + self.open_intents.lock().await.len()
+    }
+
+    // A cancellation only costs its fee
+    async fn record_cancel(&self, sell: &InFlight, receipt: &TransactionReceipt) {
+        let fee = receipt.gas_used.unwrap_or_default() * receipt.effective_gas_price.unwrap_or_default();
+        self.report.lock().await.tokens.entry(sell.token).or_default().fees_paid += fee;
+        println!("Cancelled expired sell of {}", self.format_amount(sell.token, sell.sell_amount));
+    }
+
+    // Polls receipts of unconfirmed sells, and open CoW orders, until they
+    // land or `timeout` passes
+    pub(crate) async fn settle_in_flight(&self, timeout: Duration) -> Result<()> {
+        let deadline = Instant::now() + timeout;
+
+        loop {
+            if self.poll_in_flight().await? + self.poll_intents().await == 0 {
+                return Ok(());
+            }
+
+            if Instant::now() >= deadline {
+                println!("Gave up waiting for {} unconfirmed sells", self.unconfirmed().await);
+                return Ok(());
+            }
+            tokio::time::sleep(Duration::from_secs(2)).await;
+        }
+    }
+}
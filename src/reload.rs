@@ -0,0 +1,60 @@
+use crate::config::Config;
+use crate::error::{MktmkrError, Result};
+use crate::params::ParamsHandle;
+use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher};
+use std::path::{Path, PathBuf};
+
+// Watches the config file and pushes strategy changes into running bots.
+// Dropping the watcher stops reloading.
+pub struct ConfigWatcher {
+    _watcher: RecommendedWatcher,
+}
+
+impl ConfigWatcher {
+    // `load` re-reads the full configuration (file plus any overrides) and
+    // `markets` maps market names to the handles of their running bots
+    pub fn spawn<F>(path: &Path, load: F, markets: Vec<(String, ParamsHandle)>) -> Result<Self>
+    where
+        F: Fn() -> Result<Config> + Send + 'static,
+    {
+        let path = path.canonicalize()?;
+        // Editors often save by replacing the file, so watch the directory
+        // and filter on the file name
+        let dir = path.parent().map(Path::to_path_buf).unwrap_or_else(|| PathBuf::from("."));
+
+        let watched = path.clone();
+        let mut watcher = notify::recommended_watcher(move |event: notify::Result<Event>| {
+            let Ok(event) = event else { return };
+            if !(event.kind.is_modify() || event.kind.is_create()) {
+                return;
+            }
+            if !event.paths.iter().any(|p| p.file_name() == watched.file_name()) {
+                return;
+            }
+
+            if let Err(e) = reload(&load, &markets) {
+                println!("Config reload failed, keeping current parameters: {}", e);
+            }
+        })
+        .map_err(|e| MktmkrError::Config(format!("failed to watch {}: {}", path.display(), e)))?;
+
+        watcher
+            .watch(&dir, RecursiveMode::NonRecursive)
+            .map_err(|e| MktmkrError::Config(format!("failed to watch {}: {}", dir.display(), e)))?;
+
+        Ok(Self { _watcher: watcher })
+    }
+}
+
+fn reload<F>(load: &F, markets: &[(String, ParamsHandle)]) -> Result<()>
+where
+    F: Fn() -> Result<Config>,
+{
+    let config = load()?;
+    for market in config.markets() {
+        if let Some((_, handle)) = markets.iter().find(|(name, _)| *name == market.name) {
+            handle.apply(config.strategy_params(&market)?)?;
+        }
+    }
+    Ok(())
+}
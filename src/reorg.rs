@@ -0,0 +1,125 @@
+use crate::bot::TradingBot;
+use crate::error::Result;
+use crate::receipts::InFlight;
+use ethers::{
+    providers::Middleware,
+    types::{Address, Block, H256, U256},
+};
+use std::collections::BTreeMap;
+
+// Blocks of history kept for spotting reorganisations and re-checking fills
+const REORG_DEPTH: u64 = 64;
+
+// Recent canonical block hashes, by number
+#[derive(Debug, Default)]
+pub(crate) struct ChainTracker {
+    blocks: BTreeMap<u64, H256>,
+}
+
+impl ChainTracker {
+    // Records a new head; true when it replaces recorded blocks, either by
+    // arriving at or below the tip or by building on a different parent
+    pub fn push(&mut self, number: u64, hash: H256, parent: H256) -> bool {
+        let replaced = self.blocks.get(&number).is_some_and(|recorded| *recorded != hash)
+            || number.checked_sub(1).and_then(|below| self.blocks.get(&below)).is_some_and(|recorded| *recorded != parent);
+        self.blocks.split_off(&number);
+        self.blocks.insert(number, hash);
+        self.blocks = self.blocks.split_off(&number.saturating_sub(REORG_DEPTH));
+        replaced
+    }
+}
+
+// A confirmed sell, kept while a reorg could still undo it
+#[derive(Debug, Clone)]
+pub(crate) struct Fill {
+    pub token: Address,
+    pub sell_amount: U256,
+    pub eth_out: U256,
+    pub stable_out: U256,
+    pub fee: U256,
+    pub succeeded: bool,
+    pub venue: String,
+    pub block_hash: H256,
+    pub block_number: u64,
+}
+
+impl TradingBot {
+    // Follows the primary's heads; on a reorg, re-checks recent fills and
+    // the included buys they answered
+    pub(crate) async fn track_head(&self, head: &Block<H256>) -> Result<()> {
+        let (Some(number), Some(hash)) = (head.number, head.hash) else { return Ok(()) };
+        let number = number.as_u64();
+        if !self.chain.lock().await.push(number, hash, head.parent_hash) {
+            let horizon = number.saturating_sub(REORG_DEPTH);
+            self.recent_fills.lock().await.retain(|_, fill| fill.block_number >= horizon);
+            self.recent_buys.lock().await.retain(|_, (_, block_number)| *block_number >= horizon);
+            return Ok(());
+        }
+
+        println!("Chain reorganised at block {}; re-checking recent sells", number);
+        self.reconcile_fills().await?;
+        self.reconcile_buys().await
+    }
+
+    // Takes sells no longer in the block they were recorded in back out of
+    // the report. Those still mined elsewhere are recorded again; the rest
+    // wait as unconfirmed.
+    async fn reconcile_fills(&self) -> Result<()> {
+        let fills: Vec<_> = self.recent_fills.lock().await.iter().map(|(hash, fill)| (*hash, fill.clone())).collect();
+        for (tx_hash, fill) in fills {
+            let receipt = self.provider.get_transaction_receipt(tx_hash).await?;
+            if receipt.as_ref().is_some_and(|r| r.block_hash == Some(fill.block_hash)) {
+                continue;
+            }
+
+            self.recent_fills.lock().await.remove(&tx_hash);
+            {
+                let mut report = self.report.lock().await;
+                let sold = report.tokens.entry(fill.token).or_default();
+                sold.fees_paid = sold.fees_paid.saturating_sub(fill.fee);
+                if fill.succeeded {
+                    sold.sells = sold.sells.saturating_sub(1);
+                    sold.tokens_sold = sold.tokens_sold.saturating_sub(fill.sell_amount);
+                    sold.eth_received = sold.eth_received.saturating_sub(fill.eth_out);
+                    sold.stable_received = sold.stable_received.saturating_sub(fill.stable_out);
+                } else {
+                    sold.failed_sells = sold.failed_sells.saturating_sub(1);
+                }
+            }
+
+            match receipt {
+                Some(receipt) => {
+                    println!("Sell {:?} of {:?} moved to another block", tx_hash, fill.token);
+                    self.record_fill(fill.token, fill.sell_amount, &fill.venue, &receipt).await;
+                }
+                None => {
+                    println!("Sell {:?} of {:?} was reorged out; waiting for it again", tx_hash, fill.token);
+                    let nonce = self.provider.get_transaction(tx_hash).await?.map(|tx| tx.nonce);
+                    let sell = InFlight { venue: fill.venue, ..InFlight::new(fill.token, fill.sell_amount, nonce) };
+                    self.in_flight.lock().await.insert(tx_hash, sell);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // Warns about sells already made into included buys that a reorg
+    // dropped; those sells can't be taken back
+    async fn reconcile_buys(&self) -> Result<()> {
+        let buys: Vec<_> = self.recent_buys.lock().await.iter().map(|(hash, (block, _))| (*hash, *block)).collect();
+        for (buy_hash, block_hash) in buys {
+            match self.provider.get_transaction_receipt(buy_hash).await? {
+                Some(receipt) if receipt.block_hash == Some(block_hash) => {}
+                Some(receipt) => {
+                    let number = receipt.block_number.unwrap_or_default().as_u64();
+                    self.recent_buys.lock().await.insert(buy_hash, (receipt.block_hash.unwrap_or_default(), number));
+                }
+                None => {
+                    println!("Buy {:?} we sold into was reorged out", buy_hash);
+                    self.recent_buys.lock().await.remove(&buy_hash);
+                }
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,115 @@
+use ethers::types::{Address, Bytes, Transaction, H256, U256};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, PoisonError, RwLock};
+use std::time::{Duration, Instant};
+
+// How long a buy is remembered for matching replacements
+const PENDING_BUY_TTL: Duration = Duration::from_secs(600);
+
+// A buy that triggered a sell, keyed by (sender, nonce) so a transaction
+// replacing it can be recognised
+#[derive(Debug, Clone)]
+pub struct PendingBuy {
+    pub hash: H256,
+    pub token: Address,
+    pub buy_amount: U256,
+    // Part of the triggered sell still waiting behind sell limits
+    pub queued: U256,
+    pub seen_at: Instant,
+    // The signed buy, for bundling a sell behind it
+    pub raw: Option<Bytes>,
+}
+
+// What a pending transaction means for the buys already acted on
+#[derive(Debug)]
+pub enum Replacement {
+    // No earlier transaction with this sender and nonce
+    Fresh,
+    // Replaces an earlier buy with another buy (usually a speed-up)
+    Buy(PendingBuy),
+    // Replaces an earlier buy with something else, e.g. a cancel
+    Cancel(PendingBuy),
+}
+
+#[derive(Debug, Default)]
+pub struct PendingBuys {
+    buys: HashMap<(Address, U256), PendingBuy>,
+    // Senders with a tracked buy, shared with the pending-tx filter so their
+    // cancels, which aren't sent to the router, still reach the decoder
+    senders: Arc<RwLock<HashSet<Address>>>,
+    // Buys known only from MEV-Share hints, which can't be replaced but can
+    // be backrun by hash
+    hints: HashMap<H256, Instant>,
+}
+
+impl PendingBuys {
+    pub fn senders(&self) -> Arc<RwLock<HashSet<Address>>> {
+        self.senders.clone()
+    }
+
+    // Classifies `tx` against tracked buys, forgetting the one it replaces;
+    // hinted transactions from private flow carry no sender and are always
+    // fresh
+    pub fn classify(&mut self, tx: &Transaction, is_buy: bool, now: Instant) -> Replacement {
+        self.evict(now);
+        if tx.from.is_zero() {
+            return Replacement::Fresh;
+        }
+        let key = (tx.from, tx.nonce);
+        match self.buys.get(&key) {
+            Some(previous) if previous.hash != tx.hash => {}
+            _ => return Replacement::Fresh,
+        }
+        let previous = self.buys.remove(&key).expect("checked above");
+        self.untrack(tx.from);
+        if is_buy {
+            Replacement::Buy(previous)
+        } else {
+            Replacement::Cancel(previous)
+        }
+    }
+
+    pub fn track(&mut self, tx: &Transaction, buy: PendingBuy) {
+        if tx.from.is_zero() {
+            self.hints.insert(tx.hash, buy.seen_at);
+            return;
+        }
+        self.buys.insert((tx.from, tx.nonce), buy);
+        self.senders.write().unwrap_or_else(PoisonError::into_inner).insert(tx.from);
+    }
+
+    // The signed transaction of a tracked buy
+    pub fn raw(&self, hash: H256) -> Option<Bytes> {
+        self.buys.values().find(|buy| buy.hash == hash).and_then(|buy| buy.raw.clone())
+    }
+
+    pub fn is_hint(&self, hash: H256) -> bool {
+        self.hints.contains_key(&hash)
+    }
+
+    // Adds to the part of a tracked buy's sell held back by sell limits
+    pub fn add_queued(&mut self, hash: H256, amount: U256) {
+        if let Some(buy) = self.buys.values_mut().find(|buy| buy.hash == hash) {
+            buy.queued += amount;
+        }
+    }
+
+    fn evict(&mut self, now: Instant) {
+        self.hints.retain(|_, seen_at| now.duration_since(*seen_at) < PENDING_BUY_TTL);
+        let expired: Vec<_> = self.buys
+            .iter()
+            .filter(|(_, buy)| now.duration_since(buy.seen_at) >= PENDING_BUY_TTL)
+            .map(|(key, _)| *key)
+            .collect();
+        for key in expired {
+            self.buys.remove(&key);
+            self.untrack(key.0);
+        }
+    }
+
+    fn untrack(&mut self, sender: Address) {
+        if !self.buys.keys().any(|(from, _)| *from == sender) {
+            self.senders.write().unwrap_or_else(PoisonError::into_inner).remove(&sender);
+        }
+    }
+}
@@ -0,0 +1,300 @@
+use crate::chainlink::eth_to_usd;
+use crate::chains::chain_preset;
+use crate::metadata::TokenMetadata;
+use ethers::{
+    types::{Address, U256},
+    utils::{format_ether, format_units},
+};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt;
+
+#[derive(Debug, Clone, Default)]
+pub struct TokenReport {
+    pub sells: u32,
+    pub failed_sells: u32,
+    pub tokens_sold: U256,
+    pub eth_received: U256,
+    // Stablecoin units, when sells pay out the stablecoin
+    pub stable_received: U256,
+    pub fees_paid: U256,
+    // Buys by market-making strategies, paid in ETH
+    pub buys: u32,
+    pub failed_buys: u32,
+    pub tokens_bought: U256,
+    pub eth_spent: U256,
+}
+
+impl TokenReport {
+    fn add(&mut self, other: &TokenReport) {
+        self.sells += other.sells;
+        self.failed_sells += other.failed_sells;
+        self.tokens_sold += other.tokens_sold;
+        self.eth_received += other.eth_received;
+        self.stable_received += other.stable_received;
+        self.fees_paid += other.fees_paid;
+        self.buys += other.buys;
+        self.failed_buys += other.failed_buys;
+        self.tokens_bought += other.tokens_bought;
+        self.eth_spent += other.eth_spent;
+    }
+}
+
+// Confirmed results of a bot's session
+#[derive(Debug, Clone, Default)]
+pub struct SessionReport {
+    pub tokens: HashMap<Address, TokenReport>,
+    // Sells still unconfirmed when the report was taken
+    pub unconfirmed: usize,
+    // For printing token amounts in whole units
+    pub metadata: HashMap<Address, TokenMetadata>,
+    // The stablecoin sells pay out, if not ETH
+    pub stable: Option<Address>,
+    // Bundled sells included, by the builder that included them
+    pub builders: BTreeMap<String, u32>,
+    // Chainlink prices when the report was taken, USD per ETH and per whole
+    // token for tokens with a feed
+    pub eth_usd: Option<f64>,
+    pub token_usd: HashMap<Address, f64>,
+    // Where the session traded, and what the wallet still held of each
+    // token when the report was taken
+    pub chain_id: u64,
+    pub wallet: Address,
+    pub balances: HashMap<Address, U256>,
+}
+
+impl SessionReport {
+    pub fn token(&self, token: Address) -> TokenReport {
+        self.tokens.get(&token).cloned().unwrap_or_default()
+    }
+
+    pub fn total_eth_received(&self) -> U256 {
+        self.tokens.values().fold(U256::zero(), |acc, t| acc + t.eth_received)
+    }
+
+    pub fn total_stable_received(&self) -> U256 {
+        self.tokens.values().fold(U256::zero(), |acc, t| acc + t.stable_received)
+    }
+
+    // ETH received, with its USD value when priced, or the stablecoin in
+    // whole units
+    fn received(&self, eth: U256, stable: U256) -> String {
+        match (self.stable, self.eth_usd) {
+            (Some(token), _) => self.metadata.get(&token).cloned().unwrap_or_else(|| TokenMetadata::unknown(token)).format(stable),
+            (None, Some(eth_usd)) => format!("{} ETH (${:.2})", format_ether(eth), eth_to_usd(eth, eth_usd)),
+            (None, None) => format!("{} ETH", format_ether(eth)),
+        }
+    }
+
+    // Tokens sold in whole units, with their USD value at the token's feed
+    fn sold(&self, metadata: &TokenMetadata, token: Address, amount: U256) -> String {
+        let Some(usd) = self.token_usd.get(&token) else { return metadata.format(amount) };
+        let whole = format_units(amount, u32::from(metadata.decimals)).ok().and_then(|whole| whole.parse::<f64>().ok());
+        match whole {
+            Some(whole) => format!("{} (${:.2})", metadata.format(amount), whole * usd),
+            None => metadata.format(amount),
+        }
+    }
+
+    pub fn total_eth_spent(&self) -> U256 {
+        self.tokens.values().fold(U256::zero(), |acc, t| acc + t.eth_spent)
+    }
+
+    pub fn total_fees_paid(&self) -> U256 {
+        self.tokens.values().fold(U256::zero(), |acc, t| acc + t.fees_paid)
+    }
+
+    // What a token's sells took in, less buys and fees, in USD: stablecoin
+    // proceeds at face value, ETH at the Chainlink price. None unpriced.
+    pub fn net_usd(&self, report: &TokenReport) -> Option<f64> {
+        let eth_usd = self.eth_usd;
+        let received = match self.stable {
+            Some(stable) => {
+                let decimals = self.metadata.get(&stable).map_or(6, |metadata| metadata.decimals);
+                format_units(report.stable_received, u32::from(decimals)).ok()?.parse::<f64>().ok()?
+            }
+            None => eth_to_usd(report.eth_received, eth_usd?),
+        };
+        let fees = match (report.fees_paid + report.eth_spent).is_zero() {
+            true => 0.0,
+            false => eth_to_usd(report.fees_paid + report.eth_spent, eth_usd?),
+        };
+        Some(received - fees)
+    }
+}
+
+// A token on one chain across every market trading it there
+#[derive(Debug, Clone)]
+pub struct ChainTokenReport {
+    pub markets: Vec<String>,
+    pub metadata: TokenMetadata,
+    pub report: TokenReport,
+    // By wallet, so markets sharing one aren't counted twice
+    pub balances: HashMap<Address, U256>,
+    // The stablecoin its sells paid out, if not the chain's native coin
+    pub stable: Option<TokenMetadata>,
+    pub net_usd: Option<f64>,
+}
+
+impl ChainTokenReport {
+    pub fn balance(&self) -> U256 {
+        self.balances.values().fold(U256::zero(), |acc, balance| acc + *balance)
+    }
+
+    // Proceeds less buys and fees in the chain's native coin; None when
+    // sells paid out a stablecoin
+    pub fn net(&self) -> Option<U256> {
+        let spent = self.report.fees_paid + self.report.eth_spent;
+        self.stable.is_none().then(|| self.report.eth_received.saturating_sub(spent))
+    }
+}
+
+// Sessions of several markets, possibly on different chains, keyed by token
+// and chain
+#[derive(Debug, Clone, Default)]
+pub struct ConsolidatedReport {
+    pub tokens: BTreeMap<(Address, u64), ChainTokenReport>,
+    pub unconfirmed: usize,
+}
+
+impl ConsolidatedReport {
+    // Folds in a market's session report
+    pub fn add(&mut self, market: &str, session: &SessionReport) {
+        self.unconfirmed += session.unconfirmed;
+        for (token, report) in &session.tokens {
+            let entry = self.tokens.entry((*token, session.chain_id)).or_insert_with(|| ChainTokenReport {
+                markets: Vec::new(),
+                metadata: session.metadata.get(token).cloned().unwrap_or_else(|| TokenMetadata::unknown(*token)),
+                report: TokenReport::default(),
+                balances: HashMap::new(),
+                stable: session.stable.map(|stable| {
+                    session.metadata.get(&stable).cloned().unwrap_or_else(|| TokenMetadata::unknown(stable))
+                }),
+                net_usd: Some(0.0),
+            });
+            entry.markets.push(market.to_string());
+            entry.report.add(report);
+            if let Some(balance) = session.balances.get(token) {
+                entry.balances.insert(session.wallet, *balance);
+            }
+            entry.net_usd = entry.net_usd.zip(session.net_usd(report)).map(|(net, usd)| net + usd);
+        }
+    }
+
+    pub fn chains(&self) -> BTreeSet<u64> {
+        self.tokens.keys().map(|(_, chain_id)| *chain_id).collect()
+    }
+
+    // Net proceeds in USD across every chain; None when any is unpriced,
+    // as native coins differ between chains
+    pub fn total_net_usd(&self) -> Option<f64> {
+        self.tokens.values().try_fold(0.0, |acc, token| Some(acc + token.net_usd?))
+    }
+}
+
+// A chain by registry name, else by id
+fn chain_name(chain_id: u64) -> String {
+    chain_preset(chain_id).map_or_else(|| format!("chain {}", chain_id), |chain| chain.name.to_string())
+}
+
+fn native(chain_id: u64) -> &'static str {
+    chain_preset(chain_id).map_or("ETH", |chain| chain.native)
+}
+
+impl fmt::Display for ConsolidatedReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Consolidated summary")?;
+        for ((token, chain_id), entry) in &self.tokens {
+            let received = match &entry.stable {
+                Some(stable) => stable.format(entry.report.stable_received),
+                None => format!("{} {}", format_ether(entry.report.eth_received), native(*chain_id)),
+            };
+            let net = match (entry.net(), entry.net_usd) {
+                (Some(net), Some(usd)) => format!("{} {} (${:.2})", format_ether(net), native(*chain_id), usd),
+                (Some(net), None) => format!("{} {}", format_ether(net), native(*chain_id)),
+                (None, Some(usd)) => format!("${:.2}", usd),
+                (None, None) => "unpriced".to_string(),
+            };
+            writeln!(
+                f,
+                "  {} ({:?}) on {} [{}]: {} sells ({} failed), {} sold, {} held, {} received, {} {} fees, net {}",
+                entry.metadata.name,
+                token,
+                chain_name(*chain_id),
+                entry.markets.join(", "),
+                entry.report.sells,
+                entry.report.failed_sells,
+                entry.metadata.format(entry.report.tokens_sold),
+                entry.metadata.format(entry.balance()),
+                received,
+                format_ether(entry.report.fees_paid),
+                native(*chain_id),
+                net,
+            )?;
+            if entry.report.buys > 0 {
+                writeln!(
+                    f,
+                    "    {} buys, {} bought for {} {}",
+                    entry.report.buys,
+                    entry.metadata.format(entry.report.tokens_bought),
+                    format_ether(entry.report.eth_spent),
+                    native(*chain_id),
+                )?;
+            }
+        }
+        match self.total_net_usd() {
+            Some(usd) => writeln!(f, "  Total: net ${:.2} across {} chains", usd, self.chains().len())?,
+            None => writeln!(f, "  Total: not every chain is priced in USD; see each token's net above")?,
+        }
+        if self.unconfirmed > 0 {
+            writeln!(f, "  {} sells still unconfirmed", self.unconfirmed)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for SessionReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Session summary")?;
+        for (token, report) in &self.tokens {
+            let metadata = self.metadata.get(token).cloned().unwrap_or_else(|| TokenMetadata::unknown(*token));
+            writeln!(
+                f,
+                "  {} ({:?}): {} sells ({} failed), {} sold, {} received, {} ETH fees",
+                metadata.name,
+                token,
+                report.sells,
+                report.failed_sells,
+                self.sold(&metadata, *token, report.tokens_sold),
+                self.received(report.eth_received, report.stable_received),
+                format_ether(report.fees_paid),
+            )?;
+            if report.buys + report.failed_buys > 0 {
+                writeln!(
+                    f,
+                    "    {} buys ({} failed), {} bought for {} ETH",
+                    report.buys,
+                    report.failed_buys,
+                    self.sold(&metadata, *token, report.tokens_bought),
+                    format_ether(report.eth_spent),
+                )?;
+            }
+        }
+        writeln!(
+            f,
+            "  Total: {} received, {} ETH fees",
+            self.received(self.total_eth_received(), self.total_stable_received()),
+            format_ether(self.total_fees_paid()),
+        )?;
+        if !self.total_eth_spent().is_zero() {
+            writeln!(f, "  Spent on buys: {} ETH", format_ether(self.total_eth_spent()))?;
+        }
+        if !self.builders.is_empty() {
+            let builders: Vec<_> = self.builders.iter().map(|(name, count)| format!("{} {}", name, count)).collect();
+            writeln!(f, "  Bundles included by: {}", builders.join(", "))?;
+        }
+        if self.unconfirmed > 0 {
+            writeln!(f, "  {} sells still unconfirmed", self.unconfirmed)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,88 @@
+use crate::bot::TradingBot;
+use crate::routing::Venue;
+use crate::transport::logs;
+use ethers::{
+    types::{Address, Filter, Log, H256, U256},
+    utils::keccak256,
+};
+use futures_util::stream::{self, BoxStream, StreamExt};
+use std::collections::{HashMap, HashSet};
+
+// V2 pair reserves kept current from the pairs' Sync logs while the bot
+// follows them, so reading them costs no call
+#[derive(Debug, Default)]
+pub(crate) struct ReserveCache {
+    // Pairs whose Sync logs are being followed; only these are cached
+    followed: HashSet<Address>,
+    // (reserve0, reserve1) by pair
+    reserves: HashMap<Address, (U256, U256)>,
+}
+
+impl ReserveCache {
+    pub fn follow(&mut self, pairs: HashSet<Address>) {
+        self.followed = pairs;
+        self.reserves.clear();
+    }
+
+    // Forgets everything once Sync logs stop arriving, as it would go stale
+    pub fn stop(&mut self) {
+        self.followed.clear();
+        self.reserves.clear();
+    }
+
+    pub fn get(&self, pair: Address) -> Option<(U256, U256)> {
+        self.reserves.get(&pair).copied()
+    }
+
+    // Reserves read from the pair itself; a Sync log applied meanwhile is
+    // newer and kept
+    pub fn seed(&mut self, pair: Address, reserves: (U256, U256)) {
+        if self.followed.contains(&pair) {
+            self.reserves.entry(pair).or_insert(reserves);
+        }
+    }
+
+    // Takes a Sync log's reserves. A log dropped by a reorg leaves the
+    // pair to be read afresh.
+    pub fn apply(&mut self, log: &Log) {
+        if !self.followed.contains(&log.address) {
+            return;
+        }
+        if log.removed == Some(true) || log.data.len() != 64 {
+            self.reserves.remove(&log.address);
+            return;
+        }
+        let reserve0 = U256::from_big_endian(&log.data[..32]);
+        let reserve1 = U256::from_big_endian(&log.data[32..]);
+        self.reserves.insert(log.address, (reserve0, reserve1));
+    }
+}
+
+impl TradingBot {
+    // Sync logs of the pairs V2-sold tokens are priced from, followed into
+    // the reserve cache. Without them reserves are read from the pairs.
+    pub(crate) async fn subscribe_syncs(&self) -> BoxStream<'_, Log> {
+        let mut pairs = HashSet::new();
+        for token in self.tokens().into_iter().filter(|token| matches!(token.venue, Venue::V2 | Venue::Best)) {
+            if let Ok(pair) = self.pair_for(token.address).await {
+                pairs.insert(pair);
+            }
+        }
+        if pairs.is_empty() {
+            return stream::pending().boxed();
+        }
+        let filter = Filter::new()
+            .address(pairs.iter().copied().collect::<Vec<_>>())
+            .topic0(H256::from(keccak256("Sync(uint112,uint112)")));
+        match logs(&self.provider, &filter).await {
+            Ok(syncs) => {
+                self.reserve_cache.lock().await.follow(pairs);
+                syncs
+            }
+            Err(e) => {
+                println!("Could not follow pair reserves; reading them per quote: {}", e);
+                stream::pending().boxed()
+            }
+        }
+    }
+}
@@ -0,0 +1,397 @@
+use crate::aerodrome::AERODROME_SELL_GAS;
+use crate::balancer::{BALANCER_SELL_GAS, BALANCER_VAULT};
+use crate::bot::{TokenTarget, TradingBot};
+use crate::curve::CURVE_SELL_GAS;
+use crate::error::{MktmkrError, Result};
+use crate::fees::{GasBid, SELL_GAS_FALLBACK};
+use crate::intents::GPV2_VAULT_RELAYER;
+use crate::paths::HOP_GAS;
+use crate::v4::PERMIT2;
+use ethers::{
+    signers::Signer,
+    types::{Address, Bytes, U256},
+    utils::format_ether,
+};
+use futures_util::future::join_all;
+use serde::Deserialize;
+use serde_json::Value;
+use std::time::Duration;
+
+pub const DEFAULT_ZERO_EX_API: &str = "https://api.0x.org";
+pub const DEFAULT_ONE_INCH_API: &str = "https://api.1inch.dev";
+
+// How long an aggregator API gets to quote before the sell goes without it
+const QUOTE_TIMEOUT: Duration = Duration::from_secs(2);
+// What the aggregator APIs take for native ETH
+const NATIVE_ETH: Address = Address::repeat_byte(0xee);
+
+// Where a token is sold
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Venue {
+    // Its WETH pair on the Uniswap V2 router
+    #[default]
+    V2,
+    // Whichever of its Uniswap V3 WETH pools quotes best
+    V3,
+    // Its configured Uniswap V4 ETH pool, hooks included
+    V4,
+    // Its configured Curve pool, selling for the pool's ETH or WETH
+    Curve,
+    // Its configured Balancer V2 pool with WETH, through the Vault
+    Balancer,
+    // The better of its volatile and stable WETH pools on Aerodrome (or
+    // Velodrome on Optimism)
+    Aerodrome,
+    // Whichever of the above and the aggregator APIs nets the most after
+    // gas, quoted afresh for each sell
+    Best,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AggregatorKind {
+    ZeroEx,
+    OneInch,
+}
+
+// An aggregator's swap API, which returns ready-to-send calldata
+#[derive(Debug, Clone)]
+pub struct AggregatorApi {
+    pub kind: AggregatorKind,
+    url: String,
+    api_key: String,
+    client: reqwest::Client,
+}
+
+impl AggregatorApi {
+    pub fn new(kind: AggregatorKind, url: impl Into<String>, api_key: impl Into<String>) -> Self {
+        Self { kind, url: url.into(), api_key: api_key.into(), client: reqwest::Client::new() }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self.kind {
+            AggregatorKind::ZeroEx => "0x",
+            AggregatorKind::OneInch => "1inch",
+        }
+    }
+
+    // A swap of `amount` of `token` into `buy_token` sent from `taker`,
+    // bounded by the API to `slippage_bps` under its quote
+    async fn quote(
+        &self,
+        chain_id: u64,
+        token: Address,
+        buy_token: Address,
+        amount: U256,
+        taker: Address,
+        slippage_bps: u32,
+    ) -> Result<Route> {
+        let request = match self.kind {
+            AggregatorKind::ZeroEx => self.client
+                .get(format!("{}/swap/allowance-holder/quote", self.url))
+                .header("0x-api-key", &self.api_key)
+                .header("0x-version", "v2")
+                .query(&[
+                    ("chainId", chain_id.to_string()),
+                    ("sellToken", format!("{:?}", token)),
+                    ("buyToken", format!("{:?}", buy_token)),
+                    ("sellAmount", amount.to_string()),
+                    ("taker", format!("{:?}", taker)),
+                    ("slippageBps", slippage_bps.to_string()),
+                ]),
+            AggregatorKind::OneInch => self.client
+                .get(format!("{}/swap/v6.0/{}/swap", self.url, chain_id))
+                .bearer_auth(&self.api_key)
+                .query(&[
+                    ("src", format!("{:?}", token)),
+                    ("dst", format!("{:?}", buy_token)),
+                    ("amount", amount.to_string()),
+                    ("from", format!("{:?}", taker)),
+                    ("origin", format!("{:?}", taker)),
+                    ("slippage", (slippage_bps as f64 / 100.0).to_string()),
+                    ("disableEstimate", "true".to_string()),
+                ]),
+        };
+        let response: Value = request
+            .timeout(QUOTE_TIMEOUT)
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            .map_err(|e| self.error(e))?
+            .json()
+            .await
+            .map_err(|e| self.error(e))?;
+
+        let (amount_out, tx, spender) = match self.kind {
+            AggregatorKind::ZeroEx => (&response["buyAmount"], &response["transaction"], &response["allowanceTarget"]),
+            AggregatorKind::OneInch => (&response["dstAmount"], &response["tx"], &response["tx"]["to"]),
+        };
+        let field = |value: &Value| {
+            value.as_str().map(str::to_string).ok_or_else(|| self.error(format!("unexpected response {}", response)))
+        };
+        let router: Address = field(&tx["to"])?.parse().map_err(|e| self.error(e))?;
+        let amount_out = U256::from_dec_str(&field(amount_out)?).map_err(|e| self.error(e))?;
+        Ok(Route {
+            venue: self.name().to_string(),
+            router,
+            spender: field(spender)?.parse().map_err(|e| self.error(e))?,
+            amount_out,
+            proceeds: amount_out,
+            gas: tx["gas"].as_str().and_then(|gas| U256::from_dec_str(gas).ok())
+                .or_else(|| tx["gas"].as_u64().map(U256::from))
+                .filter(|gas| !gas.is_zero())
+                .unwrap_or(U256::from(SELL_GAS_FALLBACK)),
+            call: RouteCall::Prebuilt(field(&tx["data"])?.parse().map_err(|e| self.error(e))?),
+        })
+    }
+
+    fn error(&self, error: impl std::fmt::Display) -> MktmkrError {
+        MktmkrError::Contract(format!("{} quote: {}", self.name(), error))
+    }
+}
+
+// How a sell's calldata is put together
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum RouteCall {
+    V2 { path: Vec<Address> },
+    V3 { fee: u32 },
+    V4,
+    Curve,
+    Balancer,
+    Aerodrome { stable: bool },
+    // From an aggregator API, with its own output bound
+    Prebuilt(Bytes),
+}
+
+// A quoted way of making one sell
+#[derive(Debug, Clone)]
+pub(crate) struct Route {
+    // Named in the trade log
+    pub venue: String,
+    // The contract called, and the one needing the allowance
+    pub router: Address,
+    pub spender: Address,
+    // ETH (or WETH) out, or what stablecoin proceeds are worth in ETH,
+    // before any sell tax
+    pub amount_out: U256,
+    // What the wallet is paid: amount_out, or the stablecoin's units
+    pub proceeds: U256,
+    pub gas: U256,
+    pub call: RouteCall,
+}
+
+impl TradingBot {
+    pub(crate) fn venue(&self, token: Address) -> Venue {
+        self.token(token).map(|token| token.venue).unwrap_or_default()
+    }
+
+    // The route a sell of `amount` takes: the token's own venue, or for
+    // "best" whichever quote nets the most once each route's gas is paid
+    pub(crate) async fn route_sell(&self, target: &TokenTarget, amount: U256, slippage_bps: u32, bid: GasBid) -> Result<Route> {
+        let token = target.address;
+        let no_quote = || MktmkrError::Contract(format!("no venue quotes a sell of {:?}", token));
+        match target.venue {
+            Venue::V2 => return self.v2_route(target, amount).await,
+            Venue::V3 => return self.v3_route(token, amount).await?.ok_or_else(no_quote),
+            Venue::V4 => return self.v4_route(target, amount).await?.ok_or_else(no_quote),
+            Venue::Curve => return self.curve_route(target, amount).await?.ok_or_else(no_quote),
+            Venue::Balancer => return self.balancer_route(target, amount).await?.ok_or_else(no_quote),
+            Venue::Aerodrome => return self.aerodrome_route(token, amount).await?.ok_or_else(no_quote),
+            Venue::Best => {}
+        }
+
+        let buy_token = match (self.stable, self.weth_proceeds) {
+            (Some(stable), _) => stable,
+            (None, Some(_)) => self.weth,
+            (None, None) => NATIVE_ETH,
+        };
+        let chain_id = self.wallet.chain_id();
+        let (v2, v3, v4, curve, balancer, aerodrome, quoted) = tokio::join!(
+            self.v2_route(target, amount),
+            self.v3_route(token, amount),
+            self.v4_route(target, amount),
+            self.curve_route(target, amount),
+            self.balancer_route(target, amount),
+            self.aerodrome_route(token, amount),
+            join_all(self.aggregator_apis.iter().map(|api| {
+                api.quote(chain_id, token, buy_token, amount, self.wallet.address(), slippage_bps)
+            }))
+        );
+        let mut routes: Vec<Route> = v2
+            .ok()
+            .into_iter()
+            .chain(v3.ok().flatten())
+            .chain(v4.ok().flatten())
+            .chain(curve.ok().flatten())
+            .chain(balancer.ok().flatten())
+            .chain(aerodrome.ok().flatten())
+            .collect();
+        for (api, quote) in self.aggregator_apis.iter().zip(quoted) {
+            match quote {
+                Ok(mut route) => {
+                    // Valued in ETH to compare with the other routes
+                    if let Some(stable) = self.stable {
+                        route.amount_out = self.quote_path(&[stable, self.weth], route.proceeds).await.unwrap_or_default();
+                    }
+                    routes.push(route)
+                }
+                Err(e) => println!("No {} quote for {:?}: {}", api.name(), token, e),
+            }
+        }
+
+        let price = self.expected_gas_price(bid).await?;
+        let net = |route: &Route| route.amount_out.saturating_sub(route.gas * price);
+        let best = routes.iter().max_by_key(|route| net(route)).cloned().ok_or_else(no_quote)?;
+        if routes.len() > 1 {
+            let quotes: Vec<String> = routes
+                .iter()
+                .map(|route| format!("{} {} ETH", route.venue, format_ether(net(route))))
+                .collect();
+            println!(
+                "Routing sell of {} via {} (net of gas: {})",
+                self.format_amount(token, amount), best.venue, quotes.join(", ")
+            );
+        }
+        if matches!(best.call, RouteCall::Prebuilt(_)) {
+            self.aggregator_spenders.lock().await.insert(best.spender);
+        }
+        Ok(best)
+    }
+
+    async fn v2_route(&self, target: &TokenTarget, amount: U256) -> Result<Route> {
+        let (mut path, amount_out) = self.sell_path(target, amount).await?;
+        // Stablecoin proceeds take one more swap out of WETH
+        let proceeds = match self.stable {
+            Some(stable) => {
+                path.push(stable);
+                self.quote_path(&path, amount).await?
+            }
+            None => amount_out,
+        };
+        let venue = match path.len() {
+            2 => self.v2_name.clone(),
+            _ => format!("{} {}", self.v2_name, self.format_path(&path)),
+        };
+        Ok(Route {
+            venue,
+            router: self.router,
+            spender: self.router,
+            amount_out,
+            proceeds,
+            gas: U256::from(SELL_GAS_FALLBACK + HOP_GAS * (path.len() as u64 - 2)),
+            call: RouteCall::V2 { path },
+        })
+    }
+
+    // None when no V3 pool quotes, or there's no V3 router on the chain.
+    // V3 sells only pay out WETH.
+    async fn v3_route(&self, token: Address, amount: U256) -> Result<Option<Route>> {
+        let Some(v3) = self.v3.filter(|_| self.stable.is_none()) else { return Ok(None) };
+        Ok(self.v3_quote(token, amount).await?.map(|(fee, amount_out, gas)| Route {
+            venue: format!("{} {}%", v3.name, fee as f64 / 10_000.0),
+            router: v3.router,
+            spender: v3.router,
+            amount_out,
+            proceeds: amount_out,
+            gas,
+            call: RouteCall::V3 { fee },
+        }))
+    }
+
+    // None without a V4 pool for the token. V4 sells only pay out ETH.
+    async fn v4_route(&self, target: &TokenTarget, amount: U256) -> Result<Option<Route>> {
+        let Some(v4) = self.v4 else { return Ok(None) };
+        if target.v4_pool.is_none() || self.stable.is_some() || self.weth_proceeds.is_some() {
+            return Ok(None);
+        }
+        let (amount_out, gas) = self.v4_quote(target, amount).await?;
+        Ok(Some(Route {
+            venue: "Uniswap V4".to_string(),
+            router: v4.universal_router,
+            spender: PERMIT2,
+            amount_out,
+            proceeds: amount_out,
+            gas,
+            call: RouteCall::V4,
+        }))
+    }
+
+    // None without a Curve pool for the token. Curve sells pay out ETH or
+    // WETH only.
+    async fn curve_route(&self, target: &TokenTarget, amount: U256) -> Result<Option<Route>> {
+        let Some(pool) = target.curve_pool.filter(|_| self.stable.is_none()) else { return Ok(None) };
+        let amount_out = self.curve_quote(target.address, amount).await?;
+        Ok(Some(Route {
+            venue: "Curve".to_string(),
+            router: pool,
+            spender: pool,
+            amount_out,
+            proceeds: amount_out,
+            gas: U256::from(CURVE_SELL_GAS),
+            call: RouteCall::Curve,
+        }))
+    }
+
+    // None without a Balancer pool for the token. Balancer sells pay out
+    // ETH or WETH only.
+    async fn balancer_route(&self, target: &TokenTarget, amount: U256) -> Result<Option<Route>> {
+        if target.balancer_pool.is_none() || self.stable.is_some() {
+            return Ok(None);
+        }
+        let amount_out = self.balancer_quote(target.address, amount).await?;
+        Ok(Some(Route {
+            venue: "Balancer".to_string(),
+            router: BALANCER_VAULT,
+            spender: BALANCER_VAULT,
+            amount_out,
+            proceeds: amount_out,
+            gas: U256::from(BALANCER_SELL_GAS),
+            call: RouteCall::Balancer,
+        }))
+    }
+
+    // None when neither Aerodrome pool quotes, or there's no Aerodrome
+    // router on the chain. Aerodrome sells pay out ETH or WETH only.
+    async fn aerodrome_route(&self, token: Address, amount: U256) -> Result<Option<Route>> {
+        let Some(aerodrome) = self.aerodrome.filter(|_| self.stable.is_none()) else { return Ok(None) };
+        Ok(self.aerodrome_quote(token, amount).await?.map(|(stable, amount_out)| Route {
+            venue: format!("{} {}", aerodrome.name, if stable { "stable" } else { "volatile" }),
+            router: aerodrome.router,
+            spender: aerodrome.router,
+            amount_out,
+            proceeds: amount_out,
+            gas: U256::from(AERODROME_SELL_GAS),
+            call: RouteCall::Aerodrome { stable },
+        }))
+    }
+
+    // Routers a token's sells may need allowances for: its venue's router or
+    // pool, and for "best" every one configured plus any aggregator picked
+    // so far.
+    // V4 sells spend through Permit2, and CoW orders through its relayer.
+    pub(crate) async fn spenders(&self, token: Address) -> Vec<Address> {
+        let v3 = self.v3.map(|v3| v3.router);
+        let v4 = self.token(token).and_then(|target| target.v4_pool).and(self.v4).map(|_| PERMIT2);
+        let curve = self.token(token).and_then(|target| target.curve_pool);
+        let balancer = self.token(token).and_then(|target| target.balancer_pool).map(|_| BALANCER_VAULT);
+        let aerodrome = self.aerodrome_router();
+        let cow = self.intents.as_ref().map(|_| GPV2_VAULT_RELAYER);
+        let spenders: Vec<Address> = match self.venue(token) {
+            Venue::V2 => vec![self.router],
+            Venue::V3 => v3.into_iter().collect(),
+            Venue::V4 => v4.into_iter().collect(),
+            Venue::Curve => curve.into_iter().collect(),
+            Venue::Balancer => balancer.into_iter().collect(),
+            Venue::Aerodrome => aerodrome.into_iter().collect(),
+            Venue::Best => std::iter::once(self.router)
+                .chain(v3)
+                .chain(v4)
+                .chain(curve)
+                .chain(balancer)
+                .chain(aerodrome)
+                .chain(self.aggregator_spenders.lock().await.iter().copied())
+                .collect(),
+        };
+        spenders.into_iter().chain(cow).collect()
+    }
+}
@@ -0,0 +1,78 @@
+use crate::error::{MktmkrError, Result};
+use chrono::{DateTime, Datelike, Duration, NaiveTime, Utc, Weekday};
+
+// A daily UTC session on the given weekdays. When `end` is not after `start`
+// the window runs past midnight and `days` refers to the day it opens.
+#[derive(Debug, Clone)]
+pub struct TradingWindow {
+    pub days: Vec<Weekday>,
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+}
+
+impl TradingWindow {
+    pub fn parse(days: &[String], start: &str, end: &str) -> Result<Self> {
+        let days = if days.is_empty() {
+            vec![Weekday::Mon, Weekday::Tue, Weekday::Wed, Weekday::Thu, Weekday::Fri, Weekday::Sat, Weekday::Sun]
+        } else {
+            days.iter()
+                .map(|day| day.parse::<Weekday>().map_err(|_| MktmkrError::Config(format!("invalid weekday {:?}", day))))
+                .collect::<Result<_>>()?
+        };
+        let parse_time = |value: &str| {
+            NaiveTime::parse_from_str(value, "%H:%M")
+                .map_err(|e| MktmkrError::Config(format!("invalid time {:?} (expected HH:MM): {}", value, e)))
+        };
+
+        Ok(Self { days, start: parse_time(start)?, end: parse_time(end)? })
+    }
+
+    // Open and close of every occurrence that could contain `at`
+    fn occurrences(&self, at: DateTime<Utc>) -> impl Iterator<Item = (DateTime<Utc>, DateTime<Utc>)> + '_ {
+        (-1..=7).filter_map(move |offset| {
+            let day = at.date_naive() + Duration::days(offset);
+            if !self.days.contains(&day.weekday()) {
+                return None;
+            }
+            let open = day.and_time(self.start).and_utc();
+            let close = if self.end > self.start {
+                day.and_time(self.end).and_utc()
+            } else {
+                (day + Duration::days(1)).and_time(self.end).and_utc()
+            };
+            Some((open, close))
+        })
+    }
+}
+
+// Trading sessions; an empty schedule is always open
+#[derive(Debug, Clone, Default)]
+pub struct Schedule {
+    pub windows: Vec<TradingWindow>,
+}
+
+impl Schedule {
+    pub fn is_open(&self, at: DateTime<Utc>) -> bool {
+        self.windows.is_empty() || self.current_close(at).is_some()
+    }
+
+    // When the window containing `at` closes; None if closed or always open
+    pub fn current_close(&self, at: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        self.windows
+            .iter()
+            .flat_map(|window| window.occurrences(at))
+            .filter(|(open, close)| *open <= at && at < *close)
+            .map(|(_, close)| close)
+            .max()
+    }
+
+    // The next time a window opens after `at`
+    pub fn next_open(&self, at: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        self.windows
+            .iter()
+            .flat_map(|window| window.occurrences(at))
+            .map(|(open, _)| open)
+            .filter(|open| *open > at)
+            .min()
+    }
+}
@@ -0,0 +1,114 @@
+use ethers::types::H256;
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+// Hashes already acted on, forgotten after `ttl` or once `capacity` newer
+// ones have been seen. Providers re-announce rebroadcast transactions, and
+// without this the same buy would trigger a sell each time.
+#[derive(Debug)]
+pub struct SeenCache {
+    ttl: Duration,
+    capacity: usize,
+    seen: HashMap<H256, Instant>,
+    order: VecDeque<(Instant, H256)>,
+}
+
+impl SeenCache {
+    pub fn new(ttl: Duration, capacity: usize) -> Self {
+        Self { ttl, capacity, seen: HashMap::new(), order: VecDeque::new() }
+    }
+
+    // Records `hash`; false if it was already seen within the TTL
+    pub fn insert(&mut self, hash: H256, now: Instant) -> bool {
+        self.evict(now);
+        if self.seen.contains_key(&hash) {
+            return false;
+        }
+        self.seen.insert(hash, now);
+        self.order.push_back((now, hash));
+        while self.order.len() > self.capacity {
+            self.pop_oldest();
+        }
+        true
+    }
+
+    fn evict(&mut self, now: Instant) {
+        while self.order.front().is_some_and(|(at, _)| now.duration_since(*at) >= self.ttl) {
+            self.pop_oldest();
+        }
+    }
+
+    fn pop_oldest(&mut self) {
+        if let Some((_, hash)) = self.order.pop_front() {
+            self.seen.remove(&hash);
+        }
+    }
+}
+
+impl Default for SeenCache {
+    fn default() -> Self {
+        Self::new(Duration::from_secs(600), 100_000)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hash(byte: u8) -> H256 {
+        H256::repeat_byte(byte)
+    }
+
+    #[test]
+    fn drops_repeats_within_the_ttl() {
+        let mut cache = SeenCache::new(Duration::from_secs(10), 100);
+        let now = Instant::now();
+        assert!(cache.insert(hash(1), now));
+        assert!(!cache.insert(hash(1), now));
+        assert!(!cache.insert(hash(1), now + Duration::from_secs(9)));
+        assert!(cache.insert(hash(2), now));
+    }
+
+    #[test]
+    fn forgets_hashes_once_the_ttl_has_passed() {
+        let mut cache = SeenCache::new(Duration::from_secs(10), 100);
+        let now = Instant::now();
+        cache.insert(hash(1), now);
+        cache.insert(hash(2), now + Duration::from_secs(5));
+        assert!(cache.insert(hash(1), now + Duration::from_secs(10)));
+        assert!(!cache.insert(hash(2), now + Duration::from_secs(10)));
+    }
+
+    #[test]
+    fn forgets_the_oldest_beyond_capacity() {
+        let mut cache = SeenCache::new(Duration::from_secs(10), 2);
+        let now = Instant::now();
+        cache.insert(hash(1), now);
+        cache.insert(hash(2), now);
+        cache.insert(hash(3), now);
+        // Two newer hashes push the first out; the second is still held
+        assert!(!cache.insert(hash(2), now));
+        assert!(!cache.insert(hash(3), now));
+        assert!(cache.insert(hash(1), now));
+    }
+
+    #[test]
+    fn holds_exactly_its_capacity() {
+        let mut cache = SeenCache::new(Duration::from_secs(10), 1);
+        let now = Instant::now();
+        assert!(cache.insert(hash(1), now));
+        assert!(!cache.insert(hash(1), now));
+
+        let mut cache = SeenCache::new(Duration::from_secs(10), 0);
+        assert!(cache.insert(hash(1), now));
+        assert!(cache.insert(hash(1), now));
+    }
+
+    #[test]
+    fn zero_ttl_remembers_nothing() {
+        let mut cache = SeenCache::new(Duration::ZERO, 100);
+        let now = Instant::now();
+        assert!(cache.insert(hash(1), now));
+        assert!(cache.insert(hash(1), now));
+    }
+}
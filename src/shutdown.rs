@@ -0,0 +1,74 @@
+use tokio::sync::watch;
+
+// Cooperative stop signal shared by the supervisor and every bot
+#[derive(Debug, Clone)]
+pub struct Shutdown(watch::Receiver<bool>);
+
+#[derive(Debug)]
+pub struct ShutdownTrigger(watch::Sender<bool>);
+
+pub fn channel() -> (ShutdownTrigger, Shutdown) {
+    let (tx, rx) = watch::channel(false);
+    (ShutdownTrigger(tx), Shutdown(rx))
+}
+
+impl ShutdownTrigger {
+    pub fn trigger(&self) {
+        let _ = self.0.send(true);
+    }
+}
+
+impl Default for Shutdown {
+    // Never fires
+    fn default() -> Self {
+        channel().1
+    }
+}
+
+impl Shutdown {
+    pub fn is_triggered(&self) -> bool {
+        *self.0.borrow()
+    }
+
+    pub async fn wait(&mut self) {
+        while !*self.0.borrow_and_update() {
+            if self.0.changed().await.is_err() {
+                // Trigger dropped without firing
+                std::future::pending::<()>().await;
+            }
+        }
+    }
+}
+
+// Fires on the first SIGINT or SIGTERM; a second signal exits immediately
+pub fn on_signals() -> Shutdown {
+    let (trigger, shutdown) = channel();
+
+    tokio::spawn(async move {
+        wait_for_signal().await;
+        println!("Shutting down: finishing in-flight sells (signal again to force exit)");
+        trigger.trigger();
+
+        wait_for_signal().await;
+        println!("Forced exit");
+        std::process::exit(130);
+    });
+
+    shutdown
+}
+
+#[cfg(unix)]
+async fn wait_for_signal() {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut sigterm = signal(SignalKind::terminate()).expect("failed to install SIGTERM handler");
+    tokio::select! {
+        _ = tokio::signal::ctrl_c() => {}
+        _ = sigterm.recv() => {}
+    }
+}
+
+#[cfg(not(unix))]
+async fn wait_for_signal() {
+    let _ = tokio::signal::ctrl_c().await;
+}
@@ -0,0 +1,72 @@
+use ethers::signers::LocalWallet;
+use serde::Deserialize;
+use crate::error::{MktmkrError, Result};
+use std::{env, path::PathBuf};
+
+pub const DEFAULT_KEY_ENV: &str = "MKTMKR_PRIVATE_KEY";
+
+// Where the trading wallet's key comes from. Keys are never read from the
+// config file itself, only referenced by it.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "source", rename_all = "snake_case")]
+pub enum KeySource {
+    // Hex private key in an environment variable
+    Env {
+        #[serde(default = "default_key_env")]
+        var: String,
+    },
+    // Encrypted JSON keystore; the password comes from `password_env` or an
+    // interactive prompt when that is unset
+    Keystore {
+        path: PathBuf,
+        #[serde(default)]
+        password_env: Option<String>,
+    },
+    // Hex private key typed at startup with echo disabled
+    Prompt,
+}
+
+impl Default for KeySource {
+    fn default() -> Self {
+        KeySource::Env { var: default_key_env() }
+    }
+}
+
+fn default_key_env() -> String {
+    DEFAULT_KEY_ENV.to_string()
+}
+
+impl KeySource {
+    pub fn load(&self) -> Result<LocalWallet> {
+        match self {
+            KeySource::Env { var } => {
+                let private_key = env::var(var)
+                    .map_err(|_| MktmkrError::Config(format!("{} is not set", var)))?;
+                Ok(private_key.trim().parse()?)
+            }
+            KeySource::Keystore { path, password_env } => {
+                let password = match password_env.as_ref().and_then(|var| env::var(var).ok()) {
+                    Some(password) => password,
+                    None => rpassword::prompt_password(format!("Password for {}: ", path.display()))?,
+                };
+                Ok(LocalWallet::decrypt_keystore(path, password)?)
+            }
+            KeySource::Prompt => {
+                let private_key = rpassword::prompt_password("Private key: ")?;
+                Ok(private_key.trim().parse()?)
+            }
+        }
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        match self {
+            KeySource::Env { var } if var.is_empty() => {
+                Err(MktmkrError::Config("wallet.var must not be empty".into()))
+            }
+            KeySource::Keystore { path, .. } if !path.is_file() => {
+                Err(MktmkrError::Config(format!("wallet.path: keystore {} does not exist", path.display())))
+            }
+            _ => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,128 @@
+use crate::math::{apply_bps, BPS};
+use ethers::{types::U256, utils::format_ether};
+use serde::Deserialize;
+
+// How the sell answering a buy grows with the buy's size
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SizingCurve {
+    // sell_percentage of every buy
+    #[default]
+    Linear,
+    // With the square root of the buy: sell_percentage of a buy of pivot
+    // ETH, half that share of one four times larger
+    Sqrt,
+    // sell_percentage scaled by a factor interpolated between points
+    Piecewise,
+}
+
+// Sizes sells along a curve in the buy's size rather than as a flat share,
+// so large buys don't produce proportionally large dumps
+#[derive(Debug, Clone, PartialEq)]
+pub struct SellSizing {
+    pub curve: SizingCurve,
+    // For sqrt, the buy size in ETH at which sell_percentage applies as is
+    pub pivot_eth: f64,
+    // For piecewise, (buy ETH, factor) points by ascending buy size; the
+    // factor is flat beyond the first and last
+    pub points: Vec<(f64, f64)>,
+    // Bounds on each sell's ETH value
+    pub floor: U256,
+    pub cap: Option<U256>,
+}
+
+impl SellSizing {
+    // The ETH worth of tokens to sell into a buy of `buy` wei, for a flat
+    // share of `sell_bps`
+    pub fn sell_eth(&self, buy: U256, sell_bps: u64) -> U256 {
+        let buy_eth: f64 = format_ether(buy).parse().unwrap_or_default();
+        let factor = match self.curve {
+            SizingCurve::Linear => 1.0,
+            SizingCurve::Sqrt if buy_eth > 0.0 => (self.pivot_eth / buy_eth).sqrt(),
+            SizingCurve::Sqrt => 1.0,
+            SizingCurve::Piecewise => self.interpolate(buy_eth),
+        };
+        // Never more than the buy itself
+        let bps = (sell_bps as f64 * factor).min(BPS as f64) as u64;
+        let eth = apply_bps(buy, bps).max(self.floor);
+        self.cap.map_or(eth, |cap| eth.min(cap))
+    }
+
+    fn interpolate(&self, buy_eth: f64) -> f64 {
+        let Some(&(first_eth, first)) = self.points.first() else { return 1.0 };
+        if buy_eth <= first_eth {
+            return first;
+        }
+        for pair in self.points.windows(2) {
+            let ((low_eth, low), (high_eth, high)) = (pair[0], pair[1]);
+            if buy_eth <= high_eth {
+                return low + (high - low) * (buy_eth - low_eth) / (high_eth - low_eth);
+            }
+        }
+        self.points.last().map_or(1.0, |(_, factor)| *factor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sizing(curve: SizingCurve, points: Vec<(f64, f64)>) -> SellSizing {
+        SellSizing { curve, pivot_eth: 1.0, points, floor: U256::zero(), cap: None }
+    }
+
+    #[test]
+    fn interpolates_between_points() {
+        let sizing = sizing(SizingCurve::Piecewise, vec![(1.0, 1.0), (3.0, 0.5), (5.0, 0.25)]);
+        assert_eq!(sizing.interpolate(1.0), 1.0);
+        assert_eq!(sizing.interpolate(2.0), 0.75);
+        assert_eq!(sizing.interpolate(3.0), 0.5);
+        assert_eq!(sizing.interpolate(4.0), 0.375);
+        assert_eq!(sizing.interpolate(5.0), 0.25);
+    }
+
+    #[test]
+    fn holds_flat_beyond_the_ends() {
+        let sizing = sizing(SizingCurve::Piecewise, vec![(1.0, 1.0), (3.0, 0.5)]);
+        assert_eq!(sizing.interpolate(0.0), 1.0);
+        assert_eq!(sizing.interpolate(-1.0), 1.0);
+        assert_eq!(sizing.interpolate(1e9), 0.5);
+        assert_eq!(sizing.interpolate(f64::INFINITY), 0.5);
+    }
+
+    #[test]
+    fn interpolates_without_enough_points() {
+        assert_eq!(sizing(SizingCurve::Piecewise, Vec::new()).interpolate(2.0), 1.0);
+        let single = sizing(SizingCurve::Piecewise, vec![(1.0, 0.5)]);
+        assert_eq!(single.interpolate(0.5), 0.5);
+        assert_eq!(single.interpolate(2.0), 0.5);
+        // A repeated point steps rather than dividing by zero
+        let step = sizing(SizingCurve::Piecewise, vec![(0.0, 1.0), (1.0, 1.0), (1.0, 0.5)]);
+        assert_eq!(step.interpolate(1.0), 1.0);
+        assert_eq!(step.interpolate(2.0), 0.5);
+    }
+
+    #[test]
+    fn scales_sqrt_sells_around_the_pivot() {
+        let sizing = sizing(SizingCurve::Sqrt, Vec::new());
+        let eth = U256::exp10(18);
+        assert_eq!(sizing.sell_eth(eth, 5_000), eth / 2);
+        assert_eq!(sizing.sell_eth(eth * 4, 5_000), eth);
+        // Small buys never sell more than themselves
+        assert_eq!(sizing.sell_eth(eth / 100, 5_000), eth / 100);
+        assert_eq!(sizing.sell_eth(U256::zero(), 5_000), U256::zero());
+    }
+
+    #[test]
+    fn bounds_sells_by_floor_and_cap() {
+        let eth = U256::exp10(18);
+        let mut sizing = sizing(SizingCurve::Linear, Vec::new());
+        sizing.floor = eth / 10;
+        sizing.cap = Some(eth);
+        assert_eq!(sizing.sell_eth(U256::zero(), 5_000), eth / 10);
+        assert_eq!(sizing.sell_eth(eth, 5_000), eth / 2);
+        assert_eq!(sizing.sell_eth(U256::MAX, 5_000), eth);
+        sizing.cap = None;
+        assert_eq!(sizing.sell_eth(U256::MAX, BPS), U256::MAX);
+    }
+}
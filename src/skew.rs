@@ -0,0 +1,77 @@
+use crate::bot::TradingBot;
+use crate::math::{mul_div, BPS};
+use ethers::types::{Address, U256};
+use serde::Deserialize;
+
+// How the skew grows with the distance from the target ratio
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SkewCurve {
+    #[default]
+    Linear,
+    // Gentle near the target, steep far from it
+    Quadratic,
+    Exponential,
+}
+
+// Skews trading toward a target share of the wallet's value in the token.
+// Overweight, sells grow and smaller buys trigger them; underweight, the
+// reverse. Market making buys skew the opposite way.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct InventorySkew {
+    pub target_ratio: f64,
+    pub curve: SkewCurve,
+    // Factor at full deviation is 1 + strength (linear, quadratic) or
+    // e^strength (exponential)
+    pub strength: f64,
+    // Factors are kept within [1 / max_factor, max_factor]
+    pub max_factor: f64,
+}
+
+impl InventorySkew {
+    // -1 holding none of the token, 0 at the target, 1 holding nothing else
+    pub fn deviation(&self, share: f64) -> f64 {
+        let deviation = match share > self.target_ratio {
+            true => (share - self.target_ratio) / (1.0 - self.target_ratio),
+            false => (share - self.target_ratio) / self.target_ratio,
+        };
+        deviation.clamp(-1.0, 1.0)
+    }
+
+    // What sell sizes are multiplied and trigger thresholds divided by at
+    // `share` of the value held in the token
+    pub fn factor(&self, share: f64) -> f64 {
+        let d = self.deviation(share);
+        let factor = match self.curve {
+            SkewCurve::Linear => 1.0 + self.strength * d,
+            SkewCurve::Quadratic => 1.0 + self.strength * d * d.abs(),
+            SkewCurve::Exponential => (self.strength * d).exp(),
+        };
+        factor.clamp(1.0 / self.max_factor, self.max_factor)
+    }
+}
+
+impl TradingBot {
+    // The token's skew as of the last block; 1 without skewing
+    pub(crate) async fn skew_factor(&self, token: Address) -> f64 {
+        self.skew_factors.lock().await.get(&token).copied().unwrap_or(1.0)
+    }
+
+    // Re-reads each token's holdings into its skew, once a block
+    pub(crate) async fn refresh_skew(&self) {
+        let Some(skew) = self.skew else { return };
+        for token in self.tokens() {
+            let share = match self.holdings(token.address).await {
+                Ok((token_value, eth)) if !(token_value + eth).is_zero() => {
+                    mul_div(token_value, U256::from(BPS), token_value + eth).as_u64() as f64 / BPS as f64
+                }
+                Ok(_) => continue,
+                Err(e) => {
+                    println!("Could not read holdings of {:?} for skewing: {}", token.address, e);
+                    continue;
+                }
+            };
+            self.skew_factors.lock().await.insert(token.address, skew.factor(share));
+        }
+    }
+}
@@ -0,0 +1,462 @@
+use crate::bot::WS_RECONNECTS;
+use crate::error::{MktmkrError, Result};
+use crate::mempool::PendingTxMode;
+use crate::pipeline::{fetch_pending, forward, poll_txpool, receive, PENDING_BUFFER};
+use crate::transport::Transport;
+use ethers::{
+    providers::{Authorization, Middleware, Provider, ProviderError, StreamExt, Ws},
+    types::{Address, Transaction},
+    utils::rlp::Rlp,
+};
+use futures_util::{future::BoxFuture, stream::BoxStream, SinkExt};
+use serde_json::{json, Value};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{mpsc, oneshot};
+use tokio_tungstenite::tungstenite::{client::IntoClientRequest, http::HeaderValue, Message};
+
+// Decides which pending transactions are worth decoding
+pub type PendingFilter = Arc<dyn Fn(&Transaction) -> bool + Send + Sync>;
+
+// A feed of pending transactions. The bot merges every configured source
+// and drops the duplicates, so a faster private feed can run alongside the
+// node's own.
+pub trait MempoolSource: Send + Sync {
+    // Short name for logs
+    fn name(&self) -> &str;
+
+    // Starts the feed, forwarding only transactions passing `filter`
+    fn subscribe(&self, filter: PendingFilter) -> BoxFuture<'_, Result<BoxStream<'_, Transaction>>>;
+}
+
+// The node's own eth_subscribe feed, in any of the pending-tx modes
+pub struct NodeSource {
+    pub(crate) name: String,
+    pub(crate) provider: Provider<Transport>,
+    pub(crate) mode: PendingTxMode,
+    // Concurrent hash lookups when only hashes are pushed
+    pub(crate) fetch_concurrency: usize,
+    pub(crate) txpool_poll_interval: Duration,
+    // Ask Alchemy to stream only transactions to `watched`
+    pub(crate) filter_recipients: bool,
+    pub(crate) watched: Vec<Address>,
+}
+
+impl MempoolSource for NodeSource {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    // Alchemy filters on its side when `filter_recipients` is set, at the
+    // cost of not seeing cancels of tracked buys; otherwise the filter runs
+    // here, ahead of decoding.
+    fn subscribe(&self, filter: PendingFilter) -> BoxFuture<'_, Result<BoxStream<'_, Transaction>>> {
+        Box::pin(async move {
+            Ok(match self.mode {
+                PendingTxMode::Hashes => {
+                    fetch_pending(self.provider.clone(), self.fetch_concurrency, move |tx| filter(tx)).await?
+                }
+                PendingTxMode::Txpool => {
+                    poll_txpool(self.provider.clone(), self.txpool_poll_interval, move |tx| filter(tx)).await?
+                }
+                PendingTxMode::Full => self.provider
+                    .subscribe_full_pending_txs()
+                    .await?
+                    .filter(move |tx| std::future::ready(filter(tx)))
+                    .boxed(),
+                PendingTxMode::Alchemy => {
+                    let params = if self.filter_recipients {
+                        json!({ "toAddress": self.watched, "hashesOnly": false })
+                    } else {
+                        json!({ "hashesOnly": false })
+                    };
+                    self.provider
+                        .subscribe::<_, Transaction>(("alchemy_pendingTransactions", params))
+                        .await?
+                        .filter(move |tx| std::future::ready(filter(tx)))
+                        .boxed()
+                }
+            })
+        })
+    }
+}
+
+// A websocket feed speaking standard eth_subscribe with full bodies behind
+// an API key, such as Eden's
+pub struct EthFeedSource {
+    pub name: String,
+    pub url: String,
+    pub auth: Option<String>,
+}
+
+impl MempoolSource for EthFeedSource {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn subscribe(&self, filter: PendingFilter) -> BoxFuture<'_, Result<BoxStream<'_, Transaction>>> {
+        Box::pin(async move {
+            let provider = match &self.auth {
+                Some(auth) => {
+                    Provider::<Ws>::connect_with_auth_and_reconnects(&self.url, Authorization::raw(auth.as_str()), WS_RECONNECTS)
+                        .await?
+                }
+                None => Provider::<Ws>::connect_with_reconnects(&self.url, WS_RECONNECTS).await?,
+            };
+            let (ready_tx, ready_rx) = oneshot::channel();
+            let (bodies_tx, bodies_rx) = mpsc::channel(PENDING_BUFFER);
+
+            let task = tokio::spawn(async move {
+                let mut txs = match provider.subscribe_full_pending_txs().await {
+                    Ok(txs) => {
+                        let _ = ready_tx.send(Ok(()));
+                        txs
+                    }
+                    Err(e) => {
+                        let _ = ready_tx.send(Err(e));
+                        return;
+                    }
+                };
+                let mut dropped = 0u64;
+                while let Some(tx) = txs.next().await {
+                    if filter(&tx) && !forward(&bodies_tx, tx, &mut dropped) {
+                        return;
+                    }
+                }
+            });
+
+            receive(task, ready_rx, bodies_rx).await
+        })
+    }
+}
+
+// bloXroute's BDN newTxs stream. Its subscription ids aren't numbers, so it
+// is read over a plain websocket rather than through the provider.
+pub struct BloxrouteSource {
+    pub url: String,
+    // Authorization header issued with the account
+    pub auth: String,
+}
+
+impl MempoolSource for BloxrouteSource {
+    fn name(&self) -> &str {
+        "bloxroute"
+    }
+
+    fn subscribe(&self, filter: PendingFilter) -> BoxFuture<'_, Result<BoxStream<'_, Transaction>>> {
+        Box::pin(async move {
+            let mut request = self.url.as_str().into_client_request().map_err(feed_error)?;
+            request
+                .headers_mut()
+                .insert("Authorization", HeaderValue::from_str(&self.auth).map_err(feed_error)?);
+            let (mut socket, _) = tokio_tungstenite::connect_async(request).await.map_err(feed_error)?;
+            let subscribe = json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "method": "subscribe",
+                "params": ["newTxs", { "include": ["tx_hash", "tx_contents"] }],
+            });
+            socket.send(Message::Text(subscribe.to_string())).await.map_err(feed_error)?;
+
+            let (ready_tx, ready_rx) = oneshot::channel();
+            let (bodies_tx, bodies_rx) = mpsc::channel(PENDING_BUFFER);
+
+            let task = tokio::spawn(async move {
+                let mut ready_tx = Some(ready_tx);
+                let mut dropped = 0u64;
+                while let Some(Ok(message)) = socket.next().await {
+                    let Message::Text(text) = message else { continue };
+                    let Ok(message) = serde_json::from_str::<Value>(&text) else { continue };
+
+                    // The reply to our subscribe comes first
+                    if message.get("id").is_some_and(|id| !id.is_null()) {
+                        let Some(ready_tx) = ready_tx.take() else { continue };
+                        let reply = match message.get("error") {
+                            Some(error) => Err(ProviderError::CustomError(format!("bloXroute subscribe: {}", error))),
+                            None => Ok(()),
+                        };
+                        let failed = reply.is_err();
+                        let _ = ready_tx.send(reply);
+                        if failed {
+                            return;
+                        }
+                        continue;
+                    }
+
+                    let contents = message.pointer("/params/result/txContents").cloned();
+                    let Some(Ok(tx)) = contents.map(serde_json::from_value::<Transaction>) else { continue };
+                    if filter(&tx) && !forward(&bodies_tx, tx, &mut dropped) {
+                        return;
+                    }
+                }
+            });
+
+            receive(task, ready_rx, bodies_rx).await
+        })
+    }
+}
+
+// Fiber's SubscribeNewTxsV2 gRPC stream. Each message carries a signed
+// transaction's RLP (field 1) and its sender (field 2); the request is an
+// empty filter, so everything is streamed and filtered here.
+pub struct FiberSource {
+    pub url: String,
+    pub api_key: String,
+}
+
+impl MempoolSource for FiberSource {
+    fn name(&self) -> &str {
+        "fiber"
+    }
+
+    fn subscribe(&self, filter: PendingFilter) -> BoxFuture<'_, Result<BoxStream<'_, Transaction>>> {
+        Box::pin(async move {
+            let client = reqwest::Client::builder().http2_prior_knowledge().build().map_err(feed_error)?;
+            let mut response = client
+                .post(format!("{}/api.API/SubscribeNewTxsV2", self.url.trim_end_matches('/')))
+                .header("content-type", "application/grpc")
+                .header("te", "trailers")
+                .header("x-api-key", &self.api_key)
+                // One uncompressed, empty message
+                .body(vec![0u8; 5])
+                .send()
+                .await
+                .map_err(feed_error)?
+                .error_for_status()
+                .map_err(feed_error)?;
+
+            let (ready_tx, ready_rx) = oneshot::channel();
+            let (bodies_tx, bodies_rx) = mpsc::channel(PENDING_BUFFER);
+            let _ = ready_tx.send(Ok(()));
+
+            let task = tokio::spawn(async move {
+                let mut buffer = Vec::new();
+                let mut dropped = 0u64;
+                while let Ok(Some(chunk)) = response.chunk().await {
+                    buffer.extend_from_slice(&chunk);
+                    // gRPC frames: a compression flag, a big-endian length,
+                    // then the message
+                    while buffer.len() >= 5 {
+                        let length = u32::from_be_bytes([buffer[1], buffer[2], buffer[3], buffer[4]]) as usize;
+                        if buffer.len() < 5 + length {
+                            break;
+                        }
+                        let frame: Vec<u8> = buffer.drain(..5 + length).skip(5).collect();
+                        let Some(tx) = decode_fiber_tx(&frame) else { continue };
+                        if filter(&tx) && !forward(&bodies_tx, tx, &mut dropped) {
+                            return;
+                        }
+                    }
+                }
+            });
+
+            receive(task, ready_rx, bodies_rx).await
+        })
+    }
+}
+
+// Flashbots MEV-Share's server-sent event stream of hinted private
+// transactions, which never reach the public mempool. Hints sharing a
+// target and calldata become transactions without sender, value or gas
+// price; hints without calldata can't be decoded and are skipped.
+pub struct MevShareSource {
+    pub url: String,
+}
+
+impl MempoolSource for MevShareSource {
+    fn name(&self) -> &str {
+        "mev_share"
+    }
+
+    fn subscribe(&self, filter: PendingFilter) -> BoxFuture<'_, Result<BoxStream<'_, Transaction>>> {
+        Box::pin(async move {
+            let mut response = reqwest::Client::new()
+                .get(&self.url)
+                .header("accept", "text/event-stream")
+                .send()
+                .await
+                .map_err(feed_error)?
+                .error_for_status()
+                .map_err(feed_error)?;
+
+            let (ready_tx, ready_rx) = oneshot::channel();
+            let (bodies_tx, bodies_rx) = mpsc::channel(PENDING_BUFFER);
+            let _ = ready_tx.send(Ok(()));
+
+            let task = tokio::spawn(async move {
+                let mut buffer = String::new();
+                let mut dropped = 0u64;
+                while let Ok(Some(chunk)) = response.chunk().await {
+                    buffer.push_str(&String::from_utf8_lossy(&chunk));
+                    while let Some(end) = buffer.find('\n') {
+                        let line: String = buffer.drain(..=end).collect();
+                        let Some(data) = line.trim_end().strip_prefix("data:") else { continue };
+                        let Ok(event) = serde_json::from_str::<Value>(data.trim()) else { continue };
+                        for tx in hinted_txs(&event) {
+                            if filter(&tx) && !forward(&bodies_tx, tx, &mut dropped) {
+                                return;
+                            }
+                        }
+                    }
+                }
+            });
+
+            receive(task, ready_rx, bodies_rx).await
+        })
+    }
+}
+
+// Transactions of a MEV-Share event that hint both their target and calldata
+fn hinted_txs(event: &Value) -> Vec<Transaction> {
+    let Some(hash) = event.get("hash").and_then(|hash| serde_json::from_value(hash.clone()).ok()) else {
+        return Vec::new();
+    };
+    let Some(txs) = event.get("txs").and_then(Value::as_array) else { return Vec::new() };
+    txs.iter()
+        .filter_map(|hint| {
+            let to = serde_json::from_value(hint.get("to")?.clone()).ok()?;
+            let input = serde_json::from_value(hint.get("callData")?.clone()).ok()?;
+            Some(Transaction { hash, to: Some(to), input, ..Default::default() })
+        })
+        .collect()
+}
+
+// Reads the transaction out of a Fiber TransactionWithSenderMsg
+fn decode_fiber_tx(message: &[u8]) -> Option<Transaction> {
+    let mut rlp = None;
+    let mut sender = None;
+    let mut rest = message;
+    while !rest.is_empty() {
+        let (key, after) = read_varint(rest)?;
+        // Only length-delimited fields are expected
+        if key & 7 != 2 {
+            return None;
+        }
+        let (length, after) = read_varint(after)?;
+        let length = usize::try_from(length).ok()?;
+        let value = after.get(..length)?;
+        match key >> 3 {
+            1 => rlp = Some(value),
+            2 => sender = Some(value),
+            _ => {}
+        }
+        rest = &after[length..];
+    }
+
+    let mut tx: Transaction = Rlp::new(rlp?).as_val().ok()?;
+    match sender.filter(|sender| sender.len() == 20) {
+        Some(sender) => tx.from = Address::from_slice(sender),
+        None => {
+            tx.recover_from_mut().ok()?;
+        }
+    }
+    Some(tx)
+}
+
+// A protobuf varint and the bytes after it
+fn read_varint(bytes: &[u8]) -> Option<(u64, &[u8])> {
+    let mut value = 0u64;
+    for (i, byte) in bytes.iter().enumerate().take(10) {
+        value |= u64::from(byte & 0x7f) << (7 * i);
+        if byte & 0x80 == 0 {
+            return Some((value, &bytes[i + 1..]));
+        }
+    }
+    None
+}
+
+fn feed_error(error: impl std::fmt::Display) -> MktmkrError {
+    MktmkrError::Provider(ProviderError::CustomError(error.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::{
+        signers::{LocalWallet, Signer},
+        types::{transaction::eip2718::TypedTransaction, TransactionRequest, U256},
+        utils::keccak256,
+    };
+
+    // A signed legacy transfer and the address that signed it
+    fn signed_tx() -> (Vec<u8>, Address) {
+        let wallet: LocalWallet = "4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318".parse().unwrap();
+        let tx: TypedTransaction = TransactionRequest::new()
+            .to(Address::repeat_byte(1))
+            .value(U256::exp10(18))
+            .nonce(7)
+            .gas(21_000)
+            .gas_price(U256::exp10(9))
+            .chain_id(1)
+            .into();
+        let signature = wallet.sign_transaction_sync(&tx).unwrap();
+        (tx.rlp_signed(&signature).to_vec(), wallet.address())
+    }
+
+    // A length-delimited protobuf field
+    fn field(number: u8, value: &[u8]) -> Vec<u8> {
+        let mut field = vec![number << 3 | 2];
+        let mut length = value.len();
+        while length >= 0x80 {
+            field.push(length as u8 | 0x80);
+            length >>= 7;
+        }
+        field.push(length as u8);
+        field.extend_from_slice(value);
+        field
+    }
+
+    #[test]
+    fn reads_varints_at_their_bounds() {
+        assert_eq!(read_varint(&[0x00, 0xaa]), Some((0, &[0xaa][..])));
+        assert_eq!(read_varint(&[0x7f]), Some((127, &[][..])));
+        assert_eq!(read_varint(&[0xac, 0x02]), Some((300, &[][..])));
+        let max = [0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01];
+        assert_eq!(read_varint(&max), Some((u64::MAX, &[][..])));
+    }
+
+    #[test]
+    fn rejects_empty_unterminated_and_overlong_varints() {
+        assert_eq!(read_varint(&[]), None);
+        assert_eq!(read_varint(&[0x80, 0x80]), None);
+        assert_eq!(read_varint(&[0x80; 10]), None);
+        assert_eq!(read_varint(&[0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x00]), None);
+    }
+
+    #[test]
+    fn decodes_a_fiber_transaction_with_its_sender() {
+        let (rlp, signer) = signed_tx();
+        let sender = Address::repeat_byte(2);
+        let message = [field(1, &rlp), field(2, sender.as_bytes())].concat();
+        let tx = decode_fiber_tx(&message).unwrap();
+        assert_eq!(tx.hash, keccak256(&rlp).into());
+        assert_eq!(tx.to, Some(Address::repeat_byte(1)));
+        assert_eq!(tx.nonce, U256::from(7));
+        // The sender sent along is taken over the signature's
+        assert_eq!(tx.from, sender);
+        assert_ne!(tx.from, signer);
+    }
+
+    #[test]
+    fn recovers_the_sender_when_missing_or_malformed() {
+        let (rlp, signer) = signed_tx();
+        assert_eq!(decode_fiber_tx(&field(1, &rlp)).unwrap().from, signer);
+        let message = [field(1, &rlp), field(2, &[2; 19])].concat();
+        assert_eq!(decode_fiber_tx(&message).unwrap().from, signer);
+        // Unknown fields are skipped
+        let message = [field(3, b"ignored"), field(1, &rlp)].concat();
+        assert_eq!(decode_fiber_tx(&message).unwrap().from, signer);
+    }
+
+    #[test]
+    fn rejects_malformed_fiber_messages() {
+        let (rlp, _) = signed_tx();
+        assert_eq!(decode_fiber_tx(&[]), None);
+        assert_eq!(decode_fiber_tx(&field(2, Address::zero().as_bytes())), None);
+        // A varint field where only length-delimited ones are expected
+        assert_eq!(decode_fiber_tx(&[0x08, 0x01]), None);
+        let message = field(1, &rlp);
+        assert_eq!(decode_fiber_tx(&message[..message.len() - 1]), None);
+        assert_eq!(decode_fiber_tx(&field(1, &[0xc0])), None);
+    }
+}
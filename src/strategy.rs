@@ -0,0 +1,109 @@
+use crate::bot::TradingBot;
+use crate::error::Result;
+use crate::trigger::{Seen, TriggerMode};
+use ethers::types::{Address, H256, U256};
+use futures_util::future::BoxFuture;
+use serde::Deserialize;
+
+// Which built-in strategy the config selects
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StrategyKind {
+    #[default]
+    SellIntoBuys,
+    MarketMaking,
+    Grid,
+    Momentum,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    Buy,
+    Sell,
+}
+
+// A swap of one of our tokens by someone else, decoded from a pending
+// transaction or, with the swap_logs trigger, from a confirmed Swap log.
+// Buys have passed the bot's filters and replacement accounting already.
+#[derive(Debug, Clone, Copy)]
+pub struct Trade {
+    // None for buys read from Swap logs
+    pub hash: Option<H256>,
+    pub token: Address,
+    pub side: Side,
+    // ETH spent on a buy, or the ETH value of a sell at spot
+    pub eth: U256,
+    // Tokens sold, for sells
+    pub amount: Option<U256>,
+    // Still pending rather than included
+    pub pending: bool,
+    // When it was seen; buys held back by a cooldown keep their first
+    pub seen: Seen,
+}
+
+// One of our own swaps, once confirmed (or simulated in paper mode)
+#[derive(Debug, Clone, Copy)]
+pub struct Filled {
+    pub token: Address,
+    pub side: Side,
+    // Tokens sold or bought
+    pub amount: U256,
+    // ETH received or spent
+    pub eth: U256,
+    pub fee: U256,
+}
+
+// Decides what the bot trades. The bot decodes the mempool, follows blocks
+// and executes orders; a strategy turns those events into orders, so new
+// ones plug in without touching that plumbing.
+pub trait Strategy: Send + Sync {
+    // Short name for logs
+    fn name(&self) -> &str;
+
+    // Whether others' sells are decoded and passed on too, which costs a
+    // valuation per sell
+    fn wants_sells(&self) -> bool {
+        false
+    }
+
+    fn on_pending_tx<'a>(&'a self, bot: &'a TradingBot, trade: Trade) -> BoxFuture<'a, Result<()>>;
+
+    fn on_block<'a>(&'a self, _bot: &'a TradingBot, _block: H256) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async { Ok(()) })
+    }
+
+    fn on_fill<'a>(&'a self, _bot: &'a TradingBot, _fill: Filled) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async { Ok(()) })
+    }
+}
+
+// The default: sells sell_percentage of each buy back into it, as soon as
+// it's seen or once it's included depending on the trigger mode
+pub struct SellIntoBuys;
+
+impl Strategy for SellIntoBuys {
+    fn name(&self) -> &str {
+        "sell_into_buys"
+    }
+
+    fn on_pending_tx<'a>(&'a self, bot: &'a TradingBot, trade: Trade) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            if trade.side != Side::Buy {
+                return Ok(());
+            }
+            match (trade.pending, trade.hash) {
+                (true, Some(hash)) => bot.trigger_sell(hash, trade.token, trade.eth, trade.seen).await,
+                _ => bot.enqueue_sell(trade.hash, trade.token, trade.eth, false, Some(trade.seen)).await,
+            }
+        })
+    }
+
+    fn on_block<'a>(&'a self, bot: &'a TradingBot, block: H256) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            match bot.trigger {
+                TriggerMode::Confirmed => bot.sell_into_included(block).await,
+                TriggerMode::Mempool | TriggerMode::SwapLogs => Ok(()),
+            }
+        })
+    }
+}
@@ -0,0 +1,187 @@
+use crate::bot::TradingBot;
+use crate::builder::TradingBotBuilder;
+use crate::report::ConsolidatedReport;
+use crate::shutdown::Shutdown;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+#[derive(Debug, Clone)]
+pub enum MarketStatus {
+    Starting,
+    Running { since: Instant },
+    Restarting { attempt: u32, error: String },
+    Finished,
+    Failed { error: String },
+}
+
+// Runs one TradingBot per market in its own task, so a crash or panic in one
+// market is contained and retried with exponential backoff
+pub struct Supervisor {
+    markets: Vec<(String, TradingBotBuilder)>,
+    max_restarts: u32,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    shutdown: Shutdown,
+    status: Arc<Mutex<HashMap<String, MarketStatus>>>,
+    // Each market's latest bot, kept after it finishes for the final report
+    bots: Arc<Mutex<HashMap<String, Arc<TradingBot>>>>,
+}
+
+impl Default for Supervisor {
+    fn default() -> Self {
+        Self {
+            markets: Vec::new(),
+            max_restarts: 5,
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(60),
+            shutdown: Shutdown::default(),
+            status: Arc::new(Mutex::new(HashMap::new())),
+            bots: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+}
+
+impl Supervisor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Bots are given the supervisor's shutdown signal
+    pub fn market(mut self, name: impl Into<String>, builder: TradingBotBuilder) -> Self {
+        self.markets.push((name.into(), builder));
+        self
+    }
+
+    // Stops restarts and, through each bot, the markets themselves
+    pub fn shutdown(mut self, shutdown: Shutdown) -> Self {
+        self.shutdown = shutdown;
+        self
+    }
+
+    pub fn max_restarts(mut self, max_restarts: u32) -> Self {
+        self.max_restarts = max_restarts;
+        self
+    }
+
+    pub fn backoff(mut self, initial: Duration, max: Duration) -> Self {
+        self.initial_backoff = initial;
+        self.max_backoff = max;
+        self
+    }
+
+    pub async fn status(&self) -> HashMap<String, MarketStatus> {
+        self.status.lock().await.clone()
+    }
+
+    // Every market's sells so far, and its wallet's inventory, keyed by
+    // token and chain. Markets that crashed count from their latest restart.
+    pub async fn consolidated(&self) -> ConsolidatedReport {
+        let bots: Vec<_> = self.bots.lock().await.iter().map(|(name, bot)| (name.clone(), bot.clone())).collect();
+        let mut report = ConsolidatedReport::default();
+        for (name, bot) in bots {
+            report.add(&name, &bot.report().await);
+        }
+        report
+    }
+
+    // Runs every market to completion and returns their final statuses
+    pub async fn run(&self) -> HashMap<String, MarketStatus> {
+        let handles: Vec<_> = self.markets
+            .iter()
+            .map(|(name, builder)| {
+                tokio::spawn(supervise(
+                    name.clone(),
+                    builder.clone().shutdown(self.shutdown.clone()),
+                    self.status.clone(),
+                    self.bots.clone(),
+                    self.shutdown.clone(),
+                    self.max_restarts,
+                    (self.initial_backoff, self.max_backoff),
+                ))
+            })
+            .collect();
+
+        for handle in handles {
+            // supervise() never panics itself; bot panics are caught inside it
+            let _ = handle.await;
+        }
+
+        self.status().await
+    }
+}
+
+async fn supervise(
+    name: String,
+    builder: TradingBotBuilder,
+    status: Arc<Mutex<HashMap<String, MarketStatus>>>,
+    bots: Arc<Mutex<HashMap<String, Arc<TradingBot>>>>,
+    mut shutdown: Shutdown,
+    max_restarts: u32,
+    // Initial and longest wait between restarts
+    (initial_backoff, max_backoff): (Duration, Duration),
+) {
+    let mut attempt = 0;
+    let mut backoff = initial_backoff;
+
+    loop {
+        status.lock().await.insert(name.clone(), MarketStatus::Starting);
+
+        let task_status = status.clone();
+        let task_name = name.clone();
+        let task_builder = builder.clone();
+        let task_bots = bots.clone();
+        let result = tokio::spawn(async move {
+            let bot = Arc::new(task_builder.build().await?);
+            task_bots.lock().await.insert(task_name.clone(), bot.clone());
+            task_status.lock().await.insert(task_name, MarketStatus::Running { since: Instant::now() });
+            bot.run().await
+        })
+        .await;
+
+        let error = match result {
+            Ok(Ok(())) => {
+                println!("[{}] session finished", name);
+                status.lock().await.insert(name, MarketStatus::Finished);
+                return;
+            }
+            Ok(Err(e)) => e.to_string(),
+            Err(e) if e.is_panic() => format!("panicked: {}", panic_message(e.into_panic())),
+            Err(e) => e.to_string(),
+        };
+
+        attempt += 1;
+        if shutdown.is_triggered() {
+            println!("[{}] stopped during shutdown: {}", name, error);
+            status.lock().await.insert(name, MarketStatus::Failed { error });
+            return;
+        }
+        if attempt > max_restarts {
+            println!("[{}] giving up after {} restarts: {}", name, max_restarts, error);
+            status.lock().await.insert(name, MarketStatus::Failed { error });
+            return;
+        }
+
+        println!("[{}] crashed ({}), restarting in {:?}", name, error, backoff);
+        status.lock().await.insert(name.clone(), MarketStatus::Restarting { attempt, error });
+        tokio::select! {
+            _ = tokio::time::sleep(backoff) => {}
+            _ = shutdown.wait() => {
+                status.lock().await.insert(name, MarketStatus::Finished);
+                return;
+            }
+        }
+        backoff = (backoff * 2).min(max_backoff);
+    }
+}
+
+fn panic_message(payload: Box<dyn std::any::Any + Send>) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "unknown panic".to_string()
+    }
+}
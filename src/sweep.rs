@@ -0,0 +1,59 @@
+use crate::bot::TradingBot;
+use crate::error::Result;
+use crate::fees::GasBid;
+use ethers::{
+    prelude::*,
+    types::{Address, BlockNumber, U256},
+    utils::format_ether,
+};
+
+// Gas of a plain ETH transfer
+const TRANSFER_GAS: u64 = 21_000;
+
+// Moving proceeds out of the hot wallet when a session ends
+#[derive(Debug, Clone, Copy)]
+pub struct Sweep {
+    // Cold or treasury address receiving the proceeds
+    pub to: Address,
+    // ETH left in the hot wallet for gas
+    pub float: U256,
+}
+
+impl TradingBot {
+    // Sends the wallet's ETH above the float, less the transfer's own fee, to
+    // the sweep address. The balance is read at the pending block so a WETH
+    // unwrap just sent is counted.
+    pub(crate) async fn sweep_proceeds(&self) -> Result<()> {
+        let Some(sweep) = self.sweep else { return Ok(()) };
+        if self.dry_run || self.paper.is_some() {
+            return Ok(());
+        }
+        let balance = self.provider.get_balance(self.wallet.address(), Some(BlockNumber::Pending.into())).await?;
+        let bid = self.approval_bid().await?;
+        let price = match bid {
+            GasBid::Legacy(gas_price) => gas_price.unwrap_or(self.provider.get_gas_price().await?),
+            GasBid::Eip1559 { max_fee, .. } => max_fee,
+        };
+        let fee = price * TRANSFER_GAS;
+        let amount = balance.saturating_sub(sweep.float).saturating_sub(fee);
+        if amount.is_zero() {
+            return Ok(());
+        }
+
+        let mut tx = bid.transaction(self.wallet.address(), sweep.to, Bytes::default());
+        tx.set_value(amount);
+        tx.set_gas(TRANSFER_GAS);
+        let nonce = self.next_nonce().await?;
+        tx.set_nonce(nonce);
+        match self.sign_and_send(&mut tx).await {
+            Ok(tx_hash) => {
+                println!("Swept {} ETH to {:?} in {}", format_ether(amount), sweep.to, self.tx_link(tx_hash));
+                Ok(())
+            }
+            Err(e) => {
+                self.nonces.lock().await.release(nonce);
+                Err(e)
+            }
+        }
+    }
+}
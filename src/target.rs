@@ -0,0 +1,219 @@
+use crate::abi::{IUniswapV2Router02, IERC20};
+use crate::bot::{TokenTarget, TradingBot};
+use crate::chainlink::eth_to_usd;
+use crate::error::{MktmkrError, Result};
+use ethers::{
+    signers::Signer,
+    types::{Address, U256},
+    utils::{format_ether, format_units},
+};
+use std::fmt;
+use std::sync::Arc;
+
+// What a token's session is working towards
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Target {
+    // Wei of ETH proceeds
+    EthReceived(U256),
+    // Raw token units sold
+    TokensSold(U256),
+    // Dollar value of the ETH proceeds, priced through the USD stablecoin
+    // pool, or the stablecoin received when sells pay it out
+    UsdReceived(f64),
+}
+
+impl fmt::Display for Target {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Target::EthReceived(wei) => write!(f, "{} ETH received", format_ether(*wei)),
+            Target::TokensSold(amount) => write!(f, "{} tokens sold", amount),
+            Target::UsdReceived(usd) => write!(f, "${} received", usd),
+        }
+    }
+}
+
+// What happens to a token once its target is reached
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OnTarget {
+    // Stop selling the token
+    Stop,
+    // Keep selling at this smaller percentage of each buy
+    Reduce(f64),
+    // Sell the remaining inventory through the limit queue, then stop
+    Sweep,
+    // Stop selling until the price dips below the cycle's average sell
+    // price, buy back, then sell towards the target again
+    BuyBack(BuyBack),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BuyBack {
+    // How far below the average sell price the pool must fall
+    pub dip_percentage: f64,
+    // Share of the cycle's ETH proceeds spent on the buy-back
+    pub spend_percentage: f64,
+}
+
+impl fmt::Display for OnTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OnTarget::Stop => write!(f, "stop"),
+            OnTarget::Reduce(sell_percentage) => write!(f, "reduce to {}%", sell_percentage),
+            OnTarget::Sweep => write!(f, "sweep"),
+            OnTarget::BuyBack(buy_back) => write!(
+                f,
+                "buy back with {}% of proceeds {}% below the average sell price",
+                buy_back.spend_percentage, buy_back.dip_percentage
+            ),
+        }
+    }
+}
+
+// A token's (tokens sold, ETH received, stablecoin received) so far
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Progress {
+    pub tokens_sold: U256,
+    pub eth_received: U256,
+    pub stable_received: U256,
+}
+
+impl Progress {
+    // What was made after `start`
+    pub fn since(&self, start: &Progress) -> Progress {
+        Progress {
+            tokens_sold: self.tokens_sold.saturating_sub(start.tokens_sold),
+            eth_received: self.eth_received.saturating_sub(start.eth_received),
+            stable_received: self.stable_received.saturating_sub(start.stable_received),
+        }
+    }
+}
+
+impl TradingBot {
+    // Confirmed proceeds, or virtual ones in paper-trading mode
+    pub async fn progress(&self, token: Address) -> Progress {
+        if let Some(book) = &self.paper {
+            let book = book.lock().await;
+            let position = book.positions.get(&token).cloned().unwrap_or_default();
+            return Progress {
+                tokens_sold: position.tokens_sold,
+                eth_received: position.eth_received,
+                stable_received: U256::zero(),
+            };
+        }
+        let report = self.report.lock().await.token(token);
+        Progress {
+            tokens_sold: report.tokens_sold,
+            eth_received: report.eth_received,
+            stable_received: report.stable_received,
+        }
+    }
+
+    // Progress since the token's last buy-back, or the whole session
+    pub async fn cycle_progress(&self, token: Address) -> Progress {
+        let start = self.cycle_start.lock().await.get(&token).copied().unwrap_or_default();
+        self.progress(token).await.since(&start)
+    }
+
+    pub async fn target_reached(&self, token: Address) -> Result<bool> {
+        let Some(target) = self.token(token).map(|t| t.target) else { return Ok(false) };
+        let progress = self.cycle_progress(token).await;
+
+        Ok(match target {
+            Target::EthReceived(wei) => progress.eth_received >= wei,
+            Target::TokensSold(amount) => progress.tokens_sold >= amount,
+            // Paper trades always pay out ETH
+            Target::UsdReceived(usd) => match self.stable.filter(|_| self.paper.is_none()) {
+                Some(stable) => {
+                    let received = format_units(progress.stable_received, u32::from(self.metadata(stable).decimals))
+                        .map_err(|e| MktmkrError::Contract(format!("USD proceeds: {}", e)))?;
+                    received.parse::<f64>().unwrap_or_default() >= usd
+                }
+                None => self.usd_value(progress.eth_received).await? >= usd,
+            },
+        })
+    }
+
+    // Runs the token's post-target action the first time its target is met
+    pub(crate) async fn check_target(&self, token: Address) -> Result<()> {
+        if self.reached.lock().await.contains(&token) || !self.target_reached(token).await? {
+            return Ok(());
+        }
+        let Some(target) = self.token(token) else { return Ok(()) };
+        self.reached.lock().await.insert(token);
+        println!("Target reached for {:?}: {}; {}", token, target.target, target.on_target);
+
+        match target.on_target {
+            OnTarget::Stop => self.throttle.lock().await.clear(token),
+            OnTarget::Reduce(_) | OnTarget::BuyBack(_) => {}
+            OnTarget::Sweep => {
+                let inventory = self.inventory(token).await?;
+                self.throttle.lock().await.enqueue(token, inventory);
+                println!("Sweeping {}", self.format_amount(token, inventory));
+            }
+        }
+        Ok(())
+    }
+
+    // None once the token's target is met; otherwise the percentage of each
+    // buy to sell back
+    pub(crate) async fn sell_percentage(&self, target: &TokenTarget) -> Option<f64> {
+        if !self.reached.lock().await.contains(&target.address) {
+            return Some(target.sell_percentage);
+        }
+        match target.on_target {
+            OnTarget::Reduce(sell_percentage) => Some(sell_percentage),
+            OnTarget::Stop | OnTarget::Sweep | OnTarget::BuyBack(_) => None,
+        }
+    }
+
+    // True once every token has reached its target and has nothing left to do
+    pub(crate) async fn targets_done(&self) -> bool {
+        let reached = self.reached.lock().await;
+        let throttle = self.throttle.lock().await;
+        self.tokens().iter().all(|token| {
+            reached.contains(&token.address)
+                && match token.on_target {
+                    OnTarget::Stop => true,
+                    OnTarget::Reduce(_) | OnTarget::BuyBack(_) => false,
+                    OnTarget::Sweep => throttle.queued(token.address).is_zero(),
+                }
+        })
+    }
+
+    // Unsold tokens: the virtual inventory when paper trading, otherwise the
+    // wallet balance
+    pub(crate) async fn inventory(&self, token: Address) -> Result<U256> {
+        if let Some(book) = &self.paper {
+            return Ok(book.lock().await.positions.get(&token).map(|p| p.inventory).unwrap_or_default());
+        }
+        let balance = IERC20::new(token, Arc::new(self.provider.clone()))
+            .balance_of(self.wallet.address())
+            .call()
+            .await?;
+        Ok(balance)
+    }
+
+    // Prices `wei` at the Chainlink ETH/USD price, or by quoting it into
+    // the USD stablecoin
+    async fn usd_value(&self, wei: U256) -> Result<f64> {
+        if let Some(eth_usd) = self.eth_usd().await? {
+            return Ok(eth_to_usd(wei, eth_usd));
+        }
+        let usd_token = self.usd_token.ok_or_else(|| {
+            MktmkrError::Config("a USD target needs a usd_token on this chain".into())
+        })?;
+        if wei.is_zero() {
+            return Ok(0.0);
+        }
+
+        let client = Arc::new(self.provider.clone());
+        let router = IUniswapV2Router02::new(self.router, client.clone());
+        let quote = router.get_amounts_out(wei, vec![self.weth, usd_token]).call().await?;
+        let decimals = IERC20::new(usd_token, client).decimals().call().await?;
+
+        let usd = quote.last().copied().unwrap_or_default();
+        let usd = format_units(usd, decimals as u32)
+            .map_err(|e| MktmkrError::Contract(format!("USD quote: {}", e)))?;
+        Ok(usd.parse().unwrap_or_default())
+    }
+}
@@ -0,0 +1,135 @@
+use crate::abi::{encode_function_data, IUniswapV2Router02};
+use crate::bot::TradingBot;
+use crate::error::Result;
+use crate::math::{apply_bps, less_bps, mul_div, BPS};
+use crate::routing::Venue;
+use ethers::{
+    abi::Token,
+    prelude::*,
+    types::{transaction::eip2718::TypedTransaction, Address, BlockNumber, TransactionRequest, U256},
+};
+use std::sync::Arc;
+
+// ETH the buy probe spends, and the sell probe's size in tokens of that worth
+const PROBE_WEI: u64 = 1_000_000_000_000_000;
+
+// Transfer taxes measured on a token's buys and sells, in basis points of
+// the amount moved
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct TokenTax {
+    pub buy_bps: u64,
+    pub sell_bps: u64,
+}
+
+impl TradingBot {
+    pub(crate) async fn tax(&self, token: Address) -> TokenTax {
+        self.taxes.lock().await.get(&token).copied().unwrap_or_default()
+    }
+
+    // Measures each token's buy and sell tax by simulating small swaps with
+    // eth_call and finding the highest amountOutMin they still clear. A
+    // probe that can't run (no balance, no allowance) leaves that side at
+    // zero.
+    pub(crate) async fn detect_taxes(&self) -> Result<()> {
+        // The probes swap through the V2 router
+        for token in self.tokens().into_iter().filter(|token| token.venue == Venue::V2) {
+            let token = token.address;
+            let tax = TokenTax {
+                buy_bps: self.probe_buy_tax(token).await?.unwrap_or_default(),
+                sell_bps: self.probe_sell_tax(token).await?.unwrap_or_default(),
+            };
+            if tax != TokenTax::default() {
+                println!(
+                    "{} charges a {}% buy tax and a {}% sell tax",
+                    self.metadata(token).symbol,
+                    tax.buy_bps as f64 / 100.0,
+                    tax.sell_bps as f64 / 100.0
+                );
+            }
+            self.taxes.lock().await.insert(token, tax);
+        }
+        Ok(())
+    }
+
+    async fn probe_buy_tax(&self, token: Address) -> Result<Option<u64>> {
+        let amount = U256::from(PROBE_WEI);
+        let router = IUniswapV2Router02::new(self.router, Arc::new(self.provider.clone()));
+        let quote = router.get_amounts_out(amount, vec![self.weth, token]).call().await?;
+        let quote = quote.last().copied().unwrap_or_default();
+        let deadline = self.swap_deadline_timestamp().await?;
+
+        let buy = |amount_out_min: U256| -> Result<TypedTransaction> {
+            let data = encode_function_data(
+                "swapExactETHForTokensSupportingFeeOnTransferTokens",
+                &[
+                    Token::Uint(amount_out_min),
+                    Token::Array(vec![Token::Address(self.weth), Token::Address(token)]),
+                    Token::Address(self.wallet.address()),
+                    Token::Uint(deadline),
+                ],
+            )?;
+            Ok(TransactionRequest::new().from(self.wallet.address()).to(self.router).value(amount).data(data).into())
+        };
+        self.probe_tax(quote, buy).await
+    }
+
+    async fn probe_sell_tax(&self, token: Address) -> Result<Option<u64>> {
+        let (token_reserve, weth_reserve) = self.reserves(token).await?;
+        let amount = mul_div(U256::from(PROBE_WEI), token_reserve, weth_reserve).min(self.inventory(token).await?);
+        if amount.is_zero() {
+            return Ok(None);
+        }
+        let router = IUniswapV2Router02::new(self.router, Arc::new(self.provider.clone()));
+        let quote = router.get_amounts_out(amount, vec![token, self.weth]).call().await?;
+        let quote = quote.last().copied().unwrap_or_default();
+        let deadline = self.swap_deadline_timestamp().await?;
+
+        let sell = |amount_out_min: U256| -> Result<TypedTransaction> {
+            let data = self.v2_sell_call(
+                "swapExactTokensForETHSupportingFeeOnTransferTokens",
+                amount,
+                amount_out_min,
+                &[token, self.weth],
+                deadline,
+            )?;
+            Ok(TransactionRequest::new().from(self.wallet.address()).to(self.router).data(data).into())
+        };
+        self.probe_tax(quote, sell).await
+    }
+
+    // Binary-searches the share of `quote` a swap still delivers; the rest,
+    // in basis points, is the tax. None if the swap fails even with no
+    // minimum.
+    async fn probe_tax(&self, quote: U256, swap: impl Fn(U256) -> Result<TypedTransaction>) -> Result<Option<u64>> {
+        if quote.is_zero() || !self.simulates(&swap(U256::zero())?).await? {
+            return Ok(None);
+        }
+        if self.simulates(&swap(quote)?).await? {
+            return Ok(Some(0));
+        }
+        let (mut cleared, mut failed) = (0, BPS);
+        while failed - cleared > 1 {
+            let mid = (cleared + failed) / 2;
+            match self.simulates(&swap(apply_bps(quote, mid))?).await? {
+                true => cleared = mid,
+                false => failed = mid,
+            }
+        }
+        Ok(Some(BPS - cleared))
+    }
+
+    // Whether the call goes through against the pending block; transport
+    // failures are errors, reverts are false
+    async fn simulates(&self, tx: &TypedTransaction) -> Result<bool> {
+        match self.provider.call(tx, Some(BlockNumber::Pending.into())).await {
+            Ok(_) => Ok(true),
+            Err(e) if RpcError::as_error_response(&e).is_some() => Ok(false),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+// What arrives of `amount` after `bps` of it is taxed
+pub(crate) fn after_tax(amount: U256, bps: u64) -> U256 {
+    less_bps(amount, bps)
+}
@@ -0,0 +1,143 @@
+use crate::bot::{TokenTarget, TradingBot};
+use crate::error::Result;
+use ethers::types::{Address, U256};
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+const WINDOW: Duration = Duration::from_secs(3600);
+
+// Enforces per-transaction and rolling hourly sell limits. Whatever a limit
+// holds back is queued and released in later slices.
+#[derive(Debug, Default)]
+pub struct SellThrottle {
+    // Sells counted against the hourly limit, oldest first
+    history: HashMap<Address, VecDeque<(Instant, U256)>>,
+    queued: HashMap<Address, U256>,
+}
+
+impl SellThrottle {
+    // Splits `desired` into what may be sold now and what must wait; the
+    // allowed part is counted immediately
+    pub fn reserve(&mut self, target: &TokenTarget, desired: U256, now: Instant) -> U256 {
+        let allowed = desired.min(self.available(target, now));
+        if desired > allowed {
+            *self.queued.entry(target.address).or_default() += desired - allowed;
+        }
+        self.record(target, allowed, now);
+        allowed
+    }
+
+    // Releases as much of the token's queue as the limits allow
+    pub fn release(&mut self, target: &TokenTarget, now: Instant) -> U256 {
+        let queued = self.queued(target.address);
+        let amount = queued.min(self.available(target, now));
+        if amount.is_zero() {
+            return amount;
+        }
+        self.queued.insert(target.address, queued - amount);
+        self.record(target, amount, now);
+        amount
+    }
+
+    // Uncounts up to `amount` of the token's most recent sells, for a
+    // reservation that wasn't sold
+    pub fn unreserve(&mut self, target: &TokenTarget, amount: U256) {
+        let Some(history) = self.history.get_mut(&target.address) else { return };
+        let mut left = amount;
+        while !left.is_zero() {
+            let Some((_, last)) = history.back_mut() else { break };
+            let taken = left.min(*last);
+            *last -= taken;
+            left -= taken;
+            if last.is_zero() {
+                history.pop_back();
+            }
+        }
+    }
+
+    // Queues `amount` without selling any of it now
+    pub fn enqueue(&mut self, token: Address, amount: U256) {
+        *self.queued.entry(token).or_default() += amount;
+    }
+
+    // Withdraws up to `amount` from the token's queue
+    pub fn dequeue(&mut self, token: Address, amount: U256) {
+        if let Some(queued) = self.queued.get_mut(&token) {
+            *queued = queued.saturating_sub(amount);
+        }
+    }
+
+    pub fn clear(&mut self, token: Address) {
+        self.queued.remove(&token);
+    }
+
+    pub fn queued(&self, token: Address) -> U256 {
+        self.queued.get(&token).copied().unwrap_or_default()
+    }
+
+    fn available(&mut self, target: &TokenTarget, now: Instant) -> U256 {
+        let mut available = target.max_sell_per_tx.unwrap_or(U256::MAX);
+        if let Some(max_per_hour) = target.max_sell_per_hour {
+            let history = self.history.entry(target.address).or_default();
+            while history.front().is_some_and(|(at, _)| now.duration_since(*at) >= WINDOW) {
+                history.pop_front();
+            }
+            let sold = history.iter().fold(U256::zero(), |acc, (_, amount)| acc + amount);
+            available = available.min(max_per_hour.saturating_sub(sold));
+        }
+        available
+    }
+
+    fn record(&mut self, target: &TokenTarget, amount: U256, now: Instant) {
+        if target.max_sell_per_hour.is_some() && !amount.is_zero() {
+            self.history.entry(target.address).or_default().push_back((now, amount));
+        }
+    }
+}
+
+impl TradingBot {
+    // Sells the slice of each token's queue that its limits currently allow
+    pub(crate) async fn drain_queued_sells(&self) -> Result<()> {
+        for target in self.tokens() {
+            let released = self.throttle.lock().await.release(&target, Instant::now());
+            if released.is_zero() {
+                continue;
+            }
+            let amount = match self.impact_limited(target.address, released).await {
+                Ok(amount) => amount,
+                Err(e) => {
+                    self.requeue(&target, released).await;
+                    if e.ends_session() {
+                        return Err(e);
+                    }
+                    println!("Queued sell of {} failed: {}", self.format_amount(target.address, released), e);
+                    continue;
+                }
+            };
+            if amount.is_zero() {
+                continue;
+            }
+            println!("Selling {} from the queue", self.format_amount(target.address, amount));
+            let sold = match self.paper {
+                Some(_) => self.paper_fill(target.address, amount, None).await,
+                None => self.dispose(target.address, amount).await,
+            };
+            if let Err(e) = sold {
+                self.requeue(&target, amount).await;
+                if e.ends_session() {
+                    return Err(e);
+                }
+                println!("Queued sell of {} failed: {}", self.format_amount(target.address, amount), e);
+            }
+        }
+        Ok(())
+    }
+
+    // Puts a released amount that wasn't sold back in the queue, no longer
+    // counted as sold
+    async fn requeue(&self, target: &TokenTarget, amount: U256) {
+        let mut throttle = self.throttle.lock().await;
+        throttle.unreserve(target, amount);
+        throttle.enqueue(target.address, amount);
+    }
+}
@@ -0,0 +1,151 @@
+use crate::bot::WS_RECONNECTS;
+use crate::error::Result;
+use async_trait::async_trait;
+use ethers::{
+    providers::{
+        Http, HttpClientError, Ipc, IpcError, JsonRpcClient, JsonRpcError, Middleware, Provider, ProviderError,
+        PubsubClient, RpcError, StreamExt, Ws, WsClientError,
+    },
+    types::{Block, Filter, Log, H256, U256},
+};
+use futures_util::stream::BoxStream;
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::value::RawValue;
+use std::fmt::Debug;
+use std::time::Duration;
+use thiserror::Error;
+
+// A node connection over websocket, IPC or HTTP, picked by the endpoint's
+// URL: ws:// and wss:// URLs, http:// and https:// URLs, or a socket path.
+// HTTP has no subscriptions, so streams over it are polled.
+#[derive(Debug, Clone)]
+pub enum Transport {
+    Ws(Ws),
+    Ipc(Ipc),
+    Http(Http),
+}
+
+#[derive(Debug, Error)]
+pub enum TransportError {
+    #[error(transparent)]
+    Ws(#[from] WsClientError),
+
+    #[error(transparent)]
+    Ipc(#[from] IpcError),
+
+    #[error(transparent)]
+    Http(#[from] HttpClientError),
+
+    #[error("subscriptions need a websocket or IPC endpoint")]
+    NoSubscriptions,
+}
+
+impl RpcError for TransportError {
+    fn as_error_response(&self) -> Option<&JsonRpcError> {
+        match self {
+            TransportError::Ws(e) => e.as_error_response(),
+            TransportError::Ipc(e) => e.as_error_response(),
+            TransportError::Http(e) => e.as_error_response(),
+            TransportError::NoSubscriptions => None,
+        }
+    }
+
+    fn as_serde_error(&self) -> Option<&serde_json::Error> {
+        match self {
+            TransportError::Ws(e) => e.as_serde_error(),
+            TransportError::Ipc(e) => e.as_serde_error(),
+            TransportError::Http(e) => e.as_serde_error(),
+            TransportError::NoSubscriptions => None,
+        }
+    }
+}
+
+impl From<TransportError> for ProviderError {
+    fn from(error: TransportError) -> Self {
+        ProviderError::JsonRpcClientError(Box::new(error))
+    }
+}
+
+#[async_trait]
+impl JsonRpcClient for Transport {
+    type Error = TransportError;
+
+    async fn request<T, R>(&self, method: &str, params: T) -> Result<R, TransportError>
+    where
+        T: Debug + Serialize + Send + Sync,
+        R: DeserializeOwned + Send,
+    {
+        Ok(match self {
+            Transport::Ws(ws) => ws.request(method, params).await?,
+            Transport::Ipc(ipc) => ipc.request(method, params).await?,
+            Transport::Http(http) => http.request(method, params).await?,
+        })
+    }
+}
+
+impl PubsubClient for Transport {
+    type NotificationStream = BoxStream<'static, Box<RawValue>>;
+
+    fn subscribe<T: Into<U256>>(&self, id: T) -> Result<Self::NotificationStream, TransportError> {
+        Ok(match self {
+            Transport::Ws(ws) => ws.subscribe(id)?.boxed(),
+            Transport::Ipc(ipc) => ipc.subscribe(id)?.boxed(),
+            Transport::Http(_) => return Err(TransportError::NoSubscriptions),
+        })
+    }
+
+    fn unsubscribe<T: Into<U256>>(&self, id: T) -> Result<(), TransportError> {
+        match self {
+            Transport::Ws(ws) => Ok(ws.unsubscribe(id)?),
+            Transport::Ipc(ipc) => Ok(ipc.unsubscribe(id)?),
+            Transport::Http(_) => Err(TransportError::NoSubscriptions),
+        }
+    }
+}
+
+impl Transport {
+    pub fn can_subscribe(&self) -> bool {
+        !matches!(self, Transport::Http(_))
+    }
+}
+
+// Connects to `url`; HTTP endpoints are polled every `poll_interval`
+pub async fn connect(url: &str, poll_interval: Duration) -> Result<Provider<Transport>> {
+    let transport = if url.starts_with("ws://") || url.starts_with("wss://") {
+        Transport::Ws(Ws::connect_with_reconnects(url, WS_RECONNECTS).await.map_err(|e| ProviderError::from(TransportError::from(e)))?)
+    } else if url.starts_with("http://") || url.starts_with("https://") {
+        Transport::Http(url.parse().map_err(|e| ProviderError::CustomError(format!("invalid URL {}: {}", url, e)))?)
+    } else {
+        Transport::Ipc(Ipc::connect(url).await.map_err(|e| ProviderError::from(TransportError::from(e)))?)
+    };
+    Ok(Provider::new(transport).interval(poll_interval))
+}
+
+// New block headers, pushed where the endpoint can subscribe and polled
+// otherwise
+pub(crate) async fn heads(provider: &Provider<Transport>) -> Result<BoxStream<'_, Block<H256>>, ProviderError> {
+    if provider.as_ref().can_subscribe() {
+        return Ok(provider.subscribe_blocks().await?.boxed());
+    }
+    Ok(provider
+        .watch_blocks()
+        .await?
+        .filter_map(move |hash| async move { provider.get_block(hash).await.ok().flatten() })
+        .boxed())
+}
+
+// Hashes of new pending transactions, pushed or polled like `heads`
+pub(crate) async fn pending_hashes(provider: &Provider<Transport>) -> Result<BoxStream<'_, H256>, ProviderError> {
+    if provider.as_ref().can_subscribe() {
+        return Ok(provider.subscribe_pending_txs().await?.boxed());
+    }
+    Ok(provider.watch_pending_transactions().await?.boxed())
+}
+
+// Logs matching `filter`, pushed or polled like `heads`
+pub(crate) async fn logs<'a>(provider: &'a Provider<Transport>, filter: &Filter) -> Result<BoxStream<'a, Log>, ProviderError> {
+    if provider.as_ref().can_subscribe() {
+        return Ok(provider.subscribe_logs(filter).await?.boxed());
+    }
+    Ok(provider.watch(filter).await?.boxed())
+}
@@ -0,0 +1,176 @@
+use crate::bot::TradingBot;
+use crate::error::Result;
+use crate::transport::logs;
+use crate::queue::SellOrder;
+use crate::routing::Venue;
+use ethers::{
+    providers::Middleware,
+    types::{Address, Filter, Log, H256, U256},
+    utils::keccak256,
+};
+use futures_util::stream::BoxStream;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+// Buys not included within this long are assumed dropped
+const AWAIT_TTL: Duration = Duration::from_secs(600);
+
+// When a detected buy triggers its sell
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TriggerMode {
+    // As soon as the buy is seen pending
+    #[default]
+    Mempool,
+    // Once the buy is included in a block without reverting
+    Confirmed,
+    // From our pairs' Swap logs instead of the mempool, for providers
+    // without pending-transaction subscriptions
+    SwapLogs,
+}
+
+// When a buy was seen, and the pool's spot price then, for re-checking
+// buys whose sell waited past buy_ttl
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Seen {
+    pub at: Instant,
+    pub spot: Option<U256>,
+}
+
+impl Seen {
+    pub fn now() -> Self {
+        Self { at: Instant::now(), spot: None }
+    }
+}
+
+// A buy whose sell waits for its inclusion
+#[derive(Debug, Clone)]
+pub(crate) struct AwaitingBuy {
+    pub token: Address,
+    pub buy_amount: U256,
+    pub seen_at: Instant,
+    // Pool spot price when the buy was seen, for re-checking stale buys
+    pub spot: Option<U256>,
+}
+
+impl TradingBot {
+    // Queues a sell into the buy now, or defers it until the buy is included
+    pub(crate) async fn trigger_sell(&self, tx_hash: H256, token: Address, buy_amount: U256, seen: Seen) -> Result<()> {
+        match self.trigger {
+            TriggerMode::Mempool => self.enqueue_sell(Some(tx_hash), token, buy_amount, true, Some(seen)).await,
+            TriggerMode::Confirmed | TriggerMode::SwapLogs => {
+                let spot = match seen.spot {
+                    Some(spot) => Some(spot),
+                    None => self.spot(token).await.ok(),
+                };
+                let buy = AwaitingBuy { token, buy_amount, seen_at: seen.at, spot };
+                self.awaiting.lock().await.insert(tx_hash, buy);
+                Ok(())
+            }
+        }
+    }
+
+    // Sells into awaited buys included in `block`, skipping reverted ones
+    pub(crate) async fn sell_into_included(&self, block: H256) -> Result<()> {
+        {
+            let mut awaiting = self.awaiting.lock().await;
+            awaiting.retain(|_, buy| buy.seen_at.elapsed() < AWAIT_TTL);
+            if awaiting.is_empty() {
+                return Ok(());
+            }
+        }
+        // Not held across the fetch, so new buys can be awaited meanwhile
+        let Some(block) = self.provider.get_block(block).await? else { return Ok(()) };
+        let included: Vec<_> = {
+            let mut awaiting = self.awaiting.lock().await;
+            block.transactions.iter().filter_map(|hash| awaiting.remove(hash).map(|buy| (*hash, buy))).collect()
+        };
+
+        for (hash, buy) in included {
+            let Some(receipt) = self.provider.get_transaction_receipt(hash).await?.filter(|r| r.status == Some(1u64.into())) else {
+                println!("Buy {:?} reverted; not selling into it", hash);
+                continue;
+            };
+            if let (Some(block_hash), Some(block_number)) = (receipt.block_hash, receipt.block_number) {
+                self.recent_buys.lock().await.insert(hash, (block_hash, block_number.as_u64()));
+            }
+            let seen = Seen { at: buy.seen_at, spot: buy.spot };
+            if buy.seen_at.elapsed() >= self.buy_ttl && !self.still_supported(buy.token, seen).await? {
+                println!("Buy {:?} lingered {:?} and the price has since fallen; not selling into it", hash, buy.seen_at.elapsed());
+                continue;
+            }
+            self.enqueue_sell(Some(hash), buy.token, buy.buy_amount, false, Some(seen)).await?;
+        }
+        Ok(())
+    }
+
+    // Whether a stale buy is still worth selling into: the pool's price
+    // hasn't dropped below where it stood when the buy was seen, so the
+    // demand it signalled hasn't been sold away meanwhile
+    async fn still_supported(&self, token: Address, seen: Seen) -> Result<bool> {
+        let Some(spot) = seen.spot else { return Ok(false) };
+        Ok(self.spot(token).await? >= spot)
+    }
+
+    // Drops the parts of an order answering buys that waited past buy_ttl,
+    // in the queue or a cooldown, while the price has fallen since
+    pub(crate) async fn drop_stale_parts(&self, order: &mut SellOrder) -> Result<()> {
+        let mut kept = Vec::with_capacity(order.parts.len());
+        for part in std::mem::take(&mut order.parts) {
+            let Some(seen) = part.seen.filter(|seen| seen.at.elapsed() >= self.buy_ttl) else {
+                kept.push(part);
+                continue;
+            };
+            if self.still_supported(order.token, seen).await? {
+                kept.push(part);
+                continue;
+            }
+            println!(
+                "Buy {:?} of {:?} waited {:?} and the price has since fallen; not selling into it",
+                part.buy, order.token, seen.at.elapsed()
+            );
+        }
+        order.parts = kept;
+        Ok(())
+    }
+
+    // Swap logs of each token's WETH pair, with the pairs mapped back to
+    // their tokens
+    pub(crate) async fn subscribe_swaps(&self) -> Result<(BoxStream<'_, Log>, HashMap<Address, Address>)> {
+        let mut pairs = HashMap::new();
+        // V3 pools log swaps differently, so tokens traded there aren't followed
+        for token in self.tokens().into_iter().filter(|token| token.venue == Venue::V2) {
+            pairs.insert(self.pair_for(token.address).await?, token.address);
+        }
+        let filter = Filter::new()
+            .address(pairs.keys().copied().collect::<Vec<_>>())
+            .topic0(H256::from(keccak256("Swap(address,uint256,uint256,uint256,uint256,address)")));
+        Ok((logs(&self.provider, &filter).await?, pairs))
+    }
+
+    // The token bought and ETH spent in a confirmed Swap log, if it buys one
+    // of ours with WETH. Logs dropped by a reorg are skipped.
+    pub(crate) async fn swap_buy(&self, pairs: &HashMap<Address, Address>, log: &Log) -> Option<(Address, U256)> {
+        if log.removed == Some(true) || log.data.len() != 128 {
+            return None;
+        }
+        let token = *pairs.get(&log.address)?;
+        if let Some(hash) = log.transaction_hash {
+            if !self.seen.lock().await.insert(hash, Instant::now()) {
+                return None;
+            }
+        }
+
+        // amount0In, amount1In, amount0Out, amount1Out; pairs sort their
+        // tokens by address
+        let amount = |i: usize| U256::from_big_endian(&log.data[i * 32..(i + 1) * 32]);
+        let (weth_in, token_out) = if token < self.weth { (amount(1), amount(2)) } else { (amount(0), amount(3)) };
+        if weth_in.is_zero() || token_out.is_zero() {
+            return None;
+        }
+
+        let min_buy_eth = self.token(token).map(|t| t.min_buy_eth).unwrap_or_default();
+        (weth_in >= min_buy_eth).then_some((token, weth_in))
+    }
+}
@@ -0,0 +1,127 @@
+use crate::bot::{TradingBot, SETTLE_TIMEOUT};
+use crate::error::{MktmkrError, Result};
+use crate::mempool::SCHEDULE_INTERVAL;
+use crate::shutdown;
+use ethers::types::{Address, U256};
+use std::time::{Duration, Instant};
+
+// Selling `amount` of a token in `slices` even slices spread over
+// `duration`, whatever buys come in
+#[derive(Debug, Clone, PartialEq)]
+pub struct Twap {
+    pub token: Address,
+    pub amount: U256,
+    pub duration: Duration,
+    pub slices: usize,
+}
+
+impl Twap {
+    // One slice a minute unless `slices` is given
+    pub fn new(token: Address, amount: U256, duration: Duration, slices: Option<usize>) -> Self {
+        let slices = slices.unwrap_or((duration.as_secs() / 60) as usize).max(1);
+        Self { token, amount, duration, slices }
+    }
+}
+
+// A TWAP under way: the slices left and when the next is due
+#[derive(Debug, Clone)]
+pub(crate) struct TwapSchedule {
+    pub twap: Twap,
+    pub next_at: Instant,
+    pub slices_left: usize,
+}
+
+impl TwapSchedule {
+    pub fn new(twap: Twap) -> Self {
+        Self { next_at: Instant::now(), slices_left: twap.slices, twap }
+    }
+
+    // The even share, with the last slice taking the rounding
+    fn slice(&self) -> U256 {
+        let slice = self.twap.amount / self.twap.slices;
+        match self.slices_left {
+            1 => self.twap.amount - slice * (self.twap.slices - 1),
+            _ => slice,
+        }
+    }
+}
+
+impl TradingBot {
+    // Sells each TWAP's slice that has come due. A slice that can't be
+    // sold in full (the wallet is short, or prices skip it) isn't made up
+    // later.
+    pub(crate) async fn sell_due_twap_slices(&self) -> Result<()> {
+        let now = Instant::now();
+        let due: Vec<_> = {
+            let mut twaps = self.twaps.lock().await;
+            let mut due = Vec::new();
+            for schedule in twaps.iter_mut().filter(|schedule| schedule.next_at <= now) {
+                due.push((schedule.twap.token, schedule.slice(), schedule.slices_left - 1));
+                schedule.slices_left -= 1;
+                schedule.next_at += schedule.twap.duration / schedule.twap.slices as u32;
+            }
+            twaps.retain(|schedule| schedule.slices_left > 0);
+            due
+        };
+
+        for (token, amount, left) in due {
+            println!("TWAP: selling {} ({} slices left)", self.format_amount(token, amount), left);
+            let sold = match self.paper {
+                Some(_) => self.paper_fill(token, amount, None).await,
+                None => self.dispose(token, amount).await,
+            };
+            // The deadline still ends a TWAP run on its own
+            if let Err(e) = sold {
+                if e.ends_session() || matches!(e, MktmkrError::DeadlineExpired) {
+                    return Err(e);
+                }
+                println!("TWAP slice of {} failed: {}", self.format_amount(token, amount), e);
+            }
+        }
+        Ok(())
+    }
+
+    // Runs a TWAP on its own, without watching for buys, then waits for its
+    // sells to confirm and prints the session summary
+    pub async fn run_twap(&self, twap: Twap) -> Result<()> {
+        self.target_for(twap.token)?;
+        self.prepare().await?;
+        println!(
+            "TWAP: selling {} in {} slices over {:?}",
+            self.format_amount(twap.token, twap.amount), twap.slices, twap.duration
+        );
+        self.twaps.lock().await.push(TwapSchedule::new(twap));
+
+        let (close, closing) = shutdown::channel();
+        let mut shutdown = self.shutdown.clone();
+        let slices = async {
+            let result = loop {
+                tokio::select! {
+                    _ = shutdown.wait() => break Ok(()),
+                    _ = tokio::time::sleep(SCHEDULE_INTERVAL) => {}
+                }
+                match self.sell_due_twap_slices().await {
+                    Err(MktmkrError::DeadlineExpired) => break Ok(()),
+                    Err(e) => break Err(e),
+                    Ok(()) if self.twaps.lock().await.is_empty() => break Ok(()),
+                    Ok(()) => {}
+                }
+            };
+            close.trigger();
+            result
+        };
+        tokio::try_join!(slices, self.watch_receipts(closing))?;
+
+        for schedule in self.twaps.lock().await.drain(..) {
+            println!("TWAP: {} slices left unsold", schedule.slices_left);
+        }
+        self.settle_in_flight(SETTLE_TIMEOUT).await?;
+        self.unwrap_weth(true).await?;
+        self.sweep_proceeds().await?;
+        print!("{}", self.report().await);
+        if let Some(book) = self.paper_report().await? {
+            print!("{}", book);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,100 @@
+use crate::abi::IUniversalRouterCalls;
+use crate::decode::{RouterSwap, SwapAmounts};
+use crate::v3::decode_path;
+use crate::v4::{decode_v4_swap, V4_SWAP};
+use ethers::{
+    abi::{decode, AbiDecode, ParamType, Token},
+    types::{Address, U256},
+};
+
+// Universal Router command types, taken from the low bits of each command
+// byte; the high bit only marks the command as allowed to revert
+const COMMAND_TYPE_MASK: u8 = 0x3f;
+const PERMIT2_TRANSFER_FROM: u8 = 0x02;
+const V3_SWAP_EXACT_IN: u8 = 0x00;
+const V3_SWAP_EXACT_OUT: u8 = 0x01;
+const V2_SWAP_EXACT_IN: u8 = 0x08;
+const V2_SWAP_EXACT_OUT: u8 = 0x09;
+const WRAP_ETH: u8 = 0x0b;
+
+// Amount meaning "everything the router currently holds"
+fn contract_balance() -> U256 {
+    U256::one() << 255
+}
+
+// Decodes the first Uniswap V2, V3 or V4 swap in a Universal Router `execute` call.
+// Input the router already holds (wrapped from the call's ETH, or pulled in
+// by a Permit2 transfer) is resolved from the commands before the swap.
+pub fn decode_universal_swap(input: &[u8]) -> Option<RouterSwap> {
+    let (commands, inputs, deadline) = match IUniversalRouterCalls::decode(input).ok()? {
+        IUniversalRouterCalls::ExecuteWithCommandsAndInputs(c) => (c.commands, c.inputs, c.deadline),
+        IUniversalRouterCalls::Execute(c) => (c.commands, c.inputs, U256::MAX),
+    };
+
+    // What the router holds for the next swap paid from its own balance:
+    // None for wrapped call value, Some for tokens transferred in
+    let mut held: Option<Option<U256>> = None;
+
+    for (command, input) in commands.iter().zip(inputs.iter()) {
+        match command & COMMAND_TYPE_MASK {
+            WRAP_ETH => held = Some(None),
+            V4_SWAP => {
+                if let Some(swap) = decode_v4_swap(input, deadline) {
+                    return Some(swap);
+                }
+            }
+            PERMIT2_TRANSFER_FROM => {
+                // (token, recipient, amount)
+                let tokens = decode(&[ParamType::Address, ParamType::Address, ParamType::Uint(160)], input).ok()?;
+                held = Some(tokens[2].clone().into_uint());
+            }
+            V2_SWAP_EXACT_IN | V2_SWAP_EXACT_OUT | V3_SWAP_EXACT_IN | V3_SWAP_EXACT_OUT => {
+                let kind = command & COMMAND_TYPE_MASK;
+                let (to, amount, limit, path, payer_is_user) = match kind {
+                    V3_SWAP_EXACT_IN | V3_SWAP_EXACT_OUT => decode_swap_input(input, ParamType::Bytes)?,
+                    _ => decode_swap_input(input, ParamType::Array(Box::new(ParamType::Address)))?,
+                };
+
+                // A swap paid from the router's balance spends what an earlier
+                // command put there; wrapped call value counts as paying ETH
+                let amounts = if matches!(kind, V2_SWAP_EXACT_IN | V3_SWAP_EXACT_IN) {
+                    let amount_in = match (amount == contract_balance(), held) {
+                        (false, _) => Some(amount),
+                        (true, Some(held)) => held,
+                        (true, None) => continue,
+                    };
+                    SwapAmounts::ExactIn { amount_in, amount_out_min: limit }
+                } else {
+                    SwapAmounts::ExactOut { amount_out: amount, amount_in_max: (payer_is_user || held != Some(None)).then_some(limit) }
+                };
+
+                if path.len() >= 2 {
+                    return Some(RouterSwap { amounts, path, to, deadline });
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+// A swap command's input: (recipient, amount, limit, path, payerIsUser).
+// V2 paths are address arrays; V3 paths are packed bytes, and run from the
+// output back to the input for exact-output swaps.
+fn decode_swap_input(input: &[u8], path_type: ParamType) -> Option<(Address, U256, U256, Vec<Address>, bool)> {
+    let tokens = decode(
+        &[ParamType::Address, ParamType::Uint(256), ParamType::Uint(256), path_type, ParamType::Bool],
+        input,
+    )
+    .ok()?;
+    let mut tokens = tokens.into_iter();
+    let to = tokens.next()?.into_address()?;
+    let amount = tokens.next()?.into_uint()?;
+    let limit = tokens.next()?.into_uint()?;
+    let path = match tokens.next()? {
+        Token::Bytes(path) => decode_path(&path)?,
+        path => path.into_array()?.into_iter().filter_map(Token::into_address).collect(),
+    };
+    let payer_is_user = tokens.next()?.into_bool()?;
+    Some((to, amount, limit, path, payer_is_user))
+}
@@ -0,0 +1,77 @@
+use crate::bot::{TradingBot, SETTLE_TIMEOUT};
+use crate::error::Result;
+use crate::math::{less_bps, mul_div};
+use crate::pool::get_amount_out;
+use ethers::types::{Address, U256};
+
+// Sells what's left of a token that missed its target once the session
+// deadline has passed
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Unwind {
+    // Remaining inventory is split into this many equal sells
+    pub chunks: u32,
+    // Largest accepted shortfall of a chunk's output against the spot price
+    pub max_slippage_bps: u32,
+}
+
+impl TradingBot {
+    pub(crate) async fn unwind_remaining(&self) -> Result<()> {
+        let Some(unwind) = self.unwind else { return Ok(()) };
+        for token in self.tokens() {
+            if !self.target_reached(token.address).await? {
+                self.unwind(token.address, unwind).await?;
+            }
+        }
+        Ok(())
+    }
+
+    // Sells one chunk at a time, waiting for each to confirm so the next is
+    // priced against the moved pool, and stops at the first chunk whose price
+    // impact exceeds the bound
+    async fn unwind(&self, token: Address, unwind: Unwind) -> Result<()> {
+        let Some(target) = self.token(token) else { return Ok(()) };
+        self.open_paper_position(token).await?;
+        let inventory = self.inventory(token).await?;
+        if inventory.is_zero() {
+            return Ok(());
+        }
+
+        let chunks = U256::from(unwind.chunks.max(1));
+        let chunk = (inventory + chunks - 1) / chunks;
+        println!("Unwinding {} in chunks of {}", self.format_amount(token, inventory), self.format_amount(token, chunk));
+
+        let mut remaining = inventory;
+        while !remaining.is_zero() {
+            if self.shutdown.is_triggered() {
+                break;
+            }
+            let amount = chunk.min(remaining);
+
+            let (token_reserve, weth_reserve) = self.reserves(token).await?;
+            if token_reserve.is_zero() {
+                break;
+            }
+            let expected = get_amount_out(amount, token_reserve, weth_reserve);
+            let at_spot = mul_div(amount, weth_reserve, token_reserve);
+            if expected < less_bps(at_spot, unwind.max_slippage_bps.into()) {
+                println!(
+                    "Stopping unwind of {:?} with {} left: price impact above {} bps",
+                    token, remaining, unwind.max_slippage_bps
+                );
+                break;
+            }
+
+            // A halved sell leaves its other half for the next chunk
+            let mut unsent = U256::zero();
+            if self.paper.is_some() {
+                self.paper_fill(token, amount, None).await?;
+            } else {
+                unsent = self.submit_sell(&target, amount, unwind.max_slippage_bps, None).await?;
+                self.settle_in_flight(SETTLE_TIMEOUT).await?;
+            }
+            remaining -= amount - unsent;
+        }
+
+        Ok(())
+    }
+}
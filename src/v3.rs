@@ -0,0 +1,397 @@
+use crate::abi::{
+    ExactInputSingleParams, IMulticallCalls, IQuoterV2, ISwapRouter02, IUniswapV3Factory, IUniswapV3Pool,
+    MulticallWithDeadlineCall, QuoteExactInputSingleParams,
+};
+use crate::bot::TradingBot;
+use crate::decode::{RouterSwap, SwapAmounts};
+use crate::error::{MktmkrError, Result};
+use crate::math::mul_div;
+use ethers::{
+    abi::{decode, AbiEncode, ParamType},
+    signers::Signer,
+    types::{Address, Bytes, U256},
+    utils::keccak256,
+};
+use futures_util::future::join_all;
+use std::sync::Arc;
+
+// Uniswap V3 fee tiers, in hundredths of a basis point
+pub const FEE_TIERS: &[u32] = &[100, 500, 3_000, 10_000];
+// PancakeSwap V3 swaps 0.3% for 0.25%
+pub const PANCAKE_FEE_TIERS: &[u32] = &[100, 500, 2_500, 10_000];
+
+// Uniswap V3 contracts or a fork sharing their interface: SwapRouter02
+// (PancakeSwap's SmartRouter) for sells, QuoterV2 for quotes
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct V3Venue {
+    // Names it in the trade log
+    pub name: &'static str,
+    pub router: Address,
+    pub quoter: Address,
+    pub fee_tiers: &'static [u32],
+}
+
+impl V3Venue {
+    // A Uniswap V3 deployment
+    pub fn uniswap(router: Address, quoter: Address) -> Self {
+        Self { name: "Uniswap V3", router, quoter, fee_tiers: FEE_TIERS }
+    }
+}
+
+// The V3 router functions that swap, by whether they swap exactly their
+// input and through a single pool
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Exact {
+    InputSingle,
+    Input,
+    OutputSingle,
+    Output,
+}
+
+impl Exact {
+    // SwapRouter02's parameters; the original SwapRouter adds a deadline
+    // after the recipient
+    fn params(self, deadline: bool) -> ParamType {
+        let single = matches!(self, Exact::InputSingle | Exact::OutputSingle);
+        let mut params = match single {
+            true => vec![ParamType::Address, ParamType::Address, ParamType::Uint(24), ParamType::Address],
+            false => vec![ParamType::Bytes, ParamType::Address],
+        };
+        if deadline {
+            params.push(ParamType::Uint(256));
+        }
+        params.extend([ParamType::Uint(256), ParamType::Uint(256)]);
+        if single {
+            params.push(ParamType::Uint(160));
+        }
+        ParamType::Tuple(params)
+    }
+
+    fn signature(self, deadline: bool) -> String {
+        let name = match self {
+            Exact::InputSingle => "exactInputSingle",
+            Exact::Input => "exactInput",
+            Exact::OutputSingle => "exactOutputSingle",
+            Exact::Output => "exactOutput",
+        };
+        let params = ethers::abi::param_type::Writer::write(&self.params(deadline));
+        format!("{}({})", name, params)
+    }
+}
+
+// Token addresses along a V3 path: each 20-byte address followed by a
+// 3-byte fee, ending on an address
+pub fn decode_path(path: &[u8]) -> Option<Vec<Address>> {
+    if path.len() < 43 || !(path.len() - 20).is_multiple_of(23) {
+        return None;
+    }
+    Some(path.chunks(23).map(|hop| Address::from_slice(&hop[..20])).collect())
+}
+
+// Decodes a swap on SwapRouter02 or the original V3 SwapRouter. ETH paid
+// in goes as WETH, so those swaps start from WETH with an explicit input.
+pub fn decode_v3_swap(input: &[u8]) -> Option<RouterSwap> {
+    let selector = input.get(..4)?;
+    let (swap, deadline) = [Exact::InputSingle, Exact::Input, Exact::OutputSingle, Exact::Output]
+        .into_iter()
+        .flat_map(|swap| [(swap, false), (swap, true)])
+        .find(|(swap, deadline)| keccak256(swap.signature(*deadline))[..4] == *selector)?;
+
+    let tokens = decode(&[swap.params(deadline)], &input[4..]).ok()?.pop()?.into_tuple()?;
+    let mut tokens = tokens.into_iter();
+    let path = match swap {
+        Exact::InputSingle | Exact::OutputSingle => {
+            let token_in = tokens.next()?.into_address()?;
+            let token_out = tokens.next()?.into_address()?;
+            tokens.next()?;
+            vec![token_in, token_out]
+        }
+        // Exact-output paths run from the output back to the input
+        Exact::Input => decode_path(&tokens.next()?.into_bytes()?)?,
+        Exact::Output => decode_path(&tokens.next()?.into_bytes()?)?.into_iter().rev().collect(),
+    };
+    let to = tokens.next()?.into_address()?;
+    let deadline = match deadline {
+        true => tokens.next()?.into_uint()?,
+        false => U256::MAX,
+    };
+    let amount = tokens.next()?.into_uint()?;
+    let limit = tokens.next()?.into_uint()?;
+
+    let amounts = match swap {
+        Exact::InputSingle | Exact::Input => {
+            SwapAmounts::ExactIn { amount_in: Some(amount), amount_out_min: limit }
+        }
+        Exact::OutputSingle | Exact::Output => {
+            SwapAmounts::ExactOut { amount_out: amount, amount_in_max: Some(limit) }
+        }
+    };
+    Some(RouterSwap { amounts, path, to, deadline })
+}
+
+// Virtual reserves of a V3 pool within its current tick: liquidity over
+// and times the square-root price
+pub fn virtual_reserves(sqrt_price_x96: U256, liquidity: U256) -> (U256, U256) {
+    if sqrt_price_x96.is_zero() {
+        return (U256::zero(), U256::zero());
+    }
+    let q96 = U256::one() << 96;
+    (mul_div(liquidity, q96, sqrt_price_x96), mul_div(liquidity, sqrt_price_x96, q96))
+}
+
+impl TradingBot {
+    fn v3_venue(&self) -> Result<V3Venue> {
+        self.v3.ok_or_else(|| MktmkrError::Config("no V3 router on this chain, set one explicitly".into()))
+    }
+
+    // The best QuoterV2 quote for selling `amount` of the token for WETH
+    // across the fee tiers, as (fee, WETH out, gas estimate); None when no
+    // pool quotes
+    pub(crate) async fn v3_quote(&self, token: Address, amount: U256) -> Result<Option<(u32, U256, U256)>> {
+        let v3 = self.v3_venue()?;
+        let quoter = IQuoterV2::new(v3.quoter, Arc::new(self.provider.clone()));
+        let calls: Vec<_> = v3
+            .fee_tiers
+            .iter()
+            .map(|fee| {
+                quoter.quote_exact_input_single(QuoteExactInputSingleParams {
+                    token_in: token,
+                    token_out: self.weth,
+                    amount_in: amount,
+                    fee: *fee,
+                    sqrt_price_limit_x96: U256::zero(),
+                })
+            })
+            .collect();
+        let quotes = join_all(calls.iter().map(|call| call.call())).await;
+        Ok(v3
+            .fee_tiers
+            .iter()
+            .copied()
+            .zip(quotes)
+            .filter_map(|(fee, quote)| {
+                let (amount_out, _, _, gas) = quote.ok()?;
+                Some((fee, amount_out, gas))
+            })
+            .max_by_key(|(_, amount_out, _)| *amount_out))
+    }
+
+    // Calldata selling `amount` through the `fee` pool under a deadline, or
+    // None if it doesn't encode. The router keeps the WETH and unwraps it to
+    // the wallet, unless proceeds stay in WETH.
+    pub(crate) fn v3_sell_call(
+        &self,
+        token: Address,
+        fee: u32,
+        amount: U256,
+        amount_out_min: U256,
+        deadline: U256,
+    ) -> Result<Option<Bytes>> {
+        let v3 = self.v3_venue()?;
+        let router = ISwapRouter02::new(v3.router, Arc::new(self.provider.clone()));
+        let wallet = self.wallet.address();
+        let recipient = if self.weth_proceeds.is_some() { wallet } else { v3.router };
+        let swap = router
+            .exact_input_single(ExactInputSingleParams {
+                token_in: token,
+                token_out: self.weth,
+                fee,
+                recipient,
+                amount_in: amount,
+                amount_out_minimum: amount_out_min,
+                sqrt_price_limit_x96: U256::zero(),
+            })
+            .calldata();
+        let mut data = vec![swap];
+        if recipient == v3.router {
+            data.push(router.unwrap_weth9(amount_out_min, wallet).calldata());
+        }
+        let Some(data) = data.into_iter().collect() else { return Ok(None) };
+        Ok(Some(IMulticallCalls::MulticallWithDeadline(MulticallWithDeadlineCall { deadline, data }).encode().into()))
+    }
+
+    // The token's deepest V3 WETH pool, looked up once
+    pub(crate) async fn v3_pool(&self, token: Address) -> Result<Address> {
+        if let Some(pool) = self.v3_pools.lock().await.get(&token) {
+            return Ok(*pool);
+        }
+
+        let v3 = self.v3_venue()?;
+        let client = Arc::new(self.provider.clone());
+        let factory = IQuoterV2::new(v3.quoter, client.clone()).factory().call().await?;
+        let factory = IUniswapV3Factory::new(factory, client.clone());
+        let mut deepest = None;
+        for &fee in v3.fee_tiers {
+            let pool = factory.get_pool(token, self.weth, fee).call().await?;
+            if pool.is_zero() {
+                continue;
+            }
+            let liquidity = IUniswapV3Pool::new(pool, client.clone()).liquidity().call().await?;
+            if deepest.is_none_or(|(_, deepest)| liquidity > deepest) {
+                deepest = Some((pool, liquidity));
+            }
+        }
+        let Some((pool, _)) = deepest else {
+            return Err(MktmkrError::Config(format!("no {:?}/WETH pool on {}", token, v3.name)));
+        };
+
+        self.v3_pools.lock().await.insert(token, pool);
+        Ok(pool)
+    }
+
+    // (token, WETH) virtual reserves of the token's deepest V3 pool, which
+    // price small trades like a V2 pair would
+    pub(crate) async fn v3_reserves(&self, token: Address) -> Result<(U256, U256)> {
+        let pool = IUniswapV3Pool::new(self.v3_pool(token).await?, Arc::new(self.provider.clone()));
+        let (sqrt_price_x96, ..) = pool.slot_0().call().await?;
+        let liquidity = pool.liquidity().call().await?;
+        let (reserve0, reserve1) = virtual_reserves(sqrt_price_x96, U256::from(liquidity));
+
+        // Pools sort their tokens by address
+        if token < self.weth {
+            Ok((reserve0, reserve1))
+        } else {
+            Ok((reserve1, reserve0))
+        }
+    }
+
+    // ETH spent by a buy touching a V3- or V4-traded token, which has no V2
+    // path to quote through: the ETH or WETH paid in when that's the input, else the
+    // bought tokens at spot
+    pub(crate) async fn v3_eth_equivalent(&self, swap: &RouterSwap, value: U256) -> Result<U256> {
+        let token_out = swap.token_out().unwrap_or_default();
+        match swap.amounts {
+            SwapAmounts::ExactIn { amount_in: None, .. } if !value.is_zero() => Ok(value),
+            SwapAmounts::ExactIn { amount_in: Some(amount_in), .. } if swap.token_in() == Some(self.weth) => Ok(amount_in),
+            SwapAmounts::ExactIn { amount_out_min, .. } => self.eth_value(token_out, amount_out_min).await,
+            SwapAmounts::ExactOut { amount_out, .. } => self.eth_value(token_out, amount_out).await,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::{abi::Token, utils::hex};
+
+    const WETH: &str = "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2";
+    const PEPE: &str = "0x6982508145454Ce325dDbE47a25d4ec3d2311933";
+    const USDC: &str = "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48";
+
+    // exactInputSingle buying PEPE with 0.1 WETH through the 0.3% pool, as
+    // sent to mainnet SwapRouter02
+    const EXACT_INPUT_SINGLE: &str = concat!(
+        "04e45aaf",
+        "000000000000000000000000c02aaa39b223fe8d0a0e5c4f27ead9083c756cc2",
+        "0000000000000000000000006982508145454ce325ddbe47a25d4ec3d2311933",
+        "0000000000000000000000000000000000000000000000000000000000000bb8",
+        "0000000000000000000000003fc91a3afd70395cd496c647d5a6cc9d4b2b7fad",
+        "000000000000000000000000000000000000000000000000016345785d8a0000",
+        "00000000000000000000000000000000000000000000003635c9adc5dea00000",
+        "0000000000000000000000000000000000000000000000000000000000000000",
+    );
+
+    fn address(s: &str) -> Address {
+        s.parse().unwrap()
+    }
+
+    fn path(hops: &[(&str, u32)], last: &str) -> Vec<u8> {
+        let mut path = Vec::new();
+        for (token, fee) in hops {
+            path.extend_from_slice(address(token).as_bytes());
+            path.extend_from_slice(&fee.to_be_bytes()[1..]);
+        }
+        path.extend_from_slice(address(last).as_bytes());
+        path
+    }
+
+    #[test]
+    fn decodes_paths_hop_by_hop() {
+        assert_eq!(decode_path(&path(&[(WETH, 3_000)], PEPE)), Some(vec![address(WETH), address(PEPE)]));
+        assert_eq!(
+            decode_path(&path(&[(USDC, 500), (WETH, 10_000)], PEPE)),
+            Some(vec![address(USDC), address(WETH), address(PEPE)])
+        );
+    }
+
+    #[test]
+    fn rejects_empty_and_misaligned_paths() {
+        let two_hops = path(&[(WETH, 3_000)], PEPE);
+        assert_eq!(decode_path(&[]), None);
+        // A lone token isn't a swap
+        assert_eq!(decode_path(&two_hops[..20]), None);
+        assert_eq!(decode_path(&two_hops[..42]), None);
+        assert_eq!(decode_path(&[two_hops.as_slice(), &[0]].concat()), None);
+        assert_eq!(decode_path(&path(&[(WETH, 3_000)], PEPE)[..23]), None);
+    }
+
+    #[test]
+    fn selectors_match_the_mainnet_routers() {
+        let selector = |swap: Exact, deadline| hex::encode(&keccak256(swap.signature(deadline))[..4]);
+        // SwapRouter02
+        assert_eq!(selector(Exact::InputSingle, false), "04e45aaf");
+        assert_eq!(selector(Exact::Input, false), "b858183f");
+        assert_eq!(selector(Exact::OutputSingle, false), "5023b4df");
+        assert_eq!(selector(Exact::Output, false), "09b81346");
+        // SwapRouter
+        assert_eq!(selector(Exact::InputSingle, true), "414bf389");
+        assert_eq!(selector(Exact::Input, true), "c04b8d59");
+        assert_eq!(selector(Exact::OutputSingle, true), "db3e2198");
+        assert_eq!(selector(Exact::Output, true), "f28c0498");
+    }
+
+    #[test]
+    fn decodes_mainnet_exact_input_single() {
+        let swap = decode_v3_swap(&hex::decode(EXACT_INPUT_SINGLE).unwrap()).unwrap();
+        assert_eq!(swap.path, vec![address(WETH), address(PEPE)]);
+        assert_eq!(swap.to, address("0x3fC91A3afd70395Cd496C647d5a6CC9D4B2b7FAD"));
+        assert_eq!(swap.deadline, U256::MAX);
+        assert_eq!(
+            swap.amounts,
+            SwapAmounts::ExactIn { amount_in: Some(U256::exp10(17)), amount_out_min: U256::exp10(21) }
+        );
+    }
+
+    #[test]
+    fn reverses_exact_output_paths() {
+        // The original SwapRouter's exactOutput, paying USDC for PEPE via WETH
+        let params = Token::Tuple(vec![
+            Token::Bytes(path(&[(PEPE, 10_000), (WETH, 500)], USDC)),
+            Token::Address(Address::repeat_byte(1)),
+            Token::Uint(U256::from(1_700_000_000u64)),
+            Token::Uint(U256::MAX),
+            Token::Uint(U256::zero()),
+        ]);
+        let calldata = [hex::decode("f28c0498").unwrap(), ethers::abi::encode(&[params])].concat();
+        let swap = decode_v3_swap(&calldata).unwrap();
+        assert_eq!(swap.path, vec![address(USDC), address(WETH), address(PEPE)]);
+        assert_eq!(swap.deadline, U256::from(1_700_000_000u64));
+        assert_eq!(swap.amounts, SwapAmounts::ExactOut { amount_out: U256::MAX, amount_in_max: Some(U256::zero()) });
+    }
+
+    #[test]
+    fn rejects_empty_short_and_bad_path_calldata() {
+        let calldata = hex::decode(EXACT_INPUT_SINGLE).unwrap();
+        assert_eq!(decode_v3_swap(&[]), None);
+        assert_eq!(decode_v3_swap(&calldata[..3]), None);
+        assert_eq!(decode_v3_swap(&calldata[..4]), None);
+        assert_eq!(decode_v3_swap(&calldata[..100]), None);
+        // exactInput whose path holds a single token
+        let params = Token::Tuple(vec![
+            Token::Bytes(address(PEPE).as_bytes().to_vec()),
+            Token::Address(Address::zero()),
+            Token::Uint(U256::one()),
+            Token::Uint(U256::zero()),
+        ]);
+        let calldata = [hex::decode("b858183f").unwrap(), ethers::abi::encode(&[params])].concat();
+        assert_eq!(decode_v3_swap(&calldata), None);
+    }
+
+    #[test]
+    fn virtual_reserves_at_boundaries() {
+        assert_eq!(virtual_reserves(U256::zero(), U256::MAX), (U256::zero(), U256::zero()));
+        // A price of one leaves both reserves at the liquidity
+        let liquidity = U256::exp10(18);
+        assert_eq!(virtual_reserves(U256::one() << 96, liquidity), (liquidity, liquidity));
+    }
+}
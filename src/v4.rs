@@ -0,0 +1,265 @@
+use crate::abi::{
+    ExecuteWithCommandsAndInputsCall, IPermit2, IStateView, IUniversalRouterCalls, IV4Quoter, PoolKey,
+    QuoteExactSingleParams,
+};
+use crate::allowance::ApprovalMode;
+use crate::bot::{TokenTarget, TradingBot};
+use crate::decode::{RouterSwap, SwapAmounts};
+use crate::error::{MktmkrError, Result};
+use crate::v3::virtual_reserves;
+use ethers::{
+    abi::{decode, encode, AbiEncode, ParamType, Token},
+    prelude::*,
+    types::{Address, Bytes, TransactionReceipt, H256, I256, U256},
+    utils::keccak256,
+};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Permit2, which V4 sells through the Universal Router pull tokens with
+pub const PERMIT2: Address = H160([
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x22, 0xd4, 0x73, 0x03, 0x0f, 0x11, 0x6d, 0xde, 0xe9, 0xf6, 0xb4, 0x3a, 0xc7, 0x8b, 0xa3,
+]);
+
+// V4 router actions, one byte each in a V4_SWAP command
+const SWAP_EXACT_IN_SINGLE: u8 = 0x06;
+const SWAP_EXACT_IN: u8 = 0x07;
+const SWAP_EXACT_OUT_SINGLE: u8 = 0x08;
+const SWAP_EXACT_OUT: u8 = 0x09;
+const SETTLE_ALL: u8 = 0x0c;
+const TAKE_ALL: u8 = 0x0f;
+// The Universal Router command running V4 router actions
+pub(crate) const V4_SWAP: u8 = 0x10;
+
+// Uniswap V4 contracts: a Universal Router that can swap on V4 for sells,
+// the quoter and state lens for quotes and prices, and the PoolManager
+// whose Swap events report what sells paid out
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct V4Venue {
+    pub universal_router: Address,
+    pub quoter: Address,
+    pub state_view: Address,
+    pub pool_manager: Address,
+}
+
+// A token's V4 pool against native ETH. V4 pools aren't looked up, so the
+// rest of its key is configured.
+#[derive(Debug, Clone, PartialEq)]
+pub struct V4Pool {
+    pub fee: u32,
+    pub tick_spacing: i32,
+    // Hook contract; zero for none
+    pub hooks: Address,
+    // Passed to the hooks on every quote and swap
+    pub hook_data: Bytes,
+}
+
+impl V4Pool {
+    // Native ETH is currency 0 of every pool it's in
+    pub fn key(&self, token: Address) -> PoolKey {
+        PoolKey {
+            currency_0: Address::zero(),
+            currency_1: token,
+            fee: self.fee,
+            tick_spacing: self.tick_spacing,
+            hooks: self.hooks,
+        }
+    }
+
+    // keccak256(abi.encode(key)), as the PoolManager names the pool
+    pub fn id(&self, token: Address) -> H256 {
+        H256::from(keccak256(encode(&key_tokens(&self.key(token)))))
+    }
+}
+
+fn key_tokens(key: &PoolKey) -> Vec<Token> {
+    vec![
+        Token::Address(key.currency_0),
+        Token::Address(key.currency_1),
+        Token::Uint(U256::from(key.fee)),
+        Token::Int(I256::from(key.tick_spacing).into_raw()),
+        Token::Address(key.hooks),
+    ]
+}
+
+fn key_type() -> ParamType {
+    ParamType::Tuple(vec![ParamType::Address, ParamType::Address, ParamType::Uint(24), ParamType::Int(24), ParamType::Address])
+}
+
+fn path_key_type() -> ParamType {
+    ParamType::Tuple(vec![ParamType::Address, ParamType::Uint(24), ParamType::Int(24), ParamType::Address, ParamType::Bytes])
+}
+
+// Decodes the first swap among a V4_SWAP command's actions. Native ETH
+// shows up as the zero address; swaps spending it are paid with call value.
+pub fn decode_v4_swap(input: &[u8], deadline: U256) -> Option<RouterSwap> {
+    let tokens = decode(&[ParamType::Bytes, ParamType::Array(Box::new(ParamType::Bytes))], input).ok()?;
+    let mut tokens = tokens.into_iter();
+    let actions = tokens.next()?.into_bytes()?;
+    let params = tokens.next()?.into_array()?;
+
+    let (action, params) = actions
+        .into_iter()
+        .zip(params)
+        .find(|(action, _)| (SWAP_EXACT_IN_SINGLE..=SWAP_EXACT_OUT).contains(action))?;
+    let params = params.into_bytes()?;
+    let (path, amount, limit) = match action {
+        SWAP_EXACT_IN_SINGLE | SWAP_EXACT_OUT_SINGLE => {
+            let single = ParamType::Tuple(vec![key_type(), ParamType::Bool, ParamType::Uint(128), ParamType::Uint(128), ParamType::Bytes]);
+            let mut fields = decode(&[single], &params).ok()?.pop()?.into_tuple()?.into_iter();
+            let mut key = fields.next()?.into_tuple()?.into_iter();
+            let (currency0, currency1) = (key.next()?.into_address()?, key.next()?.into_address()?);
+            let path = match fields.next()?.into_bool()? {
+                true => vec![currency0, currency1],
+                false => vec![currency1, currency0],
+            };
+            (path, fields.next()?.into_uint()?, fields.next()?.into_uint()?)
+        }
+        // Exact-input paths start from the input currency and list each
+        // hop's output; exact-output paths list each hop's input and end on
+        // the output currency
+        _ => {
+            let multi = ParamType::Tuple(vec![
+                ParamType::Address,
+                ParamType::Array(Box::new(path_key_type())),
+                ParamType::Uint(128),
+                ParamType::Uint(128),
+            ]);
+            let mut fields = decode(&[multi], &params).ok()?.pop()?.into_tuple()?.into_iter();
+            let currency = fields.next()?.into_address()?;
+            let hops: Vec<Address> = fields
+                .next()?
+                .into_array()?
+                .into_iter()
+                .filter_map(|hop| hop.into_tuple()?.into_iter().next()?.into_address())
+                .collect();
+            let path = match action {
+                SWAP_EXACT_IN => std::iter::once(currency).chain(hops).collect(),
+                _ => hops.into_iter().chain(std::iter::once(currency)).collect(),
+            };
+            (path, fields.next()?.into_uint()?, fields.next()?.into_uint()?)
+        }
+    };
+    if path.len() < 2 {
+        return None;
+    }
+
+    let pays_eth = path[0].is_zero();
+    let amounts = match action {
+        SWAP_EXACT_IN_SINGLE | SWAP_EXACT_IN => {
+            SwapAmounts::ExactIn { amount_in: (!pays_eth).then_some(amount), amount_out_min: limit }
+        }
+        _ => SwapAmounts::ExactOut { amount_out: amount, amount_in_max: (!pays_eth).then_some(limit) },
+    };
+    // V4 swaps pay out to whoever settles them
+    Some(RouterSwap { amounts, path, to: Address::zero(), deadline })
+}
+
+impl TradingBot {
+    fn v4_venue(&self) -> Result<V4Venue> {
+        self.v4.ok_or_else(|| MktmkrError::Config("no Uniswap V4 contracts on this chain, set them explicitly".into()))
+    }
+
+    fn v4_pool(&self, token: Address) -> Result<V4Pool> {
+        self.token(token)
+            .and_then(|target| target.v4_pool)
+            .ok_or_else(|| MktmkrError::Config(format!("{:?} has no V4 pool configured", token)))
+    }
+
+    // The quoter's (ETH out, gas estimate) for selling `amount` into the
+    // token's pool, running its hooks as the swap would
+    pub(crate) async fn v4_quote(&self, target: &TokenTarget, amount: U256) -> Result<(U256, U256)> {
+        let pool = self.v4_pool(target.address)?;
+        let quoter = IV4Quoter::new(self.v4_venue()?.quoter, Arc::new(self.provider.clone()));
+        let (amount_out, gas) = quoter
+            .quote_exact_input_single(QuoteExactSingleParams {
+                pool_key: pool.key(target.address),
+                zero_for_one: false,
+                exact_amount: amount.min(U256::from(u128::MAX)).as_u128(),
+                hook_data: pool.hook_data.clone(),
+            })
+            .call()
+            .await?;
+        Ok((amount_out, gas))
+    }
+
+    // Universal Router calldata selling `amount` into the token's pool: the
+    // swap, then settling the tokens through Permit2 and taking the ETH
+    pub(crate) fn v4_sell_call(&self, token: Address, amount: U256, amount_out_min: U256, deadline: U256) -> Result<Bytes> {
+        let pool = self.v4_pool(token)?;
+        let swap = encode(&[Token::Tuple(vec![
+            Token::Tuple(key_tokens(&pool.key(token))),
+            Token::Bool(false),
+            Token::Uint(amount),
+            Token::Uint(amount_out_min),
+            Token::Bytes(pool.hook_data.to_vec()),
+        ])]);
+        let settle = encode(&[Token::Address(token), Token::Uint(amount)]);
+        let take = encode(&[Token::Address(Address::zero()), Token::Uint(amount_out_min)]);
+        let input = encode(&[
+            Token::Bytes(vec![SWAP_EXACT_IN_SINGLE, SETTLE_ALL, TAKE_ALL]),
+            Token::Array(vec![Token::Bytes(swap), Token::Bytes(settle), Token::Bytes(take)]),
+        ]);
+        let call = IUniversalRouterCalls::ExecuteWithCommandsAndInputs(ExecuteWithCommandsAndInputsCall {
+            commands: vec![V4_SWAP].into(),
+            inputs: vec![input.into()],
+            deadline,
+        });
+        Ok(call.encode().into())
+    }
+
+    // (token, ETH) virtual reserves of the token's V4 pool
+    pub(crate) async fn v4_reserves(&self, token: Address) -> Result<(U256, U256)> {
+        let id = self.v4_pool(token)?.id(token).0;
+        let state = IStateView::new(self.v4_venue()?.state_view, Arc::new(self.provider.clone()));
+        let (sqrt_price_x96, ..) = state.get_slot_0(id).call().await?;
+        let liquidity = state.get_liquidity(id).call().await?;
+        let (eth_reserve, token_reserve) = virtual_reserves(sqrt_price_x96, U256::from(liquidity));
+        Ok((token_reserve, eth_reserve))
+    }
+
+    // Native ETH a sell's receipt took out of our tokens' V4 pools, from the
+    // PoolManager's Swap events; amounts there are owed to the swapper when
+    // positive
+    pub(crate) fn v4_eth_received(&self, receipt: &TransactionReceipt) -> U256 {
+        let Some(v4) = self.v4 else { return U256::zero() };
+        let swap = H256::from(keccak256("Swap(bytes32,address,int128,int128,uint160,uint128,int24,uint24)"));
+        let pools: Vec<H256> = self
+            .tokens()
+            .iter()
+            .filter_map(|token| Some(token.v4_pool.as_ref()?.id(token.address)))
+            .collect();
+        let received = receipt
+            .logs
+            .iter()
+            .filter(|log| log.address == v4.pool_manager && log.topics.len() == 3 && log.topics[0] == swap)
+            .filter(|log| pools.contains(&log.topics[1]) && log.data.len() >= 32)
+            .fold(I256::zero(), |acc, log| acc + I256::from_raw(U256::from_big_endian(&log.data[..32])));
+        received.max(I256::zero()).into_raw()
+    }
+
+    // True once Permit2 lets the Universal Router pull `amount` of the
+    // token, approving it first if auto_approve is on. The token's own
+    // allowance for Permit2 is handled like any router's.
+    pub(crate) async fn ensure_permit2(&self, token: Address, spender: Address, amount: U256) -> Result<bool> {
+        let permit2 = IPermit2::new(PERMIT2, Arc::new(self.provider.clone()));
+        let (allowed, expiration, _) = permit2.allowance(self.wallet.address(), token, spender).call().await?;
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+        if allowed >= amount && expiration > now {
+            return Ok(true);
+        }
+        if !self.approvals.auto_approve {
+            println!("Permit2 allowance for {:?} is {}, short of {}; approve it or enable auto_approve", token, allowed, amount);
+            return Ok(false);
+        }
+
+        // Permit2 allowances are 160-bit and expire; these never do
+        let max = (U256::one() << 160) - 1;
+        let approved = match self.approvals.mode {
+            ApprovalMode::Exact => amount.max(self.inventory(token).await?).min(max),
+            ApprovalMode::Infinite => max,
+        };
+        let data = permit2.approve(token, spender, approved, (1u64 << 48) - 1).calldata().unwrap_or_default();
+        self.send_approval(token, PERMIT2, data, spender, approved).await
+    }
+}
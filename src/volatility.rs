@@ -0,0 +1,73 @@
+use crate::bot::TradingBot;
+use ethers::types::Address;
+use std::collections::{HashMap, VecDeque};
+
+// Scales sell_percentage by the pool's short-term volatility against
+// baseline_bps, within [min_percentage, max_percentage]: a pump twice as
+// violent as the baseline sells twice the fraction into each buy
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VolatilityScaling {
+    // Price moves kept per token
+    pub window: usize,
+    // Volatility, per move, at which sell_percentage applies unscaled
+    pub baseline_bps: f64,
+    pub min_percentage: f64,
+    pub max_percentage: f64,
+}
+
+// Recent prices of each token, one per block its pool traded in
+#[derive(Debug, Default)]
+pub struct PriceMoves {
+    prices: HashMap<Address, VecDeque<f64>>,
+}
+
+impl PriceMoves {
+    // Adds a price unless it hasn't moved since the last one
+    pub fn record(&mut self, token: Address, price: f64, window: usize) {
+        let prices = self.prices.entry(token).or_default();
+        if prices.back() == Some(&price) || price <= 0.0 {
+            return;
+        }
+        prices.push_back(price);
+        while prices.len() > window + 1 {
+            prices.pop_front();
+        }
+    }
+
+    // Standard deviation of log returns over the window, in basis points;
+    // None before a few moves are in
+    pub fn bps(&self, token: Address) -> Option<f64> {
+        let prices = self.prices.get(&token)?;
+        if prices.len() < 3 {
+            return None;
+        }
+        let returns: Vec<f64> = prices.iter().zip(prices.iter().skip(1)).map(|(a, b)| (b / a).ln()).collect();
+        let mean = returns.iter().sum::<f64>() / returns.len() as f64;
+        let variance = returns.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / (returns.len() - 1) as f64;
+        Some(variance.sqrt() * 10_000.0)
+    }
+}
+
+impl TradingBot {
+    // Samples each token's spot price for the volatility estimate, once a
+    // block
+    pub(crate) async fn sample_volatility(&self) {
+        let Some(scaling) = self.volatility_scaling else { return };
+        for token in self.tokens() {
+            match self.spot(token.address).await {
+                Ok(spot) => {
+                    let price = self.whole_price(token.address, spot);
+                    self.price_moves.lock().await.record(token.address, price, scaling.window);
+                }
+                Err(e) => println!("Could not sample the price of {:?}: {}", token.address, e),
+            }
+        }
+    }
+
+    // `sell_percentage` scaled by the token's volatility, when configured
+    pub(crate) async fn volatility_scaled(&self, token: Address, sell_percentage: f64) -> f64 {
+        let Some(scaling) = self.volatility_scaling else { return sell_percentage };
+        let Some(bps) = self.price_moves.lock().await.bps(token) else { return sell_percentage };
+        (sell_percentage * bps / scaling.baseline_bps).clamp(scaling.min_percentage, scaling.max_percentage)
+    }
+}
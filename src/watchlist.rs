@@ -0,0 +1,12 @@
+use ethers::types::Address;
+
+// A wallet whose trades in our tokens are flagged apart from retail flow
+#[derive(Debug, Clone, PartialEq)]
+pub struct WatchedWallet {
+    pub address: Address,
+    pub label: String,
+    // Sells this share of the tokens the wallet sells alongside it. The bot
+    // only sells, so the wallet's buys aren't mirrored; they are always sold
+    // into instead, whatever their size or the net flow.
+    pub mirror_percentage: Option<f64>,
+}
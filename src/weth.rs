@@ -0,0 +1,53 @@
+use crate::abi::{IERC20, IWETH};
+use crate::bot::TradingBot;
+use crate::error::Result;
+use ethers::{
+    prelude::*,
+    types::{BlockNumber, U256},
+    utils::format_ether,
+};
+use std::sync::Arc;
+
+// Selling to WETH instead of ETH: the router skips its unwrap on every sell,
+// and the wallet unwraps its WETH in one go once it builds up
+#[derive(Debug, Clone, Copy)]
+pub struct WethProceeds {
+    // WETH balance at which it's unwrapped
+    pub unwrap_threshold: U256,
+}
+
+impl TradingBot {
+    // Unwraps the wallet's whole WETH balance once it reaches the threshold,
+    // or whatever there is when `flush` is set. The balance is read at the
+    // pending block, so an unwrap still unmined isn't sent again.
+    pub(crate) async fn unwrap_weth(&self, flush: bool) -> Result<()> {
+        let Some(proceeds) = self.weth_proceeds else { return Ok(()) };
+        if self.dry_run || self.paper.is_some() {
+            return Ok(());
+        }
+        let client = Arc::new(self.provider.clone());
+        let balance = IERC20::new(self.weth, client.clone())
+            .balance_of(self.wallet.address())
+            .block(BlockNumber::Pending)
+            .call()
+            .await?;
+        if balance.is_zero() || (!flush && balance < proceeds.unwrap_threshold) {
+            return Ok(());
+        }
+
+        let data = IWETH::new(self.weth, client).withdraw(balance).calldata().unwrap_or_default();
+        let mut tx = self.approval_bid().await?.transaction(self.wallet.address(), self.weth, data);
+        let nonce = self.next_nonce().await?;
+        tx.set_nonce(nonce);
+        match self.sign_and_send(&mut tx).await {
+            Ok(tx_hash) => {
+                println!("Unwrapping {} WETH in {}", format_ether(balance), self.tx_link(tx_hash));
+                Ok(())
+            }
+            Err(e) => {
+                self.nonces.lock().await.release(nonce);
+                Err(e)
+            }
+        }
+    }
+}